@@ -0,0 +1,71 @@
+//! Time taken by every built-in effect to draw a frame at common screen resolutions.
+//!
+//! Run with `cargo bench`, optionally followed by `--` and a regular expression picking
+//! the benchmarks whose name matches it, such as `cargo bench -- 'plasma/1080p|tunnel'`.
+//! Benchmarks are measured with criterion, grouped by effect and named after the
+//! resolution, and report the time taken by a frame along with the pixels drawn per
+//! second. Simulations are advanced for a few frames beforehand so they draw a busy scene,
+//! but only `draw` is timed.
+use criterion::{criterion_group, criterion_main, Criterion, SamplingMode, Throughput};
+use plasma::common::DemoEffect;
+use plasma::effects::{self, Settings};
+use std::hint::black_box;
+use std::time::Duration;
+
+/// Resolutions every effect is drawn at
+const RESOLUTIONS: [(&str, usize, usize); 3] = [
+    ("720p", 1280, 720),
+    ("1080p", 1920, 1080),
+    ("4k", 3840, 2160),
+];
+
+/// Time spent drawing the frames of a single benchmark
+const MEASUREMENT_TIME: Duration = Duration::from_secs(2);
+
+/// Time spent drawing frames before measuring a single benchmark
+const WARM_UP_TIME: Duration = Duration::from_millis(500);
+
+/// Samples taken by a single benchmark, the fewest criterion accepts
+const SAMPLE_SIZE: usize = 10;
+
+/// Frames the simulations are advanced by before being timed
+const WARM_UP_FRAMES: usize = 30;
+
+/// Time step of the simulations in seconds
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+fn draw(c: &mut Criterion) {
+    let settings = Settings::default();
+    for name in effects::names() {
+        let mut group = c.benchmark_group(name);
+        // Frames take milliseconds, which flat sampling suits better than linear sampling
+        group
+            .sampling_mode(SamplingMode::Flat)
+            .sample_size(SAMPLE_SIZE)
+            .warm_up_time(WARM_UP_TIME)
+            .measurement_time(MEASUREMENT_TIME);
+        for (resolution, width, height) in RESOLUTIONS {
+            group.throughput(Throughput::Elements((width * height) as u64));
+            // Built on first use, since criterion skips the benchmarks left out by a filter
+            let mut state: Option<(Box<dyn DemoEffect>, Vec<u32>, f32)> = None;
+            group.bench_function(resolution, |b| {
+                let (effect, buffer, time) = state.get_or_insert_with(|| {
+                    let mut effect = effects::create(name, width, height, &settings)
+                        .unwrap_or_else(|e| panic!("building {name}: {e}"));
+                    for _ in 0..WARM_UP_FRAMES {
+                        effect.update(FRAME_TIME);
+                    }
+                    (effect, vec![0; width * height], 0.0)
+                });
+                b.iter(|| {
+                    effect.draw(black_box(buffer), black_box(*time));
+                    *time += FRAME_TIME;
+                });
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, draw);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+//! Monochrome rendering with Unicode Braille patterns.
+//!
+//! Every Braille character holds a grid of two by four dots, giving eight pixels per
+//! character cell with nothing but plain text, which even the simplest terminals and
+//! slow SSH links handle well. Frames are averaged down to the dot grid, and every dot
+//! is lit when its luminance exceeds a threshold taken from a Bayer matrix, an ordered
+//! dithering that renders gradients as patterns which stay put from frame to frame.
+
+/// Thresholds of the ordered dithering, from 0 to 15, repeating every four dots
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Bit of every dot of a Braille character, indexed by row then column
+const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Appends the characters drawing `lines` lines of `columns` Braille characters, taking
+/// the dots from a frame of `columns * 2` by `lines * 4` packed ARGB pixels.
+pub fn encode(output: &mut String, dots: &[u32], columns: usize, lines: usize) {
+    let width = columns * 2;
+    output.push_str("\x1b[H\x1b[0m");
+    for line in 0..lines {
+        if line > 0 {
+            output.push_str("\r\n");
+        }
+        for column in 0..columns {
+            let mut pattern = 0;
+            for (dy, row) in DOTS.iter().enumerate() {
+                for (dx, bit) in row.iter().enumerate() {
+                    let (x, y) = (column * 2 + dx, line * 4 + dy);
+                    let threshold = u32::from(BAYER[y % 4][x % 4]) * 16 + 8;
+                    if luminance(dots[y * width + x]) > threshold {
+                        pattern |= bit;
+                    }
+                }
+            }
+            output.push(char::from_u32(0x2800 + u32::from(pattern)).unwrap_or(' '));
+        }
+    }
+}
+
+/// Returns the perceived brightness of a packed ARGB pixel, from 0 to 255.
+fn luminance(pixel: u32) -> u32 {
+    let [_, r, g, b] = pixel.to_be_bytes().map(u32::from);
+    (299 * r + 587 * g + 114 * b) / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn black_and_white_light_no_dots_and_every_dot() {
+        let mut output = String::new();
+        let mut dots = [0xff00_0000; 16];
+        for row in dots.chunks_mut(4) {
+            row[2..].fill(0xffff_ffff);
+        }
+        encode(&mut output, &dots, 2, 1);
+        // The left character covers columns 0 and 1, all black, the right one all white
+        assert!(output.ends_with("\u{2800}\u{28ff}"));
+    }
+
+    #[test]
+    fn dots_map_to_their_braille_bits() {
+        let mut output = String::new();
+        // Only the bottom right dot is lit
+        let mut dots = [0xff00_0000; 8];
+        dots[7] = 0xffff_ffff;
+        encode(&mut output, &dots, 1, 1);
+        assert!(output.ends_with('\u{2880}'));
+    }
+
+    #[test]
+    fn gray_lights_half_of_the_dots() {
+        let mut output = String::new();
+        encode(&mut output, &[0xff80_8080; 2 * 4 * 4 * 4], 4, 4);
+        let lit: u32 = output
+            .chars()
+            .filter(|c| ('\u{2800}'..='\u{28ff}').contains(c))
+            .map(|c| (c as u32 - 0x2800).count_ones())
+            .sum();
+        assert_eq!(lit, 64);
+    }
+}
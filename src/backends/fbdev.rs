@@ -0,0 +1,251 @@
+//! Rendering straight into the Linux framebuffer device.
+//!
+//! Without a display server, such as on a kiosk or a Raspberry Pi booted to the console,
+//! frames can be written to `/dev/fb0`, or to the device named by the `FRAMEBUFFER`
+//! environment variable like other framebuffer programs do. Effects render at the
+//! resolution of the screen, and every pixel is packed into the bit fields the device
+//! reports, covering the usual 16, 24 and 32 bits per pixel layouts. Keys are read from
+//! the console with the [`Keyboard`] of the terminal backend.
+use super::terminal::Keyboard;
+use super::{Backend, Event};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::FileExt;
+
+/// Device opened when `FRAMEBUFFER` is not set
+const DEFAULT_DEVICE: &str = "/dev/fb0";
+
+/// Request reading the variable screen information, from `linux/fb.h`
+const FBIOGET_VSCREENINFO: libc::c_ulong = 0x4600;
+
+/// Request reading the fixed screen information, from `linux/fb.h`
+const FBIOGET_FSCREENINFO: libc::c_ulong = 0x4602;
+
+/// Position and size of a color channel within a pixel, `struct fb_bitfield`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BitField {
+    /// Bit offset of the channel
+    pub offset: u32,
+    /// Number of bits of the channel
+    pub length: u32,
+    /// Whether the most significant bit comes first, never set in practice
+    pub msb_right: u32,
+}
+
+/// Variable screen information, `struct fb_var_screeninfo`
+#[repr(C)]
+#[derive(Default)]
+struct VarScreenInfo {
+    xres: u32,
+    yres: u32,
+    xres_virtual: u32,
+    yres_virtual: u32,
+    xoffset: u32,
+    yoffset: u32,
+    bits_per_pixel: u32,
+    grayscale: u32,
+    red: BitField,
+    green: BitField,
+    blue: BitField,
+    transp: BitField,
+    nonstd: u32,
+    activate: u32,
+    height: u32,
+    width: u32,
+    accel_flags: u32,
+    timing: [u32; 10],
+    colorspace: u32,
+    reserved: [u32; 4],
+}
+
+/// Fixed screen information, `struct fb_fix_screeninfo`
+#[repr(C)]
+#[derive(Default)]
+struct FixScreenInfo {
+    id: [u8; 16],
+    smem_start: libc::c_ulong,
+    smem_len: u32,
+    kind: u32,
+    type_aux: u32,
+    visual: u32,
+    xpanstep: u16,
+    ypanstep: u16,
+    ywrapstep: u16,
+    line_length: u32,
+    mmio_start: libc::c_ulong,
+    mmio_len: u32,
+    accel: u32,
+    capabilities: u16,
+    reserved: [u16; 2],
+}
+
+/// Layout of the pixels of a framebuffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelFormat {
+    /// Bytes per pixel
+    pub bytes: usize,
+    /// Red channel
+    pub red: BitField,
+    /// Green channel
+    pub green: BitField,
+    /// Blue channel
+    pub blue: BitField,
+}
+
+impl PixelFormat {
+    /// Packs a pixel of packed ARGB into the bytes of a framebuffer pixel, least
+    /// significant first.
+    pub fn pack(&self, pixel: u32, bytes: &mut [u8]) {
+        let [_, r, g, b] = pixel.to_be_bytes();
+        let channel = |value: u8, field: BitField| {
+            let length = field.length.min(8);
+            (u32::from(value) >> (8 - length)) << field.offset
+        };
+        let value = channel(r, self.red) | channel(g, self.green) | channel(b, self.blue);
+        bytes.copy_from_slice(&value.to_le_bytes()[..self.bytes]);
+    }
+}
+
+/// The screen behind a Linux framebuffer device
+pub struct Framebuffer {
+    /// The framebuffer device
+    device: File,
+    /// Visible width in pixels
+    width: usize,
+    /// Visible height in pixels
+    height: usize,
+    /// Bytes between the starts of two rows
+    line_length: usize,
+    /// Offset of the visible screen in the device memory
+    offset: u64,
+    /// Layout of the pixels
+    format: PixelFormat,
+    /// Scratch buffer holding a converted frame
+    bytes: Vec<u8>,
+    /// Keys typed on the console
+    keyboard: Keyboard,
+}
+
+impl Framebuffer {
+    /// Opens the framebuffer device and switches the console to raw mode.
+    ///
+    /// # Errors
+    /// Fails if the device cannot be opened, or uses fewer than 16 bits per pixel.
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let path = std::env::var("FRAMEBUFFER").unwrap_or_else(|_| DEFAULT_DEVICE.to_string());
+        let device = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("opening {path}: {e}"))?;
+        let mut var = VarScreenInfo::default();
+        let mut fix = FixScreenInfo::default();
+        // SAFETY: both requests fill in the structure the pointer refers to
+        unsafe {
+            if libc::ioctl(device.as_raw_fd(), FBIOGET_VSCREENINFO, &mut var) != 0
+                || libc::ioctl(device.as_raw_fd(), FBIOGET_FSCREENINFO, &mut fix) != 0
+            {
+                return Err(io::Error::last_os_error().into());
+            }
+        }
+        if !matches!(var.bits_per_pixel, 16 | 24 | 32) {
+            return Err(format!("{} bits per pixel are not supported", var.bits_per_pixel).into());
+        }
+        let bytes = var.bits_per_pixel as usize / 8;
+        Ok(Self {
+            device,
+            width: var.xres as usize,
+            height: var.yres as usize,
+            line_length: fix.line_length as usize,
+            offset: u64::from(var.yoffset) * u64::from(fix.line_length)
+                + u64::from(var.xoffset) * bytes as u64,
+            format: PixelFormat {
+                bytes,
+                red: var.red,
+                green: var.green,
+                blue: var.blue,
+            },
+            bytes: Vec::new(),
+            keyboard: Keyboard::new()?,
+        })
+    }
+}
+
+impl Backend for Framebuffer {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        // Frames smaller than the screen are drawn in its top left corner
+        let stride = width;
+        let (width, height) = (width.min(self.width), height.min(self.height));
+        let size = self.format.bytes;
+        self.bytes.resize(self.line_length * height, 0);
+        for y in 0..height {
+            let row = &mut self.bytes[y * self.line_length..][..width * size];
+            for (x, bytes) in row.chunks_exact_mut(size).enumerate() {
+                self.format.pack(pixels[y * stride + x], bytes);
+            }
+        }
+        self.device.write_all_at(&self.bytes, self.offset)?;
+        Ok(())
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        self.keyboard.read_events().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(offset: u32, length: u32) -> BitField {
+        BitField {
+            offset,
+            length,
+            msb_right: 0,
+        }
+    }
+
+    #[test]
+    fn screen_information_matches_the_kernel_layout() {
+        assert_eq!(std::mem::size_of::<VarScreenInfo>(), 160);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(std::mem::size_of::<FixScreenInfo>(), 80);
+    }
+
+    #[test]
+    fn pixels_are_packed_into_the_bit_fields() {
+        let xrgb = PixelFormat {
+            bytes: 4,
+            red: field(16, 8),
+            green: field(8, 8),
+            blue: field(0, 8),
+        };
+        let mut bytes = [0; 4];
+        xrgb.pack(0xff12_3456, &mut bytes);
+        assert_eq!(bytes, [0x56, 0x34, 0x12, 0x00]);
+
+        let rgb565 = PixelFormat {
+            bytes: 2,
+            red: field(11, 5),
+            green: field(5, 6),
+            blue: field(0, 5),
+        };
+        let mut bytes = [0; 2];
+        rgb565.pack(0xffff_ffff, &mut bytes);
+        assert_eq!(bytes, [0xff, 0xff]);
+        rgb565.pack(0xff00_ff00, &mut bytes);
+        assert_eq!(u16::from_le_bytes(bytes), 0b0000_0111_1110_0000);
+    }
+}
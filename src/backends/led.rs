@@ -0,0 +1,357 @@
+//! Driving RGB LED matrices over a serial line.
+//!
+//! Frames are shrunk to the size of the panel and sent with the Adalight protocol, which
+//! microcontroller sketches and firmwares such as WLED understand: a header holding the
+//! magic word `Ada`, the number of LEDs minus one and a checksum, followed by the red,
+//! green and blue bytes of every LED in wiring order. LEDs respond linearly to their
+//! input, so colors are gamma corrected first, and scaled down to limit the brightness
+//! and current drawn by large panels.
+use super::{downsample, Backend, Event};
+use clap::Args;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Options of the LED matrix backend
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "LED matrix options")]
+#[group(id = "led-options")]
+pub struct Config {
+    #[arg(
+        id = "led-device",
+        long = "led-device",
+        value_name = "PATH",
+        default_value = "/dev/ttyUSB0",
+        help = "Serial device the LED controller is attached to"
+    )]
+    pub device: PathBuf,
+
+    #[arg(
+        id = "led-width",
+        long = "led-width",
+        value_name = "LEDS",
+        default_value_t = 16,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Number of LED columns of the panel"
+    )]
+    pub width: u16,
+
+    #[arg(
+        id = "led-height",
+        long = "led-height",
+        value_name = "LEDS",
+        default_value_t = 16,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Number of LED rows of the panel"
+    )]
+    pub height: u16,
+
+    #[arg(
+        id = "led-gamma",
+        long = "led-gamma",
+        value_name = "GAMMA",
+        default_value_t = 2.2,
+        value_parser = parse_gamma,
+        help = "Gamma correction applied to the colors"
+    )]
+    pub gamma: f32,
+
+    #[arg(
+        id = "led-brightness",
+        long = "led-brightness",
+        value_name = "BRIGHTNESS",
+        default_value_t = 0.5,
+        value_parser = parse_brightness,
+        help = "Largest brightness of the LEDs, from 0 to 1"
+    )]
+    pub brightness: f32,
+
+    #[arg(
+        id = "led-serpentine",
+        long = "led-serpentine",
+        help = "Reverse every other row, for panels wired in a zigzag"
+    )]
+    pub serpentine: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            device: PathBuf::from("/dev/ttyUSB0"),
+            width: 16,
+            height: 16,
+            gamma: 2.2,
+            brightness: 0.5,
+            serpentine: false,
+        }
+    }
+}
+
+/// Most LEDs an Adalight header can count
+const MAX_LEDS: usize = 1 << 16;
+
+/// Parses a gamma correction, which must be a positive number.
+fn parse_gamma(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(gamma) if gamma > 0.0 && gamma.is_finite() => Ok(gamma),
+        _ => Err(format!(
+            "invalid gamma '{spec}', expected a positive number"
+        )),
+    }
+}
+
+/// Parses a brightness, which must be between 0 and 1.
+fn parse_brightness(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(brightness) if (0.0..=1.0).contains(&brightness) => Ok(brightness),
+        _ => Err(format!(
+            "invalid brightness '{spec}', expected a number from 0 to 1"
+        )),
+    }
+}
+
+/// An LED panel fed with Adalight frames
+pub struct LedMatrix {
+    /// Serial device of the LED controller
+    device: File,
+    /// Panel settings
+    config: Config,
+    /// Width of the frames the effects render in pixels
+    width: usize,
+    /// Height of the frames the effects render in pixels
+    height: usize,
+    /// LED level of every color level, with gamma and brightness applied
+    levels: [u8; 256],
+    /// Scratch buffer holding a frame
+    bytes: Vec<u8>,
+}
+
+impl LedMatrix {
+    /// Opens the serial device of the panel, ready to show frames of `width * height`
+    /// pixels shrunk to the size of the panel.
+    ///
+    /// # Errors
+    /// Fails if the device cannot be opened or configured, if the panel has more LEDs
+    /// than Adalight can address, or if the gamma is not positive or the brightness is
+    /// outside of 0 to 1.
+    pub fn new(config: &Config, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        if !(0.0..=1.0).contains(&config.brightness) {
+            return Err("the LED brightness must be between 0 and 1".into());
+        }
+        if !(config.gamma > 0.0 && config.gamma.is_finite()) {
+            return Err("the LED gamma must be a positive number".into());
+        }
+        let leds = usize::from(config.width) * usize::from(config.height);
+        if leds > MAX_LEDS {
+            return Err(format!("{leds} LEDs are more than Adalight can address").into());
+        }
+        let path = &config.device;
+        let device = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| format!("opening {}: {e}", path.display()))?;
+        configure_serial(&device)?;
+        Ok(Self {
+            device,
+            config: config.clone(),
+            width,
+            height,
+            levels: levels(config.gamma, config.brightness),
+            bytes: Vec::new(),
+        })
+    }
+}
+
+impl Backend for LedMatrix {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let (columns, rows) = (
+            usize::from(self.config.width),
+            usize::from(self.config.height),
+        );
+        let leds = downsample(pixels, width, height, columns, rows);
+        self.bytes.clear();
+        encode(
+            &mut self.bytes,
+            &leds,
+            columns,
+            &self.levels,
+            self.config.serpentine,
+        )?;
+        self.device.write_all(&self.bytes)?;
+        Ok(())
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        Vec::new()
+    }
+}
+
+/// Switches a serial line to raw 8-bit transfers at 115200 baud, the usual Adalight
+/// speed. Devices that are not terminals, such as pipes, are left as they are.
+#[cfg(unix)]
+fn configure_serial(device: &File) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    let fd = device.as_raw_fd();
+    // SAFETY: isatty only inspects the descriptor
+    if unsafe { libc::isatty(fd) } == 0 {
+        return Ok(());
+    }
+    // SAFETY: termios is plain data filled in by tcgetattr
+    let mut settings: libc::termios = unsafe { std::mem::zeroed() };
+    // SAFETY: the pointers refer to a live termios
+    unsafe {
+        if libc::tcgetattr(fd, &mut settings) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut settings);
+        if libc::cfsetspeed(&mut settings, libc::B115200) != 0
+            || libc::tcsetattr(fd, libc::TCSANOW, &settings) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Leaves the serial line as the system configured it.
+#[cfg(not(unix))]
+fn configure_serial(_device: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// Returns the LED level of every color level, gamma corrected and scaled by
+/// `brightness`.
+fn levels(gamma: f32, brightness: f32) -> [u8; 256] {
+    std::array::from_fn(|level| {
+        ((level as f32 / 255.0).powf(gamma) * brightness * 255.0).round() as u8
+    })
+}
+
+/// Appends the Adalight frame lighting the LEDs of a panel `columns` wide, with every
+/// color level mapped through `levels`.
+///
+/// # Errors
+/// Fails if there are more than [`MAX_LEDS`] LEDs, as the header counts them in 16 bits.
+fn encode(
+    bytes: &mut Vec<u8>,
+    leds: &[u32],
+    columns: usize,
+    levels: &[u8; 256],
+    serpentine: bool,
+) -> Result<(), String> {
+    if leds.len() > MAX_LEDS {
+        return Err(format!(
+            "{} LEDs are more than Adalight can address",
+            leds.len()
+        ));
+    }
+    let count = leds.len().saturating_sub(1) as u16;
+    let [high, low] = count.to_be_bytes();
+    bytes.extend([b'A', b'd', b'a', high, low, high ^ low ^ 0x55]);
+    for (y, row) in leds.chunks(columns).enumerate() {
+        let mut push = |&pixel: &u32| {
+            let [_, r, g, b] = pixel.to_be_bytes();
+            bytes.extend([r, g, b].map(|c| levels[usize::from(c)]));
+        };
+        // Odd rows of zigzag panels run from right to left
+        if serpentine && y % 2 == 1 {
+            row.iter().rev().for_each(&mut push);
+        } else {
+            row.iter().for_each(&mut push);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_start_with_the_adalight_header() {
+        let mut bytes = Vec::new();
+        let leds = vec![0xff00_0000; 300];
+        encode(&mut bytes, &leds, 20, &levels(1.0, 1.0), false).unwrap();
+        // 299 LEDs after the first, 0x012b
+        assert_eq!(
+            bytes[..6],
+            [b'A', b'd', b'a', 0x01, 0x2b, 0x01 ^ 0x2b ^ 0x55]
+        );
+        assert_eq!(bytes.len(), 6 + 300 * 3);
+    }
+
+    #[test]
+    fn panels_larger_than_the_header_can_count_are_rejected() {
+        let mut bytes = Vec::new();
+        let leds = vec![0xff00_0000; MAX_LEDS];
+        encode(&mut bytes, &leds, 256, &levels(1.0, 1.0), false).unwrap();
+        assert_eq!(bytes[3..5], [0xff, 0xff]);
+        bytes.clear();
+        let leds = vec![0xff00_0000; MAX_LEDS + 1];
+        assert!(encode(&mut bytes, &leds, 256, &levels(1.0, 1.0), false).is_err());
+        let config = Config {
+            width: 257,
+            height: 256,
+            ..Config::default()
+        };
+        let error = LedMatrix::new(&config, 4, 4).err().unwrap();
+        assert!(error.to_string().contains("65792 LEDs"));
+    }
+
+    #[test]
+    fn gamma_and_brightness_are_checked_when_parsed() {
+        assert_eq!(parse_gamma("2.2"), Ok(2.2));
+        for gamma in ["0", "-1", "inf", "NaN", "bright"] {
+            assert!(parse_gamma(gamma).is_err(), "{gamma}");
+        }
+        assert_eq!(parse_brightness("0"), Ok(0.0));
+        assert_eq!(parse_brightness("1"), Ok(1.0));
+        for brightness in ["-0.1", "1.5", "inf", "NaN"] {
+            assert!(parse_brightness(brightness).is_err(), "{brightness}");
+        }
+    }
+
+    #[test]
+    fn zigzag_panels_reverse_odd_rows() {
+        let mut bytes = Vec::new();
+        let leds = [0xff01_0101, 0xff02_0202, 0xff03_0303, 0xff04_0404];
+        encode(&mut bytes, &leds, 2, &levels(1.0, 1.0), true).unwrap();
+        assert_eq!(bytes[6..], [1, 1, 1, 2, 2, 2, 4, 4, 4, 3, 3, 3]);
+    }
+
+    #[test]
+    fn levels_are_gamma_corrected_and_dimmed() {
+        let full = levels(2.2, 1.0);
+        assert_eq!((full[0], full[255]), (0, 255));
+        assert!(full[128] < 64);
+        assert_eq!(levels(1.0, 0.5)[255], 128);
+    }
+
+    #[test]
+    fn files_receive_the_frames() {
+        let path = std::env::temp_dir().join(format!("plasma-leds-{}", std::process::id()));
+        File::create(&path).unwrap();
+        let config = Config {
+            device: path.clone(),
+            width: 2,
+            height: 1,
+            ..Config::default()
+        };
+        let mut matrix = LedMatrix::new(&config, 4, 4).unwrap();
+        matrix.present(&[0xffff_ffff; 16], 4, 4).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(bytes.len(), 6 + 2 * 3);
+        assert_eq!(bytes[6], 128);
+    }
+}
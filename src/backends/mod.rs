@@ -0,0 +1,422 @@
+//! Frontends showing the rendered frames.
+//!
+//! Every frontend implements [`Backend`], so the main loop renders frames and handles
+//! input the same way wherever they are shown. The demo normally runs in a [`window`],
+//! or in a [`winit`] window on desktops where minifb misbehaves, such as Wayland. The
+//! [`wgpu`] backend draws into such a window on the GPU, running the fragment shaders of
+//! the effects having one at the full resolution of the screen. Built for WebAssembly,
+//! the [`web`] backend draws into an HTML canvas instead.
+//! The [`terminal`] backend draws the frames with colored text, [`sixel`] images or
+//! [`braille`] dots instead, so effects can also be watched in a terminal, over SSH for
+//! example. The terminal is driven through crossterm, so it works on every desktop.
+//! On Linux, [`fbdev`] draws straight into the framebuffer when there is no display
+//! server at all, and [`led`] drives physical LED panels over a serial line. On X11
+//! desktops, [`x11`] animates the background of the root window, or draws into windows
+//! created by other programs such as XScreenSaver.
+use crate::common::{for_each_row, ShaderSource};
+use clap::ValueEnum;
+use minifb::Key;
+use std::error::Error;
+
+pub mod braille;
+#[cfg(target_os = "linux")]
+pub mod fbdev;
+pub mod led;
+pub mod sixel;
+#[cfg(not(target_family = "wasm"))]
+pub mod terminal;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(all(feature = "wgpu", not(target_family = "wasm")))]
+pub mod wgpu;
+pub mod window;
+#[cfg(all(feature = "winit", not(target_family = "wasm")))]
+pub mod winit;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub mod x11;
+
+/// Frontend selected on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum BackendKind {
+    /// Desktop window
+    #[default]
+    Window,
+    /// Desktop window drawn with winit, for Wayland and HiDPI screens
+    Winit,
+    /// Desktop window drawn on the GPU with wgpu, running the shaders of the effects
+    /// having one
+    Wgpu,
+    /// Half-block characters with 24-bit ANSI colors
+    Terminal,
+    /// Sixel images in terminals supporting them
+    Sixel,
+    /// Monochrome Braille patterns with eight dots per character
+    Braille,
+    /// Linux framebuffer device, without a display server
+    Fbdev,
+    /// RGB LED matrix driven over a serial line
+    Led,
+    /// Background of the X11 desktop, selected with `--wallpaper`
+    #[value(skip)]
+    Wallpaper,
+}
+
+/// How frames rendered below the resolution of the backend are enlarged
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum Filter {
+    /// Repeats the pixels, keeping them sharp and blocky
+    #[default]
+    Nearest,
+    /// Blends the four nearest pixels, smoothing the picture
+    Bilinear,
+}
+
+/// Input gathered by a backend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// A key was pressed
+    Key {
+        /// The key, never Shift itself
+        key: Key,
+        /// Whether Shift was held down
+        shift: bool,
+    },
+    /// The pointer is over the frame
+    Mouse {
+        /// Horizontal pointer position in pixels
+        x: f32,
+        /// Vertical pointer position in pixels
+        y: f32,
+        /// Whether the left mouse button is held down
+        pressed: bool,
+    },
+}
+
+/// Area of the desktop covered by a monitor, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Monitor {
+    /// Horizontal position of the left edge
+    pub x: i32,
+    /// Vertical position of the top edge
+    pub y: i32,
+    /// Width of the monitor
+    pub width: usize,
+    /// Height of the monitor
+    pub height: usize,
+}
+
+impl Monitor {
+    /// Returns the smallest area covering all of `monitors`, for windows spanning them.
+    pub fn span(monitors: &[Monitor]) -> Option<Monitor> {
+        let right = |m: &Monitor| m.x + m.width as i32;
+        let bottom = |m: &Monitor| m.y + m.height as i32;
+        let x = monitors.iter().map(|m| m.x).min()?;
+        let y = monitors.iter().map(|m| m.y).min()?;
+        let width = monitors.iter().map(right).max()? - x;
+        let height = monitors.iter().map(bottom).max()? - y;
+        Some(Monitor {
+            x,
+            y,
+            width: width as usize,
+            height: height as usize,
+        })
+    }
+}
+
+/// A place frames are shown in, and input comes from
+pub trait Backend {
+    /// Returns whether the backend is still showing frames, which stops when a window is
+    /// closed.
+    fn is_open(&self) -> bool {
+        true
+    }
+
+    /// Returns the size in pixels effects should render at.
+    fn size(&self) -> (usize, usize);
+
+    /// Shows a frame of packed ARGB pixels.
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Returns the input received since the previous call, oldest first.
+    fn poll_events(&mut self) -> Vec<Event>;
+
+    /// Stops waiting for the display between frames, so they are shown as fast as they
+    /// are rendered.
+    ///
+    /// Backends that never wait, or that keep a frame rate of their own to spare a slow
+    /// output, keep the default, which does nothing.
+    fn disable_vsync(&mut self) {}
+
+    /// Returns whether the backend draws the frames of effects with a fragment shader
+    /// through [`Backend::draw_shader`].
+    ///
+    /// Backends without a GPU keep the default, which returns `false`.
+    fn draws_shaders(&self) -> bool {
+        false
+    }
+
+    /// Shows the frame at `time` drawn by `shader`, for an effect rendering at
+    /// `width * height` pixels, instead of a frame rendered on the CPU.
+    ///
+    /// Only called when [`Backend::draws_shaders`] returns `true`, which backends keeping
+    /// the default do not, so the default fails.
+    ///
+    /// # Errors
+    /// Fails if the shader cannot be compiled, or if the frame cannot be shown.
+    fn draw_shader(
+        &mut self,
+        _shader: &ShaderSource,
+        _width: usize,
+        _height: usize,
+        _time: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        Err("this backend does not draw shaders".into())
+    }
+}
+
+/// Creates the selected backend for frames of `width * height` pixels, with the panel
+/// described by `led` for LED matrices.
+///
+/// # Errors
+/// Fails if the window or device cannot be opened, if a terminal backend does not run in
+/// a terminal, or if the backend was left out of the build.
+pub fn create(
+    kind: BackendKind,
+    title: &str,
+    width: usize,
+    height: usize,
+    led: &led::Config,
+) -> Result<Box<dyn Backend>, Box<dyn Error>> {
+    #[cfg(not(target_family = "wasm"))]
+    use terminal::{Graphics, Terminal};
+    Ok(match kind {
+        BackendKind::Window => Box::new(window::WindowBackend::new(title, width, height)?),
+        #[cfg(all(feature = "winit", not(target_family = "wasm")))]
+        BackendKind::Winit => Box::new(winit::WinitBackend::new(title, width, height)?),
+        #[cfg(not(all(feature = "winit", not(target_family = "wasm"))))]
+        BackendKind::Winit => return Err("plasma was built without the winit feature".into()),
+        #[cfg(all(feature = "wgpu", not(target_family = "wasm")))]
+        BackendKind::Wgpu => Box::new(wgpu::WgpuBackend::new(title, width, height)?),
+        #[cfg(not(all(feature = "wgpu", not(target_family = "wasm"))))]
+        BackendKind::Wgpu => return Err("plasma was built without the wgpu feature".into()),
+        BackendKind::Led => Box::new(led::LedMatrix::new(led, width, height)?),
+        #[cfg(not(target_family = "wasm"))]
+        BackendKind::Terminal => Box::new(Terminal::new(Graphics::HalfBlocks, width, height)?),
+        #[cfg(not(target_family = "wasm"))]
+        BackendKind::Sixel => Box::new(Terminal::new(Graphics::Sixel, width, height)?),
+        #[cfg(not(target_family = "wasm"))]
+        BackendKind::Braille => Box::new(Terminal::new(Graphics::Braille, width, height)?),
+        #[cfg(target_os = "linux")]
+        BackendKind::Fbdev => Box::new(fbdev::Framebuffer::new()?),
+        #[cfg(not(target_os = "linux"))]
+        BackendKind::Fbdev => return Err("the framebuffer is only available on Linux".into()),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        BackendKind::Wallpaper => Box::new(x11::X11Surface::root()?),
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        BackendKind::Wallpaper => return Err("wallpapers are only available on X11".into()),
+        #[cfg(target_family = "wasm")]
+        _ => return Err("terminal backends are not available in the browser".into()),
+    })
+}
+
+/// Creates a backend drawing into the existing X11 window `window_id`.
+///
+/// # Errors
+/// Fails if the window cannot be drawn into, or on systems without X11.
+pub fn embed(window_id: u64) -> Result<Box<dyn Backend>, Box<dyn Error>> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return Ok(Box::new(x11::X11Surface::window(window_id)?));
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    return Err(format!("cannot draw into window {window_id} without X11").into());
+}
+
+/// Returns the monitors of the desktop, in the order the display server lists them.
+///
+/// # Errors
+/// Fails if the display cannot be opened, or on systems without X11.
+pub fn monitors() -> Result<Vec<Monitor>, Box<dyn Error>> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    return x11::monitors();
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    return Err("monitors can only be listed on X11".into());
+}
+
+/// Parses an X11 window id, in hexadecimal with a `0x` prefix as `xwininfo` prints them,
+/// or in decimal.
+///
+/// # Errors
+/// Fails if `id` is not a number.
+pub fn parse_window_id(id: &str) -> Result<u64, String> {
+    let id = id.trim();
+    match id.strip_prefix("0x").or_else(|| id.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => id.parse(),
+    }
+    .map_err(|_| format!("invalid X11 window id: {id}"))
+}
+
+/// Shrinks a frame to `target_width * target_height` pixels, averaging the pixels
+/// covered by every target pixel.
+pub fn downsample(
+    pixels: &[u32],
+    width: usize,
+    height: usize,
+    target_width: usize,
+    target_height: usize,
+) -> Vec<u32> {
+    let mut target = Vec::with_capacity(target_width * target_height);
+    for ty in 0..target_height {
+        let (y0, y1) = span(ty, target_height, height);
+        for tx in 0..target_width {
+            let (x0, x1) = span(tx, target_width, width);
+            let mut sum = [0u32; 3];
+            for y in y0..y1 {
+                for &pixel in &pixels[y * width + x0..y * width + x1] {
+                    let [_, r, g, b] = pixel.to_be_bytes();
+                    sum[0] += u32::from(r);
+                    sum[1] += u32::from(g);
+                    sum[2] += u32::from(b);
+                }
+            }
+            let count = ((x1 - x0) * (y1 - y0)) as u32;
+            let [r, g, b] = sum.map(|channel| channel / count);
+            target.push(0xff00_0000 | (r << 16) | (g << 8) | b);
+        }
+    }
+    target
+}
+
+/// Enlarges a frame of `width * height` pixels into `target`, a frame of
+/// `target_width * target_height` pixels.
+pub fn upscale(
+    pixels: &[u32],
+    (width, height): (usize, usize),
+    target: &mut [u32],
+    (target_width, target_height): (usize, usize),
+    filter: Filter,
+) {
+    // Position of the center of every target column among the source pixels
+    let source_x = |tx: usize| (tx as f32 + 0.5) * width as f32 / target_width as f32 - 0.5;
+    let columns: Vec<(usize, usize, f32)> = (0..target_width)
+        .map(|tx| match filter {
+            Filter::Nearest => (tx * width / target_width, 0, 0.0),
+            Filter::Bilinear => bilinear_span(source_x(tx), width),
+        })
+        .collect();
+    for_each_row(target, target_width, |ty, row| {
+        let (y0, y1, fy) = match filter {
+            Filter::Nearest => (ty * height / target_height, 0, 0.0),
+            Filter::Bilinear => bilinear_span(
+                (ty as f32 + 0.5) * height as f32 / target_height as f32 - 0.5,
+                height,
+            ),
+        };
+        let (top, bottom) = (&pixels[y0 * width..], &pixels[y1 * width..]);
+        for (pixel, &(x0, x1, fx)) in row.iter_mut().zip(&columns) {
+            *pixel = match filter {
+                Filter::Nearest => top[x0],
+                Filter::Bilinear => {
+                    let upper = lerp(top[x0], top[x1], fx);
+                    let lower = lerp(bottom[x0], bottom[x1], fx);
+                    lerp(upper, lower, fy)
+                }
+            };
+        }
+    });
+}
+
+/// Returns the two source pixels around `position` and the weight of the second one.
+fn bilinear_span(position: f32, size: usize) -> (usize, usize, f32) {
+    let position = position.clamp(0.0, (size - 1) as f32);
+    let first = position as usize;
+    (first, (first + 1).min(size - 1), position - first as f32)
+}
+
+/// Blends two opaque pixels, `weight` being the share of the second one.
+fn lerp(a: u32, b: u32, weight: f32) -> u32 {
+    let channel = |shift: u32| {
+        let (a, b) = (((a >> shift) & 0xff) as f32, ((b >> shift) & 0xff) as f32);
+        ((a + (b - a) * weight).round() as u32) << shift
+    };
+    0xff00_0000 | channel(16) | channel(8) | channel(0)
+}
+
+/// Returns the range of source pixels covered by a target pixel, never empty.
+fn span(index: usize, target: usize, source: usize) -> (usize, usize) {
+    let start = index * source / target;
+    let end = ((index + 1) * source / target).max(start + 1).min(source);
+    (start.min(end - 1), end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsampling_averages_the_covered_pixels() {
+        let pixels = [0xff00_0000, 0xff00_00ff, 0xffff_0000, 0xffff_00ff];
+        assert_eq!(downsample(&pixels, 2, 2, 1, 1), [0xff7f_007f]);
+        assert_eq!(downsample(&pixels, 2, 2, 2, 2), pixels);
+        // Growing repeats the pixels
+        assert_eq!(downsample(&pixels[..1], 1, 1, 2, 1), [0xff00_0000; 2]);
+    }
+
+    #[test]
+    fn nearest_upscaling_repeats_the_pixels() {
+        let pixels = [0xff00_0000, 0xffff_ffff];
+        let mut target = [0; 4];
+        upscale(&pixels, (2, 1), &mut target, (4, 1), Filter::Nearest);
+        assert_eq!(target, [0xff00_0000, 0xff00_0000, 0xffff_ffff, 0xffff_ffff]);
+    }
+
+    #[test]
+    fn bilinear_upscaling_blends_neighbors() {
+        let pixels = [0xff00_0000, 0xff00_00c8];
+        let mut target = [0; 8];
+        upscale(&pixels, (2, 1), &mut target, (4, 2), Filter::Bilinear);
+        // The outer pixels keep the colors of the edges
+        assert_eq!(
+            target[..4],
+            [0xff00_0000, 0xff00_0032, 0xff00_0096, 0xff00_00c8]
+        );
+        assert_eq!(target[..4], target[4..]);
+    }
+
+    #[test]
+    fn spans_cover_all_monitors() {
+        let left = Monitor {
+            x: 0,
+            y: 120,
+            width: 1280,
+            height: 960,
+        };
+        let right = Monitor {
+            x: 1280,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let span = Monitor {
+            x: 0,
+            y: 0,
+            width: 3200,
+            height: 1080,
+        };
+        assert_eq!(Monitor::span(&[left, right]), Some(span));
+        assert_eq!(Monitor::span(&[left]), Some(left));
+        assert_eq!(Monitor::span(&[]), None);
+    }
+
+    #[test]
+    fn window_ids_parse_in_hexadecimal_or_decimal() {
+        assert_eq!(parse_window_id("0x1a00003"), Ok(0x1a0_0003));
+        assert_eq!(parse_window_id("27262979"), Ok(27_262_979));
+        assert!(parse_window_id("0xzz").is_err());
+        assert!(parse_window_id("window").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Encoding frames as Sixel graphics.
+//!
+//! Sixel images are drawn by terminals such as xterm, mlterm and WezTerm at full pixel
+//! resolution. An image is a run of horizontal bands six pixels high: for every color
+//! of the band, a character per column holds the six bits of the pixels of that color,
+//! and runs of the same character are compressed to a repeat count. Every frame gets its
+//! own palette of at most 256 colors from [`crate::export::gif::quantize`].
+use crate::export::gif::quantize;
+use std::fmt::Write;
+
+/// Height of a band in pixels
+const BAND_HEIGHT: usize = 6;
+
+/// Appends the escape sequence drawing a frame of packed ARGB pixels at the cursor.
+pub fn encode(output: &mut String, pixels: &[u32], width: usize, height: usize) {
+    let (palette, indices) = quantize(pixels);
+    // Introduce the image with square pixels, then define the palette in percents
+    let _ = write!(output, "\x1bP0;1q\"1;1;{width};{height}");
+    for (i, color) in palette.iter().enumerate() {
+        let [r, g, b] = color.map(|c| u32::from(c) * 100 / 255);
+        let _ = write!(output, "#{i};2;{r};{g};{b}");
+    }
+
+    let mut bits = vec![0u8; palette.len() * width];
+    let mut used = vec![false; palette.len()];
+    for top in (0..height).step_by(BAND_HEIGHT) {
+        // Gather the six bits of every color in every column of the band
+        bits.fill(0);
+        used.fill(false);
+        for row in top..(top + BAND_HEIGHT).min(height) {
+            for x in 0..width {
+                let index = usize::from(indices[row * width + x]);
+                bits[index * width + x] |= 1 << (row - top);
+                used[index] = true;
+            }
+        }
+        if top > 0 {
+            output.push('-');
+        }
+        let mut first = true;
+        for (index, columns) in bits.chunks(width).enumerate() {
+            if !used[index] {
+                continue;
+            }
+            // Go back to the start of the band to draw the next color over it
+            if !first {
+                output.push('$');
+            }
+            first = false;
+            let _ = write!(output, "#{index}");
+            sixels(output, columns);
+        }
+    }
+    output.push_str("\x1b\\");
+}
+
+/// Appends the characters of a row of sixels, compressing runs of the same sixel.
+fn sixels(output: &mut String, columns: &[u8]) {
+    let mut x = 0;
+    while x < columns.len() {
+        let run = columns[x..]
+            .iter()
+            .take_while(|&&c| c == columns[x])
+            .count();
+        let sixel = char::from(b'?' + columns[x]);
+        if run > 3 {
+            let _ = write!(output, "!{run}{sixel}");
+        } else {
+            output.extend(std::iter::repeat_n(sixel, run));
+        }
+        x += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_are_compressed() {
+        let mut output = String::new();
+        sixels(&mut output, &[0, 0, 63, 63, 63, 63, 63, 1]);
+        assert_eq!(output, "??!5~@");
+    }
+
+    #[test]
+    fn every_color_of_a_band_is_drawn_over_it() {
+        // A white pixel above a black one, in a single band
+        let mut output = String::new();
+        encode(&mut output, &[0xffff_ffff, 0xff00_0000], 1, 2);
+        assert!(output.starts_with("\x1bP0;1q\"1;1;1;2"));
+        assert!(output.ends_with("\x1b\\"));
+        assert_eq!(output.matches('$').count(), 1);
+        assert!(!output.contains('-'));
+    }
+
+    #[test]
+    fn bands_are_six_pixels_high() {
+        let mut output = String::new();
+        encode(&mut output, &[0xff00_0000; 4 * 13], 4, 13);
+        // Three bands, the last one only one pixel high
+        assert_eq!(output.matches('-').count(), 2);
+        assert!(output.contains("!4@\x1b\\"));
+    }
+}
@@ -0,0 +1,386 @@
+//! Rendering into a terminal.
+//!
+//! With [`Graphics::HalfBlocks`] every character cell shows two pixels stacked
+//! vertically: the upper half block `▀` is drawn in the 24-bit ANSI color of the top
+//! pixel over a background in the color of the bottom one. Frames are averaged down to
+//! twice as many pixel rows as the terminal has lines, and colors are only sent when they
+//! change from the previous cell. With [`Graphics::Sixel`] frames are drawn as images by
+//! [`super::sixel`] instead, shrunk to fit the terminal when it reports its size in
+//! pixels, and with [`Graphics::Braille`] they are dithered to the dots of
+//! [`super::braille`] characters.
+//!
+//! The terminal is switched to its alternate screen and to raw mode with crossterm, so
+//! keys are read as soon as they are pressed, and restored when the [`Keyboard`] is
+//! dropped. Key presses are read as crossterm events, which works the same on Unix and
+//! Windows.
+use super::{braille, downsample, sixel, Backend, Event};
+use crossterm::cursor::{Hide, Show};
+use crossterm::event::{self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::style::ResetColor;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use minifb::Key;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Most frames drawn per second, since terminals redraw slowly
+const FRAME_RATE: f32 = 30.0;
+
+/// How frames are drawn in the terminal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Graphics {
+    /// Two pixels per character with half blocks in 24-bit color
+    HalfBlocks,
+    /// Sixel images at the resolution of the terminal
+    Sixel,
+    /// Eight monochrome dots per character with Braille patterns
+    Braille,
+}
+
+/// The terminal attached to the standard input and output, in raw mode while it lives
+pub struct Terminal {
+    /// How frames are drawn
+    graphics: Graphics,
+    /// Width of the frames the effects render in pixels
+    width: usize,
+    /// Height of the frames the effects render in pixels
+    height: usize,
+    /// Time the last frame was drawn
+    last_frame_time: Instant,
+    /// Keys typed in the terminal
+    keyboard: Keyboard,
+    /// Scratch buffer holding the escape sequences of a frame
+    output: String,
+}
+
+impl Terminal {
+    /// Switches the terminal to raw mode and to its alternate screen, ready to draw
+    /// frames of `width * height` pixels with `graphics`.
+    ///
+    /// Frames keep that size whatever the size of the terminal, which they are shrunk to
+    /// when drawn.
+    ///
+    /// # Errors
+    /// Fails if the standard input is not a terminal.
+    pub fn new(graphics: Graphics, width: usize, height: usize) -> io::Result<Self> {
+        Ok(Self {
+            graphics,
+            width,
+            height,
+            last_frame_time: Instant::now(),
+            keyboard: Keyboard::new()?,
+            output: String::new(),
+        })
+    }
+
+    /// Draws a frame of packed ARGB pixels over the whole terminal.
+    fn draw(&mut self, pixels: &[u32], width: usize, height: usize) -> io::Result<()> {
+        let (columns, lines) = terminal::size()?;
+        let (columns, lines) = (usize::from(columns), usize::from(lines));
+        self.output.clear();
+        match self.graphics {
+            Graphics::HalfBlocks => {
+                let cells = downsample(pixels, width, height, columns, lines * 2);
+                half_blocks(&mut self.output, &cells, columns, lines);
+            }
+            Graphics::Braille => {
+                let dots = downsample(pixels, width, height, columns * 2, lines * 4);
+                braille::encode(&mut self.output, &dots, columns, lines);
+            }
+            Graphics::Sixel => {
+                // Terminals that do not report their size in pixels get the whole frame
+                let (x_pixels, y_pixels) = terminal::window_size().map_or((0, 0), |size| {
+                    (usize::from(size.width), usize::from(size.height))
+                });
+                self.output.push_str("\x1b[H");
+                if x_pixels > 0 && y_pixels > 0 && lines > 0 {
+                    // Leave the last line free so the image never scrolls the screen
+                    let (fit_width, fit_height) = (x_pixels, y_pixels - y_pixels / lines);
+                    let scale = (fit_width as f32 / width as f32)
+                        .min(fit_height as f32 / height as f32)
+                        .min(1.0);
+                    let target_width = ((width as f32 * scale) as usize).max(1);
+                    let target_height = ((height as f32 * scale) as usize).max(1);
+                    let image = downsample(pixels, width, height, target_width, target_height);
+                    sixel::encode(&mut self.output, &image, target_width, target_height);
+                } else {
+                    sixel::encode(&mut self.output, pixels, width, height);
+                }
+            }
+        }
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(self.output.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+impl Backend for Terminal {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let frame_time = Duration::from_secs_f32(1.0 / FRAME_RATE);
+        std::thread::sleep(frame_time.saturating_sub(self.last_frame_time.elapsed()));
+        self.last_frame_time = Instant::now();
+        Ok(self.draw(pixels, width, height)?)
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        // A keyboard that cannot be read has nothing to report
+        self.keyboard.read_events().unwrap_or_default()
+    }
+}
+
+/// The keyboard of the terminal attached to the standard input, in raw mode while it
+/// lives
+///
+/// The screen is switched to the alternate screen with the cursor hidden at the same
+/// time, so nothing typed or printed shows up over the frames.
+pub struct Keyboard(());
+
+impl Keyboard {
+    /// Switches the terminal to raw mode and to its alternate screen.
+    ///
+    /// # Errors
+    /// Fails if the standard input is not a terminal.
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        // Built before entering the alternate screen so the terminal is restored on error
+        let keyboard = Self(());
+        execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            Hide,
+            Clear(ClearType::All)
+        )?;
+        Ok(keyboard)
+    }
+
+    /// Returns the keys pressed since the last call, oldest first.
+    pub fn read_events(&mut self) -> io::Result<Vec<Event>> {
+        let mut keys = Vec::new();
+        while event::poll(Duration::ZERO)? {
+            if let event::Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Release {
+                    keys.extend(key_event(key));
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
+
+impl Drop for Keyboard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Appends the escape sequences drawing `lines` lines of half blocks, taking the pixels
+/// of every line from two rows of `cells`.
+fn half_blocks(output: &mut String, cells: &[u32], columns: usize, lines: usize) {
+    output.push_str("\x1b[H");
+    let (mut foreground, mut background) = (None, None);
+    for line in 0..lines {
+        if line > 0 {
+            output.push_str("\r\n");
+        }
+        for column in 0..columns {
+            let top = cells[2 * line * columns + column] & 0xff_ffff;
+            let bottom = cells[(2 * line + 1) * columns + column] & 0xff_ffff;
+            if foreground != Some(top) {
+                let [_, r, g, b] = top.to_be_bytes();
+                let _ = write!(output, "\x1b[38;2;{r};{g};{b}m");
+                foreground = Some(top);
+            }
+            if background != Some(bottom) {
+                let [_, r, g, b] = bottom.to_be_bytes();
+                let _ = write!(output, "\x1b[48;2;{r};{g};{b}m");
+                background = Some(bottom);
+            }
+            output.push('▀');
+        }
+    }
+}
+
+/// Returns the key of a key press, or `None` for keys the effects have no use for.
+fn key_event(event: KeyEvent) -> Option<Event> {
+    let shift = event.modifiers.contains(KeyModifiers::SHIFT);
+    let plain = |key| Some(Event::Key { key, shift: false });
+    let shifted = |key| Some(Event::Key { key, shift: true });
+    if event.modifiers.contains(KeyModifiers::CONTROL) {
+        // Raw mode lets Ctrl+C through as a key, which quits like Escape
+        return match event.code {
+            KeyCode::Char('c') => plain(Key::Escape),
+            _ => None,
+        };
+    }
+    match event.code {
+        KeyCode::Up => plain(Key::Up),
+        KeyCode::Down => plain(Key::Down),
+        KeyCode::Right => plain(Key::Right),
+        KeyCode::Left => plain(Key::Left),
+        KeyCode::Home => plain(Key::Home),
+        KeyCode::Tab => Some(Event::Key {
+            key: Key::Tab,
+            shift,
+        }),
+        KeyCode::BackTab => shifted(Key::Tab),
+        KeyCode::Enter => plain(Key::Enter),
+        KeyCode::Backspace => plain(Key::Backspace),
+        KeyCode::Esc => plain(Key::Escape),
+        KeyCode::F(number @ 1..=8) => Some(Event::Key {
+            key: FUNCTIONS[usize::from(number - 1)],
+            shift,
+        }),
+        KeyCode::Char(character) => match character {
+            ' ' => plain(Key::Space),
+            '-' => plain(Key::Minus),
+            '=' => plain(Key::Equal),
+            '+' => shifted(Key::Equal),
+            '.' => plain(Key::Period),
+            ',' => plain(Key::Comma),
+            '[' => plain(Key::LeftBracket),
+            ']' => plain(Key::RightBracket),
+            '0'..='9' => plain(DIGITS[character as usize - '0' as usize]),
+            'a'..='z' => plain(LETTERS[character as usize - 'a' as usize]),
+            'A'..='Z' => shifted(LETTERS[character as usize - 'A' as usize]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Keys of the function keys, from F1 to F8
+const FUNCTIONS: [Key; 8] = [
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+];
+
+/// Keys of the digits, in order
+const DIGITS: [Key; 10] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+
+/// Keys of the letters, in order
+const LETTERS: [Key; 26] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> Option<Event> {
+        key_event(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn half_blocks_stack_two_pixels_per_cell() {
+        let mut output = String::new();
+        half_blocks(&mut output, &[0xff01_0203, 0xff04_0506], 1, 1);
+        assert_eq!(output, "\x1b[H\x1b[38;2;1;2;3m\x1b[48;2;4;5;6m▀");
+    }
+
+    #[test]
+    fn colors_are_only_sent_when_they_change() {
+        let mut output = String::new();
+        half_blocks(&mut output, &[0xff00_0000; 4], 2, 1);
+        assert_eq!(output.matches("\x1b[38").count(), 1);
+        assert_eq!(output.matches('▀').count(), 2);
+    }
+
+    #[test]
+    fn key_presses_are_mapped_to_keys() {
+        let none = KeyModifiers::NONE;
+        let shift = KeyModifiers::SHIFT;
+        assert_eq!(
+            [
+                press(KeyCode::Char('q'), none),
+                press(KeyCode::Up, none),
+                press(KeyCode::BackTab, shift),
+                press(KeyCode::Char('S'), shift),
+                press(KeyCode::F(1), none),
+                press(KeyCode::F(6), shift),
+                press(KeyCode::Char('+'), shift),
+                press(KeyCode::Char(']'), none),
+                press(KeyCode::Home, none),
+                press(KeyCode::Char('7'), none),
+            ],
+            [
+                (Key::Q, false),
+                (Key::Up, false),
+                (Key::Tab, true),
+                (Key::S, true),
+                (Key::F1, false),
+                (Key::F6, true),
+                (Key::Equal, true),
+                (Key::RightBracket, false),
+                (Key::Home, false),
+                (Key::Key7, false),
+            ]
+            .map(|(key, shift)| Some(Event::Key { key, shift }))
+        );
+    }
+
+    #[test]
+    fn ctrl_c_quits_like_escape() {
+        let escape = Some(Event::Key {
+            key: Key::Escape,
+            shift: false,
+        });
+        assert_eq!(press(KeyCode::Esc, KeyModifiers::NONE), escape);
+        assert_eq!(press(KeyCode::Char('c'), KeyModifiers::CONTROL), escape);
+        assert_eq!(press(KeyCode::Char('a'), KeyModifiers::CONTROL), None);
+        assert_eq!(press(KeyCode::F(9), KeyModifiers::NONE), None);
+    }
+}
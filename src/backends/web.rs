@@ -0,0 +1,411 @@
+//! Rendering into an HTML canvas from WebAssembly.
+//!
+//! Built for `wasm32-unknown-unknown` with the `web` feature, the library exports a
+//! [`WebDemo`] to JavaScript through wasm-bindgen. Browsers never let a page block, so
+//! instead of running the main loop of the binary, the page calls [`WebDemo::frame`]
+//! from `requestAnimationFrame`. The frames are drawn into the canvas at its size in
+//! pixels by a [`CanvasBackend`], which also gathers the keys pressed while the canvas
+//! has the focus and the pointer moving over it. Keys drive the controls of the running
+//! effect as in the window, while Tab switches to the next effect, Shift+Tab to the
+//! previous one, and P pauses the animation.
+use super::{Backend, Event};
+use crate::common::{DemoEffect, InputEvent};
+use crate::effects::{self, registry::EffectRegistry, Settings};
+use crate::timing::{Clock, FixedTimestep};
+use minifb::Key;
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData, KeyboardEvent, MouseEvent};
+
+/// Updates per second of the simulated effects
+const TICK_RATE: f32 = 60.0;
+
+/// Events of the canvas gathered as input
+const LISTENED: [&str; 5] = ["keydown", "mousemove", "mousedown", "mouseup", "mouseleave"];
+
+/// Input gathered by the listeners of the canvas
+#[derive(Debug, Default)]
+struct Input {
+    /// Keys pressed since the last poll, oldest first
+    events: Vec<Event>,
+    /// Pointer position in CSS pixels while it is over the canvas
+    pointer: Option<(f32, f32)>,
+    /// Whether the main mouse button is held down
+    pressed: bool,
+}
+
+impl Input {
+    /// Records a browser event received by the canvas.
+    fn handle(&mut self, event: &web_sys::Event) {
+        if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+            if let Some(key) = key(&event.code()) {
+                // Keeps Tab, Space and the arrows from moving the focus or the page
+                event.prevent_default();
+                let shift = event.shift_key();
+                self.events.push(Event::Key { key, shift });
+            }
+        } else if let Some(event) = event.dyn_ref::<MouseEvent>() {
+            match event.type_().as_str() {
+                "mouseleave" => self.pointer = None,
+                kind => {
+                    self.pointer = Some((event.offset_x() as f32, event.offset_y() as f32));
+                    if event.button() == 0 && kind != "mousemove" {
+                        self.pressed = kind == "mousedown";
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Listener of an event of the canvas, removed along with the backend
+type Listener = Closure<dyn FnMut(web_sys::Event)>;
+
+/// An HTML canvas drawn with its 2D context
+pub struct CanvasBackend {
+    /// The canvas
+    canvas: HtmlCanvasElement,
+    /// Context drawing into the canvas
+    context: CanvasRenderingContext2d,
+    /// Input gathered by the listeners
+    input: Rc<RefCell<Input>>,
+    /// Listeners of the canvas, called by the browser as long as they live
+    listeners: Vec<(&'static str, Listener)>,
+    /// Red, green, blue and alpha bytes of the frame shown
+    rgba: Vec<u8>,
+}
+
+impl CanvasBackend {
+    /// Draws into `canvas`, listening to its keyboard and mouse events, and gives it the
+    /// focus so keys reach it right away.
+    ///
+    /// # Errors
+    /// Fails if the canvas has no 2D context, or does not accept the listeners.
+    pub fn new(canvas: HtmlCanvasElement) -> Result<Self, Box<dyn Error>> {
+        let context = canvas
+            .get_context("2d")
+            .map_err(js_error)?
+            .ok_or("the canvas has no 2D context")?
+            .dyn_into::<CanvasRenderingContext2d>()
+            .map_err(|_| "the canvas has no 2D context")?;
+        let input = Rc::new(RefCell::new(Input::default()));
+        let mut listeners = Vec::new();
+        for name in LISTENED {
+            let input = Rc::clone(&input);
+            let listener: Listener =
+                Closure::new(move |event: web_sys::Event| input.borrow_mut().handle(&event));
+            canvas
+                .add_event_listener_with_callback(name, listener.as_ref().unchecked_ref())
+                .map_err(js_error)?;
+            listeners.push((name, listener));
+        }
+        // Canvases only receive keys once they can take the focus
+        canvas.set_tab_index(0);
+        canvas.focus().map_err(js_error)?;
+        Ok(Self {
+            canvas,
+            context,
+            input,
+            listeners,
+            rgba: Vec::new(),
+        })
+    }
+}
+
+impl Drop for CanvasBackend {
+    fn drop(&mut self) {
+        for (name, listener) in &self.listeners {
+            let callback = listener.as_ref().unchecked_ref();
+            // The canvas outlives the backend, and must stop calling the listeners
+            let _ = self
+                .canvas
+                .remove_event_listener_with_callback(name, callback);
+        }
+    }
+}
+
+impl Backend for CanvasBackend {
+    /// Returns the size of the canvas in pixels, set by its `width` and `height`.
+    fn size(&self) -> (usize, usize) {
+        (self.canvas.width() as usize, self.canvas.height() as usize)
+    }
+
+    /// Shows a frame in the top left corner of the canvas, which it fills unless the
+    /// canvas was resized since the frame was rendered.
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        self.rgba.clear();
+        self.rgba.extend(pixels.iter().flat_map(|&pixel| {
+            let [_, r, g, b] = pixel.to_be_bytes();
+            [r, g, b, 255]
+        }));
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.rgba),
+            width as u32,
+            height as u32,
+        )
+        .map_err(js_error)?;
+        self.context
+            .put_image_data(&image, 0.0, 0.0)
+            .map_err(js_error)?;
+        Ok(())
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        let mut input = self.input.borrow_mut();
+        let mut events = std::mem::take(&mut input.events);
+        if let Some((x, y)) = input.pointer {
+            // The canvas may be stretched by the page, so CSS pixels are mapped onto its
+            // own pixels
+            let (width, height) = self.size();
+            let scale_x = width as f32 / self.canvas.client_width().max(1) as f32;
+            let scale_y = height as f32 / self.canvas.client_height().max(1) as f32;
+            events.push(Event::Mouse {
+                x: x * scale_x,
+                y: y * scale_y,
+                pressed: input.pressed,
+            });
+        }
+        events
+    }
+}
+
+/// Demo effects running in an HTML canvas, driven by the page
+#[wasm_bindgen]
+pub struct WebDemo {
+    /// The canvas shown in
+    backend: CanvasBackend,
+    /// Every effect, built with the default settings
+    registry: EffectRegistry,
+    /// Names of the effects in the order Tab cycles through them
+    names: Vec<String>,
+    /// Position of the running effect in `names`
+    current: usize,
+    /// The running effect
+    effect: Box<dyn DemoEffect>,
+    /// Pixels of the frame, at the size of the canvas
+    buffer: Vec<u32>,
+    /// Width and height of the frame
+    size: (usize, usize),
+    /// Animation time, paused with P
+    clock: Clock,
+    /// Splits the elapsed time into updates of the effect
+    timestep: FixedTimestep,
+    /// Timestamp of the previous frame in milliseconds, if any
+    last_frame: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl WebDemo {
+    /// Starts the effect `name` in `canvas`, at the size of the canvas.
+    ///
+    /// # Errors
+    /// Fails if there is no such effect, or if the canvas cannot be drawn into.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, name: &str) -> Result<WebDemo, JsError> {
+        let backend = CanvasBackend::new(canvas).map_err(rust_error)?;
+        let registry = effects::registry(&Settings::default());
+        let names: Vec<String> = registry.names().map(String::from).collect();
+        let current = names
+            .iter()
+            .position(|n| n == name)
+            .ok_or_else(|| JsError::new(&format!("unknown effect '{name}'")))?;
+        let size = backend.size();
+        let effect = registry.create(name, size.0, size.1).map_err(rust_error)?;
+        Ok(Self {
+            backend,
+            registry,
+            names,
+            current,
+            effect,
+            buffer: vec![0; size.0 * size.1],
+            size,
+            clock: Clock::new(),
+            timestep: FixedTimestep::new(TICK_RATE),
+            last_frame: None,
+        })
+    }
+
+    /// Returns the name of the running effect.
+    pub fn effect(&self) -> String {
+        self.names[self.current].clone()
+    }
+
+    /// Handles the input received since the previous frame, advances the effect to
+    /// `timestamp`, in milliseconds as passed by `requestAnimationFrame`, and draws it.
+    ///
+    /// # Errors
+    /// Fails if the frame cannot be drawn into the canvas.
+    pub fn frame(&mut self, timestamp: f64) -> Result<(), JsError> {
+        let size = self.backend.size();
+        if size != self.size {
+            self.size = size;
+            self.buffer = vec![0; size.0 * size.1];
+            self.effect.resize(size.0, size.1);
+        }
+        for event in self.backend.poll_events() {
+            match event {
+                Event::Key {
+                    key: Key::Tab,
+                    shift,
+                } => self.switch_effect(shift),
+                Event::Key { key: Key::P, .. } => self.clock.toggle_pause(),
+                Event::Key { key, shift: true } => {
+                    self.effect.handle_input(InputEvent::ShiftKey(key));
+                }
+                Event::Key { key, shift: false } => self.effect.handle_input(InputEvent::Key(key)),
+                Event::Mouse { x, y, pressed } => {
+                    self.effect
+                        .handle_input(InputEvent::Mouse { x, y, pressed });
+                }
+            }
+        }
+
+        let elapsed = self
+            .last_frame
+            .map_or(0.0, |last| (timestamp - last) / 1000.0);
+        self.last_frame = Some(timestamp);
+        let dt = self.clock.advance(elapsed as f32);
+        for _ in 0..self.timestep.advance(dt) {
+            self.effect.update(self.timestep.step());
+        }
+        self.effect.draw(&mut self.buffer, self.clock.time());
+        self.backend
+            .present(&self.buffer, self.size.0, self.size.1)
+            .map_err(rust_error)
+    }
+}
+
+impl WebDemo {
+    /// Switches to the next effect, or the previous one if `backward`, skipping the
+    /// effects failing to build.
+    fn switch_effect(&mut self, backward: bool) {
+        let count = self.names.len();
+        let step = if backward { count - 1 } else { 1 };
+        let mut next = self.current;
+        for _ in 1..count {
+            next = (next + step) % count;
+            if let Ok(effect) = self
+                .registry
+                .create(&self.names[next], self.size.0, self.size.1)
+            {
+                (self.current, self.effect) = (next, effect);
+                return;
+            }
+        }
+    }
+}
+
+/// Turns a JavaScript exception into an error.
+fn js_error(value: JsValue) -> Box<dyn Error> {
+    match value.as_string() {
+        Some(message) => message.into(),
+        None => format!("{value:?}").into(),
+    }
+}
+
+/// Turns an error into a JavaScript exception.
+fn rust_error(e: Box<dyn Error>) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// Returns the key reported for the `code` of a browser keyboard event, naming a key by
+/// its position on the keyboard, if the controls use it.
+///
+/// Shift is left out since it only modifies other keys.
+fn key(code: &str) -> Option<Key> {
+    Some(match code {
+        "KeyA" => Key::A,
+        "KeyB" => Key::B,
+        "KeyC" => Key::C,
+        "KeyD" => Key::D,
+        "KeyE" => Key::E,
+        "KeyF" => Key::F,
+        "KeyG" => Key::G,
+        "KeyH" => Key::H,
+        "KeyI" => Key::I,
+        "KeyJ" => Key::J,
+        "KeyK" => Key::K,
+        "KeyL" => Key::L,
+        "KeyM" => Key::M,
+        "KeyN" => Key::N,
+        "KeyO" => Key::O,
+        "KeyP" => Key::P,
+        "KeyQ" => Key::Q,
+        "KeyR" => Key::R,
+        "KeyS" => Key::S,
+        "KeyT" => Key::T,
+        "KeyU" => Key::U,
+        "KeyV" => Key::V,
+        "KeyW" => Key::W,
+        "KeyX" => Key::X,
+        "KeyY" => Key::Y,
+        "KeyZ" => Key::Z,
+        "Digit0" => Key::Key0,
+        "Digit1" => Key::Key1,
+        "Digit2" => Key::Key2,
+        "Digit3" => Key::Key3,
+        "Digit4" => Key::Key4,
+        "Digit5" => Key::Key5,
+        "Digit6" => Key::Key6,
+        "Digit7" => Key::Key7,
+        "Digit8" => Key::Key8,
+        "Digit9" => Key::Key9,
+        "F1" => Key::F1,
+        "F2" => Key::F2,
+        "F3" => Key::F3,
+        "F4" => Key::F4,
+        "F5" => Key::F5,
+        "F6" => Key::F6,
+        "F7" => Key::F7,
+        "F8" => Key::F8,
+        "F9" => Key::F9,
+        "F10" => Key::F10,
+        "F11" => Key::F11,
+        "F12" => Key::F12,
+        "ArrowUp" => Key::Up,
+        "ArrowDown" => Key::Down,
+        "ArrowLeft" => Key::Left,
+        "ArrowRight" => Key::Right,
+        "Space" => Key::Space,
+        "Tab" => Key::Tab,
+        "Enter" => Key::Enter,
+        "Escape" => Key::Escape,
+        "Backspace" => Key::Backspace,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "Minus" => Key::Minus,
+        "Equal" => Key::Equal,
+        "Comma" => Key::Comma,
+        "Period" => Key::Period,
+        "BracketLeft" => Key::LeftBracket,
+        "BracketRight" => Key::RightBracket,
+        "NumpadAdd" => Key::NumPadPlus,
+        "NumpadSubtract" => Key::NumPadMinus,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_codes_map_onto_the_controls() {
+        assert_eq!(key("KeyQ"), Some(Key::Q));
+        assert_eq!(key("Digit3"), Some(Key::Key3));
+        assert_eq!(key("BracketRight"), Some(Key::RightBracket));
+        assert_eq!(key("NumpadAdd"), Some(Key::NumPadPlus));
+        assert_eq!(key("ShiftLeft"), None);
+    }
+}
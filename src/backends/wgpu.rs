@@ -0,0 +1,658 @@
+//! Rendering into a desktop window on the GPU with wgpu.
+//!
+//! Effects having a fragment shader, see [`DemoEffect::shader_source`], are drawn by the
+//! GPU, which keeps smooth patterns fluid on large HiDPI screens at high refresh rates.
+//! The other effects are rendered on the CPU as usual, at the physical size of the window
+//! like the [`super::winit`] backend, and their frames are uploaded into a texture the
+//! GPU stretches over the window.
+//!
+//! The shader code of an effect only defines its `shade` function. It is compiled along
+//! with a prelude declaring the uniforms shared by every effect, see [`ShaderSource`],
+//! once for every effect shown, and reused as long as it stays the same.
+//!
+//! [`DemoEffect::shader_source`]: crate::common::DemoEffect::shader_source
+use super::winit::WinitWindow;
+use super::{Backend, Event};
+use crate::common::{ShaderSource, SHADER_COLORS, SHADER_PARAMS};
+use std::error::Error;
+use std::sync::Arc;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferUsages,
+    Color, CommandEncoderDescriptor, CurrentSurfaceTexture, Device, ErrorFilter, Extent3d,
+    FilterMode, FragmentState, LoadOp, MultisampleState, Operations, PipelineLayout,
+    PipelineLayoutDescriptor, PresentMode, PrimitiveState, Queue, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerDescriptor,
+    ShaderModuleDescriptor, ShaderSource as Wgsl, ShaderStages, StoreOp, Surface,
+    SurfaceConfiguration, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
+    VertexState,
+};
+
+/// Uniforms and helpers prepended to the code of every effect
+const PRELUDE: &str = r#"
+struct Frame {
+    // Size of the frame in the pixels the effect renders at
+    resolution: vec2<f32>,
+    // Size of the window in physical pixels
+    surface: vec2<f32>,
+    // Animation time in seconds
+    time: f32,
+    params: array<vec4<f32>, 8>,
+}
+
+@group(0) @binding(0) var<uniform> frame: Frame;
+@group(0) @binding(1) var<uniform> palette: array<vec4<f32>, 256>;
+
+const PI: f32 = 3.14159265;
+const TAU: f32 = 6.28318531;
+
+// Returns the parameter `i` passed along with the code.
+fn param(i: u32) -> f32 {
+    return frame.params[i / 4u][i % 4u];
+}
+
+// Returns the color `v` of the way from the start of the palette to its end.
+fn palette_color(v: f32) -> vec3<f32> {
+    let x = clamp(v, 0.0, 1.0) * 255.0;
+    let i = u32(x);
+    return mix(palette[i].rgb, palette[min(i + 1u, 255u)].rgb, x - f32(i));
+}
+
+// Covers the window with a single triangle.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // Centers of the physical pixels, among the pixels of the frame
+    let pixel = position.xy / frame.surface * frame.resolution - 0.5;
+    return vec4<f32>(shade(pixel), 1.0);
+}
+"#;
+
+/// Shader stretching a frame rendered on the CPU over the window
+const BLIT: &str = r#"
+@group(0) @binding(0) var pixels: texture_2d<f32>;
+@group(0) @binding(1) var pixel_sampler: sampler;
+
+struct Corner {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> Corner {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return Corner(vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0), vec2<f32>(corner.x, 1.0 - corner.y));
+}
+
+@fragment
+fn fs_main(corner: Corner) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSample(pixels, pixel_sampler, corner.uv).rgb, 1.0);
+}
+"#;
+
+/// A resizable window drawn with wgpu
+pub struct WgpuBackend {
+    /// The window and its events
+    window: WinitWindow,
+    /// Surface of the window the frames are drawn into
+    surface: Surface<'static>,
+    /// How the surface is set up, resized along with the window
+    config: SurfaceConfiguration,
+    /// Pipelines and buffers drawing the frames
+    renderer: Renderer,
+}
+
+impl WgpuBackend {
+    /// Opens a window of `width * height` logical pixels, drawn by the first GPU able to.
+    ///
+    /// # Errors
+    /// Fails if the window cannot be opened, or if no GPU can draw into it.
+    pub fn new(title: &str, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        let window = WinitWindow::new(title, width, height)?;
+        let display = Box::new(window.display_handle());
+        let instance = wgpu::Instance::new(
+            wgpu::InstanceDescriptor::new_with_display_handle_from_env(display),
+        );
+        let surface = instance.create_surface(Arc::clone(window.window()))?;
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }))?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))?;
+
+        let size = window.window().inner_size();
+        let mut config = surface
+            .get_default_config(&adapter, size.width.max(1), size.height.max(1))
+            .ok_or("the GPU cannot draw into the window")?;
+        // Frames hold sRGB colors already, so they are written into the surface as is
+        let format = config.format.remove_srgb_suffix();
+        config.view_formats = vec![format];
+        config.present_mode = PresentMode::AutoVsync;
+        surface.configure(&device, &config);
+
+        Ok(Self {
+            window,
+            surface,
+            config,
+            renderer: Renderer::new(device, queue, format),
+        })
+    }
+
+    /// Draws a frame with `pipeline` into the window, resizing the surface to the window
+    /// first if needed.
+    fn draw(&mut self, pipeline: Pipeline) -> Result<(), Box<dyn Error>> {
+        let size = self.window.window().inner_size();
+        if size.width == 0 || size.height == 0 {
+            // Minimized windows have nothing to draw into
+            return Ok(());
+        }
+        if (size.width, size.height) != (self.config.width, self.config.height) {
+            self.config.width = size.width;
+            self.config.height = size.height;
+            self.surface.configure(&self.renderer.device, &self.config);
+        }
+        let texture = match self.surface.get_current_texture() {
+            CurrentSurfaceTexture::Success(texture)
+            | CurrentSurfaceTexture::Suboptimal(texture) => texture,
+            CurrentSurfaceTexture::Timeout | CurrentSurfaceTexture::Occluded => return Ok(()),
+            CurrentSurfaceTexture::Outdated => {
+                // The next frame is drawn into the surface set up anew
+                self.surface.configure(&self.renderer.device, &self.config);
+                return Ok(());
+            }
+            _ => return Err("the window surface was lost".into()),
+        };
+        let view = texture.texture.create_view(&TextureViewDescriptor {
+            format: Some(self.renderer.format),
+            ..Default::default()
+        });
+        self.renderer.render(&view, pipeline);
+        self.window.window().pre_present_notify();
+        self.renderer.queue.present(texture);
+        Ok(())
+    }
+}
+
+impl Backend for WgpuBackend {
+    fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    /// Returns the size of the window in physical pixels.
+    fn size(&self) -> (usize, usize) {
+        self.window.size()
+    }
+
+    /// Shows a frame stretched over the window by the GPU.
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        self.renderer.upload(pixels, width, height);
+        self.draw(Pipeline::Blit)
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        self.window.poll_events()
+    }
+
+    fn disable_vsync(&mut self) {
+        self.config.present_mode = PresentMode::AutoNoVsync;
+        self.surface.configure(&self.renderer.device, &self.config);
+    }
+
+    fn draws_shaders(&self) -> bool {
+        true
+    }
+
+    /// Draws the frame at `time` with `shader` at the physical resolution of the window.
+    fn draw_shader(
+        &mut self,
+        shader: &ShaderSource,
+        width: usize,
+        height: usize,
+        time: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        let size = self.window.window().inner_size();
+        let surface = (size.width as f32, size.height as f32);
+        self.renderer
+            .prepare(shader, (width as f32, height as f32), surface, time)?;
+        self.draw(Pipeline::Effect)
+    }
+}
+
+/// Pipeline drawing a frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pipeline {
+    /// Stretches the latest frame uploaded from the CPU
+    Blit,
+    /// Runs the shader of the effect last prepared
+    Effect,
+}
+
+/// Frame rendered on the CPU, uploaded into a texture
+struct Upload {
+    /// Width and height of the frame in pixels
+    size: (usize, usize),
+    /// Texture holding the frame
+    texture: Texture,
+    /// Binds the texture to the blit shader
+    bind_group: BindGroup,
+}
+
+/// Compiled shader of an effect
+struct EffectPipeline {
+    /// WGSL code the pipeline was compiled from
+    code: &'static str,
+    /// The compiled pipeline
+    pipeline: RenderPipeline,
+}
+
+/// Draws frames with a GPU device, into any texture of the same format
+struct Renderer {
+    /// The GPU
+    device: Device,
+    /// Queue of the commands sent to the GPU
+    queue: Queue,
+    /// Format of the textures drawn into
+    format: TextureFormat,
+    /// Frame uniform of the shaders, see [`PRELUDE`]
+    frame: Buffer,
+    /// Palette uniform of the shaders
+    palette: Buffer,
+    /// Layout of the effect pipelines, binding both uniforms whether the code reads them
+    /// or not
+    layout: PipelineLayout,
+    /// Binds the uniforms to the effect pipelines
+    uniforms: BindGroup,
+    /// Pipeline stretching the frames rendered on the CPU
+    blit: RenderPipeline,
+    /// Sampler of the blit shader, repeating the pixels like nearest-neighbor upscaling
+    sampler: Sampler,
+    /// The latest frame rendered on the CPU
+    upload: Option<Upload>,
+    /// The latest shader compiled
+    effect: Option<EffectPipeline>,
+}
+
+impl Renderer {
+    fn new(device: Device, queue: Queue, format: TextureFormat) -> Self {
+        let frame = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("frame"),
+            contents: &frame_uniform(&[], (1.0, 1.0), (1.0, 1.0), 0.0),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let palette = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("palette"),
+            contents: &palette_uniform(&[]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let uniform = |binding: u32| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let uniforms_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("uniforms"),
+            entries: &[uniform(0), uniform(1)],
+        });
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("effect"),
+            bind_group_layouts: &[Some(&uniforms_layout)],
+            immediate_size: 0,
+        });
+        let uniforms = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("uniforms"),
+            layout: &uniforms_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: frame.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: palette.as_entire_binding(),
+                },
+            ],
+        });
+        let blit = pipeline(&device, None, format, "blit", BLIT);
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+        Self {
+            device,
+            queue,
+            format,
+            frame,
+            palette,
+            layout,
+            uniforms,
+            blit,
+            sampler,
+            upload: None,
+            effect: None,
+        }
+    }
+
+    /// Copies a frame of `width * height` packed ARGB pixels into the texture drawn by
+    /// the blit pipeline.
+    fn upload(&mut self, pixels: &[u32], width: usize, height: usize) {
+        let size = Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        };
+        if self.upload.as_ref().map(|upload| upload.size) != Some((width, height)) {
+            let texture = self.device.create_texture(&TextureDescriptor {
+                label: Some("pixels"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                // Packed ARGB pixels are laid out as blue, green, red and alpha bytes
+                format: TextureFormat::Bgra8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&TextureViewDescriptor::default());
+            let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+                label: Some("pixels"),
+                layout: &self.blit.get_bind_group_layout(0),
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&view),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            self.upload = Some(Upload {
+                size: (width, height),
+                texture,
+                bind_group,
+            });
+        }
+        let Some(upload) = &self.upload else {
+            return;
+        };
+        let bytes: Vec<u8> = pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_le_bytes())
+            .collect();
+        self.queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &upload.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &bytes,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(width as u32 * 4),
+                rows_per_image: Some(height as u32),
+            },
+            size,
+        );
+    }
+
+    /// Compiles the code of `shader` unless it is the one last compiled, and updates the
+    /// uniforms for a frame of `resolution` drawn over a `surface`, both in pixels.
+    ///
+    /// # Errors
+    /// Fails if the code does not compile.
+    fn prepare(
+        &mut self,
+        shader: &ShaderSource,
+        resolution: (f32, f32),
+        surface: (f32, f32),
+        time: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        let compiled = self.effect.as_ref().map(|effect| effect.code);
+        if compiled != Some(shader.code) {
+            self.effect = Some(self.compile(shader.code)?);
+        }
+        let frame = frame_uniform(&shader.params, resolution, surface, time);
+        self.queue.write_buffer(&self.frame, 0, &frame);
+        self.queue
+            .write_buffer(&self.palette, 0, &palette_uniform(&shader.palette));
+        Ok(())
+    }
+
+    /// Compiles the code of an effect along with the prelude.
+    ///
+    /// # Errors
+    /// Fails if the code does not compile, with the message of the compiler.
+    fn compile(&self, code: &'static str) -> Result<EffectPipeline, Box<dyn Error>> {
+        let scope = self.device.push_error_scope(ErrorFilter::Validation);
+        let source = format!("{PRELUDE}\n{code}");
+        let layout = Some(&self.layout);
+        let pipeline = pipeline(&self.device, layout, self.format, "effect", &source);
+        if let Some(e) = pollster::block_on(scope.pop()) {
+            return Err(format!("compiling the shader of the effect: {e}").into());
+        }
+        Ok(EffectPipeline { code, pipeline })
+    }
+
+    /// Draws a frame covering `target` with `pipeline`, if it has something to draw.
+    fn render(&self, target: &TextureView, pipeline: Pipeline) {
+        let (pipeline, bind_group) = match pipeline {
+            Pipeline::Blit => match &self.upload {
+                Some(upload) => (&self.blit, &upload.bind_group),
+                None => return,
+            },
+            Pipeline::Effect => match &self.effect {
+                Some(effect) => (&effect.pipeline, &self.uniforms),
+                None => return,
+            },
+        };
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("frame"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: target,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        self.queue.submit([encoder.finish()]);
+    }
+}
+
+/// Builds a pipeline covering textures of `format` with the `vs_main` and `fs_main`
+/// entry points of `source`, with the bindings of `layout`, or those `source` uses if
+/// there is none.
+fn pipeline(
+    device: &Device,
+    layout: Option<&PipelineLayout>,
+    format: TextureFormat,
+    label: &str,
+    source: &str,
+) -> RenderPipeline {
+    let module = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some(label),
+        source: Wgsl::Wgsl(source.into()),
+    });
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some(label),
+        layout,
+        vertex: VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        fragment: Some(FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(format.into())],
+        }),
+        multiview_mask: None,
+        cache: None,
+    })
+}
+
+/// Returns the bytes of the frame uniform declared by the prelude.
+fn frame_uniform(
+    params: &[f32],
+    resolution: (f32, f32),
+    surface: (f32, f32),
+    time: f32,
+) -> Vec<u8> {
+    let mut values = vec![
+        resolution.0,
+        resolution.1,
+        surface.0,
+        surface.1,
+        time,
+        0.0,
+        0.0,
+        0.0,
+    ];
+    values.extend((0..SHADER_PARAMS).map(|i| params.get(i).copied().unwrap_or(0.0)));
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Returns the bytes of the palette uniform declared by the prelude, one color of
+/// `palette` per entry, black beyond its end.
+fn palette_uniform(palette: &[u32]) -> Vec<u8> {
+    (0..SHADER_COLORS)
+        .flat_map(|i| {
+            let color = palette.get(i).copied().unwrap_or(0);
+            let channel = |shift: u32| ((color >> shift) & 0xff) as f32 / 255.0;
+            [channel(16), channel(8), channel(0), 1.0]
+        })
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::DemoEffect;
+    use crate::effects::plasma::{Plasma, Shape};
+    use crate::effects::{self, Settings};
+    use crate::palette::Palette;
+    use clap::ValueEnum;
+
+    /// Returns a renderer on wgpu's noop backend, which checks every shader and command
+    /// without a GPU.
+    fn noop_renderer() -> Renderer {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::NOOP,
+            backend_options: wgpu::BackendOptions {
+                noop: wgpu::NoopBackendOptions {
+                    enable: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..wgpu::InstanceDescriptor::new_without_display_handle()
+        });
+        let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+            .expect("the noop backend is enabled");
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&Default::default())).unwrap();
+        Renderer::new(device, queue, TextureFormat::Bgra8Unorm)
+    }
+
+    #[test]
+    fn every_effect_shader_compiles() {
+        let mut renderer = noop_renderer();
+        let settings = Settings::default();
+        let mut shaders = Vec::new();
+        for builtin in effects::EFFECTS {
+            let effect = (builtin.constructor)(64, 48, &settings).unwrap();
+            shaders.extend(effect.shader_source(1.0));
+        }
+        for shape in Shape::value_variants() {
+            let plasma = Plasma::new(64, 48, shape.clone(), Palette::Rainbow, 10.0);
+            shaders.extend(plasma.shader_source(1.0));
+        }
+        assert!(shaders.len() > 3);
+        for shader in &shaders {
+            assert!(shader.params.len() <= SHADER_PARAMS);
+            assert!(shader.palette.len() <= SHADER_COLORS);
+            renderer
+                .prepare(shader, (64.0, 48.0), (128.0, 96.0), 1.0)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn uploaded_frames_are_drawn_into_textures() {
+        let mut renderer = noop_renderer();
+        let target = renderer.device.create_texture(&TextureDescriptor {
+            label: None,
+            size: Extent3d {
+                width: 8,
+                height: 6,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: renderer.format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = target.create_view(&TextureViewDescriptor::default());
+        let scope = renderer.device.push_error_scope(ErrorFilter::Validation);
+        renderer.upload(&[0xff00ff00; 4 * 3], 4, 3);
+        renderer.render(&view, Pipeline::Blit);
+        assert!(pollster::block_on(scope.pop()).is_none());
+    }
+
+    #[test]
+    fn uniforms_follow_the_layout_of_the_prelude() {
+        let frame = frame_uniform(&[7.0], (320.0, 240.0), (640.0, 480.0), 2.5);
+        // The parameters start on the first 16 byte boundary after the time
+        assert_eq!(frame.len(), (8 + SHADER_PARAMS) * 4);
+        assert_eq!(frame[16..20], 2.5f32.to_le_bytes());
+        assert_eq!(frame[32..36], 7.0f32.to_le_bytes());
+        let palette = palette_uniform(&[0xff_ff_80_00]);
+        assert_eq!(palette.len(), SHADER_COLORS * 16);
+        assert_eq!(palette[0..4], 1.0f32.to_le_bytes());
+        assert_eq!(palette[4..8], (128.0f32 / 255.0).to_le_bytes());
+        assert_eq!(palette[8..12], 0.0f32.to_le_bytes());
+    }
+}
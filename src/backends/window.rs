@@ -0,0 +1,103 @@
+//! Rendering into a desktop window with minifb.
+use super::{Backend, Event, Monitor};
+use minifb::{Key, MouseButton, MouseMode, ScaleMode, Window, WindowOptions};
+use std::error::Error;
+use std::time::Instant;
+
+/// Minimum time in seconds between key presses
+///
+/// Oddly, the minifb functions `set_key_repeat()` and `set_key_delay()` don't work as
+/// expected so we resorted to manual key delay handling.
+const KEY_DELAY: f32 = 0.15;
+
+/// A resizable window showing the frames at their own resolution, or a borderless window
+/// covering whole monitors
+pub struct WindowBackend {
+    /// The minifb window
+    window: Window,
+    /// Size frames are rendered at when it differs from the window, which scales them
+    resolution: Option<(usize, usize)>,
+    /// Time the last key press was reported
+    last_key_time: Instant,
+}
+
+impl WindowBackend {
+    /// Opens a window of `width * height` pixels.
+    pub fn new(title: &str, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        let options = WindowOptions {
+            resize: true,
+            ..WindowOptions::default()
+        };
+        Ok(Self {
+            window: Window::new(title, width, height, options)?,
+            resolution: None,
+            last_key_time: Instant::now(),
+        })
+    }
+
+    /// Opens a borderless window covering `monitor`, showing frames rendered at the native
+    /// resolution of the monitor, or at `resolution` scaled to fit it with black bars
+    /// keeping their aspect ratio.
+    pub fn fullscreen(
+        title: &str,
+        monitor: Monitor,
+        resolution: Option<(usize, usize)>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let options = WindowOptions {
+            borderless: true,
+            topmost: true,
+            scale_mode: ScaleMode::AspectRatioStretch,
+            ..WindowOptions::default()
+        };
+        let mut window = Window::new(title, monitor.width, monitor.height, options)?;
+        window.set_position(monitor.x as isize, monitor.y as isize);
+        Ok(Self {
+            window,
+            resolution,
+            last_key_time: Instant::now(),
+        })
+    }
+}
+
+impl Backend for WindowBackend {
+    fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    fn size(&self) -> (usize, usize) {
+        self.resolution.unwrap_or_else(|| self.window.get_size())
+    }
+
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(self.window.update_with_buffer(pixels, width, height)?)
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        if self.last_key_time.elapsed().as_secs_f32() >= KEY_DELAY {
+            let keys = self.window.get_keys();
+            let is_shift = |key: &Key| matches!(key, Key::LeftShift | Key::RightShift);
+            // Shift only modifies other keys, so it is never reported on its own
+            if let Some(&key) = keys.iter().find(|key| !is_shift(key)) {
+                let shift = keys.iter().any(is_shift);
+                events.push(Event::Key { key, shift });
+                self.last_key_time = Instant::now();
+            }
+        }
+        if let Some((x, y)) = self.window.get_mouse_pos(MouseMode::Discard) {
+            let pressed = self.window.get_mouse_down(MouseButton::Left);
+            events.push(Event::Mouse { x, y, pressed });
+        }
+        events
+    }
+
+    fn disable_vsync(&mut self) {
+        // minifb cannot wait for the display, and instead caps updates to 250 per second
+        self.window.set_target_fps(0);
+    }
+}
@@ -0,0 +1,365 @@
+//! Rendering into a desktop window with winit and softbuffer.
+//!
+//! This is an alternative to the minifb [`super::window`] for desktops where minifb
+//! misbehaves, Wayland compositors in particular. The window is opened at the requested
+//! size in logical pixels, so it keeps the same apparent size on HiDPI screens, while
+//! effects render at its physical resolution, so every pixel of the screen is drawn and
+//! frames stay sharp. The resolution follows the window as it is resized or moved to a
+//! monitor with another scale factor.
+//!
+//! winit normally runs its own event loop, so the events are pumped once per frame
+//! instead, which winit supports on Linux, Windows and macOS.
+use super::{Backend, Event};
+use minifb::Key;
+use softbuffer::{Context, Surface};
+use std::error::Error;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+use winit::application::ApplicationHandler;
+use winit::dpi::LogicalSize;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::platform::pump_events::{EventLoopExtPumpEvents, PumpStatus};
+use winit::window::{Window, WindowId};
+
+/// A resizable window drawn with softbuffer
+pub struct WinitBackend {
+    /// The window and its events
+    window: WinitWindow,
+    /// Pixels shown in the window
+    surface: Surface<Arc<Window>, Arc<Window>>,
+}
+
+impl WinitBackend {
+    /// Opens a window of `width * height` logical pixels.
+    pub fn new(title: &str, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        let window = WinitWindow::new(title, width, height)?;
+        let context = Context::new(window.window().clone())?;
+        let surface = Surface::new(&context, window.window().clone())?;
+        Ok(Self { window, surface })
+    }
+}
+
+impl Backend for WinitBackend {
+    fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    /// Returns the size of the window in physical pixels.
+    fn size(&self) -> (usize, usize) {
+        self.window.size()
+    }
+
+    /// Shows a frame in the window, which it fills unless the window was resized since
+    /// the frame was rendered.
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let size = self.window.window().inner_size();
+        let (Some(target_width), Some(target_height)) =
+            (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+        else {
+            // Minimized windows have nothing to draw into
+            return Ok(());
+        };
+        self.surface.resize(target_width, target_height)?;
+        let mut buffer = self.surface.buffer_mut()?;
+        let target = (size.width as usize, size.height as usize);
+        if (width, height) == target {
+            buffer.copy_from_slice(pixels);
+        } else {
+            copy_clipped(pixels, (width, height), &mut buffer, target);
+        }
+        buffer.present()?;
+        Ok(())
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        self.window.poll_events()
+    }
+}
+
+/// A winit window along with the event loop gathering its input, shared by the
+/// backends drawing into such windows
+pub(super) struct WinitWindow {
+    /// Event loop of the window, pumped by [`WinitWindow::poll_events`]
+    event_loop: EventLoop<()>,
+    /// The window and the input gathered from it
+    app: App,
+}
+
+impl WinitWindow {
+    /// Opens a window of `width * height` logical pixels.
+    pub(super) fn new(title: &str, width: usize, height: usize) -> Result<Self, Box<dyn Error>> {
+        let mut window = Self {
+            event_loop: EventLoop::new()?,
+            app: App {
+                title: title.to_string(),
+                size: LogicalSize::new(width as f64, height as f64),
+                window: None,
+                error: None,
+                open: true,
+                shift: false,
+                pointer: None,
+                pressed: false,
+                events: Vec::new(),
+            },
+        };
+        // The window is created once the event loop resumes, which it does right away
+        window.pump();
+        if let Some(e) = window.app.error.take() {
+            return Err(e);
+        }
+        if window.app.window.is_none() {
+            return Err("the window was not created".into());
+        }
+        Ok(window)
+    }
+
+    /// Returns the window, which stays open as long as `self` lives.
+    pub(super) fn window(&self) -> &Arc<Window> {
+        self.app
+            .window
+            .as_ref()
+            .expect("the window is opened by WinitWindow::new")
+    }
+
+    /// Returns a handle to the display server the window is shown by.
+    #[cfg(feature = "wgpu")]
+    pub(super) fn display_handle(&self) -> winit::event_loop::OwnedDisplayHandle {
+        self.event_loop.owned_display_handle()
+    }
+
+    /// Returns whether the window is still open.
+    pub(super) fn is_open(&self) -> bool {
+        self.app.open
+    }
+
+    /// Returns the size of the window in physical pixels.
+    pub(super) fn size(&self) -> (usize, usize) {
+        let size = self.window().inner_size();
+        (size.width as usize, size.height as usize)
+    }
+
+    /// Returns the input received since the previous call, oldest first, with the
+    /// pointer position in physical pixels.
+    pub(super) fn poll_events(&mut self) -> Vec<Event> {
+        self.pump();
+        let mut events = std::mem::take(&mut self.app.events);
+        if let Some((x, y)) = self.app.pointer {
+            events.push(Event::Mouse {
+                x: x as f32,
+                y: y as f32,
+                pressed: self.app.pressed,
+            });
+        }
+        events
+    }
+
+    /// Handles the events waiting in the event loop without blocking.
+    fn pump(&mut self) {
+        let status = self
+            .event_loop
+            .pump_app_events(Some(Duration::ZERO), &mut self.app);
+        if let PumpStatus::Exit(_) = status {
+            self.app.open = false;
+        }
+    }
+}
+
+/// State updated by the event loop
+struct App {
+    /// Title of the window
+    title: String,
+    /// Size the window is opened at
+    size: LogicalSize<f64>,
+    /// The window, once opened
+    window: Option<Arc<Window>>,
+    /// Reason the window could not be opened
+    error: Option<Box<dyn Error>>,
+    /// Whether the window is still open
+    open: bool,
+    /// Whether Shift is held down
+    shift: bool,
+    /// Pointer position in physical pixels while it is over the window
+    pointer: Option<(f64, f64)>,
+    /// Whether the left mouse button is held down
+    pressed: bool,
+    /// Input received since the last poll, oldest first
+    events: Vec<Event>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+        let attributes = Window::default_attributes()
+            .with_title(&self.title)
+            .with_inner_size(self.size);
+        match event_loop.create_window(attributes) {
+            Ok(window) => self.window = Some(Arc::new(window)),
+            Err(e) => {
+                self.error = Some(e.into());
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                self.open = false;
+                event_loop.exit();
+            }
+            WindowEvent::ModifiersChanged(modifiers) => self.shift = modifiers.state().shift_key(),
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                if let PhysicalKey::Code(code) = event.physical_key {
+                    if let Some(key) = key(code) {
+                        let shift = self.shift;
+                        self.events.push(Event::Key { key, shift });
+                    }
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.pointer = Some((position.x, position.y));
+            }
+            WindowEvent::CursorLeft { .. } => self.pointer = None,
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => self.pressed = state == ElementState::Pressed,
+            // The size and scale factor are read back from the window before every frame
+            _ => {}
+        }
+    }
+}
+
+/// Copies a frame of `width * height` pixels into the top left corner of `target`, a
+/// frame of `target_width * target_height` pixels, cutting off what does not fit and
+/// filling the rest with black.
+fn copy_clipped(
+    pixels: &[u32],
+    (width, height): (usize, usize),
+    target: &mut [u32],
+    (target_width, target_height): (usize, usize),
+) {
+    let columns = width.min(target_width);
+    for (y, row) in target.chunks_exact_mut(target_width).enumerate() {
+        if y < height.min(target_height) {
+            row[..columns].copy_from_slice(&pixels[y * width..y * width + columns]);
+            row[columns..].fill(0);
+        } else {
+            row.fill(0);
+        }
+    }
+}
+
+/// Returns the key reported for a key of the keyboard, if the controls use it.
+///
+/// Shift is left out since it only modifies other keys.
+fn key(code: KeyCode) -> Option<Key> {
+    Some(match code {
+        KeyCode::KeyA => Key::A,
+        KeyCode::KeyB => Key::B,
+        KeyCode::KeyC => Key::C,
+        KeyCode::KeyD => Key::D,
+        KeyCode::KeyE => Key::E,
+        KeyCode::KeyF => Key::F,
+        KeyCode::KeyG => Key::G,
+        KeyCode::KeyH => Key::H,
+        KeyCode::KeyI => Key::I,
+        KeyCode::KeyJ => Key::J,
+        KeyCode::KeyK => Key::K,
+        KeyCode::KeyL => Key::L,
+        KeyCode::KeyM => Key::M,
+        KeyCode::KeyN => Key::N,
+        KeyCode::KeyO => Key::O,
+        KeyCode::KeyP => Key::P,
+        KeyCode::KeyQ => Key::Q,
+        KeyCode::KeyR => Key::R,
+        KeyCode::KeyS => Key::S,
+        KeyCode::KeyT => Key::T,
+        KeyCode::KeyU => Key::U,
+        KeyCode::KeyV => Key::V,
+        KeyCode::KeyW => Key::W,
+        KeyCode::KeyX => Key::X,
+        KeyCode::KeyY => Key::Y,
+        KeyCode::KeyZ => Key::Z,
+        KeyCode::Digit0 => Key::Key0,
+        KeyCode::Digit1 => Key::Key1,
+        KeyCode::Digit2 => Key::Key2,
+        KeyCode::Digit3 => Key::Key3,
+        KeyCode::Digit4 => Key::Key4,
+        KeyCode::Digit5 => Key::Key5,
+        KeyCode::Digit6 => Key::Key6,
+        KeyCode::Digit7 => Key::Key7,
+        KeyCode::Digit8 => Key::Key8,
+        KeyCode::Digit9 => Key::Key9,
+        KeyCode::F1 => Key::F1,
+        KeyCode::F2 => Key::F2,
+        KeyCode::F3 => Key::F3,
+        KeyCode::F4 => Key::F4,
+        KeyCode::F5 => Key::F5,
+        KeyCode::F6 => Key::F6,
+        KeyCode::F7 => Key::F7,
+        KeyCode::F8 => Key::F8,
+        KeyCode::F9 => Key::F9,
+        KeyCode::F10 => Key::F10,
+        KeyCode::F11 => Key::F11,
+        KeyCode::F12 => Key::F12,
+        KeyCode::ArrowUp => Key::Up,
+        KeyCode::ArrowDown => Key::Down,
+        KeyCode::ArrowLeft => Key::Left,
+        KeyCode::ArrowRight => Key::Right,
+        KeyCode::Space => Key::Space,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Escape => Key::Escape,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Minus => Key::Minus,
+        KeyCode::Equal => Key::Equal,
+        KeyCode::Comma => Key::Comma,
+        KeyCode::Period => Key::Period,
+        KeyCode::BracketLeft => Key::LeftBracket,
+        KeyCode::BracketRight => Key::RightBracket,
+        KeyCode::NumpadAdd => Key::NumPadPlus,
+        KeyCode::NumpadSubtract => Key::NumPadMinus,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_frames_are_clipped_to_the_window() {
+        let pixels = [1, 2, 3, 4, 5, 6];
+        // The window grew past the frame
+        let mut target = [9; 12];
+        copy_clipped(&pixels, (3, 2), &mut target, (4, 3));
+        assert_eq!(target, [1, 2, 3, 0, 4, 5, 6, 0, 0, 0, 0, 0]);
+        // The window shrank below it
+        let mut target = [9; 2];
+        copy_clipped(&pixels, (3, 2), &mut target, (2, 1));
+        assert_eq!(target, [1, 2]);
+    }
+
+    #[test]
+    fn keys_map_onto_the_controls() {
+        assert_eq!(key(KeyCode::KeyQ), Some(Key::Q));
+        assert_eq!(key(KeyCode::Digit3), Some(Key::Key3));
+        assert_eq!(key(KeyCode::BracketRight), Some(Key::RightBracket));
+        assert_eq!(key(KeyCode::NumpadAdd), Some(Key::NumPadPlus));
+        assert_eq!(key(KeyCode::ShiftLeft), None);
+    }
+}
@@ -0,0 +1,278 @@
+//! Drawing into an existing X11 window, such as the root window behind the desktop or a
+//! window handed over by XScreenSaver.
+//!
+//! Frames are uploaded into a pixmap set as the background of the window, the way
+//! wallpaper setters do, so the X server repaints the window on its own whenever other
+//! windows uncover it. Xlib is loaded at runtime, and only 24 and 32 bit true color
+//! screens are supported since their pixels are laid out like the frames. Wallpapers are
+//! capped to a low frame rate to spare the CPU of a machine that is mostly doing
+//! something else.
+use super::{parse_window_id, Backend, Event, Monitor};
+use std::error::Error;
+use std::ptr;
+use std::time::{Duration, Instant};
+use x11_dl::xinerama;
+use x11_dl::xlib::{self, Xlib};
+
+/// Most frames drawn per second on the root window
+const WALLPAPER_FRAME_RATE: f32 = 15.0;
+
+/// Most frames drawn per second in other windows
+const FRAME_RATE: f32 = 30.0;
+
+/// An X11 window showing the frames as its background
+pub struct X11Surface {
+    /// Entry points of the Xlib library
+    xlib: Xlib,
+    /// Connection to the X server
+    display: *mut xlib::Display,
+    /// Window drawn into
+    window: xlib::Window,
+    /// Background of the window holding the last frame
+    pixmap: xlib::Pixmap,
+    /// Size of the pixmap in pixels
+    pixmap_size: (usize, usize),
+    /// Graphics context drawing into the pixmap
+    gc: xlib::GC,
+    /// Visual of the window
+    visual: *mut xlib::Visual,
+    /// Bits per pixel of the window
+    depth: u32,
+    /// Shortest time between two frames
+    frame_time: Duration,
+    /// Time the last frame was drawn
+    last_frame_time: Instant,
+}
+
+impl X11Surface {
+    /// Connects to the X server named by `DISPLAY` to draw on its root window.
+    ///
+    /// Like other screensavers, the window named by `XSCREENSAVER_WINDOW` stands in for
+    /// the root window when XScreenSaver runs plasma with `-root`.
+    ///
+    /// # Errors
+    /// Fails if Xlib cannot be loaded, the display cannot be opened, or the screen does
+    /// not use 24 or 32 bit true color.
+    pub fn root() -> Result<Self, Box<dyn Error>> {
+        let screensaver = std::env::var("XSCREENSAVER_WINDOW").ok();
+        let screensaver = screensaver.as_deref().map(parse_window_id).transpose()?;
+        let (xlib, display) = open_display()?;
+        // SAFETY: the display was just opened
+        let window = screensaver.unwrap_or_else(|| unsafe { (xlib.XDefaultRootWindow)(display) });
+        Self::new(xlib, display, window, WALLPAPER_FRAME_RATE)
+    }
+
+    /// Connects to the X server named by `DISPLAY` to draw into the window `id`.
+    ///
+    /// # Errors
+    /// Fails if Xlib cannot be loaded, the display cannot be opened, or the window does
+    /// not exist or use 24 or 32 bit true color.
+    pub fn window(id: u64) -> Result<Self, Box<dyn Error>> {
+        let (xlib, display) = open_display()?;
+        Self::new(xlib, display, id, FRAME_RATE)
+    }
+
+    /// Prepares to draw into `window` over an open display at up to `frame_rate` frames
+    /// per second, taking ownership of the connection.
+    fn new(
+        xlib: Xlib,
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        frame_rate: f32,
+    ) -> Result<Self, Box<dyn Error>> {
+        // SAFETY: XWindowAttributes is plain data filled in by XGetWindowAttributes
+        let mut attributes: xlib::XWindowAttributes = unsafe { std::mem::zeroed() };
+        // SAFETY: the display is open and the pointers refer to live values
+        let supported = unsafe {
+            (xlib.XGetWindowAttributes)(display, window, &mut attributes) != 0
+                && attributes.depth >= 24
+                && (*attributes.visual).red_mask == 0xff_0000
+                && (*attributes.visual).green_mask == 0xff00
+                && (*attributes.visual).blue_mask == 0xff
+        };
+        if !supported {
+            // SAFETY: the display is open and not used afterwards
+            unsafe { (xlib.XCloseDisplay)(display) };
+            return Err("the X window must use 24 or 32 bit true color".into());
+        }
+        let (width, height) = (attributes.width as usize, attributes.height as usize);
+        let depth = attributes.depth as u32;
+        // SAFETY: the display is open and the window exists
+        let (pixmap, gc) = unsafe {
+            let pixmap = (xlib.XCreatePixmap)(display, window, width as u32, height as u32, depth);
+            let gc = (xlib.XCreateGC)(display, pixmap, 0, ptr::null_mut());
+            (xlib.XSetWindowBackgroundPixmap)(display, window, pixmap);
+            (pixmap, gc)
+        };
+        Ok(Self {
+            xlib,
+            display,
+            window,
+            pixmap,
+            pixmap_size: (width, height),
+            gc,
+            visual: attributes.visual,
+            depth,
+            frame_time: Duration::from_secs_f32(1.0 / frame_rate),
+            last_frame_time: Instant::now(),
+        })
+    }
+
+    /// Replaces the background pixmap with one of `width * height` pixels.
+    fn resize_pixmap(&mut self, width: usize, height: usize) {
+        // SAFETY: the old pixmap was created on this display, and the window keeps its
+        // background until the new pixmap replaces it
+        unsafe {
+            (self.xlib.XFreePixmap)(self.display, self.pixmap);
+            self.pixmap = (self.xlib.XCreatePixmap)(
+                self.display,
+                self.window,
+                width as u32,
+                height as u32,
+                self.depth,
+            );
+            (self.xlib.XSetWindowBackgroundPixmap)(self.display, self.window, self.pixmap);
+        }
+        self.pixmap_size = (width, height);
+    }
+}
+
+/// Loads Xlib and connects to the X server named by `DISPLAY`.
+fn open_display() -> Result<(Xlib, *mut xlib::Display), Box<dyn Error>> {
+    let xlib = Xlib::open().map_err(|e| format!("loading Xlib: {e}"))?;
+    // SAFETY: a null name opens the display named by DISPLAY
+    let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+    if display.is_null() {
+        return Err("cannot open the X display".into());
+    }
+    Ok((xlib, display))
+}
+
+/// Returns the monitors of the X display, as reported by Xinerama, or the whole screen
+/// as a single monitor without it.
+///
+/// # Errors
+/// Fails if Xlib cannot be loaded or the display cannot be opened.
+pub fn monitors() -> Result<Vec<Monitor>, Box<dyn Error>> {
+    let (xlib, display) = open_display()?;
+    // SAFETY: the display is open until the end of the block
+    unsafe {
+        let monitors = xinerama_monitors(&xlib, display).unwrap_or_else(|| {
+            let screen = (xlib.XDefaultScreen)(display);
+            vec![Monitor {
+                x: 0,
+                y: 0,
+                width: (xlib.XDisplayWidth)(display, screen) as usize,
+                height: (xlib.XDisplayHeight)(display, screen) as usize,
+            }]
+        });
+        (xlib.XCloseDisplay)(display);
+        Ok(monitors)
+    }
+}
+
+/// Returns the monitors Xinerama reports, if the extension is active.
+///
+/// # Safety
+/// `display` must be open.
+unsafe fn xinerama_monitors(xlib: &Xlib, display: *mut xlib::Display) -> Option<Vec<Monitor>> {
+    let xinerama = xinerama::Xlib::open().ok()?;
+    if (xinerama.XineramaIsActive)(display) == 0 {
+        return None;
+    }
+    let mut count = 0;
+    let screens = (xinerama.XineramaQueryScreens)(display, &mut count);
+    if screens.is_null() {
+        return None;
+    }
+    let monitors = std::slice::from_raw_parts(screens, count as usize)
+        .iter()
+        .map(|screen| Monitor {
+            x: screen.x_org.into(),
+            y: screen.y_org.into(),
+            width: screen.width as usize,
+            height: screen.height as usize,
+        })
+        .collect();
+    (xlib.XFree)(screens.cast());
+    Some(monitors)
+}
+
+impl Backend for X11Surface {
+    fn size(&self) -> (usize, usize) {
+        // SAFETY: XWindowAttributes is plain data filled in by XGetWindowAttributes
+        let mut attributes: xlib::XWindowAttributes = unsafe { std::mem::zeroed() };
+        // SAFETY: the display is open and the pointer refers to a live value
+        unsafe { (self.xlib.XGetWindowAttributes)(self.display, self.window, &mut attributes) };
+        (attributes.width as usize, attributes.height as usize)
+    }
+
+    fn present(
+        &mut self,
+        pixels: &[u32],
+        width: usize,
+        height: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        std::thread::sleep(
+            self.frame_time
+                .saturating_sub(self.last_frame_time.elapsed()),
+        );
+        self.last_frame_time = Instant::now();
+        if (width, height) != self.pixmap_size {
+            self.resize_pixmap(width, height);
+        }
+
+        // SAFETY: the image borrows the frame, which Xlib only reads, and is released
+        // without freeing the frame before this function returns
+        unsafe {
+            let image = (self.xlib.XCreateImage)(
+                self.display,
+                self.visual,
+                self.depth,
+                xlib::ZPixmap,
+                0,
+                pixels.as_ptr() as *mut _,
+                width as u32,
+                height as u32,
+                32,
+                0,
+            );
+            if image.is_null() {
+                return Err("cannot create an X image".into());
+            }
+            (self.xlib.XPutImage)(
+                self.display,
+                self.pixmap,
+                self.gc,
+                image,
+                0,
+                0,
+                0,
+                0,
+                width as u32,
+                height as u32,
+            );
+            (self.xlib.XFree)(image.cast());
+            (self.xlib.XClearWindow)(self.display, self.window);
+            // Waiting for the server keeps requests from piling up on slow connections
+            (self.xlib.XSync)(self.display, xlib::False);
+        }
+        Ok(())
+    }
+
+    fn poll_events(&mut self) -> Vec<Event> {
+        Vec::new()
+    }
+}
+
+impl Drop for X11Surface {
+    fn drop(&mut self) {
+        // The window keeps the last frame as its background after the pixmap is freed
+        // SAFETY: the resources were created on this display, which is still open
+        unsafe {
+            (self.xlib.XFreeGC)(self.display, self.gc);
+            (self.xlib.XFreePixmap)(self.display, self.pixmap);
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+    }
+}
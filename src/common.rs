@@ -0,0 +1,760 @@
+//! Building blocks shared by all demo effects.
+//!
+//! Every effect implements the [`DemoEffect`] trait so the main loop can drive it without
+//! knowing which effect is running, and embeds a [`DemoBase`] holding the dimensions of the
+//! pixel buffer it renders into. A [`Compositor`] stacks several effects on top of each
+//! other and is itself an effect. Effects computing every pixel on their own render the
+//! rows of a frame in parallel with [`for_each_row`], and can trade the precision of
+//! their sines for speed with a [`SinTable`].
+//!
+//! Effects mapping a scalar field onto a palette may also draw palette indices, one byte
+//! per pixel, through [`DemoEffect::draw_indices`]. A [`ColorCycle`] colors those indices
+//! with a table of 256 palette entries rotated a little further every frame, the way
+//! old demos animated still pictures by cycling the colors of their hardware palette.
+//!
+//! Effects may also describe their frames as a WGSL fragment shader through
+//! [`DemoEffect::shader_source`], which backends having a GPU run instead of `draw`.
+//!
+//! The [`color`] module converts between sRGB and the perceptual OKLab and OKLCH color
+//! spaces.
+use crate::effects::Settings;
+use crate::palette::Crossfade;
+use clap::ValueEnum;
+use minifb::Key;
+use rayon::prelude::*;
+use std::cell::{Ref, RefCell};
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub mod color;
+
+/// Alpha channel mask for ARGB color format (fully opaque)
+pub const ALPHA: u32 = 255 << 24;
+
+/// Input forwarded by the main loop to the running effect
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    /// A key was pressed
+    Key(Key),
+    /// A key was pressed while Shift was held down
+    ShiftKey(Key),
+    /// The pointer is over the window, sent every frame
+    Mouse {
+        /// Horizontal pointer position in pixels
+        x: f32,
+        /// Vertical pointer position in pixels
+        y: f32,
+        /// Whether the left mouse button is held down
+        pressed: bool,
+    },
+}
+
+/// Interface implemented by every effect that can be rendered by the main loop
+///
+/// Every frame the main loop forwards the pending input to [`DemoEffect::handle_input`],
+/// advances the effect with [`DemoEffect::update`], and finally renders it with
+/// [`DemoEffect::draw`]. Simulations keep their state in `update` so that drawing never
+/// changes the effect.
+pub trait DemoEffect {
+    /// Advances the state of the effect.
+    ///
+    /// # Arguments
+    /// * `dt` - Time elapsed since the previous update in seconds
+    ///
+    /// Effects that are a pure function of time keep the default, which does nothing.
+    fn update(&mut self, _dt: f32) {}
+
+    /// Prepares drawing the state `alpha` of the way from the update before last to the
+    /// last one, from 0 drawing the older state to 1 drawing the latest.
+    ///
+    /// The main loop advances simulations in fixed steps, so frames usually fall between
+    /// two updates. Effects keep the default, which draws the latest state.
+    fn interpolate(&mut self, _alpha: f32) {}
+
+    /// Renders the effect into the provided pixel buffer.
+    ///
+    /// # Arguments
+    /// * `buffer` - Mutable slice of u32 values representing the pixel buffer
+    /// * `time` - Current time value in seconds, used for animation
+    fn draw(&self, buffer: &mut [u32], time: f32);
+
+    /// Renders the effect as indices into the palette it returns, where index 0 stands for
+    /// the start of the palette and 255 for its end. The palette comes along with its fade
+    /// and the way it is reversed or turned.
+    ///
+    /// Effects choosing the color of every pixel on their own keep the default, which
+    /// leaves `indices` as is and returns `None`.
+    fn draw_indices(&self, _indices: &mut [u8], _time: f32) -> Option<&Crossfade> {
+        None
+    }
+
+    /// Reacts to input forwarded by the main loop.
+    ///
+    /// Effects without interactive controls keep the default, which ignores the event.
+    fn handle_input(&mut self, _event: InputEvent) {}
+
+    /// Adapts the effect to a new screen size.
+    ///
+    /// Every buffer passed to `draw` afterwards holds `width * height` pixels. Effects
+    /// whose state is tied to the screen size reallocate it here, possibly starting over.
+    /// Effects able to fill a buffer of any size keep the default, which does nothing.
+    fn resize(&mut self, _width: usize, _height: usize) {}
+
+    /// Writes the parameters changed with the interactive controls into `settings`, so
+    /// the effect can be built again the way it looks now.
+    ///
+    /// Effects without such parameters keep the default, which leaves `settings` as is.
+    fn save_settings(&self, _settings: &mut Settings) {}
+
+    /// Returns a fragment shader drawing the frame at `time` the way `draw` would, for
+    /// backends rendering on the GPU.
+    ///
+    /// Effects drawn on the CPU keep the default, which returns `None`, and so do
+    /// effects whose current settings the shader cannot reproduce. The main loop then
+    /// draws them with `draw`.
+    fn shader_source(&self, _time: f32) -> Option<ShaderSource> {
+        None
+    }
+}
+
+/// Most parameters a [`ShaderSource`] can pass to its code
+pub const SHADER_PARAMS: usize = 32;
+
+/// Number of palette colors a [`ShaderSource`] passes to its code
+pub const SHADER_COLORS: usize = 256;
+
+/// Fragment shader drawing an effect on the GPU
+///
+/// The WGSL `code` defines `fn shade(pixel: vec2<f32>) -> vec3<f32>`, returning the
+/// color of the point `pixel` of the frame, in the pixels the effect renders at. The
+/// backend prepends a prelude to it, giving access to the `frame.resolution` and
+/// `frame.time` of the frame, to the `params` through `param(i)`, and to the `palette`
+/// through `palette_color(v)`, which blends neighboring colors of the palette for `v`
+/// between 0 and 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderSource {
+    /// WGSL code of the effect, compiled once and reused as long as it stays the same
+    pub code: &'static str,
+    /// Values read by `param(i)`, at most [`SHADER_PARAMS`] of them
+    pub params: Vec<f32>,
+    /// Colors blended by `palette_color(v)` from the start of the palette to its end,
+    /// at most [`SHADER_COLORS`] of them, empty if the code does not use it
+    pub palette: Vec<u32>,
+}
+
+impl ShaderSource {
+    /// Returns the [`SHADER_COLORS`] colors of a palette mapping values from 0 to 1
+    /// onto packed ARGB pixels with `color`, evenly spread from its start to its end.
+    pub fn sample_palette(color: impl Fn(f32) -> u32) -> Vec<u32> {
+        (0..SHADER_COLORS)
+            .map(|i| color(i as f32 / (SHADER_COLORS - 1) as f32))
+            .collect()
+    }
+}
+
+/// State common to all demo effects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DemoBase {
+    /// Width of the effect in pixels
+    pub width: usize,
+    /// Height of the effect in pixels
+    pub height: usize,
+}
+
+impl DemoBase {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    /// Returns the total number of pixels covered by the effect.
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Returns the center coordinates of the display area.
+    pub fn center(&self) -> (f32, f32) {
+        (self.width as f32 * 0.5, self.height as f32 * 0.5)
+    }
+
+    /// Sets a single pixel, ignoring coordinates outside the display area.
+    pub fn plot(&self, buffer: &mut [u32], x: i32, y: i32, color: u32) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            buffer[y as usize * self.width + x as usize] = color;
+        }
+    }
+
+    /// Draws a line between two points using Bresenham's algorithm.
+    ///
+    /// Pixels falling outside the display area are clipped.
+    pub fn draw_line(&self, buffer: &mut [u32], from: (i32, i32), to: (i32, i32), color: u32) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let step_x = if x < to.0 { 1 } else { -1 };
+        let step_y = if y < to.1 { 1 } else { -1 };
+        // Accumulated error between the ideal line and the rasterized pixels
+        let mut error = dx + dy;
+
+        loop {
+            self.plot(buffer, x, y, color);
+            if (x, y) == to {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
+
+/// An effect rendered into its own offscreen buffer.
+///
+/// Effects that post-process another effect wrap it in a layer, render it first, and then
+/// read the resulting pixels while drawing their own output. Updates and input are
+/// forwarded to the wrapped effect so it keeps animating and its interactive controls
+/// keep working.
+pub struct Layer {
+    /// Effect rendered into the layer
+    effect: Box<dyn DemoEffect>,
+    /// Offscreen pixel buffer holding the latest frame of the effect, borrowed mutably
+    /// while rendering from the immutable `draw` of the wrapping effect
+    pixels: RefCell<Vec<u32>>,
+}
+
+impl Layer {
+    pub fn new(base: DemoBase, effect: Box<dyn DemoEffect>) -> Self {
+        Self {
+            effect,
+            pixels: RefCell::new(vec![0; base.pixel_count()]),
+        }
+    }
+
+    /// Renders the wrapped effect and returns the resulting pixels.
+    pub fn render(&self, time: f32) -> Ref<'_, [u32]> {
+        self.effect.draw(&mut self.pixels.borrow_mut(), time);
+        Ref::map(self.pixels.borrow(), Vec::as_slice)
+    }
+
+    /// Advances the wrapped effect.
+    pub fn update(&mut self, dt: f32) {
+        self.effect.update(dt);
+    }
+
+    /// Interpolates the state of the wrapped effect between its last two updates.
+    pub fn interpolate(&mut self, alpha: f32) {
+        self.effect.interpolate(alpha);
+    }
+
+    /// Forwards input to the wrapped effect.
+    pub fn handle_input(&mut self, event: InputEvent) {
+        self.effect.handle_input(event);
+    }
+
+    /// Resizes the offscreen buffer along with the wrapped effect.
+    pub fn resize(&mut self, base: DemoBase) {
+        self.pixels.get_mut().resize(base.pixel_count(), 0);
+        self.effect.resize(base.width, base.height);
+    }
+
+    /// Saves the interactive parameters of the wrapped effect.
+    pub fn save_settings(&self, settings: &mut Settings) {
+        self.effect.save_settings(settings);
+    }
+}
+
+/// How the pixels of a layer are combined with the layers below it
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum BlendMode {
+    /// Sums the colors, saturating at white
+    Add,
+    /// Multiplies the colors, darkening the picture
+    Multiply,
+    /// Inverts, multiplies, and inverts again, brightening the picture
+    Screen,
+    /// Covers the layers below
+    Alpha,
+}
+
+impl BlendMode {
+    /// Combines the pixel of a layer with the pixel below it.
+    ///
+    /// # Arguments
+    /// * `below` - Pixel composited so far
+    /// * `above` - Pixel of the layer
+    /// * `opacity` - Weight of the blended color against `below`, in the [0,1] range
+    pub fn blend(self, below: u32, above: u32, opacity: f32) -> u32 {
+        let channel = |shift: u32| {
+            let b = ((below >> shift) & 0xff) as f32 / 255.0;
+            let a = ((above >> shift) & 0xff) as f32 / 255.0;
+            let blended = match self {
+                BlendMode::Add => (b + a).min(1.0),
+                BlendMode::Multiply => b * a,
+                BlendMode::Screen => 1.0 - (1.0 - b) * (1.0 - a),
+                BlendMode::Alpha => a,
+            };
+            ((b + (blended - b) * opacity) * 255.0).round() as u8
+        };
+        rgb(channel(16), channel(8), channel(0))
+    }
+}
+
+/// A stack of effects blended together into a single picture.
+///
+/// Every layer renders into its own offscreen buffer, and the layers are then blended from
+/// bottom to top over a black background. Updates, interpolation, input, resizes, and
+/// saved settings reach every layer.
+pub struct Compositor {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Layers from bottom to top, along with their blend mode and opacity
+    layers: Vec<(Layer, BlendMode, f32)>,
+}
+
+impl Compositor {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Adds an effect on top of the stack.
+    ///
+    /// The effect must have been built for the size of the compositor.
+    pub fn push(&mut self, effect: Box<dyn DemoEffect>, mode: BlendMode, opacity: f32) {
+        let layer = Layer::new(self.base, effect);
+        self.layers.push((layer, mode, opacity.clamp(0.0, 1.0)));
+    }
+}
+
+impl DemoEffect for Compositor {
+    fn update(&mut self, dt: f32) {
+        for (layer, _, _) in &mut self.layers {
+            layer.update(dt);
+        }
+    }
+
+    fn interpolate(&mut self, alpha: f32) {
+        for (layer, _, _) in &mut self.layers {
+            layer.interpolate(alpha);
+        }
+    }
+
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+        for (layer, mode, opacity) in &self.layers {
+            let pixels = layer.render(time);
+            for (pixel, &above) in buffer.iter_mut().zip(pixels.iter()) {
+                *pixel = mode.blend(*pixel, above, *opacity);
+            }
+        }
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        for (layer, _, _) in &mut self.layers {
+            layer.handle_input(event);
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        for (layer, _, _) in &mut self.layers {
+            layer.resize(self.base);
+        }
+    }
+
+    /// Saves the parameters of every layer from the bottom up, so the top layer wins
+    /// where two of them share a parameter.
+    fn save_settings(&self, settings: &mut Settings) {
+        for (layer, _, _) in &self.layers {
+            layer.save_settings(settings);
+        }
+    }
+}
+
+/// An effect whose palette indices are colored through a rotating palette.
+///
+/// Every frame the 256 entries of the palette are shifted by `speed` turns per second of
+/// animation time, so the colors flow through the picture even when the effect itself
+/// stands still. Effects without palette indices are drawn unchanged.
+pub struct ColorCycle {
+    /// Effect drawing the palette indices
+    effect: Box<dyn DemoEffect>,
+    /// Palette indices of the latest frame, borrowed mutably while drawing
+    indices: RefCell<Vec<u8>>,
+    /// Palette turns per second, negative to rotate backward
+    speed: f32,
+}
+
+impl ColorCycle {
+    pub fn new(base: DemoBase, effect: Box<dyn DemoEffect>, speed: f32) -> Self {
+        Self {
+            effect,
+            indices: RefCell::new(vec![0; base.pixel_count()]),
+            speed,
+        }
+    }
+
+    /// Returns the colors of the 256 palette indices at `time`, the palette having turned
+    /// by `speed` turns every second.
+    fn table(&self, palette: &Crossfade, time: f32) -> [u32; 256] {
+        let shift = ((time * self.speed).rem_euclid(1.0) * 256.0) as usize;
+        std::array::from_fn(|i| palette.color(((i + shift) % 256) as f32 / 255.0))
+    }
+}
+
+impl DemoEffect for ColorCycle {
+    fn update(&mut self, dt: f32) {
+        self.effect.update(dt);
+    }
+
+    fn interpolate(&mut self, alpha: f32) {
+        self.effect.interpolate(alpha);
+    }
+
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let mut indices = self.indices.borrow_mut();
+        indices.resize(buffer.len(), 0);
+        match self.effect.draw_indices(&mut indices, time) {
+            Some(palette) => {
+                let table = self.table(palette, time);
+                for (pixel, &index) in buffer.iter_mut().zip(indices.iter()) {
+                    *pixel = table[index as usize];
+                }
+            }
+            None => self.effect.draw(buffer, time),
+        }
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        self.effect.handle_input(event);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.indices.get_mut().resize(width * height, 0);
+        self.effect.resize(width, height);
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        self.effect.save_settings(settings);
+    }
+}
+
+/// Sines sampled at regular steps over a turn.
+///
+/// Sines in between two samples are interpolated linearly, so the error shrinks with the
+/// square of the number of samples: 1024 samples stay within about 1e-5 of `f32::sin`,
+/// and 64 within about 1e-3, which is invisible in most effects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinTable {
+    /// Sines of the sampled angles, with the first one repeated at the end
+    samples: Vec<f32>,
+    /// Samples per radian
+    rate: f32,
+}
+
+impl SinTable {
+    /// Samples the sine at `resolution` evenly spread angles, at least 4.
+    pub fn new(resolution: usize) -> Self {
+        let resolution = resolution.max(4);
+        let step = TAU / resolution as f32;
+        Self {
+            samples: (0..=resolution).map(|i| (i as f32 * step).sin()).collect(),
+            rate: resolution as f32 / TAU,
+        }
+    }
+
+    /// Approximates the sine of `x` radians.
+    pub fn sin(&self, x: f32) -> f32 {
+        let last = self.samples.len() - 2;
+        let position = (x * self.rate).rem_euclid(last as f32 + 1.0);
+        let index = (position as usize).min(last);
+        let (low, high) = (self.samples[index], self.samples[index + 1]);
+        low + (high - low) * (position - index as f32)
+    }
+
+    /// Approximates the cosine of `x` radians.
+    pub fn cos(&self, x: f32) -> f32 {
+        self.sin(x + FRAC_PI_2)
+    }
+}
+
+/// Number of bands the rows of a frame are split into, 0 until known
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the number of threads [`for_each_row`] renders with, where 0 picks one thread per
+/// CPU core.
+pub fn set_threads(count: usize) {
+    THREADS.store(count, Ordering::Relaxed);
+}
+
+/// Returns the number of threads [`for_each_row`] renders with.
+pub fn threads() -> usize {
+    match THREADS.load(Ordering::Relaxed) {
+        0 => {
+            let count = rayon::current_num_threads();
+            THREADS.store(count, Ordering::Relaxed);
+            count
+        }
+        count => count,
+    }
+}
+
+/// Calls `row` with the index and the items of every row of a buffer `width` items wide.
+///
+/// The rows are split into one band of consecutive rows per thread, rendered in parallel
+/// on the rayon thread pool, so `row` must not depend on the order the rows are visited in.
+pub fn for_each_row<T: Send>(buffer: &mut [T], width: usize, row: impl Fn(usize, &mut [T]) + Sync) {
+    split_rows(buffer, width, threads(), row);
+}
+
+/// Calls `row` on every row of `buffer`, split into up to `threads` bands.
+fn split_rows<T: Send>(
+    buffer: &mut [T],
+    width: usize,
+    threads: usize,
+    row: impl Fn(usize, &mut [T]) + Sync,
+) {
+    if width == 0 {
+        return;
+    }
+    let rows = buffer.len() / width;
+    let band = rows.div_ceil(threads.clamp(1, rows.max(1)));
+    if band >= rows {
+        buffer
+            .chunks_exact_mut(width)
+            .enumerate()
+            .for_each(|(y, items)| row(y, items));
+        return;
+    }
+    buffer[..rows * width]
+        .par_chunks_mut(band * width)
+        .enumerate()
+        .for_each(|(index, chunk)| {
+            for (y, items) in chunk.chunks_exact_mut(width).enumerate() {
+                row(index * band + y, items);
+            }
+        });
+}
+
+/// Packs 8-bit red, green, and blue components into a fully opaque ARGB pixel.
+pub fn rgb(r: u8, g: u8, b: u8) -> u32 {
+    ALPHA | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::Palette;
+
+    #[test]
+    fn plot_ignores_pixels_outside_display() {
+        let base = DemoBase::new(4, 4);
+        let mut buffer = vec![0; base.pixel_count()];
+        base.plot(&mut buffer, -1, 0, 1);
+        base.plot(&mut buffer, 0, 4, 1);
+        assert!(buffer.iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn draw_line_covers_both_endpoints() {
+        let base = DemoBase::new(8, 8);
+        let mut buffer = vec![0; base.pixel_count()];
+        base.draw_line(&mut buffer, (1, 6), (6, 2), 1);
+        assert_eq!(buffer[6 * 8 + 1], 1);
+        assert_eq!(buffer[2 * 8 + 6], 1);
+    }
+
+    #[test]
+    fn draw_line_draws_one_pixel_per_major_step() {
+        let base = DemoBase::new(8, 8);
+        let mut buffer = vec![0; base.pixel_count()];
+        base.draw_line(&mut buffer, (0, 0), (7, 3), 1);
+        assert_eq!(buffer.iter().filter(|&&p| p == 1).count(), 8);
+    }
+
+    #[test]
+    fn layer_renders_wrapped_effect_offscreen() {
+        struct Solid;
+        impl DemoEffect for Solid {
+            fn draw(&self, buffer: &mut [u32], _time: f32) {
+                buffer.fill(7);
+            }
+        }
+
+        let layer = Layer::new(DemoBase::new(4, 2), Box::new(Solid));
+        assert_eq!(*layer.render(0.0), [7; 8]);
+    }
+
+    #[test]
+    fn layer_forwards_updates_interpolation_and_input() {
+        struct Counter(u32);
+        impl DemoEffect for Counter {
+            fn update(&mut self, _dt: f32) {
+                self.0 += 1;
+            }
+            fn draw(&self, buffer: &mut [u32], _time: f32) {
+                buffer.fill(self.0);
+            }
+            fn handle_input(&mut self, event: InputEvent) {
+                if event == InputEvent::Key(Key::Space) {
+                    self.0 += 10;
+                }
+            }
+            fn interpolate(&mut self, _alpha: f32) {
+                self.0 += 100;
+            }
+        }
+
+        let mut layer = Layer::new(DemoBase::new(2, 2), Box::new(Counter(0)));
+        layer.update(0.1);
+        layer.handle_input(InputEvent::Key(Key::Space));
+        assert_eq!(*layer.render(0.0), [11; 4]);
+        layer.interpolate(0.5);
+        assert_eq!(*layer.render(0.0), [111; 4]);
+    }
+
+    #[test]
+    fn blend_modes_combine_channels() {
+        let (below, above) = (rgb(200, 100, 0), rgb(100, 255, 51));
+        assert_eq!(BlendMode::Add.blend(below, above, 1.0), rgb(255, 255, 51));
+        assert_eq!(
+            BlendMode::Multiply.blend(below, above, 1.0),
+            rgb(78, 100, 0)
+        );
+        assert_eq!(
+            BlendMode::Screen.blend(below, above, 1.0),
+            rgb(222, 255, 51)
+        );
+        assert_eq!(BlendMode::Alpha.blend(below, above, 1.0), above);
+    }
+
+    #[test]
+    fn opacity_mixes_the_blend_with_the_pixels_below() {
+        let (below, above) = (rgb(0, 100, 200), rgb(200, 200, 200));
+        assert_eq!(
+            BlendMode::Alpha.blend(below, above, 0.5),
+            rgb(100, 150, 200)
+        );
+        assert_eq!(BlendMode::Alpha.blend(below, above, 0.0), below);
+    }
+
+    #[test]
+    fn compositor_blends_layers_from_bottom_to_top() {
+        struct Solid(u32);
+        impl DemoEffect for Solid {
+            fn draw(&self, buffer: &mut [u32], _time: f32) {
+                buffer.fill(self.0);
+            }
+        }
+
+        let mut compositor = Compositor::new(2, 2);
+        compositor.push(Box::new(Solid(rgb(100, 0, 0))), BlendMode::Alpha, 1.0);
+        compositor.push(Box::new(Solid(rgb(100, 0, 50))), BlendMode::Add, 1.0);
+        compositor.resize(3, 2);
+        let mut buffer = vec![0; 6];
+        compositor.draw(&mut buffer, 0.0);
+        assert_eq!(buffer, [rgb(200, 0, 50); 6]);
+    }
+
+    #[test]
+    fn compositor_saves_the_settings_of_every_layer() {
+        struct Scaled(f32);
+        impl DemoEffect for Scaled {
+            fn draw(&self, buffer: &mut [u32], _time: f32) {
+                buffer.fill(0);
+            }
+            fn save_settings(&self, settings: &mut Settings) {
+                settings.scale = self.0;
+            }
+        }
+
+        let mut compositor = Compositor::new(2, 2);
+        compositor.push(Box::new(Scaled(2.0)), BlendMode::Add, 1.0);
+        compositor.push(Box::new(Scaled(3.0)), BlendMode::Add, 1.0);
+        let mut settings = Settings::default();
+        compositor.save_settings(&mut settings);
+        assert_eq!(settings.scale, 3.0);
+    }
+
+    #[test]
+    fn color_cycles_rotate_the_palette_over_time() {
+        struct Ramp(Crossfade);
+        impl DemoEffect for Ramp {
+            fn draw(&self, buffer: &mut [u32], _time: f32) {
+                buffer.fill(1);
+            }
+            fn draw_indices(&self, indices: &mut [u8], _time: f32) -> Option<&Crossfade> {
+                indices.copy_from_slice(&[0, 255]);
+                Some(&self.0)
+            }
+        }
+
+        let base = DemoBase::new(2, 1);
+        let cycle = ColorCycle::new(
+            base,
+            Box::new(Ramp(Crossfade::new(Palette::BlackWhite))),
+            0.5,
+        );
+        let mut buffer = vec![0; 2];
+        cycle.draw(&mut buffer, 0.0);
+        assert_eq!(buffer, [rgb(0, 0, 0), rgb(255, 255, 255)]);
+        // Half a turn later the darkest index shows the middle of the palette, and the
+        // brightest one wraps around to just before it
+        cycle.draw(&mut buffer, 1.0);
+        assert_eq!(buffer, [rgb(128, 128, 128), rgb(127, 127, 127)]);
+    }
+
+    #[test]
+    fn color_cycles_draw_other_effects_unchanged() {
+        struct Solid;
+        impl DemoEffect for Solid {
+            fn draw(&self, buffer: &mut [u32], _time: f32) {
+                buffer.fill(7);
+            }
+        }
+
+        let mut cycle = ColorCycle::new(DemoBase::new(2, 2), Box::new(Solid), 1.0);
+        cycle.resize(3, 1);
+        let mut buffer = vec![0; 3];
+        cycle.draw(&mut buffer, 0.3);
+        assert_eq!(buffer, [7; 3]);
+    }
+
+    #[test]
+    fn sine_tables_interpolate_between_samples() {
+        let table = SinTable::new(1024);
+        for step in -5000..5000 {
+            let x = step as f32 * 0.013;
+            assert!((table.sin(x) - x.sin()).abs() < 2e-5, "sin({x})");
+            assert!((table.cos(x) - x.cos()).abs() < 2e-5, "cos({x})");
+        }
+        let coarse = SinTable::new(64);
+        assert!((coarse.sin(1.0) - 1.0f32.sin()).abs() < 1.2e-3);
+        assert_eq!(SinTable::new(4).sin(FRAC_PI_2), 1.0);
+    }
+
+    #[test]
+    fn rows_are_split_between_threads() {
+        for threads in [1, 3, 8, 100] {
+            let mut buffer = vec![0; 7 * 5];
+            split_rows(&mut buffer, 5, threads, |y, row| {
+                for (x, item) in row.iter_mut().enumerate() {
+                    *item = y * 5 + x;
+                }
+            });
+            assert_eq!(buffer, (0..35).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn rgb_packs_opaque_pixel() {
+        assert_eq!(rgb(0x12, 0x34, 0x56), 0xff12_3456);
+    }
+}
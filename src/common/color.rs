@@ -0,0 +1,238 @@
+//! Conversions between sRGB and the OKLab and OKLCH color spaces.
+//!
+//! OKLab, designed by Björn Ottosson, is a perceptual color space: equal distances in it
+//! look like equal changes of color, so blending two colors through OKLab gives an even
+//! ramp without the dark or grayish middle of blending their sRGB components. Its
+//! lightness `l` goes from 0 for black to 1 for white, and `a` and `b` lean towards red or
+//! green and yellow or blue. OKLCH writes the same colors as a lightness, a chroma, and a
+//! hue angle, which suits rotating hues or changing the saturation.
+use std::f32::consts::TAU;
+
+/// A color in the OKLab color space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    /// Perceived lightness, from 0 for black to 1 for white
+    pub l: f32,
+    /// Green to red axis
+    pub a: f32,
+    /// Blue to yellow axis
+    pub b: f32,
+}
+
+/// A color in the OKLCH color space, the polar form of OKLab
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    /// Perceived lightness, from 0 for black to 1 for white
+    pub l: f32,
+    /// Distance from the gray of the same lightness
+    pub c: f32,
+    /// Hue angle in degrees, from 0 to 360
+    pub h: f32,
+}
+
+impl Oklab {
+    /// Converts 8-bit sRGB red, green, and blue components to OKLab.
+    pub fn from_rgb((r, g, b): (u8, u8, u8)) -> Self {
+        let [r, g, b] = [r, g, b].map(srgb_to_linear);
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    /// Converts the color to 8-bit sRGB components, clipping colors outside of sRGB.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        let l = (self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b).powi(3);
+        let m = (self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b).powi(3);
+        let s = (self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b).powi(3);
+        let r = 4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s;
+        let g = -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s;
+        let b = -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s;
+        (linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+
+    /// Returns the color `t` of the way from this one to `other`, from 0 to 1.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            l: self.l + (other.l - self.l) * t,
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+        }
+    }
+}
+
+impl Oklch {
+    /// Converts 8-bit sRGB red, green, and blue components to OKLCH.
+    pub fn from_rgb(rgb: (u8, u8, u8)) -> Self {
+        Oklab::from_rgb(rgb).into()
+    }
+
+    /// Converts the color to 8-bit sRGB components, clipping colors outside of sRGB.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        Oklab::from(self).to_rgb()
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(color: Oklab) -> Self {
+        Self {
+            l: color.l,
+            c: color.a.hypot(color.b),
+            h: color.b.atan2(color.a).rem_euclid(TAU).to_degrees(),
+        }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(color: Oklch) -> Self {
+        let (sin, cos) = color.h.to_radians().sin_cos();
+        Self {
+            l: color.l,
+            a: color.c * cos,
+            b: color.c * sin,
+        }
+    }
+}
+
+/// A rotation of the hue of sRGB colors around the gray axis, keeping their luminance
+/// close to the original, as done by the CSS `hue-rotate()` filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HueRotation {
+    /// Rows of the matrix giving the red, green, and blue components
+    matrix: [[f32; 3]; 3],
+}
+
+impl HueRotation {
+    /// Returns the rotation by `degrees` of hue, towards green for positive angles.
+    pub fn new(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        // The luminance weights of the Rec. 709 primaries stay on the diagonal
+        let [r, g, b] = [0.213, 0.715, 0.072];
+        Self {
+            matrix: [
+                [
+                    r + cos * (1.0 - r) - sin * r,
+                    g - cos * g - sin * g,
+                    b - cos * b + sin * (1.0 - b),
+                ],
+                [
+                    r - cos * r + sin * 0.143,
+                    g + cos * (1.0 - g) + sin * 0.140,
+                    b - cos * b - sin * 0.283,
+                ],
+                [
+                    r - cos * r - sin * (1.0 - r),
+                    g - cos * g + sin * g,
+                    b + cos * (1.0 - b) + sin * b,
+                ],
+            ],
+        }
+    }
+
+    /// Rotates the hue of 8-bit sRGB red, green, and blue components, clipping the
+    /// result to the range of sRGB.
+    pub fn apply(&self, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        let [r, g, b] = [r, g, b].map(f32::from);
+        let channel = |[x, y, z]: [f32; 3]| (x * r + y * g + z * b).clamp(0.0, 255.0).round() as u8;
+        let [red, green, blue] = self.matrix;
+        (channel(red), channel(green), channel(blue))
+    }
+}
+
+/// Converts an 8-bit sRGB component to linear light in the [0,1] range.
+pub fn srgb_to_linear(component: u8) -> f32 {
+    let c = component as f32 / 255.0;
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts linear light to an 8-bit sRGB component, clipping it to the [0,1] range.
+pub fn linear_to_srgb(component: f32) -> u8 {
+    let c = component.clamp(0.0, 1.0);
+    let c = if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            assert!((actual - expected).abs() < 5e-4, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn primaries_match_the_reference_oklab_values() {
+        // Values published along with OKLab and used by CSS Color 4
+        let cases = [
+            ((255, 255, 255), [1.0, 0.0, 0.0]),
+            ((255, 0, 0), [0.627_955, 0.224_863, 0.125_846]),
+            ((0, 255, 0), [0.866_440, -0.233_888, 0.179_498]),
+            ((0, 0, 255), [0.452_014, -0.032_457, -0.311_528]),
+        ];
+        for (rgb, expected) in cases {
+            let lab = Oklab::from_rgb(rgb);
+            assert_close([lab.l, lab.a, lab.b], expected);
+        }
+        assert_eq!(Oklab::from_rgb((0, 0, 0)).l, 0.0);
+    }
+
+    #[test]
+    fn oklch_gives_the_chroma_and_hue_angle() {
+        let red = Oklch::from_rgb((255, 0, 0));
+        assert_close([red.l, red.c, red.h], [0.627_955, 0.257_683, 29.233_885]);
+        let blue = Oklch::from_rgb((0, 0, 255));
+        assert!((blue.h - 264.052).abs() < 0.01, "{}", blue.h);
+    }
+
+    #[test]
+    fn conversions_round_trip_through_every_space() {
+        for rgb in [(0, 0, 0), (255, 128, 0), (18, 52, 86), (200, 200, 200)] {
+            assert_eq!(Oklab::from_rgb(rgb).to_rgb(), rgb);
+            assert_eq!(Oklch::from_rgb(rgb).to_rgb(), rgb);
+        }
+    }
+
+    #[test]
+    fn srgb_components_follow_the_transfer_function() {
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert_eq!(srgb_to_linear(255), 1.0);
+        assert!((srgb_to_linear(128) - 0.215_861).abs() < 1e-5);
+        assert_eq!(linear_to_srgb(0.215_861), 128);
+        assert_eq!(linear_to_srgb(2.0), 255);
+        assert_eq!(linear_to_srgb(-1.0), 0);
+    }
+
+    #[test]
+    fn hue_rotations_turn_colors_around_the_gray_axis() {
+        for rgb in [(255, 0, 0), (18, 52, 86), (200, 200, 200)] {
+            assert_eq!(HueRotation::new(0.0).apply(rgb), rgb);
+            assert_eq!(HueRotation::new(360.0).apply(rgb), rgb);
+        }
+        // Grays have no hue to turn
+        assert_eq!(HueRotation::new(90.0).apply((77, 77, 77)), (77, 77, 77));
+        assert_eq!(HueRotation::new(120.0).apply((255, 0, 0)), (0, 113, 0));
+    }
+
+    #[test]
+    fn blends_keep_the_lightness_even() {
+        let (black, white) = (Oklab::from_rgb((0, 0, 0)), Oklab::from_rgb((255, 255, 255)));
+        let middle = black.lerp(white, 0.5);
+        assert!((middle.l - 0.5).abs() < 1e-5);
+        // Half way in lightness is a darker gray than half way between the sRGB components
+        assert_eq!(middle.to_rgb(), (99, 99, 99));
+    }
+}
@@ -0,0 +1,224 @@
+//! The Amiga "Boing" ball.
+//!
+//! A red and white checkered sphere bounces across a purple grid, spinning as it goes. The
+//! sphere is raster rendered without any 3D pipeline: for every pixel inside its disc the
+//! surface point is reconstructed from the distance to the center, turned into latitude and
+//! longitude, and the checker square is picked from those angles after adding the spin. A
+//! tilt of the spin axis gives the ball its famous slant, and a darkened copy of the disc
+//! offset to the side makes its shadow on the grid.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use std::f32::consts::PI;
+
+/// Number of checker squares around the equator
+const SQUARES_AROUND: f32 = 16.0;
+
+/// Number of checker squares from pole to pole
+const SQUARES_DOWN: f32 = 8.0;
+
+/// Tilt of the spin axis in radians
+const TILT: f32 = 0.3;
+
+/// Spin speed in radians per second
+const SPIN: f32 = 2.5;
+
+/// Radius of the ball as a fraction of the smallest screen dimension
+const RADIUS: f32 = 0.18;
+
+/// Downward acceleration in screen heights per second squared
+const GRAVITY: f32 = 2.5;
+
+/// Horizontal speed in screen widths per second
+const HORIZONTAL_SPEED: f32 = 0.25;
+
+/// Distance between grid lines in pixels
+const GRID_SPACING: usize = 32;
+
+/// Offset of the shadow from the ball, as a fraction of its radius
+const SHADOW_OFFSET: f32 = 0.35;
+
+/// A checkered ball bouncing over a grid
+pub struct AmigaBall {
+    /// Dimensions of the effect
+    base: DemoBase,
+}
+
+impl AmigaBall {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+        }
+    }
+
+    /// Returns the ball radius in pixels.
+    fn radius(&self) -> f32 {
+        (self.base.width.min(self.base.height) as f32 * RADIUS).max(1.0)
+    }
+
+    /// Returns the center of the ball at the given time.
+    ///
+    /// The ball follows a parabola between bounces on the floor and moves back and forth
+    /// between the side walls at constant speed.
+    fn position(&self, time: f32) -> (f32, f32) {
+        let radius = self.radius();
+        let (width, height) = (self.base.width as f32, self.base.height as f32);
+
+        // Horizontal motion is a triangle wave between the walls
+        let span = (width - 2.0 * radius).max(0.0);
+        let travel = (time * HORIZONTAL_SPEED * width).rem_euclid(2.0 * span.max(1.0));
+        let x = radius
+            + if travel > span {
+                2.0 * span - travel
+            } else {
+                travel
+            };
+
+        // Vertical motion repeats the same parabola between every bounce
+        let drop = (height - 2.0 * radius).max(0.0);
+        let gravity = GRAVITY * height;
+        let half_period = (2.0 * drop / gravity).sqrt();
+        let t = (time.rem_euclid(2.0 * half_period.max(f32::EPSILON))) - half_period;
+        let y = height - radius - drop + 0.5 * gravity * t * t;
+        (x, y.min(height - radius))
+    }
+
+    /// Returns the color of the ball surface at an offset from its center, or `None` if
+    /// the offset falls outside the ball.
+    fn surface(&self, dx: f32, dy: f32, radius: f32, spin: f32) -> Option<u32> {
+        let (nx, ny) = (dx / radius, dy / radius);
+        let nz_sq = 1.0 - nx * nx - ny * ny;
+        if nz_sq < 0.0 {
+            return None;
+        }
+        let nz = nz_sq.sqrt();
+
+        // Undo the axis tilt so latitude and longitude are measured around the spin axis
+        let (sin, cos) = TILT.sin_cos();
+        let (tx, ty) = (nx * cos + ny * sin, -nx * sin + ny * cos);
+        let latitude = ty.clamp(-1.0, 1.0).acos();
+        let longitude = tx.atan2(nz) + spin;
+
+        let column = (longitude / (2.0 * PI) * SQUARES_AROUND).floor() as i32;
+        let row = (latitude / PI * SQUARES_DOWN).floor() as i32;
+        // Light the ball from the upper left so it looks round
+        let light = (0.55 + 0.45 * (-nx * 0.4 - ny * 0.5 + nz * 0.77)).clamp(0.0, 1.0);
+        let shade = |c: f32| (c * light) as u8;
+        Some(if (column + row).rem_euclid(2) == 0 {
+            rgb(shade(230.0), shade(30.0), shade(30.0))
+        } else {
+            rgb(shade(250.0), shade(250.0), shade(250.0))
+        })
+    }
+
+    /// Draws the background grid.
+    fn background(&self, buffer: &mut [u32]) {
+        let background = rgb(170, 170, 170);
+        let line = rgb(150, 40, 150);
+        for (y, row) in buffer.chunks_exact_mut(self.base.width).enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = if x % GRID_SPACING == 0 || y % GRID_SPACING == 0 {
+                    line
+                } else {
+                    background
+                };
+            }
+        }
+    }
+}
+
+/// Darkens a pixel to half its brightness.
+fn darken(pixel: u32) -> u32 {
+    let channel = |shift: u32| (((pixel >> shift) & 0xff) / 2) as u8;
+    rgb(channel(16), channel(8), channel(0))
+}
+
+impl DemoEffect for AmigaBall {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        self.background(buffer);
+
+        let radius = self.radius();
+        let (cx, cy) = self.position(time);
+        let shadow = (
+            cx + radius * SHADOW_OFFSET,
+            cy + radius * SHADOW_OFFSET * 0.5,
+        );
+        let spin = time * SPIN;
+        let r = radius.ceil() as i32;
+
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let inside = ((dx * dx + dy * dy) as f32) <= radius * radius;
+                let (sx, sy) = (shadow.0 as i32 + dx, shadow.1 as i32 + dy);
+                if inside
+                    && sx >= 0
+                    && sy >= 0
+                    && (sx as usize) < self.base.width
+                    && (sy as usize) < self.base.height
+                {
+                    let i = sy as usize * self.base.width + sx as usize;
+                    buffer[i] = darken(buffer[i]);
+                }
+            }
+        }
+
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if let Some(color) = self.surface(dx as f32, dy as f32, radius, spin) {
+                    self.base
+                        .plot(buffer, cx as i32 + dx, cy as i32 + dy, color);
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ball_stays_on_screen() {
+        let ball = AmigaBall::new(320, 200);
+        let radius = ball.radius();
+        for step in 0..500 {
+            let (x, y) = ball.position(step as f32 * 0.037);
+            assert!(x >= radius - 0.01 && x <= 320.0 - radius + 0.01);
+            assert!(y >= radius - 0.01 && y <= 200.0 - radius + 0.01);
+        }
+    }
+
+    #[test]
+    fn ball_bounces_off_the_floor() {
+        let ball = AmigaBall::new(320, 200);
+        let floor = 200.0 - ball.radius();
+        let lowest = (0..1000)
+            .map(|step| ball.position(step as f32 * 0.01).1)
+            .fold(f32::MIN, f32::max);
+        assert!((lowest - floor).abs() < 2.0);
+    }
+
+    #[test]
+    fn surface_is_only_inside_the_disc() {
+        let ball = AmigaBall::new(100, 100);
+        assert!(ball.surface(0.0, 0.0, 10.0, 0.0).is_some());
+        assert!(ball.surface(8.0, 8.0, 10.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn spinning_changes_the_checkers() {
+        let ball = AmigaBall::new(100, 100);
+        let square = 2.0 * PI / SQUARES_AROUND;
+        assert_ne!(
+            ball.surface(0.0, 0.0, 10.0, square * 0.5),
+            ball.surface(0.0, 0.0, 10.0, square * 1.5)
+        );
+    }
+
+    #[test]
+    fn shadow_darkens_pixels() {
+        assert_eq!(darken(rgb(200, 100, 50)), rgb(100, 50, 25));
+    }
+}
@@ -0,0 +1,242 @@
+//! Strange attractor density plots.
+//!
+//! A single point is iterated through a chaotic map and every position it visits is counted
+//! in a density buffer. Because the orbit never settles, the counts gradually reveal the
+//! delicate shape of the attractor. Densities span several orders of magnitude, so they are
+//! log-scaled before being mapped through the palette. The attractor parameters drift
+//! slowly over time while old hits decay, so the shape keeps morphing.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::{Args, ValueEnum};
+
+/// Default number of iterations accumulated per frame
+const DEFAULT_ITERATIONS: usize = 100_000;
+
+/// Fraction of the accumulated density retained every frame
+const DENSITY_DECAY: f32 = 0.9;
+
+/// Integration step of the Lorenz system
+const LORENZ_STEP: f32 = 0.005;
+
+/// Strange attractors that can be plotted
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Attractor {
+    Lorenz,
+    Clifford,
+    DeJong,
+}
+
+/// User tunable attractor parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Attractor options")]
+#[group(id = "attractor-options")]
+pub struct Config {
+    #[arg(
+        id = "attractor",
+        long = "attractor",
+        value_name = "ATTRACTOR",
+        value_enum,
+        default_value_t = Attractor::Clifford,
+        help = "Strange attractor to plot"
+    )]
+    pub attractor: Attractor,
+
+    #[arg(
+        id = "attractor-iterations",
+        long = "attractor-iterations",
+        value_name = "ITERATIONS",
+        default_value_t = DEFAULT_ITERATIONS,
+        help = "Number of iterations accumulated per frame"
+    )]
+    pub iterations: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            attractor: Attractor::Clifford,
+            iterations: DEFAULT_ITERATIONS,
+        }
+    }
+}
+
+/// A strange attractor density plot
+pub struct AttractorPlot {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Attractor parameters
+    config: Config,
+    /// Color palette used for mapping densities
+    palette: Palette,
+    /// Number of hits accumulated by every pixel
+    density: Vec<f32>,
+    /// Current position of the orbit
+    point: (f32, f32, f32),
+    /// Seconds the attractor has been running, drives the parameter drift
+    elapsed: f32,
+}
+
+impl AttractorPlot {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            config,
+            palette,
+            density: vec![0.0; base.pixel_count()],
+            point: (0.1, 0.1, 0.1),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns the map parameters at the given time.
+    fn parameters(&self, time: f32) -> [f32; 4] {
+        let drift = |speed: f32, phase: f32| 0.2 * (time * speed + phase).sin();
+        match self.config.attractor {
+            // Sigma, rho, and beta
+            Attractor::Lorenz => [10.0, 28.0 + 20.0 * drift(0.1, 0.0), 8.0 / 3.0, 0.0],
+            Attractor::Clifford => [
+                -1.4 + drift(0.07, 0.0),
+                1.6 + drift(0.05, 1.0),
+                1.0 + drift(0.03, 2.0),
+                0.7 + drift(0.04, 3.0),
+            ],
+            Attractor::DeJong => [
+                1.4 + drift(0.06, 0.0),
+                -2.3 + drift(0.04, 1.0),
+                2.4 + drift(0.05, 2.0),
+                -2.1 + drift(0.03, 3.0),
+            ],
+        }
+    }
+
+    /// Applies one iteration of the attractor map to a point.
+    fn iterate(&self, (x, y, z): (f32, f32, f32), [a, b, c, d]: [f32; 4]) -> (f32, f32, f32) {
+        match self.config.attractor {
+            Attractor::Lorenz => (
+                x + LORENZ_STEP * a * (y - x),
+                y + LORENZ_STEP * (x * (b - z) - y),
+                z + LORENZ_STEP * (x * y - c * z),
+            ),
+            Attractor::Clifford => (
+                (a * y).sin() + c * (a * x).cos(),
+                (b * x).sin() + d * (b * y).cos(),
+                0.0,
+            ),
+            Attractor::DeJong => (
+                (a * y).sin() - (b * x).cos(),
+                (c * x).sin() - (d * y).cos(),
+                0.0,
+            ),
+        }
+    }
+
+    /// Projects a point of the attractor onto normalized screen coordinates in [0,1].
+    fn project(&self, (x, y, z): (f32, f32, f32)) -> (f32, f32) {
+        match self.config.attractor {
+            // Viewed from the side, the Lorenz butterfly fits in x in [-25,25], z in [0,50]
+            Attractor::Lorenz => ((x + 25.0) / 50.0, 1.0 - z / 50.0),
+            Attractor::Clifford => ((x + 3.0) / 6.0, (y + 3.0) / 6.0),
+            Attractor::DeJong => ((x + 2.2) / 4.4, (y + 2.2) / 4.4),
+        }
+    }
+
+    /// Iterates the attractor and accumulates the visited pixels into the density buffer.
+    fn accumulate(&mut self, time: f32) {
+        let params = self.parameters(time);
+        let (width, height) = (self.base.width as f32, self.base.height as f32);
+
+        for _ in 0..self.config.iterations {
+            self.point = self.iterate(self.point, params);
+            let (u, v) = self.project(self.point);
+            if (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) {
+                let i = (v * height) as usize * self.base.width + (u * width) as usize;
+                self.density[i] += 1.0;
+            }
+        }
+    }
+}
+
+impl DemoEffect for AttractorPlot {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        self.density.iter_mut().for_each(|d| *d *= DENSITY_DECAY);
+        self.accumulate(self.elapsed);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let max = self.density.iter().copied().fold(0.0, f32::max);
+        let log_max = (1.0 + max).ln().max(f32::EPSILON);
+
+        buffer
+            .iter_mut()
+            .zip(&self.density)
+            .for_each(|(pixel, &d)| {
+                *pixel = if d < 0.5 {
+                    rgb(0, 0, 0)
+                } else {
+                    self.palette.color((1.0 + d).ln() / log_max)
+                };
+            });
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.density = vec![0.0; self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_plot(attractor: Attractor) -> AttractorPlot {
+        let config = Config {
+            attractor,
+            iterations: 10_000,
+        };
+        AttractorPlot::new(100, 100, Palette::Hot, config)
+    }
+
+    #[test]
+    fn every_attractor_accumulates_hits_on_screen() {
+        for attractor in [Attractor::Lorenz, Attractor::Clifford, Attractor::DeJong] {
+            let mut plot = create_plot(attractor.clone());
+            plot.accumulate(0.0);
+            let hits: f32 = plot.density.iter().sum();
+            assert!(
+                hits > 5_000.0,
+                "{:?} orbit should stay mostly on screen",
+                attractor
+            );
+        }
+    }
+
+    #[test]
+    fn clifford_orbit_stays_bounded() {
+        let plot = create_plot(Attractor::Clifford);
+        let params = plot.parameters(0.0);
+        let mut point = (0.1, 0.1, 0.0);
+        for _ in 0..10_000 {
+            point = plot.iterate(point, params);
+            assert!(point.0.abs() <= 1.0 + params[2].abs());
+            assert!(point.1.abs() <= 1.0 + params[3].abs());
+        }
+    }
+
+    #[test]
+    fn parameters_drift_over_time() {
+        let plot = create_plot(Attractor::DeJong);
+        assert_ne!(plot.parameters(0.0), plot.parameters(10.0));
+    }
+
+    #[test]
+    fn density_decays_between_frames() {
+        let mut plot = create_plot(Attractor::Clifford);
+        plot.config.iterations = 0;
+        plot.density[0] = 100.0;
+        plot.update(0.0);
+        assert_eq!(plot.density[0], 100.0 * DENSITY_DECAY);
+    }
+}
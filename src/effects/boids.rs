@@ -0,0 +1,302 @@
+//! Boids flocking simulation.
+//!
+//! Craig Reynolds' boids steer using three local rules: separation keeps them from crowding
+//! their neighbors, alignment turns them toward the average heading of their neighbors,
+//! and cohesion pulls them toward the center of their neighbors. Only boids within the
+//! perception radius are taken into account, yet the flock as a whole moves in convincing
+//! swirling patterns. Each boid is drawn as a small triangle colored by its heading.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::Args;
+use std::f32::consts::TAU;
+
+/// Default number of boids in the flock
+const DEFAULT_COUNT: usize = 150;
+
+/// Default perception radius in pixels
+const DEFAULT_PERCEPTION: f32 = 40.0;
+
+/// Fraction of the perception radius within which boids push each other away
+const SEPARATION_RANGE: f32 = 0.4;
+
+/// Weights of the separation, alignment, and cohesion rules
+const WEIGHTS: (f32, f32, f32) = (1.5, 1.0, 0.8);
+
+/// Minimum and maximum speed in pixels per second
+const SPEED_LIMITS: (f32, f32) = (60.0, 140.0);
+
+/// Steering acceleration applied per unit of rule output, in pixels per second squared
+const STEERING: f32 = 120.0;
+
+/// Length of a boid's triangle in pixels
+const BOID_SIZE: f32 = 6.0;
+
+/// User tunable boids parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Boids options")]
+#[group(id = "boids-options")]
+pub struct Config {
+    #[arg(
+        id = "flock-size",
+        long = "flock-size",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_COUNT,
+        help = "Number of boids in the flock"
+    )]
+    pub count: usize,
+
+    #[arg(
+        id = "perception",
+        long = "perception",
+        value_name = "RADIUS",
+        default_value_t = DEFAULT_PERCEPTION,
+        help = "Distance in pixels within which boids see their neighbors"
+    )]
+    pub perception: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_COUNT,
+            perception: DEFAULT_PERCEPTION,
+        }
+    }
+}
+
+/// A single member of the flock
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Boid {
+    /// Position in pixels
+    position: (f32, f32),
+    /// Velocity in pixels per second
+    velocity: (f32, f32),
+}
+
+/// A flock of boids
+pub struct Boids {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Boids parameters
+    config: Config,
+    /// Color palette used for the boids
+    palette: Palette,
+    /// Members of the flock
+    flock: Vec<Boid>,
+}
+
+impl Boids {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let mut rng = fastrand::Rng::new();
+        let flock = (0..config.count)
+            .map(|_| {
+                let angle = rng.f32() * TAU;
+                let speed = SPEED_LIMITS.0 + rng.f32() * (SPEED_LIMITS.1 - SPEED_LIMITS.0);
+                Boid {
+                    position: (rng.f32() * width as f32, rng.f32() * height as f32),
+                    velocity: (angle.cos() * speed, angle.sin() * speed),
+                }
+            })
+            .collect();
+
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            palette,
+            flock,
+        }
+    }
+
+    /// Returns the shortest offset from `a` to `b` on the wrapping screen.
+    fn offset(&self, a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+        let (w, h) = (self.base.width as f32, self.base.height as f32);
+        let wrap = |d: f32, size: f32| d - size * (d / size).round();
+        (wrap(b.0 - a.0, w), wrap(b.1 - a.1, h))
+    }
+
+    /// Computes the combined steering direction of the three flocking rules for a boid.
+    fn steer(&self, index: usize) -> (f32, f32) {
+        let boid = self.flock[index];
+        let perception_sq = self.config.perception * self.config.perception;
+        let separation_sq = perception_sq * SEPARATION_RANGE * SEPARATION_RANGE;
+
+        let mut separation = (0.0, 0.0);
+        let mut heading = (0.0, 0.0);
+        let mut center = (0.0, 0.0);
+        let mut neighbors = 0;
+
+        for (i, other) in self.flock.iter().enumerate() {
+            let (dx, dy) = self.offset(boid.position, other.position);
+            let dist_sq = dx * dx + dy * dy;
+            if i == index || dist_sq > perception_sq {
+                continue;
+            }
+            neighbors += 1;
+            heading.0 += other.velocity.0;
+            heading.1 += other.velocity.1;
+            center.0 += dx;
+            center.1 += dy;
+            if dist_sq < separation_sq {
+                // Push away harder from closer neighbors
+                let weight = 1.0 / dist_sq.max(1.0).sqrt();
+                separation.0 -= dx * weight;
+                separation.1 -= dy * weight;
+            }
+        }
+
+        if neighbors == 0 {
+            return (0.0, 0.0);
+        }
+
+        let n = neighbors as f32;
+        let normalize = |(x, y): (f32, f32)| {
+            let len = (x * x + y * y).sqrt();
+            if len > f32::EPSILON {
+                (x / len, y / len)
+            } else {
+                (0.0, 0.0)
+            }
+        };
+        let separation = normalize(separation);
+        let alignment = normalize((heading.0 / n, heading.1 / n));
+        let cohesion = normalize((center.0 / n, center.1 / n));
+
+        (
+            separation.0 * WEIGHTS.0 + alignment.0 * WEIGHTS.1 + cohesion.0 * WEIGHTS.2,
+            separation.1 * WEIGHTS.0 + alignment.1 * WEIGHTS.1 + cohesion.1 * WEIGHTS.2,
+        )
+    }
+
+    /// Moves the flock forward by `dt` seconds.
+    fn advance(&mut self, dt: f32) {
+        let steering: Vec<(f32, f32)> = (0..self.flock.len()).map(|i| self.steer(i)).collect();
+        let (w, h) = (self.base.width as f32, self.base.height as f32);
+
+        for (boid, (sx, sy)) in self.flock.iter_mut().zip(steering) {
+            let (mut vx, mut vy) = (
+                boid.velocity.0 + sx * STEERING * dt,
+                boid.velocity.1 + sy * STEERING * dt,
+            );
+            let speed = (vx * vx + vy * vy).sqrt().max(f32::EPSILON);
+            let clamped = speed.clamp(SPEED_LIMITS.0, SPEED_LIMITS.1);
+            vx *= clamped / speed;
+            vy *= clamped / speed;
+
+            boid.velocity = (vx, vy);
+            boid.position = (
+                (boid.position.0 + vx * dt).rem_euclid(w),
+                (boid.position.1 + vy * dt).rem_euclid(h),
+            );
+        }
+    }
+}
+
+impl DemoEffect for Boids {
+    fn update(&mut self, dt: f32) {
+        self.advance(dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+        for boid in &self.flock {
+            let angle = boid.velocity.1.atan2(boid.velocity.0);
+            let color = self.palette.color(angle.rem_euclid(TAU) / TAU);
+
+            // Triangle pointing along the boid's heading
+            let vertex = |offset: f32, length: f32| {
+                let a = angle + offset;
+                (
+                    (boid.position.0 + a.cos() * length) as i32,
+                    (boid.position.1 + a.sin() * length) as i32,
+                )
+            };
+            let nose = vertex(0.0, BOID_SIZE);
+            let left = vertex(2.5, BOID_SIZE * 0.6);
+            let right = vertex(-2.5, BOID_SIZE * 0.6);
+            self.base.draw_line(buffer, nose, left, color);
+            self.base.draw_line(buffer, left, right, color);
+            self.base.draw_line(buffer, right, nose, color);
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_boids(flock: Vec<Boid>) -> Boids {
+        let mut boids = Boids::new(200, 200, Palette::Rainbow, Config::default());
+        boids.flock = flock;
+        boids
+    }
+
+    fn boid(x: f32, y: f32, vx: f32, vy: f32) -> Boid {
+        Boid {
+            position: (x, y),
+            velocity: (vx, vy),
+        }
+    }
+
+    #[test]
+    fn flock_contains_configured_number_of_boids() {
+        let config = Config {
+            count: 17,
+            ..Config::default()
+        };
+        let boids = Boids::new(100, 100, Palette::Rainbow, config);
+        assert_eq!(boids.flock.len(), 17);
+    }
+
+    #[test]
+    fn lone_boid_does_not_steer() {
+        let boids = create_boids(vec![boid(50.0, 50.0, 100.0, 0.0)]);
+        assert_eq!(boids.steer(0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn crowded_boids_separate() {
+        let boids = create_boids(vec![
+            boid(100.0, 100.0, 0.0, 100.0),
+            boid(105.0, 100.0, 0.0, 100.0),
+        ]);
+        let (sx, _) = boids.steer(0);
+        assert!(sx < 0.0, "The boid should steer away from its neighbor");
+    }
+
+    #[test]
+    fn distant_boids_cohere() {
+        let boids = create_boids(vec![
+            boid(100.0, 100.0, 0.0, 100.0),
+            boid(130.0, 100.0, 0.0, 100.0),
+        ]);
+        let (sx, _) = boids.steer(0);
+        assert!(sx > 0.0, "The boid should steer toward its neighbor");
+    }
+
+    #[test]
+    fn neighbors_are_found_across_screen_edges() {
+        let boids = create_boids(vec![
+            boid(2.0, 100.0, 0.0, 100.0),
+            boid(198.0, 100.0, 0.0, 100.0),
+        ]);
+        assert_eq!(boids.offset((2.0, 100.0), (198.0, 100.0)), (-4.0, 0.0));
+        assert_ne!(boids.steer(0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn speed_stays_within_limits() {
+        let mut boids = create_boids(vec![
+            boid(50.0, 50.0, 1000.0, 0.0),
+            boid(60.0, 50.0, 1.0, 0.0),
+        ]);
+        boids.advance(0.1);
+        for boid in &boids.flock {
+            let speed = (boid.velocity.0.powi(2) + boid.velocity.1.powi(2)).sqrt();
+            assert!(speed >= SPEED_LIMITS.0 - 1e-3 && speed <= SPEED_LIMITS.1 + 1e-3);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Bump-mapped plasma.
+//!
+//! The plasma pattern is treated as a heightmap rather than being colored directly. Surface
+//! normals are estimated from the height differences between neighboring pixels, and a
+//! point light hovering above the surface shades every pixel with simple diffuse lighting.
+//! As the light moves around, the plasma appears embossed into a glossy 3D relief.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect, InputEvent};
+use crate::effects::plasma::{Plasma, HUE_STEP};
+use crate::effects::Settings;
+use crate::palette::{Crossfade, Palette};
+use clap::{Args, ValueEnum};
+use minifb::Key;
+
+/// Default multiplier applied to the plasma heights
+const DEFAULT_HEIGHT: f32 = 40.0;
+
+/// Height of the light above the surface, as a fraction of the smallest screen dimension
+const LIGHT_ELEVATION: f32 = 0.15;
+
+/// Light intensity that remains when a pixel faces away from the light
+const AMBIENT: f32 = 0.15;
+
+/// Paths the point light can follow
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum LightPath {
+    Circle,
+    Lissajous,
+    Sweep,
+}
+
+/// User tunable bump mapping parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Bumpmap options")]
+#[group(id = "bumpmap-options")]
+pub struct Config {
+    #[arg(
+        id = "light-path",
+        long = "light-path",
+        value_name = "PATH",
+        value_enum,
+        default_value_t = LightPath::Lissajous,
+        help = "Path followed by the point light"
+    )]
+    pub light_path: LightPath,
+
+    #[arg(
+        id = "bump-height",
+        long = "bump-height",
+        value_name = "HEIGHT",
+        default_value_t = DEFAULT_HEIGHT,
+        help = "Exaggeration applied to the plasma heights"
+    )]
+    pub height: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            light_path: LightPath::Lissajous,
+            height: DEFAULT_HEIGHT,
+        }
+    }
+}
+
+/// A plasma heightmap lit by a moving point light
+pub struct BumpMap {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Bump mapping parameters
+    config: Config,
+    /// Color palette used for the surface, fading in when changed
+    palette: Crossfade,
+    /// Plasma generating the heightmap
+    plasma: Plasma,
+}
+
+impl BumpMap {
+    pub fn new(
+        width: usize,
+        height: usize,
+        plasma: Plasma,
+        palette: Palette,
+        config: Config,
+    ) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            palette: Crossfade::new(palette),
+            plasma,
+        }
+    }
+
+    /// Builds a bump map over a plasma from the command line settings.
+    pub fn from_settings(width: usize, height: usize, settings: &Settings) -> Self {
+        let plasma = Plasma::from_settings(width, height, settings);
+        let palette = settings.palette.clone();
+        let mut bumpmap = Self::new(width, height, plasma, palette, settings.bumpmap.clone());
+        let (reversed, hue) = (settings.palette_reversed, settings.palette_hue);
+        bumpmap.palette.set_look(reversed, hue);
+        bumpmap
+    }
+
+    /// Returns the position of the light above the screen at the given time.
+    fn light(&self, time: f32) -> (f32, f32, f32) {
+        let (center_x, center_y) = self.base.center();
+        let elevation = self.base.width.min(self.base.height) as f32 * LIGHT_ELEVATION;
+        let (x, y) = match self.config.light_path {
+            LightPath::Circle => (time.cos() * 0.6, time.sin() * 0.6),
+            LightPath::Lissajous => ((time * 0.9).sin() * 0.8, (time * 1.3).cos() * 0.8),
+            LightPath::Sweep => ((time * 0.5).sin(), 0.0),
+        };
+        (center_x + x * center_x, center_y + y * center_y, elevation)
+    }
+
+    /// Computes the diffuse light intensity of a pixel of the heightmap in the [0,1] range.
+    fn shade(&self, heights: &[f32], x: usize, y: usize, light: (f32, f32, f32)) -> f32 {
+        let width = self.base.width;
+        let height = self.base.height;
+        let at = |x: usize, y: usize| heights[y * width + x] * self.config.height;
+
+        // Central differences give the slope of the surface along each axis
+        let dx = at((x + 1).min(width - 1), y) - at(x.saturating_sub(1), y);
+        let dy = at(x, (y + 1).min(height - 1)) - at(x, y.saturating_sub(1));
+        let normal = (-dx, -dy, 2.0);
+
+        let to_light = (light.0 - x as f32, light.1 - y as f32, light.2 - at(x, y));
+        let dot = normal.0 * to_light.0 + normal.1 * to_light.1 + normal.2 * to_light.2;
+        let length = |v: (f32, f32, f32)| (v.0 * v.0 + v.1 * v.1 + v.2 * v.2).sqrt();
+        let cosine = dot / (length(normal) * length(to_light)).max(f32::EPSILON);
+
+        AMBIENT + (1.0 - AMBIENT) * cosine.max(0.0)
+    }
+}
+
+impl DemoEffect for BumpMap {
+    fn update(&mut self, dt: f32) {
+        self.palette.update(dt);
+        self.plasma.update(dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let mut heights = vec![0.0; self.base.pixel_count()];
+        self.plasma.field(&mut heights, time);
+        let light = self.light(time);
+        let width = self.base.width;
+
+        for_each_row(buffer, width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let intensity = self.shade(&heights, x, y, light);
+                let (r, g, b) = self.palette.rgb(heights[y * width + x]);
+                let lit = |c: u8| (c as f32 * intensity) as u8;
+                *pixel = rgb(lit(r), lit(g), lit(b));
+            }
+        });
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Space) => self.palette.next(),
+            InputEvent::Key(Key::R) => self.palette.reverse(),
+            InputEvent::Key(Key::H) => self.palette.rotate_hue(HUE_STEP),
+            InputEvent::ShiftKey(Key::H) => self.palette.rotate_hue(-HUE_STEP),
+            event => self.plasma.handle_input(event),
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.plasma.resize(width, height);
+    }
+
+    /// Saves the plasma controls along with the palette of the lit surface.
+    fn save_settings(&self, settings: &mut Settings) {
+        self.plasma.save_settings(settings);
+        settings.palette = self.palette.palette().clone();
+        settings.palette_reversed = self.palette.is_reversed();
+        settings.palette_hue = self.palette.hue();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::plasma::Shape;
+
+    fn create_bumpmap(config: Config) -> BumpMap {
+        let plasma = Plasma::new(64, 64, Shape::Ripple, Palette::Rainbow, 10.0);
+        BumpMap::new(64, 64, plasma, Palette::Rainbow, config)
+    }
+
+    #[test]
+    fn flat_surface_is_brightest_under_the_light() {
+        let bumpmap = create_bumpmap(Config::default());
+        let heights = vec![0.0; 64 * 64];
+        let light = (32.0, 32.0, 10.0);
+        let below = bumpmap.shade(&heights, 32, 32, light);
+        let aside = bumpmap.shade(&heights, 5, 5, light);
+        assert!((below - 1.0).abs() < 1e-5);
+        assert!(aside < below);
+    }
+
+    #[test]
+    fn slopes_facing_the_light_are_brighter() {
+        let bumpmap = create_bumpmap(Config::default());
+        // Heights increase toward the right, so the surface faces left
+        let heights: Vec<f32> = (0..64 * 64).map(|i| (i % 64) as f32 / 64.0).collect();
+        let left_light = bumpmap.shade(&heights, 32, 32, (0.0, 32.0, 10.0));
+        let right_light = bumpmap.shade(&heights, 32, 32, (64.0, 32.0, 10.0));
+        assert!(left_light > right_light);
+    }
+
+    #[test]
+    fn shading_never_drops_below_ambient() {
+        let bumpmap = create_bumpmap(Config::default());
+        let heights: Vec<f32> = (0..64 * 64).map(|i| ((i % 7) as f32).sin()).collect();
+        for y in 0..64 {
+            for x in 0..64 {
+                assert!(bumpmap.shade(&heights, x, y, (0.0, 0.0, 1.0)) >= AMBIENT);
+            }
+        }
+    }
+
+    #[test]
+    fn light_follows_the_configured_path() {
+        let sweep = create_bumpmap(Config {
+            light_path: LightPath::Sweep,
+            ..Config::default()
+        });
+        let (_, y0, _) = sweep.light(0.0);
+        let (_, y1, _) = sweep.light(2.0);
+        assert_eq!(y0, y1, "A sweeping light only moves horizontally");
+    }
+}
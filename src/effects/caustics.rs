@@ -0,0 +1,244 @@
+//! Underwater light caustics.
+//!
+//! Sunlight refracted by a wavy water surface gathers into a net of bright, wobbling lines
+//! on the pool floor. The net is approximated with the edges of Worley cells: feature
+//! points jitter inside a grid, and the difference between the distances to the two
+//! nearest points (F2 - F1) is zero along the cell borders, which light up. Sample
+//! positions are displaced by gradient noise before the lookup, mimicking refraction by
+//! the surface, and two layers of different size drifting in different directions are
+//! added together so the lines cross and shimmer.
+//!
+//! `Up`/`Down` make the light brighter or dimmer.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect, InputEvent};
+use crate::effects::Settings;
+use crate::noise::Noise;
+use clap::Args;
+use minifb::Key;
+use std::f32::consts::TAU;
+
+/// Default brightness of the light on the floor
+const DEFAULT_BRIGHTNESS: f32 = 1.0;
+
+/// Amount the brightness changes per key press
+const BRIGHTNESS_STEP: f32 = 0.1;
+
+/// Largest brightness selectable at runtime
+const MAX_BRIGHTNESS: f32 = 3.0;
+
+/// Number of Worley cells across the smallest screen dimension for the first layer
+const CELLS: f32 = 5.0;
+
+/// Strength of the refraction displacement, in cells
+const REFRACTION: f32 = 0.35;
+
+/// Width of the bright lines, in cells
+const LINE_WIDTH: f32 = 0.12;
+
+/// User tunable caustics parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Caustics options")]
+#[group(id = "caustics-options")]
+pub struct Config {
+    #[arg(
+        id = "caustics-brightness",
+        long = "caustics-brightness",
+        value_name = "BRIGHTNESS",
+        default_value_t = DEFAULT_BRIGHTNESS,
+        value_parser = parse_brightness,
+        help = "Brightness of the light caustics, from 0 to 3"
+    )]
+    pub brightness: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            brightness: DEFAULT_BRIGHTNESS,
+        }
+    }
+}
+
+/// Parses a brightness from 0 to [`MAX_BRIGHTNESS`].
+fn parse_brightness(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(brightness) if (0.0..=MAX_BRIGHTNESS).contains(&brightness) => Ok(brightness),
+        _ => Err(format!(
+            "invalid brightness '{spec}', expected 0 to {MAX_BRIGHTNESS}"
+        )),
+    }
+}
+
+/// Hashes integer cell coordinates into a pseudo-random value in [0,1).
+fn hash(x: i32, y: i32, salt: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ salt.wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h & 0xff_ffff) as f32 / 0x100_0000 as f32
+}
+
+/// Returns the F2 - F1 Worley value at a point given in cell units.
+///
+/// Every cell holds one feature point circling around a random spot inside it, so the
+/// cells slowly wobble over time.
+fn worley_edge(x: f32, y: f32, time: f32) -> f32 {
+    let (cx, cy) = (x.floor() as i32, y.floor() as i32);
+    let (mut f1, mut f2) = (f32::MAX, f32::MAX);
+    for ny in cy - 1..=cy + 1 {
+        for nx in cx - 1..=cx + 1 {
+            let phase = hash(nx, ny, 2) * TAU + time;
+            let px = nx as f32 + 0.5 + 0.3 * (hash(nx, ny, 0) - 0.5) + 0.2 * phase.cos();
+            let py = ny as f32 + 0.5 + 0.3 * (hash(nx, ny, 1) - 0.5) + 0.2 * phase.sin();
+            let dist = ((px - x).powi(2) + (py - y).powi(2)).sqrt();
+            if dist < f1 {
+                f2 = f1;
+                f1 = dist;
+            } else if dist < f2 {
+                f2 = dist;
+            }
+        }
+    }
+    f2 - f1
+}
+
+/// Light caustics dancing on the floor of a pool
+pub struct Caustics {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Brightness of the light, adjustable at runtime
+    brightness: f32,
+    /// Noise field refracting the light
+    noise: Noise,
+}
+
+impl Caustics {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            brightness: config.brightness.clamp(0.0, MAX_BRIGHTNESS),
+            noise: Noise::new(fastrand::u64(..)),
+        }
+    }
+
+    /// Returns the light intensity of one layer of caustics at a point given in cells.
+    fn layer(&self, x: f32, y: f32, time: f32) -> f32 {
+        let rx = x + REFRACTION * self.noise.sample(x * 0.5, y * 0.5, time * 0.3);
+        let ry = y + REFRACTION * self.noise.sample(x * 0.5 + 17.0, y * 0.5, time * 0.3);
+        let edge = worley_edge(rx, ry, time);
+        let line = (1.0 - edge / LINE_WIDTH).max(0.0);
+        line * line
+    }
+
+    /// Returns the light intensity at a pixel, before brightness is applied.
+    fn light(&self, x: usize, y: usize, time: f32) -> f32 {
+        let step = CELLS / self.base.width.min(self.base.height).max(1) as f32;
+        let (u, v) = (x as f32 * step, y as f32 * step);
+        let near = self.layer(u + time * 0.15, v + time * 0.1, time);
+        let far = self.layer(u * 1.7 - time * 0.1, v * 1.7 + 31.0, time * 1.3);
+        near * 0.7 + far * 0.5
+    }
+}
+
+/// Maps light intensity onto pool water, from deep blue through cyan to white.
+fn water(light: f32) -> u32 {
+    let light = light.clamp(0.0, 1.5);
+    let channel = |base: f32, gain: f32| (base + gain * light).min(255.0) as u8;
+    rgb(
+        channel(0.0, 170.0),
+        channel(50.0, 200.0),
+        channel(110.0, 145.0),
+    )
+}
+
+impl DemoEffect for Caustics {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = water(self.light(x, y, time) * self.brightness);
+            }
+        });
+    }
+
+    /// Handles the brightness controls.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Up) => {
+                self.brightness = (self.brightness + BRIGHTNESS_STEP).min(MAX_BRIGHTNESS)
+            }
+            InputEvent::Key(Key::Down) => {
+                self.brightness = (self.brightness - BRIGHTNESS_STEP).max(0.0)
+            }
+            _ => {}
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.caustics.brightness = self.brightness;
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_deterministic_and_normalized() {
+        assert_eq!(hash(3, -7, 1), hash(3, -7, 1));
+        assert_ne!(hash(3, -7, 1), hash(3, -7, 2));
+        for i in -50..50 {
+            assert!((0.0..1.0).contains(&hash(i, i * 3, 0)));
+        }
+    }
+
+    #[test]
+    fn worley_edge_is_never_negative() {
+        for i in 0..200 {
+            let t = i as f32 * 0.173;
+            assert!(worley_edge(t, t * 0.6 - 2.0, t) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn caustics_form_lines() {
+        let mut caustics = Caustics::new(64, 64, Config::default());
+        caustics.noise = Noise::new(4);
+        let lights: Vec<f32> = (0..64).map(|x| caustics.light(x, 20, 1.0)).collect();
+        assert!(lights.iter().any(|&l| l > 0.5));
+        assert!(lights.contains(&0.0));
+    }
+
+    #[test]
+    fn darkness_is_deep_blue() {
+        assert_eq!(water(0.0), rgb(0, 50, 110));
+        assert_eq!(water(10.0), water(1.5));
+    }
+
+    #[test]
+    fn brightness_stays_in_range() {
+        let mut caustics = Caustics::new(8, 8, Config { brightness: 0.05 });
+        caustics.handle_input(InputEvent::Key(Key::Down));
+        assert_eq!(caustics.brightness, 0.0);
+        for _ in 0..100 {
+            caustics.handle_input(InputEvent::Key(Key::Up));
+        }
+        assert_eq!(caustics.brightness, MAX_BRIGHTNESS);
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        let mut caustics = Caustics::new(8, 8, Config::default());
+        caustics.handle_input(InputEvent::Key(Key::Up));
+        let mut settings = Settings::default();
+        caustics.save_settings(&mut settings);
+        assert_eq!(
+            settings.caustics.brightness,
+            DEFAULT_BRIGHTNESS + BRIGHTNESS_STEP
+        );
+    }
+}
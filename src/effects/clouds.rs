@@ -0,0 +1,250 @@
+//! Fractal clouds made with the diamond-square algorithm.
+//!
+//! The heightfield starts with random corners and is refined by alternating two steps: the
+//! diamond step sets the center of every square to the average of its corners, and the
+//! square step sets the midpoint of every edge to the average of its neighbors, each plus
+//! a random offset that shrinks with every subdivision. The result is a cloudy fractal that
+//! is stretched over the screen and shaded from sky blue to white. Every few seconds a new
+//! field is generated and the display blends smoothly from the old field to the new one.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+use clap::Args;
+
+/// Number of subdivisions, the field has `2^DETAIL + 1` points per side
+const DETAIL: u32 = 7;
+
+/// Default time in seconds spent blending from one field to the next
+const DEFAULT_PERIOD: f32 = 4.0;
+
+/// Default factor by which the random offsets shrink with every subdivision
+const DEFAULT_ROUGHNESS: f32 = 0.55;
+
+/// User tunable clouds parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Clouds options")]
+#[group(id = "clouds-options")]
+pub struct Config {
+    #[arg(
+        id = "clouds-period",
+        long = "clouds-period",
+        value_name = "SECONDS",
+        default_value_t = DEFAULT_PERIOD,
+        help = "Seconds spent morphing from one cloud field to the next"
+    )]
+    pub period: f32,
+
+    #[arg(
+        id = "roughness",
+        long = "roughness",
+        value_name = "ROUGHNESS",
+        default_value_t = DEFAULT_ROUGHNESS,
+        help = "Factor by which the detail shrinks at every subdivision, higher is rougher"
+    )]
+    pub roughness: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            period: DEFAULT_PERIOD,
+            roughness: DEFAULT_ROUGHNESS,
+        }
+    }
+}
+
+/// Generates a square diamond-square heightfield normalized to the [0,1] range.
+fn diamond_square(rng: &mut fastrand::Rng, roughness: f32) -> Vec<f32> {
+    let size = (1 << DETAIL) + 1;
+    let mut field = vec![0.0; size * size];
+    let mut random = |amplitude: f32| (rng.f32() * 2.0 - 1.0) * amplitude;
+
+    for &(x, y) in &[(0, 0), (size - 1, 0), (0, size - 1), (size - 1, size - 1)] {
+        field[y * size + x] = random(1.0);
+    }
+
+    let mut step = size - 1;
+    let mut amplitude = 1.0;
+    while step > 1 {
+        let half = step / 2;
+
+        // Diamond step: centers of the squares
+        for y in (half..size).step_by(step) {
+            for x in (half..size).step_by(step) {
+                let corners = field[(y - half) * size + x - half]
+                    + field[(y - half) * size + x + half]
+                    + field[(y + half) * size + x - half]
+                    + field[(y + half) * size + x + half];
+                field[y * size + x] = corners / 4.0 + random(amplitude);
+            }
+        }
+
+        // Square step: edge midpoints, only averaging neighbors inside the field
+        for y in (0..size).step_by(half) {
+            let start = if (y / half).is_multiple_of(2) {
+                half
+            } else {
+                0
+            };
+            for x in (start..size).step_by(step) {
+                let neighbors = [
+                    (x.checked_sub(half), Some(y)),
+                    (Some(x + half).filter(|&v| v < size), Some(y)),
+                    (Some(x), y.checked_sub(half)),
+                    (Some(x), Some(y + half).filter(|&v| v < size)),
+                ];
+                let (sum, count) = neighbors
+                    .iter()
+                    .filter_map(|&(nx, ny)| Some(field[ny? * size + nx?]))
+                    .fold((0.0, 0.0), |(sum, count), v| (sum + v, count + 1.0));
+                field[y * size + x] = sum / count + random(amplitude);
+            }
+        }
+
+        step = half;
+        amplitude *= roughness;
+    }
+
+    let (min, max) = field
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let range = (max - min).max(f32::EPSILON);
+    field.iter_mut().for_each(|v| *v = (*v - min) / range);
+    field
+}
+
+/// Maps a cloud density in [0,1] onto a sky color, from deep blue to white.
+fn sky(v: f32) -> u32 {
+    // Thin clouds let the blue through, thick ones turn white
+    let t = ((v - 0.35) / 0.65).clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    let mix = |a: f32, b: f32| (a + (b - a) * t) as u8;
+    rgb(mix(40.0, 255.0), mix(110.0, 255.0), mix(210.0, 255.0))
+}
+
+/// Fractal clouds morphing between generated fields
+pub struct Clouds {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Clouds parameters
+    config: Config,
+    /// Field shown at the start of the current blend
+    from: Vec<f32>,
+    /// Field shown at the end of the current blend
+    to: Vec<f32>,
+    /// Time at which the current blend started
+    blend_start: Option<f32>,
+    /// Progress of the current blend in [0,1]
+    blend: f32,
+    /// Seconds the clouds have been drifting
+    elapsed: f32,
+    /// Random number generator used to generate the fields
+    rng: fastrand::Rng,
+}
+
+impl Clouds {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        let mut rng = fastrand::Rng::new();
+        let from = diamond_square(&mut rng, config.roughness);
+        let to = diamond_square(&mut rng, config.roughness);
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            from,
+            to,
+            blend_start: None,
+            blend: 0.0,
+            elapsed: 0.0,
+            rng,
+        }
+    }
+
+    /// Returns how far the current blend has progressed, starting a new one once the
+    /// current blend is complete.
+    fn progress(&mut self, time: f32) -> f32 {
+        let period = self.config.period.max(0.1);
+        let start = *self.blend_start.get_or_insert(time);
+        if time - start >= period {
+            self.from = std::mem::take(&mut self.to);
+            self.to = diamond_square(&mut self.rng, self.config.roughness);
+            self.blend_start = Some(time);
+            return 0.0;
+        }
+        // Ease in and out so each blend starts and ends without a visible jolt
+        let t = (time - start) / period;
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl DemoEffect for Clouds {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        self.blend = self.progress(self.elapsed);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let blend = self.blend;
+        let size = (1 << DETAIL) + 1;
+        let last = (size - 1) as f32;
+        let (sx, sy) = (
+            last / self.base.width.max(1) as f32,
+            last / self.base.height.max(1) as f32,
+        );
+
+        for_each_row(buffer, self.base.width, |y, row| {
+            let fy = y as f32 * sy;
+            let (y0, ty) = (fy as usize, fy.fract());
+            let y1 = (y0 + 1).min(size - 1);
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let fx = x as f32 * sx;
+                let (x0, tx) = (fx as usize, fx.fract());
+                let x1 = (x0 + 1).min(size - 1);
+                let at = |i: usize| self.from[i] + (self.to[i] - self.from[i]) * blend;
+                let top = at(y0 * size + x0) + (at(y0 * size + x1) - at(y0 * size + x0)) * tx;
+                let bottom = at(y1 * size + x0) + (at(y1 * size + x1) - at(y1 * size + x0)) * tx;
+                *pixel = sky(top + (bottom - top) * ty);
+            }
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_is_normalized() {
+        let field = diamond_square(&mut fastrand::Rng::with_seed(5), DEFAULT_ROUGHNESS);
+        assert_eq!(field.len(), ((1 << DETAIL) + 1) * ((1 << DETAIL) + 1));
+        assert!(field.iter().all(|v| (0.0..=1.0).contains(v)));
+        assert!(field.contains(&0.0) && field.contains(&1.0));
+    }
+
+    #[test]
+    fn smooth_fields_have_small_neighbor_differences() {
+        let field = diamond_square(&mut fastrand::Rng::with_seed(6), 0.3);
+        let size = (1 << DETAIL) + 1;
+        let largest = (0..size - 1)
+            .map(|x| (field[x] - field[x + 1]).abs())
+            .fold(0.0, f32::max);
+        assert!(largest < 0.2, "{largest}");
+    }
+
+    #[test]
+    fn fields_are_replaced_after_a_period() {
+        let mut clouds = Clouds::new(32, 32, Config::default());
+        let target = clouds.to.clone();
+        assert_eq!(clouds.progress(1.0), 0.0);
+        assert!(clouds.progress(1.0 + DEFAULT_PERIOD * 0.5) > 0.0);
+        assert_eq!(clouds.progress(1.0 + DEFAULT_PERIOD), 0.0);
+        assert_eq!(clouds.from, target);
+    }
+
+    #[test]
+    fn thick_clouds_are_white() {
+        assert_eq!(sky(1.0), rgb(255, 255, 255));
+        assert_eq!(sky(0.0), rgb(40, 110, 210));
+    }
+}
@@ -0,0 +1,133 @@
+//! Copper bars, also known as raster bars.
+//!
+//! On the Amiga the copper coprocessor could change the background color on every scanline,
+//! which demos used to draw shiny horizontal bars sweeping up and down the screen. Here each
+//! bar is a vertical color gradient following its own sine wave, and overlapping bars are
+//! blended additively so their colors mix where they cross.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::hsv_to_rgb;
+
+/// Number of bars on screen
+const BAR_COUNT: usize = 7;
+
+/// Height of a bar as a fraction of the screen height
+const BAR_HEIGHT: f32 = 0.08;
+
+/// Phase offset between consecutive bars in seconds
+const BAR_SPACING: f32 = 0.35;
+
+/// Angular frequency of the bar motion in radians per second
+const BAR_FREQUENCY: f32 = 1.6;
+
+/// A copper bars effect
+pub struct CopperBars {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Base color of every bar
+    colors: Vec<(u8, u8, u8)>,
+}
+
+impl CopperBars {
+    pub fn new(width: usize, height: usize) -> Self {
+        let colors = (0..BAR_COUNT)
+            .map(|i| hsv_to_rgb(i as f32 * 360.0 / BAR_COUNT as f32, 0.9, 1.0))
+            .collect();
+
+        Self {
+            base: DemoBase::new(width, height),
+            colors,
+        }
+    }
+
+    /// Returns the vertical center of every bar at the given time.
+    fn centers(&self, time: f32) -> Vec<f32> {
+        let h = self.base.height as f32;
+        let amplitude = h * (0.5 - BAR_HEIGHT);
+        (0..BAR_COUNT)
+            .map(|i| {
+                let t = time - i as f32 * BAR_SPACING;
+                h * 0.5 + amplitude * (t * BAR_FREQUENCY).sin()
+            })
+            .collect()
+    }
+
+    /// Computes the color of a scanline by additively blending every bar covering it.
+    fn scanline(&self, centers: &[f32], y: f32) -> u32 {
+        let half_height = self.base.height as f32 * BAR_HEIGHT * 0.5;
+        let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+
+        for (center, &(br, bg, bb)) in centers.iter().zip(&self.colors) {
+            let offset = (y - center).abs() / half_height;
+            if offset < 1.0 {
+                // Cosine falloff gives the bars their rounded, metallic look
+                let intensity = (offset * std::f32::consts::FRAC_PI_2).cos();
+                r += br as f32 * intensity;
+                g += bg as f32 * intensity;
+                b += bb as f32 * intensity;
+            }
+        }
+
+        rgb(r.min(255.0) as u8, g.min(255.0) as u8, b.min(255.0) as u8)
+    }
+}
+
+impl DemoEffect for CopperBars {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let centers = self.centers(time);
+
+        buffer
+            .chunks_exact_mut(self.base.width)
+            .enumerate()
+            .for_each(|(y, row)| row.fill(self.scanline(&centers, y as f32)));
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanline_is_black_away_from_bars() {
+        let bars = CopperBars::new(100, 200);
+        let centers = vec![50.0; BAR_COUNT];
+        assert_eq!(bars.scanline(&centers, 150.0), rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn scanline_is_brightest_at_bar_center() {
+        let bars = CopperBars::new(100, 200);
+        let mut centers = vec![-1000.0; BAR_COUNT];
+        centers[0] = 100.0;
+        let (r, g, b) = bars.colors[0];
+        assert_eq!(bars.scanline(&centers, 100.0), rgb(r, g, b));
+    }
+
+    #[test]
+    fn overlapping_bars_blend_additively() {
+        let bars = CopperBars::new(100, 200);
+        let mut single = vec![-1000.0; BAR_COUNT];
+        single[0] = 100.0;
+        let mut overlapping = single.clone();
+        overlapping[1] = 100.0;
+
+        let channels = |c: u32| [(c >> 16) & 0xff, (c >> 8) & 0xff, c & 0xff];
+        let one = channels(bars.scanline(&single, 100.0));
+        let two = channels(bars.scanline(&overlapping, 100.0));
+        assert!(one.iter().zip(&two).all(|(a, b)| b >= a));
+        assert_ne!(one, two);
+    }
+
+    #[test]
+    fn bars_stay_on_screen() {
+        let bars = CopperBars::new(100, 200);
+        for step in 0..100 {
+            for center in bars.centers(step as f32 * 0.13) {
+                assert!((0.0..=200.0).contains(&center));
+            }
+        }
+    }
+}
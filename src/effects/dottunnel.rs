@@ -0,0 +1,142 @@
+//! A tunnel made of rings of dots.
+//!
+//! Rings of dots are spaced evenly along the depth axis and the camera flies through them.
+//! Every ring is shifted sideways by a slow sine path, so the vanishing point keeps moving
+//! and the tunnel appears to bend, and rings are rotated a little more the farther away they
+//! are, which twists the tunnel. Dots are projected with a perspective divide and grow and
+//! brighten as they approach the camera.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use std::f32::consts::TAU;
+
+/// Number of rings visible at once
+const RINGS: usize = 40;
+
+/// Number of dots on every ring
+const DOTS_PER_RING: usize = 24;
+
+/// Distance between two consecutive rings
+const RING_SPACING: f32 = 0.25;
+
+/// Distance travelled by the camera per second
+const SPEED: f32 = 1.5;
+
+/// Closest distance at which a ring is still drawn
+const NEAR_PLANE: f32 = 0.2;
+
+/// Extra rotation of a ring per unit of depth, in radians
+const TWIST: f32 = 0.35;
+
+/// Rings of dots receding toward a moving vanishing point
+pub struct DotTunnel {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used to shade the dots by depth
+    palette: Palette,
+}
+
+impl DotTunnel {
+    pub fn new(width: usize, height: usize, palette: Palette) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+        }
+    }
+
+    /// Returns the depth of every ring, sorted from farthest to nearest.
+    fn ring_depths(time: f32) -> Vec<f32> {
+        let span = RINGS as f32 * RING_SPACING;
+        let travelled = time * SPEED;
+        let mut depths: Vec<f32> = (0..RINGS)
+            .map(|i| NEAR_PLANE + (i as f32 * RING_SPACING - travelled).rem_euclid(span))
+            .collect();
+        depths.sort_by(|a, b| b.total_cmp(a));
+        depths
+    }
+
+    /// Returns the sideways offset of the tunnel center at the given absolute depth.
+    fn path(depth: f32) -> (f32, f32) {
+        ((depth * 0.4).sin() * 0.6, (depth * 0.3).cos() * 0.4)
+    }
+
+    /// Projects a point in camera space onto the screen.
+    fn project(&self, x: f32, y: f32, z: f32) -> (i32, i32) {
+        let (center_x, center_y) = self.base.center();
+        let focal = center_x.min(center_y);
+        (
+            (center_x + x / z * focal) as i32,
+            (center_y + y / z * focal) as i32,
+        )
+    }
+}
+
+impl DemoEffect for DotTunnel {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+        let travelled = time * SPEED;
+        // Keep the camera on the path so the nearest rings stay roughly centered
+        let camera = Self::path(travelled);
+        let far = NEAR_PLANE + RINGS as f32 * RING_SPACING;
+
+        for z in Self::ring_depths(time) {
+            let (ox, oy) = Self::path(travelled + z);
+            let rotation = time * 0.5 + z * TWIST;
+            let nearness = 1.0 - (z - NEAR_PLANE) / (far - NEAR_PLANE);
+            let color = self.palette.color(nearness);
+            let size = (3.0 * nearness).round() as i32;
+
+            for dot in 0..DOTS_PER_RING {
+                let angle = dot as f32 / DOTS_PER_RING as f32 * TAU + rotation;
+                let x = ox - camera.0 + angle.cos();
+                let y = oy - camera.1 + angle.sin();
+                let (sx, sy) = self.project(x, y, z);
+                for dy in 0..=size {
+                    for dx in 0..=size {
+                        self.base.plot(buffer, sx + dx, sy + dy, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rings_stay_between_near_and_far_planes() {
+        for step in 0..50 {
+            let depths = DotTunnel::ring_depths(step as f32 * 0.37);
+            assert_eq!(depths.len(), RINGS);
+            let far = NEAR_PLANE + RINGS as f32 * RING_SPACING;
+            assert!(depths.iter().all(|&z| (NEAR_PLANE..=far).contains(&z)));
+        }
+    }
+
+    #[test]
+    fn rings_are_drawn_back_to_front() {
+        let depths = DotTunnel::ring_depths(1.3);
+        assert!(depths.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn rings_approach_the_camera() {
+        let before = DotTunnel::ring_depths(0.1);
+        let after = DotTunnel::ring_depths(0.15);
+        assert!(after.last().unwrap() < before.last().unwrap());
+    }
+
+    #[test]
+    fn nearer_points_project_farther_from_center() {
+        let tunnel = DotTunnel::new(200, 200, Palette::Rainbow);
+        let near = tunnel.project(1.0, 0.0, 1.0);
+        let far = tunnel.project(1.0, 0.0, 4.0);
+        assert!(near.0 > far.0);
+        assert_eq!(tunnel.project(0.0, 0.0, 2.0), (100, 100));
+    }
+}
@@ -0,0 +1,219 @@
+//! Video feedback spirals.
+//!
+//! Pointing a camera at the screen it is filming produces an endless tunnel of copies of
+//! the picture. This effect mimics that loop: every frame starts from the previous frame
+//! sampled slightly zoomed in and rotated around the center, dimmed a little, with a small
+//! glowing blob drawn on top. Each copy of the blob is carried outward and turned a bit
+//! further by every frame that follows, tracing spiraling trails.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default zoom applied to the previous frame every frame
+const DEFAULT_ZOOM: f32 = 1.02;
+
+/// Default rotation applied to the previous frame every frame, in radians
+const DEFAULT_ROTATION: f32 = 0.03;
+
+/// Brightness kept by the previous frame, out of 256
+const PERSISTENCE: u32 = 245;
+
+/// Radius of the generator blob as a fraction of the smallest screen dimension
+const BLOB_RADIUS: f32 = 0.04;
+
+/// User tunable feedback parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Feedback options")]
+#[group(id = "feedback-options")]
+pub struct Config {
+    #[arg(
+        id = "feedback-zoom",
+        long = "feedback-zoom",
+        value_name = "ZOOM",
+        default_value_t = DEFAULT_ZOOM,
+        help = "Zoom applied to the previous frame every frame"
+    )]
+    pub zoom: f32,
+
+    #[arg(
+        id = "feedback-rotation",
+        long = "feedback-rotation",
+        value_name = "ANGLE",
+        default_value_t = DEFAULT_ROTATION,
+        help = "Rotation in radians applied to the previous frame every frame"
+    )]
+    pub rotation: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            zoom: DEFAULT_ZOOM,
+            rotation: DEFAULT_ROTATION,
+        }
+    }
+}
+
+/// A video feedback loop fed by a moving blob
+pub struct Feedback {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the generator blob
+    palette: Palette,
+    /// Feedback parameters
+    config: Config,
+    /// Latest frame, fed back into the next one
+    frame: Vec<u32>,
+    /// Seconds the effect has been running
+    elapsed: f32,
+}
+
+impl Feedback {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            config,
+            frame: vec![rgb(0, 0, 0); base.pixel_count()],
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns the pixel of the previous frame that lands on `(x, y)` after the zoom and
+    /// rotation, or `None` if it comes from outside the screen.
+    fn source(&self, x: usize, y: usize) -> Option<usize> {
+        let (center_x, center_y) = self.base.center();
+        let (px, py) = (x as f32 - center_x, y as f32 - center_y);
+        // Invert the transform: rotate back and shrink toward the center
+        let (sin, cos) = (-self.config.rotation).sin_cos();
+        let scale = 1.0 / self.config.zoom;
+        let sx = center_x + (px * cos - py * sin) * scale;
+        let sy = center_y + (px * sin + py * cos) * scale;
+        if sx < 0.0 || sy < 0.0 {
+            return None;
+        }
+        let (sx, sy) = (sx as usize, sy as usize);
+        (sx < self.base.width && sy < self.base.height).then_some(sy * self.base.width + sx)
+    }
+
+    /// Returns the position of the generator blob at the given time.
+    fn blob(&self, time: f32) -> (f32, f32) {
+        let (center_x, center_y) = self.base.center();
+        (
+            center_x + center_x * 0.4 * (time * 1.3).cos(),
+            center_y + center_y * 0.4 * (time * 1.7).sin(),
+        )
+    }
+
+    /// Renders the previous frame zoomed, rotated, and dimmed into `buffer`, and draws the
+    /// blob on top at the given time.
+    fn feed(&self, previous: &[u32], buffer: &mut [u32], time: f32) {
+        for (i, pixel) in buffer.iter_mut().enumerate() {
+            let (x, y) = (i % self.base.width, i / self.base.width);
+            *pixel = match self.source(x, y) {
+                Some(source) => dim(previous[source]),
+                None => rgb(0, 0, 0),
+            };
+        }
+
+        let (bx, by) = self.blob(time);
+        let radius = (self.base.width.min(self.base.height) as f32 * BLOB_RADIUS).max(1.0);
+        let color = self.palette.color((time * 0.2).rem_euclid(1.0));
+        let r = radius.ceil() as i32;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if ((dx * dx + dy * dy) as f32) <= radius * radius {
+                    self.base
+                        .plot(buffer, bx as i32 + dx, by as i32 + dy, color);
+                }
+            }
+        }
+    }
+}
+
+/// Scales every channel of a pixel by `PERSISTENCE / 256`.
+fn dim(pixel: u32) -> u32 {
+    let channel = |shift: u32| (((pixel >> shift) & 0xff) * PERSISTENCE / 256) as u8;
+    rgb(channel(16), channel(8), channel(0))
+}
+
+impl DemoEffect for Feedback {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        let mut next = vec![0; self.frame.len()];
+        self.feed(&self.frame, &mut next, self.elapsed);
+        self.frame = next;
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer.copy_from_slice(&self.frame);
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.frame = vec![rgb(0, 0, 0); self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_feedback(config: Config) -> Feedback {
+        Feedback::new(100, 100, Palette::Rainbow, config)
+    }
+
+    #[test]
+    fn identity_transform_samples_same_pixel() {
+        let feedback = create_feedback(Config {
+            zoom: 1.0,
+            rotation: 0.0,
+        });
+        assert_eq!(feedback.source(30, 70), Some(70 * 100 + 30));
+    }
+
+    #[test]
+    fn zoom_samples_closer_to_center() {
+        let feedback = create_feedback(Config {
+            zoom: 2.0,
+            rotation: 0.0,
+        });
+        assert_eq!(feedback.source(90, 50), Some(50 * 100 + 70));
+    }
+
+    #[test]
+    fn shrinking_leaves_the_border_empty() {
+        let feedback = create_feedback(Config {
+            zoom: 0.5,
+            rotation: 0.0,
+        });
+        assert_eq!(feedback.source(0, 0), None);
+    }
+
+    #[test]
+    fn previous_frame_fades() {
+        assert_eq!(dim(rgb(0, 0, 0)), rgb(0, 0, 0));
+        let dimmed = dim(rgb(200, 200, 200));
+        assert!(dimmed < rgb(200, 200, 200));
+    }
+
+    #[test]
+    fn blob_trails_persist_between_frames() {
+        let mut feedback = create_feedback(Config::default());
+        feedback.update(0.0);
+        let lit = feedback
+            .frame
+            .iter()
+            .filter(|&&p| p != rgb(0, 0, 0))
+            .count();
+        feedback.update(1.0);
+        let trail = feedback
+            .frame
+            .iter()
+            .filter(|&&p| p != rgb(0, 0, 0))
+            .count();
+        assert!(trail > lit);
+    }
+}
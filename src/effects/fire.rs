@@ -0,0 +1,177 @@
+//! The classic demoscene fire effect.
+//!
+//! The bottom row of a heat buffer is seeded with random hot spots every update. Heat then
+//! propagates upward by averaging the cells below each pixel, losing a little energy on
+//! the way, which produces flickering flames that fade out toward the top of the screen.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+
+/// Amount of heat lost by a cell each time it rises one row
+const COOLING: u8 = 1;
+
+/// Number of entries in the fire palette
+const PALETTE_SIZE: usize = 256;
+
+/// A fire effect generator driven by a heat buffer
+pub struct Fire {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Heat value of every pixel, used as an index into the palette
+    heat: Vec<u8>,
+    /// Black to red to yellow to white color ramp
+    palette: [u32; PALETTE_SIZE],
+    /// Random number generator used to seed the bottom row
+    rng: fastrand::Rng,
+}
+
+impl Fire {
+    pub fn new(width: usize, height: usize) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            heat: vec![0; base.pixel_count()],
+            palette: Self::fire_palette(),
+            rng: fastrand::Rng::new(),
+        }
+    }
+
+    /// Builds the fire color ramp.
+    ///
+    /// The red channel saturates first, followed by green and finally blue, so low heat
+    /// values are dark red and the hottest values are white.
+    fn fire_palette() -> [u32; PALETTE_SIZE] {
+        let mut palette = [0; PALETTE_SIZE];
+        for (i, color) in palette.iter_mut().enumerate() {
+            let level = i * 3;
+            let r = level.min(255) as u8;
+            let g = level.saturating_sub(255).min(255) as u8;
+            let b = level.saturating_sub(510).min(255) as u8;
+            *color = rgb(r, g, b);
+        }
+        palette
+    }
+
+    /// Fills the bottom row of the heat buffer with random hot spots.
+    fn seed(&mut self) {
+        let width = self.base.width;
+        let start = self.heat.len() - width;
+        for cell in &mut self.heat[start..] {
+            *cell = if self.rng.bool() { 255 } else { 0 };
+        }
+    }
+
+    /// Moves the heat of every cell one row up, averaging its neighbors and cooling it.
+    fn propagate(&mut self) {
+        let width = self.base.width;
+        let height = self.base.height;
+
+        for y in 0..height.saturating_sub(1) {
+            for x in 0..width {
+                // Sample the three cells below and the cell two rows down
+                let below = (y + 1) * width;
+                let left = self.heat[below + (x + width - 1) % width] as u32;
+                let center = self.heat[below + x] as u32;
+                let right = self.heat[below + (x + 1) % width] as u32;
+                let further = self.heat[(y + 2).min(height - 1) * width + x] as u32;
+
+                let average = ((left + center + right + further) / 4) as u8;
+                self.heat[y * width + x] = average.saturating_sub(COOLING);
+            }
+        }
+    }
+}
+
+impl DemoEffect for Fire {
+    /// Advances the fire by one step per update, regardless of the tick length.
+    fn update(&mut self, _dt: f32) {
+        if self.heat.is_empty() {
+            return;
+        }
+
+        self.seed();
+        self.propagate();
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let width = self.base.width;
+        for_each_row(buffer, width, |y, row| {
+            for (pixel, &heat) in row.iter_mut().zip(&self.heat[y * width..]) {
+                *pixel = self.palette[heat as usize];
+            }
+        });
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.heat = vec![0; self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_fire() -> Fire {
+        let mut fire = Fire::new(64, 48);
+        fire.rng = fastrand::Rng::with_seed(42);
+        fire
+    }
+
+    #[test]
+    fn palette_ramps_from_black_to_white() {
+        let palette = Fire::fire_palette();
+        assert_eq!(palette[0], rgb(0, 0, 0), "Coldest entry should be black");
+        assert_eq!(
+            palette[PALETTE_SIZE - 1],
+            rgb(255, 255, 255),
+            "Hottest entry should be white"
+        );
+    }
+
+    #[test]
+    fn seed_only_touches_bottom_row() {
+        let mut fire = create_fire();
+        fire.seed();
+
+        let bottom = fire.heat.len() - fire.base.width;
+        assert!(
+            fire.heat[..bottom].iter().all(|&h| h == 0),
+            "Rows above the bottom should remain cold"
+        );
+        assert!(
+            fire.heat[bottom..].iter().any(|&h| h > 0),
+            "Bottom row should contain hot spots"
+        );
+    }
+
+    #[test]
+    fn heat_rises_from_bottom_row() {
+        let mut fire = create_fire();
+        for _ in 0..10 {
+            fire.update(0.0);
+        }
+
+        let row_above_bottom = (fire.base.height - 2) * fire.base.width;
+        assert!(
+            fire.heat[row_above_bottom..row_above_bottom + fire.base.width]
+                .iter()
+                .any(|&h| h > 0),
+            "Heat should propagate upward from the seeded row"
+        );
+    }
+
+    #[test]
+    fn fire_cools_down_without_seeding() {
+        let mut fire = create_fire();
+        fire.heat.fill(255);
+        for _ in 0..1000 {
+            fire.propagate();
+        }
+
+        let top_row = &fire.heat[..fire.base.width];
+        assert!(
+            top_row.iter().all(|&h| h < 255),
+            "Heat should dissipate as it rises"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Fireworks built on the particle system.
+//!
+//! Rockets are launched from the bottom of the screen at random intervals and explode into
+//! a burst of sparks when they reach the top of their trajectory. Each burst picks a random
+//! color from the active palette. Instead of clearing the screen every frame, the previous
+//! frame is dimmed, which leaves fading trails behind the rockets and sparks.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::effects::particles::{Particle, ParticleSystem};
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default number of rockets launched per second
+const DEFAULT_LAUNCH_RATE: f32 = 1.5;
+
+/// Downward acceleration in pixels per second squared
+const GRAVITY: f32 = 120.0;
+
+/// Maximum number of rockets and sparks in flight
+const CAPACITY: usize = 8000;
+
+/// Number of sparks released by an exploding rocket
+const SPARKS_PER_BURST: usize = 150;
+
+/// Lifetime of a spark in seconds
+const SPARK_LIFETIME: f32 = 1.8;
+
+/// Brightness retained by the trails every frame
+const TRAIL_DECAY: f32 = 0.88;
+
+/// User tunable fireworks parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Fireworks options")]
+#[group(id = "fireworks-options")]
+pub struct Config {
+    #[arg(
+        id = "launch-rate",
+        long = "launch-rate",
+        value_name = "LAUNCH_RATE",
+        default_value_t = DEFAULT_LAUNCH_RATE,
+        help = "Average number of rockets launched per second"
+    )]
+    pub launch_rate: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            launch_rate: DEFAULT_LAUNCH_RATE,
+        }
+    }
+}
+
+/// A fireworks display
+pub struct Fireworks {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Fireworks parameters
+    config: Config,
+    /// Color palette used for the bursts
+    palette: Palette,
+    /// Rockets climbing toward their apex
+    rockets: ParticleSystem,
+    /// Sparks released by exploded rockets
+    sparks: ParticleSystem,
+    /// Previous frame, dimmed every frame to produce the trails
+    trails: Vec<u32>,
+}
+
+impl Fireworks {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            config,
+            palette,
+            rockets: ParticleSystem::new(CAPACITY, GRAVITY),
+            sparks: ParticleSystem::new(CAPACITY, GRAVITY),
+            trails: vec![rgb(0, 0, 0); base.pixel_count()],
+        }
+    }
+
+    /// Launches a rocket from a random spot along the bottom of the screen.
+    fn launch(&mut self) {
+        let (w, h) = (self.base.width as f32, self.base.height as f32);
+        // Aim for an apex in the upper half of the screen
+        let apex = h * self.rockets.random(0.5, 0.85);
+        let speed = (2.0 * GRAVITY * apex).sqrt();
+        let x = self.rockets.random(w * 0.2, w * 0.8);
+        let drift = self.rockets.random(-w * 0.1, w * 0.1);
+        let color = self.rockets.random(0.0, 1.0);
+
+        self.rockets.spawn(Particle {
+            position: (x, h - 1.0),
+            velocity: (drift, -speed),
+            age: 0.0,
+            // The rocket explodes when it stops climbing
+            lifetime: speed / GRAVITY,
+            color,
+        });
+    }
+
+    /// Releases a spherical burst of sparks.
+    fn explode(&mut self, rocket: &Particle) {
+        for _ in 0..SPARKS_PER_BURST {
+            let angle = self.sparks.random(0.0, std::f32::consts::TAU);
+            let speed = self.sparks.random(20.0, 140.0);
+            let lifetime = self.sparks.random(0.5, 1.0) * SPARK_LIFETIME;
+            self.sparks.spawn(Particle {
+                position: rocket.position,
+                velocity: (angle.cos() * speed, angle.sin() * speed),
+                age: 0.0,
+                lifetime,
+                color: rocket.color,
+            });
+        }
+    }
+
+    /// Advances the display by `dt` seconds.
+    fn advance(&mut self, dt: f32) {
+        if self.rockets.random(0.0, 1.0) < self.config.launch_rate * dt {
+            self.launch();
+        }
+
+        let expiring: Vec<Particle> = self
+            .rockets
+            .particles
+            .iter()
+            .filter(|rocket| rocket.age + dt >= rocket.lifetime)
+            .copied()
+            .collect();
+        for rocket in &expiring {
+            self.explode(rocket);
+        }
+
+        self.rockets.update(dt);
+        self.sparks.update(dt);
+    }
+}
+
+impl DemoEffect for Fireworks {
+    fn update(&mut self, dt: f32) {
+        self.advance(dt);
+
+        for pixel in self.trails.iter_mut() {
+            let fade = |shift: u32| (((*pixel >> shift) & 0xff) as f32 * TRAIL_DECAY) as u8;
+            *pixel = rgb(fade(16), fade(8), fade(0));
+        }
+        // Rockets are drawn in grayscale, sparks take the burst color from the palette
+        self.rockets
+            .render(&mut self.trails, &self.base, &Palette::BlackWhite);
+        self.sparks
+            .render(&mut self.trails, &self.base, &self.palette);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer.copy_from_slice(&self.trails);
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.trails = vec![rgb(0, 0, 0); self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_fireworks(launch_rate: f32) -> Fireworks {
+        let config = Config { launch_rate };
+        Fireworks::new(200, 200, Palette::Rainbow, config)
+    }
+
+    #[test]
+    fn rockets_launch_from_bottom_and_climb() {
+        let mut fireworks = create_fireworks(0.0);
+        fireworks.launch();
+        let rocket = fireworks.rockets.particles[0];
+        assert_eq!(rocket.position.1, 199.0);
+        assert!(rocket.velocity.1 < 0.0);
+    }
+
+    #[test]
+    fn rockets_explode_into_sparks_at_apex() {
+        let mut fireworks = create_fireworks(0.0);
+        fireworks.launch();
+        let lifetime = fireworks.rockets.particles[0].lifetime;
+
+        let mut elapsed = 0.0;
+        while elapsed < lifetime + 0.1 {
+            fireworks.advance(0.05);
+            elapsed += 0.05;
+        }
+        assert!(fireworks.rockets.particles.is_empty());
+        assert_eq!(fireworks.sparks.particles.len(), SPARKS_PER_BURST);
+    }
+
+    #[test]
+    fn sparks_share_the_rocket_color() {
+        let mut fireworks = create_fireworks(0.0);
+        let rocket = Particle {
+            position: (100.0, 50.0),
+            velocity: (0.0, 0.0),
+            age: 0.0,
+            lifetime: 1.0,
+            color: 0.42,
+        };
+        fireworks.explode(&rocket);
+        assert!(fireworks.sparks.particles.iter().all(|s| s.color == 0.42));
+    }
+
+    #[test]
+    fn trails_fade_without_new_particles() {
+        let mut fireworks = create_fireworks(0.0);
+        fireworks.trails.fill(rgb(255, 255, 255));
+        let mut buffer = vec![0; 200 * 200];
+        fireworks.update(0.0);
+        fireworks.draw(&mut buffer, 0.0);
+        assert!(buffer[0] < rgb(255, 255, 255));
+    }
+}
@@ -0,0 +1,200 @@
+//! A mode 7 style infinite floor.
+//!
+//! Every scanline below the horizon is treated as a slice of a flat plane seen from a
+//! camera hovering above it. The distance to the plane is inversely proportional to how
+//! far the scanline is below the horizon, so each row only needs one division before its
+//! pixels step linearly across the texture, just like the SNES mode 7 hardware. The camera
+//! slowly turns while moving forward, and distant rows fade into the sky color.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+use clap::{Args, ValueEnum};
+
+/// Default distance travelled by the camera per second, in texture tiles
+const DEFAULT_SPEED: f32 = 4.0;
+
+/// Height of the camera above the floor, in texture tiles
+const CAMERA_HEIGHT: f32 = 1.0;
+
+/// Turning speed of the camera in radians per second
+const TURN_SPEED: f32 = 0.15;
+
+/// Distance at which the floor fully fades into the horizon
+const FOG_DISTANCE: f32 = 40.0;
+
+/// Sky color right above the horizon
+const HORIZON_COLOR: (u8, u8, u8) = (255, 170, 120);
+
+/// Sky color at the top of the screen
+const ZENITH_COLOR: (u8, u8, u8) = (40, 40, 120);
+
+/// Patterns that can be mapped onto the floor
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Pattern {
+    Checker,
+    Grid,
+}
+
+/// User tunable floor parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Floor options")]
+#[group(id = "floor-options")]
+pub struct Config {
+    #[arg(
+        id = "floor-pattern",
+        long = "floor-pattern",
+        value_name = "PATTERN",
+        value_enum,
+        default_value_t = Pattern::Checker,
+        help = "Pattern mapped onto the floor"
+    )]
+    pub pattern: Pattern,
+
+    #[arg(
+        id = "floor-speed",
+        long = "floor-speed",
+        value_name = "SPEED",
+        default_value_t = DEFAULT_SPEED,
+        help = "Speed at which the camera moves over the floor"
+    )]
+    pub speed: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            pattern: Pattern::Checker,
+            speed: DEFAULT_SPEED,
+        }
+    }
+}
+
+/// Linearly interpolates between two colors.
+fn mix(a: (u8, u8, u8), b: (u8, u8, u8), t: f32) -> (u8, u8, u8) {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+    (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+/// A perspective floor scrolling toward the horizon
+pub struct Floor {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Floor parameters
+    config: Config,
+    /// Scanline of the horizon
+    horizon: usize,
+}
+
+impl Floor {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            horizon: height / 3,
+        }
+    }
+
+    /// Returns the color of the floor texture at the given world coordinates.
+    fn texel(&self, u: f32, v: f32) -> (u8, u8, u8) {
+        match self.config.pattern {
+            Pattern::Checker => {
+                if (u.floor() as i64 + v.floor() as i64).rem_euclid(2) == 0 {
+                    (230, 230, 230)
+                } else {
+                    (40, 110, 60)
+                }
+            }
+            Pattern::Grid => {
+                let line = u.rem_euclid(1.0) < 0.08 || v.rem_euclid(1.0) < 0.08;
+                if line {
+                    (255, 60, 220)
+                } else {
+                    (20, 10, 40)
+                }
+            }
+        }
+    }
+
+    /// Returns the distance from the camera to the floor seen on scanline `y`, or `None` for
+    /// scanlines on or above the horizon.
+    fn distance(&self, y: usize) -> Option<f32> {
+        // Focal length chosen so a 90 degree field of view spans the screen width
+        let focal = self.base.width as f32 * 0.5;
+        (y > self.horizon).then(|| CAMERA_HEIGHT * focal / (y - self.horizon) as f32)
+    }
+}
+
+impl DemoEffect for Floor {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let (center_x, _) = self.base.center();
+        let focal = self.base.width as f32 * 0.5;
+        let heading = time * TURN_SPEED;
+        let (sin, cos) = heading.sin_cos();
+        let position = (
+            sin * time * self.config.speed,
+            cos * time * self.config.speed,
+        );
+
+        for_each_row(buffer, self.base.width, |y, row| {
+            let Some(distance) = self.distance(y) else {
+                let t = y as f32 / self.horizon.max(1) as f32;
+                let (r, g, b) = mix(ZENITH_COLOR, HORIZON_COLOR, t);
+                row.fill(rgb(r, g, b));
+                return;
+            };
+
+            let fog = (distance / FOG_DISTANCE).min(1.0);
+            // World space step between two neighboring pixels of this scanline
+            let step = distance / focal;
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let side = (x as f32 - center_x) * step;
+                // Rotate the camera space point by the heading into world space
+                let u = position.0 + side * cos + distance * sin;
+                let v = position.1 - side * sin + distance * cos;
+                let (r, g, b) = mix(self.texel(u, v), HORIZON_COLOR, fog);
+                *pixel = rgb(r, g, b);
+            }
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.horizon = height / 3;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_above_horizon_have_no_floor() {
+        let floor = Floor::new(90, 90, Config::default());
+        assert_eq!(floor.distance(0), None);
+        assert_eq!(floor.distance(30), None);
+        assert!(floor.distance(31).is_some());
+    }
+
+    #[test]
+    fn floor_gets_closer_toward_the_bottom() {
+        let floor = Floor::new(90, 90, Config::default());
+        assert!(floor.distance(40).unwrap() > floor.distance(80).unwrap());
+    }
+
+    #[test]
+    fn checker_alternates_between_tiles() {
+        let floor = Floor::new(90, 90, Config::default());
+        assert_ne!(floor.texel(0.5, 0.5), floor.texel(1.5, 0.5));
+        assert_eq!(floor.texel(0.5, 0.5), floor.texel(1.5, 1.5));
+        assert_eq!(floor.texel(-0.5, -0.5), floor.texel(0.5, 0.5));
+    }
+
+    #[test]
+    fn sky_is_a_vertical_gradient() {
+        let floor = Floor::new(90, 90, Config::default());
+        let mut buffer = vec![0; 90 * 90];
+        floor.draw(&mut buffer, 0.0);
+        let (r, g, b) = ZENITH_COLOR;
+        assert_eq!(buffer[0], rgb(r, g, b));
+        assert_eq!(buffer[0], buffer[89]);
+        assert_ne!(buffer[0], buffer[29 * 90]);
+    }
+}
@@ -0,0 +1,250 @@
+//! Animated Mandelbrot zoom and morphing Julia set.
+//!
+//! Both fractals iterate `z = z^2 + c` until the orbit escapes or the iteration budget runs
+//! out. The Mandelbrot set varies `c` with the pixel position and dives toward a point on
+//! the boundary, while the Julia set varies the starting `z` and slowly rotates `c` around
+//! the origin. Escape times are smoothed to avoid visible color bands before being mapped
+//! through the active palette.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect, ShaderSource};
+use crate::palette::Palette;
+use clap::{Args, ValueEnum};
+
+/// Default maximum number of iterations per pixel
+const DEFAULT_MAX_ITERATIONS: u32 = 256;
+
+/// Squared radius beyond which an orbit is considered escaped
+const ESCAPE_RADIUS_SQ: f64 = 256.0;
+
+/// Point on the Mandelbrot boundary the zoom dives into
+const ZOOM_TARGET: (f64, f64) = (-0.743_643_887_037_151, 0.131_825_904_205_33);
+
+/// Duration of a zoom before it restarts, in seconds
+const ZOOM_PERIOD: f32 = 30.0;
+
+/// Zoom growth rate, the magnification doubles roughly every 1.7 seconds
+const ZOOM_RATE: f64 = 0.4;
+
+/// Magnitude of the Julia set constant
+const JULIA_RADIUS: f64 = 0.7885;
+
+/// Number of palette cycles per hundred iterations
+const COLOR_DENSITY: f32 = 0.05;
+
+/// Fewest steps of single precision floats within the width of a pixel for the shader
+/// to draw the fractal, deeper zooms being drawn on the CPU in double precision
+const SHADER_PRECISION: f64 = 256.0;
+
+/// Fractal sets that can be rendered
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Fractal {
+    Mandelbrot,
+    Julia,
+}
+
+/// User tunable fractal parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Fractal options")]
+#[group(id = "fractal-options")]
+pub struct Config {
+    #[arg(
+        id = "fractal",
+        long = "fractal",
+        value_name = "FRACTAL",
+        value_enum,
+        default_value_t = Fractal::Mandelbrot,
+        help = "Fractal set to render"
+    )]
+    pub fractal: Fractal,
+
+    #[arg(
+        id = "max-iterations",
+        long = "max-iterations",
+        value_name = "MAX_ITERATIONS",
+        default_value_t = DEFAULT_MAX_ITERATIONS,
+        help = "Maximum number of iterations per pixel"
+    )]
+    pub max_iterations: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            fractal: Fractal::Mandelbrot,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+        }
+    }
+}
+
+/// An animated fractal renderer
+pub struct FractalZoom {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Fractal parameters
+    config: Config,
+    /// Color palette used for rendering escape times
+    palette: Palette,
+}
+
+impl FractalZoom {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            palette,
+        }
+    }
+
+    /// Iterates `z = z^2 + c` and returns the smoothed escape time, or `None` if the orbit
+    /// stays bounded for the whole iteration budget.
+    fn escape_time(&self, mut z: (f64, f64), c: (f64, f64)) -> Option<f32> {
+        for n in 0..self.config.max_iterations {
+            let (x, y) = z;
+            let norm_sq = x * x + y * y;
+            if norm_sq > ESCAPE_RADIUS_SQ {
+                // Normalized iteration count, see "renormalizing the Mandelbrot escape"
+                let log_zn = norm_sq.ln() * 0.5;
+                let nu = (log_zn / std::f64::consts::LN_2).log2();
+                return Some((n as f64 + 1.0 - nu) as f32);
+            }
+            z = (x * x - y * y + c.0, 2.0 * x * y + c.1);
+        }
+        None
+    }
+
+    /// Returns the center and the width of the viewed region of the complex plane.
+    fn view(&self, time: f32) -> ((f64, f64), f64) {
+        match self.config.fractal {
+            Fractal::Mandelbrot => {
+                let t = (time % ZOOM_PERIOD) as f64;
+                (ZOOM_TARGET, 3.0 / (t * ZOOM_RATE).exp())
+            }
+            Fractal::Julia => ((0.0, 0.0), 3.0),
+        }
+    }
+}
+
+impl DemoEffect for FractalZoom {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let ((center_re, center_im), view_width) = self.view(time);
+        let width = self.base.width;
+        let scale = view_width / width as f64;
+        let (center_x, center_y) = self.base.center();
+        let julia_c = julia_constant(time);
+
+        for_each_row(buffer, width, |y, row| {
+            let im = center_im + (y as f64 - center_y as f64) * scale;
+
+            row.iter_mut().enumerate().for_each(|(x, pixel)| {
+                let re = center_re + (x as f64 - center_x as f64) * scale;
+                let escape = match self.config.fractal {
+                    Fractal::Mandelbrot => self.escape_time((0.0, 0.0), (re, im)),
+                    Fractal::Julia => self.escape_time((re, im), julia_c),
+                };
+
+                *pixel = match escape {
+                    Some(n) => self.palette.color((n * COLOR_DENSITY).rem_euclid(1.0)),
+                    // Points inside the set are drawn black
+                    None => rgb(0, 0, 0),
+                };
+            });
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+
+    /// Returns the shader iterating the fractal in single precision, until the zoom goes
+    /// too deep for it to tell the pixels apart.
+    fn shader_source(&self, time: f32) -> Option<ShaderSource> {
+        let ((center_re, center_im), view_width) = self.view(time);
+        let scale = view_width / self.base.width as f64;
+        let magnitude = center_re.abs().max(center_im.abs()).max(1.0);
+        if scale < magnitude * f32::EPSILON as f64 * SHADER_PRECISION {
+            return None;
+        }
+        let fractals = Fractal::value_variants();
+        let fractal = fractals.iter().position(|f| *f == self.config.fractal)?;
+        let (julia_re, julia_im) = julia_constant(time);
+        Some(ShaderSource {
+            code: include_str!("fractal.wgsl"),
+            params: vec![
+                fractal as f32,
+                self.config.max_iterations as f32,
+                center_re as f32,
+                center_im as f32,
+                scale as f32,
+                julia_re as f32,
+                julia_im as f32,
+                COLOR_DENSITY,
+                ESCAPE_RADIUS_SQ as f32,
+            ],
+            palette: ShaderSource::sample_palette(|v| self.palette.color(v)),
+        })
+    }
+}
+
+/// Returns the constant of the Julia set at `time`, turning slowly around the origin.
+fn julia_constant(time: f32) -> (f64, f64) {
+    let angle = time as f64 * 0.3;
+    (JULIA_RADIUS * angle.cos(), JULIA_RADIUS * angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_fractal(fractal: Fractal) -> FractalZoom {
+        let config = Config {
+            fractal,
+            ..Config::default()
+        };
+        FractalZoom::new(64, 64, Palette::Rainbow, config)
+    }
+
+    #[test]
+    fn origin_is_inside_mandelbrot_set() {
+        let fractal = create_fractal(Fractal::Mandelbrot);
+        assert_eq!(fractal.escape_time((0.0, 0.0), (0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn far_points_escape_immediately() {
+        let fractal = create_fractal(Fractal::Mandelbrot);
+        let escape = fractal.escape_time((0.0, 0.0), (10.0, 10.0));
+        assert!(escape.is_some_and(|n| n < 3.0));
+    }
+
+    #[test]
+    fn smoothed_escape_time_grows_closer_to_the_set() {
+        let fractal = create_fractal(Fractal::Mandelbrot);
+        let near = fractal.escape_time((0.0, 0.0), (0.26, 0.0)).unwrap();
+        let far = fractal.escape_time((0.0, 0.0), (0.5, 0.0)).unwrap();
+        assert!(near > far);
+    }
+
+    #[test]
+    fn mandelbrot_view_zooms_in_over_time() {
+        let fractal = create_fractal(Fractal::Mandelbrot);
+        let (_, start) = fractal.view(0.0);
+        let (_, later) = fractal.view(10.0);
+        assert!(later < start);
+    }
+
+    #[test]
+    fn julia_view_is_fixed() {
+        let fractal = create_fractal(Fractal::Julia);
+        assert_eq!(fractal.view(0.0), fractal.view(10.0));
+    }
+
+    #[test]
+    fn deep_zooms_are_left_to_the_cpu() {
+        let mandelbrot = create_fractal(Fractal::Mandelbrot);
+        assert!(mandelbrot.shader_source(0.0).is_some());
+        assert!(mandelbrot.shader_source(ZOOM_PERIOD - 1.0).is_none());
+        // The zoom starts over along with the shader
+        assert!(mandelbrot.shader_source(ZOOM_PERIOD).is_some());
+        let julia = create_fractal(Fractal::Julia);
+        assert!(julia.shader_source(ZOOM_PERIOD - 1.0).is_some());
+    }
+}
@@ -0,0 +1,31 @@
+// Fractals drawn on the GPU in single precision, the way `FractalZoom::draw` iterates
+// them on the CPU.
+//
+// Parameters: 0 the index of the fractal in the order `Fractal` declares them, 1 the
+// iteration budget, 2 and 3 the complex number at the center of the frame, 4 the width
+// of a pixel in the complex plane, 5 and 6 the constant of the Julia set, 7 the color
+// density and 8 the squared escape radius.
+
+const LN_2: f32 = 0.69314718;
+
+fn shade(pixel: vec2<f32>) -> vec3<f32> {
+    let point = vec2<f32>(param(2u), param(3u)) + (pixel - frame.resolution * 0.5) * param(4u);
+    var z = vec2<f32>(0.0);
+    var c = point;
+    if u32(param(0u)) == 1u {
+        z = point;
+        c = vec2<f32>(param(5u), param(6u));
+    }
+    let iterations = u32(param(1u));
+    for (var n = 0u; n < iterations; n++) {
+        let norm_sq = dot(z, z);
+        if norm_sq > param(8u) {
+            // Normalized iteration count, see "renormalizing the Mandelbrot escape"
+            let nu = log2(log(norm_sq) * 0.5 / LN_2);
+            return palette_color(fract((f32(n) + 1.0 - nu) * param(7u)));
+        }
+        z = vec2<f32>(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+    }
+    // Points inside the set are drawn black
+    return vec3<f32>(0.0);
+}
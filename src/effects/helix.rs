@@ -0,0 +1,168 @@
+//! A rotating DNA double helix.
+//!
+//! Two strands wind around a horizontal axis half a turn apart, joined by rungs at regular
+//! intervals. The helix rotates around its axis, so each point has a depth given by the
+//! cosine of its angle. Everything is drawn back to front, with nearer points drawn larger
+//! and colored further along the palette, which gives the flat sine waves a 3D look.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use std::f32::consts::{PI, TAU};
+
+/// Number of full turns of the helix across the screen
+const TURNS: f32 = 2.5;
+
+/// Rotation speed of the helix in radians per second
+const SPIN_SPEED: f32 = 1.2;
+
+/// Number of rungs joining the strands per turn
+const RUNGS_PER_TURN: usize = 10;
+
+/// Number of points used to draw each strand
+const STRAND_POINTS: usize = 240;
+
+/// Something to draw at a given depth
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Primitive {
+    /// A filled dot on a strand
+    Dot { x: f32, y: f32, radius: f32 },
+    /// A rung between two strand points
+    Rung { from: (i32, i32), to: (i32, i32) },
+}
+
+/// A double helix rotating around the horizontal axis
+pub struct Helix {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used to shade the helix by depth
+    palette: Palette,
+}
+
+impl Helix {
+    pub fn new(width: usize, height: usize, palette: Palette) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+        }
+    }
+
+    /// Returns the screen position and depth in [-1,1] of a strand point.
+    ///
+    /// `t` runs from 0 at the left edge to 1 at the right edge, and `phase` selects the
+    /// strand.
+    fn point(&self, t: f32, phase: f32, time: f32) -> (f32, f32, f32) {
+        let (_, center_y) = self.base.center();
+        let angle = t * TURNS * TAU + time * SPIN_SPEED + phase;
+        let amplitude = self.base.height as f32 * 0.3;
+        (
+            t * self.base.width as f32,
+            center_y + angle.sin() * amplitude,
+            angle.cos(),
+        )
+    }
+
+    /// Builds every strand dot and rung together with its depth.
+    fn primitives(&self, time: f32) -> Vec<(f32, Primitive)> {
+        let max_radius = (self.base.height as f32 * 0.02).max(1.0);
+        let mut primitives = Vec::new();
+
+        for i in 0..=STRAND_POINTS {
+            let t = i as f32 / STRAND_POINTS as f32;
+            for phase in [0.0, PI] {
+                let (x, y, z) = self.point(t, phase, time);
+                let radius = max_radius * (0.6 + 0.4 * z);
+                primitives.push((z, Primitive::Dot { x, y, radius }));
+            }
+        }
+
+        let rungs = (TURNS * RUNGS_PER_TURN as f32) as usize;
+        for i in 0..=rungs {
+            let t = i as f32 / rungs as f32;
+            let (x0, y0, z0) = self.point(t, 0.0, time);
+            let (x1, y1, z1) = self.point(t, PI, time);
+            primitives.push((
+                (z0 + z1) * 0.5,
+                Primitive::Rung {
+                    from: (x0 as i32, y0 as i32),
+                    to: (x1 as i32, y1 as i32),
+                },
+            ));
+        }
+
+        // Painter's algorithm: far primitives first so near ones cover them
+        primitives.sort_by(|a, b| b.0.total_cmp(&a.0));
+        primitives
+    }
+}
+
+impl DemoEffect for Helix {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+
+        for (depth, primitive) in self.primitives(time) {
+            // Depth 1 is farthest from the viewer, map it to the start of the palette
+            let shade = 0.5 - depth * 0.5;
+            match primitive {
+                Primitive::Dot { x, y, radius } => {
+                    let color = self.palette.color(shade);
+                    let r = radius.ceil() as i32;
+                    for dy in -r..=r {
+                        for dx in -r..=r {
+                            if ((dx * dx + dy * dy) as f32) <= radius * radius {
+                                self.base.plot(buffer, x as i32 + dx, y as i32 + dy, color);
+                            }
+                        }
+                    }
+                }
+                Primitive::Rung { from, to } => {
+                    // Rungs are dimmer than the strands they connect
+                    let color = self.palette.color(shade * 0.6);
+                    self.base.draw_line(buffer, from, to, color);
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strands_are_half_a_turn_apart() {
+        let helix = Helix::new(200, 100, Palette::Rainbow);
+        let (_, y0, z0) = helix.point(0.3, 0.0, 1.0);
+        let (_, y1, z1) = helix.point(0.3, PI, 1.0);
+        assert!((y0 + y1 - 100.0).abs() < 1e-3, "Strands mirror each other");
+        assert!(
+            (z0 + z1).abs() < 1e-5,
+            "One strand is in front of the other"
+        );
+    }
+
+    #[test]
+    fn helix_spans_the_screen_width() {
+        let helix = Helix::new(200, 100, Palette::Rainbow);
+        assert_eq!(helix.point(0.0, 0.0, 0.0).0, 0.0);
+        assert_eq!(helix.point(1.0, 0.0, 0.0).0, 200.0);
+    }
+
+    #[test]
+    fn primitives_are_sorted_back_to_front() {
+        let helix = Helix::new(200, 100, Palette::Rainbow);
+        let primitives = helix.primitives(0.7);
+        assert!(primitives.windows(2).all(|w| w[0].0 >= w[1].0));
+    }
+
+    #[test]
+    fn helix_leaves_background_visible() {
+        let helix = Helix::new(200, 100, Palette::Rainbow);
+        let mut buffer = vec![0; 200 * 100];
+        helix.draw(&mut buffer, 0.0);
+        assert_eq!(buffer[0], rgb(0, 0, 0));
+        assert!(buffer.iter().any(|&p| p != rgb(0, 0, 0)));
+    }
+}
@@ -0,0 +1,208 @@
+//! A hypnotic sunburst of rotating spirals.
+//!
+//! Every pixel is described by its angle around the center and its distance from it, the
+//! same polar coordinates the plasma shapes use. Logarithmic spiral arms come from a sine
+//! of the angle offset by the logarithm of the distance, so the arms keep their shape at
+//! every scale and seem to pour endlessly into the center. Radial beams turning the other
+//! way are layered on top, and the palette is shifted with the distance so colors ripple
+//! outward.
+//!
+//! `Up`/`Down` add or remove arms and `Left`/`Right` change the rotation speed.
+use crate::common::{for_each_row, DemoBase, DemoEffect, InputEvent};
+use crate::effects::Settings;
+use crate::palette::Palette;
+use clap::Args;
+use minifb::Key;
+
+/// Default number of spiral arms
+const DEFAULT_ARMS: u32 = 6;
+
+/// Default rotation speed of the spiral in radians per second
+const DEFAULT_SPEED: f32 = 2.0;
+
+/// Largest number of arms that can be selected at runtime
+const MAX_ARMS: u32 = 32;
+
+/// Amount the rotation speed changes per key press
+const SPEED_STEP: f32 = 0.25;
+
+/// How tightly the arms wind around the center
+const TWIST: f32 = 4.0;
+
+/// Number of radial beams drawn per spiral arm
+const BEAMS_PER_ARM: u32 = 2;
+
+/// User tunable hypno spiral parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Hypno options")]
+#[group(id = "hypno-options")]
+pub struct Config {
+    #[arg(
+        id = "hypno-arms",
+        long = "hypno-arms",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_ARMS,
+        value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_ARMS)),
+        help = "Number of spiral arms, from 1 to 32"
+    )]
+    pub arms: u32,
+
+    #[arg(
+        id = "hypno-speed",
+        long = "hypno-speed",
+        value_name = "SPEED",
+        default_value_t = DEFAULT_SPEED,
+        help = "Rotation speed of the spiral in radians per second"
+    )]
+    pub speed: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            arms: DEFAULT_ARMS,
+            speed: DEFAULT_SPEED,
+        }
+    }
+}
+
+/// Rotating logarithmic spirals over radial beams
+pub struct Hypno {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the spiral
+    palette: Palette,
+    /// Spiral parameters, adjustable at runtime
+    config: Config,
+    /// Rotation of the spiral in radians, accumulated so speed changes don't jump
+    rotation: f32,
+}
+
+impl Hypno {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            config: Config {
+                arms: config.arms.clamp(1, MAX_ARMS),
+                ..config
+            },
+            rotation: 0.0,
+        }
+    }
+
+    /// Returns the spiral intensity for a pixel at the given polar coordinates, in the
+    /// [0,1] range.
+    fn intensity(&self, dist: f32, angle: f32) -> f32 {
+        let arms = self.config.arms as f32;
+        // Offsetting the angle by ln(dist) bends straight beams into logarithmic spirals
+        let spiral = (arms * angle + TWIST * dist.max(f32::EPSILON).ln() - self.rotation).sin();
+        let beams = (arms * BEAMS_PER_ARM as f32 * angle + self.rotation * 0.5).sin();
+        (spiral * 0.7 + beams * 0.3) * 0.5 + 0.5
+    }
+}
+
+impl DemoEffect for Hypno {
+    fn update(&mut self, dt: f32) {
+        self.rotation += self.config.speed * dt;
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let (center_x, center_y) = self.base.center();
+        let min_dim = center_x.min(center_y).max(1.0);
+        for_each_row(buffer, self.base.width, |y, row| {
+            let py = y as f32 - center_y;
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let px = x as f32 - center_x;
+                let dist = (px * px + py * py).sqrt() / min_dim;
+                let angle = py.atan2(px);
+                let v = self.intensity(dist, angle);
+                *pixel = self.palette.color((v * 0.5 + dist * 0.5).fract());
+            }
+        });
+    }
+
+    /// Handles the arm count and rotation speed controls.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Up) => self.config.arms = (self.config.arms + 1).min(MAX_ARMS),
+            InputEvent::Key(Key::Down) => self.config.arms = (self.config.arms - 1).max(1),
+            InputEvent::Key(Key::Right) => self.config.speed += SPEED_STEP,
+            InputEvent::Key(Key::Left) => self.config.speed -= SPEED_STEP,
+            _ => {}
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.hypno.arms = self.config.arms;
+        settings.hypno.speed = self.config.speed;
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    fn create_hypno() -> Hypno {
+        Hypno::new(100, 100, Palette::Rainbow, Config::default())
+    }
+
+    #[test]
+    fn intensity_is_normalized() {
+        let hypno = create_hypno();
+        for step in 0..500 {
+            let v = hypno.intensity(step as f32 * 0.01, step as f32 * 0.37);
+            assert!((0.0..=1.0).contains(&v), "{v} out of range");
+        }
+    }
+
+    #[test]
+    fn pattern_repeats_once_per_arm() {
+        let hypno = create_hypno();
+        let period = TAU / DEFAULT_ARMS as f32;
+        let a = hypno.intensity(0.5, 0.3);
+        let b = hypno.intensity(0.5, 0.3 + period);
+        assert!((a - b).abs() < 1e-4);
+    }
+
+    #[test]
+    fn spiral_rotates_over_time() {
+        let mut hypno = create_hypno();
+        hypno.update(0.5);
+        assert_eq!(hypno.rotation, DEFAULT_SPEED * 0.5);
+    }
+
+    #[test]
+    fn arm_count_stays_in_range() {
+        let mut hypno = Hypno::new(
+            10,
+            10,
+            Palette::Rainbow,
+            Config {
+                arms: 1,
+                ..Config::default()
+            },
+        );
+        hypno.handle_input(InputEvent::Key(Key::Down));
+        assert_eq!(hypno.config.arms, 1);
+        for _ in 0..100 {
+            hypno.handle_input(InputEvent::Key(Key::Up));
+        }
+        assert_eq!(hypno.config.arms, MAX_ARMS);
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        let mut hypno = create_hypno();
+        hypno.handle_input(InputEvent::Key(Key::Up));
+        hypno.handle_input(InputEvent::Key(Key::Right));
+        let mut settings = Settings::default();
+        hypno.save_settings(&mut settings);
+        assert_eq!(settings.hypno, hypno.config);
+    }
+}
@@ -0,0 +1,168 @@
+//! A kaleidoscope wrapped around another effect.
+//!
+//! The wrapped effect is rendered into an offscreen [`Layer`]. Every output pixel is then
+//! converted to polar coordinates around the screen center and its angle folded into a
+//! single wedge, mirroring every other wedge, before sampling the layer. The result shows
+//! one slowly rotating slice of the inner effect reflected all around the center.
+use crate::common::{for_each_row, DemoBase, DemoEffect, InputEvent, Layer};
+use crate::effects::Settings;
+use clap::Args;
+use std::f32::consts::TAU;
+
+/// Default number of mirrored wedges around the center
+const DEFAULT_SEGMENTS: usize = 8;
+
+/// Rotation speed of the sampled wedge in radians per second
+const ROTATION_SPEED: f32 = 0.2;
+
+/// User tunable kaleidoscope parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Kaleidoscope options")]
+#[group(id = "kaleidoscope-options")]
+pub struct Config {
+    #[arg(
+        id = "segments",
+        long = "segments",
+        value_name = "SEGMENTS",
+        default_value_t = DEFAULT_SEGMENTS,
+        help = "Number of mirrored wedges around the center"
+    )]
+    pub segments: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            segments: DEFAULT_SEGMENTS,
+        }
+    }
+}
+
+/// A kaleidoscope mirroring a wedge of an inner effect
+pub struct Kaleidoscope {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Effect reflected by the kaleidoscope
+    inner: Layer,
+    /// Angle covered by a single wedge in radians
+    wedge: f32,
+}
+
+impl Kaleidoscope {
+    pub fn new(width: usize, height: usize, inner: Box<dyn DemoEffect>, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            inner: Layer::new(base, inner),
+            wedge: TAU / config.segments.max(1) as f32,
+        }
+    }
+}
+
+/// Folds an angle into the first wedge of the given size, mirroring every other wedge.
+fn fold(angle: f32, wedge: f32) -> f32 {
+    let angle = angle.rem_euclid(TAU);
+    let segment = (angle / wedge) as usize;
+    let local = angle - segment as f32 * wedge;
+    if segment.is_multiple_of(2) {
+        local
+    } else {
+        wedge - local
+    }
+}
+
+impl DemoEffect for Kaleidoscope {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let (center_x, center_y) = self.base.center();
+        let (width, height) = (self.base.width, self.base.height);
+        let rotation = time * ROTATION_SPEED;
+        let source = self.inner.render(time);
+        let (source, wedge) = (&source[..], self.wedge);
+
+        for_each_row(buffer, width, |y, row| {
+            let py = y as f32 - center_y;
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let px = x as f32 - center_x;
+                let radius = (px * px + py * py).sqrt();
+
+                let angle = fold(py.atan2(px), wedge) + rotation;
+
+                let sx = (center_x + radius * angle.cos()).clamp(0.0, (width - 1) as f32) as usize;
+                let sy = (center_y + radius * angle.sin()).clamp(0.0, (height - 1) as f32) as usize;
+                *pixel = source[sy * width + sx];
+            }
+        });
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.inner.update(dt);
+    }
+
+    fn interpolate(&mut self, alpha: f32) {
+        self.inner.interpolate(alpha);
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        self.inner.handle_input(event);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.inner.resize(self.base);
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        self.inner.save_settings(settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    /// Inner effect where each pixel stores its own index
+    struct Indices;
+
+    impl DemoEffect for Indices {
+        fn draw(&self, buffer: &mut [u32], _time: f32) {
+            for (i, pixel) in buffer.iter_mut().enumerate() {
+                *pixel = i as u32;
+            }
+        }
+    }
+
+    #[test]
+    fn fold_keeps_angles_in_first_wedge() {
+        let wedge = TAU / 6.0;
+        for step in -50..50 {
+            let folded = fold(step as f32 * 0.13, wedge);
+            assert!((0.0..=wedge).contains(&folded));
+        }
+    }
+
+    #[test]
+    fn fold_mirrors_odd_wedges() {
+        let wedge = PI / 2.0;
+        assert!((fold(0.1, wedge) - 0.1).abs() < 1e-5);
+        assert!((fold(wedge + 0.1, wedge) - (wedge - 0.1)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn kaleidoscope_is_symmetric_around_the_center() {
+        let kaleidoscope = Kaleidoscope::new(100, 100, Box::new(Indices), Config::default());
+        let mut buffer = vec![0; 100 * 100];
+        kaleidoscope.draw(&mut buffer, 0.0);
+        // Points mirrored across the horizontal axis fold onto the same sample
+        assert_eq!(buffer[20 * 100 + 70], buffer[80 * 100 + 70]);
+    }
+
+    #[test]
+    fn single_segment_reproduces_the_inner_effect() {
+        let config = Config { segments: 1 };
+        let kaleidoscope = Kaleidoscope::new(101, 101, Box::new(Indices), config);
+        let mut buffer = vec![0; 101 * 101];
+        kaleidoscope.draw(&mut buffer, 0.0);
+        assert_eq!(buffer[20 * 101 + 70], 20 * 101 + 70);
+    }
+}
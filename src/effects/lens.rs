@@ -0,0 +1,195 @@
+//! A moving magnifying lens over another effect.
+//!
+//! The underlying effect is rendered into an offscreen [`Layer`] first. A spherical lens then
+//! glides over it, and every pixel covered by the lens is replaced with a pixel sampled
+//! closer to the lens center, which magnifies the pattern underneath like a glass ball.
+//! The per-pixel sampling offsets only depend on the lens size, so they are computed once
+//! as a displacement map when the effect is created.
+use crate::common::{DemoBase, DemoEffect, InputEvent, Layer};
+use crate::effects::Settings;
+
+/// Radius of the lens as a fraction of the smallest screen dimension
+const LENS_RADIUS: f32 = 0.2;
+
+/// Refraction strength relative to the lens radius, smaller values magnify more
+const LENS_DEPTH: f32 = 0.6;
+
+/// A spherical lens distorting an inner effect
+pub struct Lens {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Effect displayed beneath the lens
+    inner: Layer,
+    /// Radius of the lens in pixels
+    radius: i32,
+    /// Sampling offset of every pixel in the lens bounding square, `None` outside the lens
+    displacement: Vec<Option<(i32, i32)>>,
+}
+
+impl Lens {
+    pub fn new(width: usize, height: usize, inner: Box<dyn DemoEffect>) -> Self {
+        let base = DemoBase::new(width, height);
+        let radius = Self::lens_radius(base);
+        Self {
+            base,
+            inner: Layer::new(base, inner),
+            radius,
+            displacement: Self::displacement_map(radius),
+        }
+    }
+
+    /// Returns the radius of the lens in pixels for a screen of the given size.
+    fn lens_radius(base: DemoBase) -> i32 {
+        (base.width.min(base.height) as f32 * LENS_RADIUS).max(1.0) as i32
+    }
+
+    /// Precomputes the sampling offsets of a spherical lens of the given radius.
+    ///
+    /// A ray hitting the sphere at height `z` above the plane is bent toward the center
+    /// proportionally to `z`, so the center is magnified the most and the rim not at all.
+    fn displacement_map(radius: i32) -> Vec<Option<(i32, i32)>> {
+        let r = radius as f32;
+        let depth = r * LENS_DEPTH;
+        let size = 2 * radius;
+        (0..size * size)
+            .map(|i| {
+                let x = (i % size - radius) as f32;
+                let y = (i / size - radius) as f32;
+                let dist_sq = x * x + y * y;
+                if dist_sq >= r * r {
+                    return None;
+                }
+                let z = (r * r - dist_sq).sqrt();
+                let scale = depth / (z + depth);
+                Some(((x * scale) as i32, (y * scale) as i32))
+            })
+            .collect()
+    }
+
+    /// Returns the center of the lens at the given time.
+    fn center(&self, time: f32) -> (i32, i32) {
+        let (center_x, center_y) = self.base.center();
+        let range_x = (center_x - self.radius as f32).max(0.0);
+        let range_y = (center_y - self.radius as f32).max(0.0);
+        (
+            (center_x + range_x * (time * 0.7).sin()) as i32,
+            (center_y + range_y * (time * 0.9).cos()) as i32,
+        )
+    }
+}
+
+impl DemoEffect for Lens {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let (cx, cy) = self.center(time);
+        let source = self.inner.render(time);
+        buffer.copy_from_slice(&source);
+
+        let size = 2 * self.radius;
+        let (width, height) = (self.base.width as i32, self.base.height as i32);
+
+        for (i, offset) in self.displacement.iter().enumerate() {
+            let Some((dx, dy)) = offset else {
+                continue;
+            };
+            let i = i as i32;
+            let x = cx + i % size - self.radius;
+            let y = cy + i / size - self.radius;
+            if x < 0 || y < 0 || x >= width || y >= height {
+                continue;
+            }
+
+            let sx = (cx + dx).clamp(0, width - 1);
+            let sy = (cy + dy).clamp(0, height - 1);
+            buffer[(y * width + x) as usize] = source[(sy * width + sx) as usize];
+        }
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.inner.update(dt);
+    }
+
+    fn interpolate(&mut self, alpha: f32) {
+        self.inner.interpolate(alpha);
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        self.inner.handle_input(event);
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.radius = Self::lens_radius(self.base);
+        self.displacement = Self::displacement_map(self.radius);
+        self.inner.resize(self.base);
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        self.inner.save_settings(settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inner effect drawing a horizontal gradient where each pixel stores its column
+    struct Gradient {
+        width: usize,
+    }
+
+    impl DemoEffect for Gradient {
+        fn draw(&self, buffer: &mut [u32], _time: f32) {
+            for (i, pixel) in buffer.iter_mut().enumerate() {
+                *pixel = (i % self.width) as u32;
+            }
+        }
+    }
+
+    fn create_lens() -> Lens {
+        Lens::new(100, 100, Box::new(Gradient { width: 100 }))
+    }
+
+    #[test]
+    fn displacement_map_is_empty_outside_the_lens() {
+        let map = Lens::displacement_map(10);
+        assert_eq!(map.len(), 400);
+        assert_eq!(
+            map[0], None,
+            "Corners of the bounding square lie outside the lens"
+        );
+        assert!(map[10 * 20 + 10].is_some());
+    }
+
+    #[test]
+    fn lens_pulls_samples_toward_its_center() {
+        let map = Lens::displacement_map(10);
+        // Pixel 5 columns right of the center
+        let (dx, dy) = map[10 * 20 + 15].unwrap();
+        assert!(
+            (0..5).contains(&dx),
+            "Samples should move toward the center"
+        );
+        assert_eq!(dy, 0);
+    }
+
+    #[test]
+    fn lens_magnifies_the_inner_effect() {
+        let lens = create_lens();
+        let mut buffer = vec![0; 100 * 100];
+        lens.draw(&mut buffer, 0.0);
+
+        let (cx, cy) = lens.center(0.0);
+        let row = cy as usize * 100;
+        // The inner gradient changes by 10 over 10 pixels, the lens should compress that
+        let span = buffer[row + cx as usize + 10] - buffer[row + cx as usize];
+        assert!(span < 10);
+    }
+
+    #[test]
+    fn pixels_outside_the_lens_show_the_inner_effect() {
+        let lens = create_lens();
+        let mut buffer = vec![0; 100 * 100];
+        lens.draw(&mut buffer, 0.0);
+        assert_eq!(buffer[99], 99);
+    }
+}
@@ -0,0 +1,343 @@
+//! Conway's Game of Life.
+//!
+//! The grid wraps around at the edges so patterns leaving one side of the screen come back
+//! on the other. Every live cell tracks how many generations it has survived, and that
+//! age is mapped through the active palette so stable structures stand out from the
+//! chaotic regions around them. The grid is either seeded randomly or loaded from a
+//! pattern file in the run length encoded (RLE) format used by most Life software.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::Args;
+use std::path::PathBuf;
+
+/// Default size of a cell in screen pixels
+const DEFAULT_CELL_SIZE: usize = 4;
+
+/// Default number of generations computed per second
+const DEFAULT_RATE: f32 = 15.0;
+
+/// Fraction of cells alive after random seeding
+const SEED_DENSITY: f32 = 0.3;
+
+/// Age at which a cell reaches the end of the palette
+const MAX_AGE: u8 = 64;
+
+/// User tunable Game of Life parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Life options")]
+#[group(id = "life-options")]
+pub struct Config {
+    #[arg(
+        id = "cell-size",
+        long = "cell-size",
+        value_name = "CELL_SIZE",
+        default_value_t = DEFAULT_CELL_SIZE,
+        help = "Size of a cell in screen pixels"
+    )]
+    pub cell_size: usize,
+
+    #[arg(
+        id = "life-rate",
+        long = "life-rate",
+        value_name = "RATE",
+        default_value_t = DEFAULT_RATE,
+        help = "Number of generations computed per second"
+    )]
+    pub rate: f32,
+
+    #[arg(
+        id = "life-pattern",
+        long = "life-pattern",
+        value_name = "PATTERN",
+        help = "RLE pattern file used to seed the grid instead of random cells"
+    )]
+    pub pattern: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cell_size: DEFAULT_CELL_SIZE,
+            rate: DEFAULT_RATE,
+            pattern: None,
+        }
+    }
+}
+
+/// Parses a pattern in the RLE format and returns the coordinates of its live cells.
+///
+/// Comment lines starting with `#` and the `x = .., y = ..` header line are skipped. The
+/// body is a sequence of optionally counted tags: `b` for dead cells, `o` for live cells,
+/// `$` for the end of a row, and `!` for the end of the pattern.
+pub fn parse_rle(source: &str) -> Result<Vec<(usize, usize)>, String> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut count: Option<usize> = None;
+
+    let body = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && !line.starts_with('x'));
+
+    for line in body {
+        for c in line.chars() {
+            match c {
+                '0'..='9' => {
+                    let digit = c as usize - '0' as usize;
+                    count = Some(count.unwrap_or(0) * 10 + digit);
+                    continue;
+                }
+                'b' | '.' => x += count.unwrap_or(1),
+                'o' | 'A' => {
+                    for _ in 0..count.unwrap_or(1) {
+                        cells.push((x, y));
+                        x += 1;
+                    }
+                }
+                '$' => {
+                    y += count.unwrap_or(1);
+                    x = 0;
+                }
+                '!' => return Ok(cells),
+                c if c.is_whitespace() => {}
+                c => return Err(format!("unexpected character '{}' in RLE pattern", c)),
+            }
+            count = None;
+        }
+    }
+    Ok(cells)
+}
+
+/// A toroidal Game of Life simulation
+pub struct Life {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Game of Life parameters
+    config: Config,
+    /// Color palette used for mapping cell ages
+    palette: Palette,
+    /// Number of cell columns
+    columns: usize,
+    /// Number of cell rows
+    rows: usize,
+    /// Age of every cell, where 0 means the cell is dead
+    cells: Vec<u8>,
+    /// Scratch buffer receiving the next generation
+    next: Vec<u8>,
+    /// Number of generations computed so far
+    generation: u64,
+    /// Seconds the simulation has been running
+    elapsed: f32,
+}
+
+impl Life {
+    pub fn new(
+        width: usize,
+        height: usize,
+        palette: Palette,
+        config: Config,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let cell_size = config.cell_size.max(1);
+        let columns = (width / cell_size).max(1);
+        let rows = (height / cell_size).max(1);
+        let mut cells = vec![0; columns * rows];
+
+        match &config.pattern {
+            Some(path) => {
+                let pattern = parse_rle(&std::fs::read_to_string(path)?)?;
+                // Center the pattern on the grid
+                let pattern_width = pattern.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+                let pattern_height = pattern.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+                let left = columns.saturating_sub(pattern_width) / 2;
+                let top = rows.saturating_sub(pattern_height) / 2;
+                for (x, y) in pattern {
+                    cells[((top + y) % rows) * columns + (left + x) % columns] = 1;
+                }
+            }
+            None => {
+                let mut rng = fastrand::Rng::new();
+                for cell in cells.iter_mut() {
+                    *cell = (rng.f32() < SEED_DENSITY) as u8;
+                }
+            }
+        }
+
+        Ok(Self {
+            base: DemoBase::new(width, height),
+            config,
+            palette,
+            columns,
+            rows,
+            next: cells.clone(),
+            cells,
+            generation: 0,
+            elapsed: 0.0,
+        })
+    }
+
+    /// Counts the live neighbors of a cell, wrapping around the grid edges.
+    fn neighbors(&self, x: usize, y: usize) -> usize {
+        let mut count = 0;
+        for dy in [self.rows - 1, 0, 1] {
+            for dx in [self.columns - 1, 0, 1] {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let nx = (x + dx) % self.columns;
+                let ny = (y + dy) % self.rows;
+                count += (self.cells[ny * self.columns + nx] > 0) as usize;
+            }
+        }
+        count
+    }
+
+    /// Computes the next generation.
+    fn step(&mut self) {
+        for y in 0..self.rows {
+            for x in 0..self.columns {
+                let i = y * self.columns + x;
+                let age = self.cells[i];
+                self.next[i] = match (age > 0, self.neighbors(x, y)) {
+                    // Survival, the cell grows one generation older
+                    (true, 2) | (true, 3) => age.saturating_add(1),
+                    // Birth
+                    (false, 3) => 1,
+                    // Death by under or overpopulation
+                    _ => 0,
+                };
+            }
+        }
+        std::mem::swap(&mut self.cells, &mut self.next);
+        self.generation += 1;
+    }
+}
+
+impl DemoEffect for Life {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        // Catch up with the number of generations expected at this point in time
+        let target = (self.elapsed * self.config.rate).max(0.0) as u64;
+        while self.generation < target {
+            self.step();
+        }
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let cell_size = self.config.cell_size.max(1);
+        for_each_row(buffer, self.base.width, |y, row| {
+            let cy = (y / cell_size).min(self.rows - 1);
+            row.iter_mut().enumerate().for_each(|(x, pixel)| {
+                let cx = (x / cell_size).min(self.columns - 1);
+                let age = self.cells[cy * self.columns + cx];
+                *pixel = if age == 0 {
+                    rgb(0, 0, 0)
+                } else {
+                    self.palette.color(age.min(MAX_AGE) as f32 / MAX_AGE as f32)
+                };
+            });
+        });
+    }
+
+    /// Keeps the cells that still fit on the resized grid.
+    fn resize(&mut self, width: usize, height: usize) {
+        let cell_size = self.config.cell_size.max(1);
+        let columns = (width / cell_size).max(1);
+        let rows = (height / cell_size).max(1);
+        let mut cells = vec![0; columns * rows];
+        for y in 0..rows.min(self.rows) {
+            for x in 0..columns.min(self.columns) {
+                cells[y * columns + x] = self.cells[y * self.columns + x];
+            }
+        }
+
+        self.base = DemoBase::new(width, height);
+        self.columns = columns;
+        self.rows = rows;
+        self.next = cells.clone();
+        self.cells = cells;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_life(columns: usize, rows: usize) -> Life {
+        let config = Config {
+            cell_size: 1,
+            ..Config::default()
+        };
+        let mut life = Life::new(columns, rows, Palette::Rainbow, config).unwrap();
+        life.cells.fill(0);
+        life
+    }
+
+    fn set(life: &mut Life, cells: &[(usize, usize)]) {
+        for &(x, y) in cells {
+            life.cells[y * life.columns + x] = 1;
+        }
+    }
+
+    fn alive(life: &Life) -> Vec<(usize, usize)> {
+        (0..life.cells.len())
+            .filter(|&i| life.cells[i] > 0)
+            .map(|i| (i % life.columns, i / life.columns))
+            .collect()
+    }
+
+    #[test]
+    fn blinker_oscillates_with_period_two() {
+        let mut life = create_life(5, 5);
+        set(&mut life, &[(1, 2), (2, 2), (3, 2)]);
+        life.step();
+        assert_eq!(alive(&life), vec![(2, 1), (2, 2), (2, 3)]);
+        life.step();
+        assert_eq!(alive(&life), vec![(1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn block_is_stable_and_ages() {
+        let mut life = create_life(4, 4);
+        set(&mut life, &[(1, 1), (2, 1), (1, 2), (2, 2)]);
+        for _ in 0..5 {
+            life.step();
+        }
+        assert_eq!(alive(&life), vec![(1, 1), (2, 1), (1, 2), (2, 2)]);
+        assert_eq!(life.cells[life.columns + 1], 6);
+    }
+
+    #[test]
+    fn resizing_keeps_cells_that_still_fit() {
+        let mut life = create_life(5, 5);
+        set(&mut life, &[(1, 1), (4, 4)]);
+        life.resize(3, 8);
+        assert_eq!((life.columns, life.rows), (3, 8));
+        assert_eq!(alive(&life), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn neighbors_wrap_around_edges() {
+        let mut life = create_life(5, 5);
+        set(&mut life, &[(4, 4), (0, 4), (4, 0)]);
+        assert_eq!(life.neighbors(0, 0), 3);
+    }
+
+    #[test]
+    fn parse_rle_reads_glider() {
+        let glider = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!";
+        assert_eq!(
+            parse_rle(glider).unwrap(),
+            vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn parse_rle_handles_multiple_row_breaks() {
+        assert_eq!(parse_rle("o2$o!").unwrap(), vec![(0, 0), (0, 2)]);
+    }
+
+    #[test]
+    fn parse_rle_rejects_unknown_tags() {
+        assert!(parse_rle("3z!").is_err());
+    }
+}
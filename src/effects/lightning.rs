@@ -0,0 +1,284 @@
+//! Branching lightning bolts.
+//!
+//! A bolt starts as a single segment between the sky and its target. Midpoint displacement
+//! repeatedly splits every segment in two and nudges the new midpoint sideways by a random
+//! amount proportional to the segment length, producing a jagged path. Some midpoints also
+//! fork off a shorter, dimmer branch. Bolts are stamped into a glow buffer with a soft
+//! falloff around the core, and the buffer fades out after every strike.
+//!
+//! Bolts strike at random intervals, and clicking the window strikes at the pointer.
+use crate::common::{DemoBase, DemoEffect, InputEvent};
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default number of strikes per second
+const DEFAULT_STRIKE_RATE: f32 = 0.8;
+
+/// Number of times the bolt segments are subdivided
+const SUBDIVISIONS: u32 = 7;
+
+/// Sideways displacement of a midpoint relative to the segment length
+const ROUGHNESS: f32 = 0.35;
+
+/// Probability that a midpoint forks off a branch
+const BRANCH_CHANCE: f32 = 0.08;
+
+/// Radius of the glow around the bolt core in pixels
+const GLOW_RADIUS: i32 = 3;
+
+/// Fraction of the glow lost per second
+const FADE_RATE: f32 = 4.0;
+
+/// User tunable lightning parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Lightning options")]
+#[group(id = "lightning-options")]
+pub struct Config {
+    #[arg(
+        id = "strike-rate",
+        long = "strike-rate",
+        value_name = "RATE",
+        default_value_t = DEFAULT_STRIKE_RATE,
+        help = "Average number of lightning strikes per second"
+    )]
+    pub strike_rate: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            strike_rate: DEFAULT_STRIKE_RATE,
+        }
+    }
+}
+
+/// A straight piece of a bolt
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment {
+    from: (f32, f32),
+    to: (f32, f32),
+    /// Brightness of the segment in [0,1], branches are dimmer than the main bolt
+    intensity: f32,
+}
+
+/// Lightning bolts striking at random or at the pointer
+pub struct Lightning {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the glow
+    palette: Palette,
+    /// Lightning parameters
+    config: Config,
+    /// Glow intensity of every pixel in [0,1]
+    glow: Vec<f32>,
+    /// Whether the mouse button was held during the previous frame
+    was_pressed: bool,
+    /// Random number generator shaping the bolts
+    rng: fastrand::Rng,
+}
+
+impl Lightning {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            config,
+            glow: vec![0.0; base.pixel_count()],
+            was_pressed: false,
+            rng: fastrand::Rng::new(),
+        }
+    }
+
+    /// Generates the segments of a bolt between two points using midpoint displacement.
+    fn bolt(&mut self, from: (f32, f32), to: (f32, f32)) -> Vec<Segment> {
+        let mut segments = vec![Segment {
+            from,
+            to,
+            intensity: 1.0,
+        }];
+
+        for _ in 0..SUBDIVISIONS {
+            let mut next = Vec::with_capacity(segments.len() * 2);
+            for segment in segments {
+                let (dx, dy) = (segment.to.0 - segment.from.0, segment.to.1 - segment.from.1);
+                let length = (dx * dx + dy * dy).sqrt();
+                // Push the midpoint along the segment normal
+                let offset = (self.rng.f32() * 2.0 - 1.0) * length * ROUGHNESS;
+                let (nx, ny) = if length > 0.0 {
+                    (-dy / length, dx / length)
+                } else {
+                    (0.0, 0.0)
+                };
+                let mid = (
+                    (segment.from.0 + segment.to.0) * 0.5 + nx * offset,
+                    (segment.from.1 + segment.to.1) * 0.5 + ny * offset,
+                );
+
+                next.push(Segment { to: mid, ..segment });
+                next.push(Segment {
+                    from: mid,
+                    ..segment
+                });
+
+                if self.rng.f32() < BRANCH_CHANCE {
+                    // Continue roughly in the direction of the segment, slightly rotated
+                    let angle = (self.rng.f32() - 0.5) * 1.2;
+                    let (sin, cos) = angle.sin_cos();
+                    let reach = 0.7;
+                    next.push(Segment {
+                        from: mid,
+                        to: (
+                            mid.0 + (dx * cos - dy * sin) * reach,
+                            mid.1 + (dx * sin + dy * cos) * reach,
+                        ),
+                        intensity: segment.intensity * 0.5,
+                    });
+                }
+            }
+            segments = next;
+        }
+        segments
+    }
+
+    /// Strikes a new bolt from a random point in the sky down to `target`.
+    fn strike(&mut self, target: (f32, f32)) {
+        let start = (self.rng.f32() * self.base.width as f32, 0.0);
+        for segment in self.bolt(start, target) {
+            self.stamp(segment);
+        }
+    }
+
+    /// Adds the glow of a segment to the glow buffer.
+    fn stamp(&mut self, segment: Segment) {
+        let (dx, dy) = (segment.to.0 - segment.from.0, segment.to.1 - segment.from.1);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        let (width, height) = (self.base.width as i32, self.base.height as i32);
+
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let cx = (segment.from.0 + dx * t) as i32;
+            let cy = (segment.from.1 + dy * t) as i32;
+            for oy in -GLOW_RADIUS..=GLOW_RADIUS {
+                for ox in -GLOW_RADIUS..=GLOW_RADIUS {
+                    let (x, y) = (cx + ox, cy + oy);
+                    if x < 0 || y < 0 || x >= width || y >= height {
+                        continue;
+                    }
+                    // Bright core fading quickly into a soft halo
+                    let dist = ((ox * ox + oy * oy) as f32).sqrt();
+                    let falloff = segment.intensity / (1.0 + dist * dist);
+                    let v = &mut self.glow[(y * width + x) as usize];
+                    *v = v.max(falloff);
+                }
+            }
+        }
+    }
+}
+
+impl DemoEffect for Lightning {
+    fn update(&mut self, dt: f32) {
+        let fade = (1.0 - FADE_RATE * dt).clamp(0.0, 1.0);
+        self.glow.iter_mut().for_each(|v| *v *= fade);
+
+        if self.rng.f32() < self.config.strike_rate * dt {
+            let target = (
+                self.rng.f32() * self.base.width as f32,
+                self.base.height as f32 - 1.0,
+            );
+            self.strike(target);
+        }
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer
+            .iter_mut()
+            .zip(&self.glow)
+            .for_each(|(pixel, &v)| *pixel = self.palette.color(v));
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        if let InputEvent::Mouse { x, y, pressed } = event {
+            // Only strike once per click rather than on every frame the button is held
+            if pressed && !self.was_pressed {
+                self.strike((x, y));
+            }
+            self.was_pressed = pressed;
+        }
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.glow = vec![0.0; self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_lightning() -> Lightning {
+        let mut lightning = Lightning::new(100, 100, Palette::BlueCyan, Config::default());
+        lightning.rng = fastrand::Rng::with_seed(11);
+        lightning
+    }
+
+    #[test]
+    fn bolt_connects_its_endpoints() {
+        let mut lightning = create_lightning();
+        let segments = lightning.bolt((50.0, 0.0), (30.0, 99.0));
+        assert_eq!(segments.first().unwrap().from, (50.0, 0.0));
+        let main: Vec<_> = segments.iter().filter(|s| s.intensity == 1.0).collect();
+        assert_eq!(main.last().unwrap().to, (30.0, 99.0));
+    }
+
+    #[test]
+    fn main_bolt_is_subdivided() {
+        let mut lightning = create_lightning();
+        let segments = lightning.bolt((50.0, 0.0), (50.0, 99.0));
+        let main = segments.iter().filter(|s| s.intensity == 1.0).count();
+        assert_eq!(main, 1 << SUBDIVISIONS);
+    }
+
+    #[test]
+    fn stamp_is_brightest_on_the_core() {
+        let mut lightning = create_lightning();
+        lightning.stamp(Segment {
+            from: (10.0, 50.0),
+            to: (90.0, 50.0),
+            intensity: 1.0,
+        });
+        assert_eq!(lightning.glow[50 * 100 + 50], 1.0);
+        assert!(lightning.glow[52 * 100 + 50] < 1.0);
+        assert_eq!(lightning.glow[0], 0.0);
+    }
+
+    #[test]
+    fn clicking_strikes_once_per_press() {
+        let mut lightning = create_lightning();
+        lightning.handle_input(InputEvent::Mouse {
+            x: 50.0,
+            y: 80.0,
+            pressed: true,
+        });
+        assert!(lightning.glow[80 * 100 + 50] > 0.0);
+
+        lightning.glow.fill(0.0);
+        lightning.handle_input(InputEvent::Mouse {
+            x: 50.0,
+            y: 80.0,
+            pressed: true,
+        });
+        assert!(lightning.glow.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn glow_fades_between_frames() {
+        let mut lightning = create_lightning();
+        lightning.config.strike_rate = 0.0;
+        lightning.glow.fill(1.0);
+        lightning.update(0.1);
+        assert!(lightning.glow[0] < 1.0);
+    }
+}
@@ -0,0 +1,165 @@
+//! Animated Lissajous figures.
+//!
+//! A point moves along `x = sin(a * t + phase)`, `y = sin(b * t)`, tracing the figures
+//! known from oscilloscopes fed two sine waves. The frequency ratio `a / b` slowly drifts
+//! around simple fractions and the phase keeps turning, so the figure keeps morphing
+//! between knots, loops, and ellipses. The traced line is stamped into a trail buffer that
+//! decays every frame, and every pixel is colored by how long ago the point passed it.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+
+/// Base frequency ratio between the horizontal and vertical waves
+const BASE_RATIO: f32 = 1.5;
+
+/// Amplitude of the slow drift of the frequency ratio
+const RATIO_DRIFT: f32 = 0.5;
+
+/// Speed of the frequency ratio drift in radians per second
+const DRIFT_SPEED: f32 = 0.05;
+
+/// Speed at which the phase turns in radians per second
+const PHASE_SPEED: f32 = 0.3;
+
+/// Curve parameter travelled per second
+const TRACE_SPEED: f32 = 4.0;
+
+/// Largest parameter step between two traced points, keeps the curve smooth
+const MAX_STEP: f32 = 0.01;
+
+/// Fraction of the trail brightness lost per second
+const FADE_RATE: f32 = 0.6;
+
+/// A Lissajous figure leaving a decaying trail
+pub struct Lissajous {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used to color the trail by age
+    palette: Palette,
+    /// Brightness of the trail at every pixel in [0,1], 1 being freshly traced
+    trail: Vec<f32>,
+    /// Current curve parameter
+    t: f32,
+    /// Seconds the figure has been traced, drives the shape drift
+    elapsed: f32,
+}
+
+impl Lissajous {
+    pub fn new(width: usize, height: usize, palette: Palette) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            trail: vec![0.0; base.pixel_count()],
+            t: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns the frequency ratio and phase of the figure at the given time.
+    fn shape(time: f32) -> (f32, f32) {
+        (
+            BASE_RATIO + RATIO_DRIFT * (time * DRIFT_SPEED).sin(),
+            time * PHASE_SPEED,
+        )
+    }
+
+    /// Returns the position of the point on screen for a curve parameter.
+    fn point(&self, t: f32, ratio: f32, phase: f32) -> (f32, f32) {
+        let (center_x, center_y) = self.base.center();
+        (
+            center_x + center_x * 0.9 * (ratio * t + phase).sin(),
+            center_y + center_y * 0.9 * t.sin(),
+        )
+    }
+
+    /// Moves the point forward and stamps the traced path into the trail.
+    fn trace(&mut self, dt: f32, time: f32) {
+        let (ratio, phase) = Self::shape(time);
+        let travel = TRACE_SPEED * dt;
+        let steps = (travel / MAX_STEP).ceil() as usize;
+        for _ in 0..steps {
+            let from = self.point(self.t, ratio, phase);
+            self.t += travel / steps as f32;
+            let to = self.point(self.t, ratio, phase);
+            self.stamp(from, to);
+        }
+    }
+
+    /// Marks the pixels of a line at full brightness in the trail buffer.
+    fn stamp(&mut self, from: (f32, f32), to: (f32, f32)) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let s = step as f32 / steps as f32;
+            let (x, y) = (from.0 + dx * s, from.1 + dy * s);
+            if x >= 0.0 && y >= 0.0 {
+                let (x, y) = (x as usize, y as usize);
+                if x < self.base.width && y < self.base.height {
+                    self.trail[y * self.base.width + x] = 1.0;
+                }
+            }
+        }
+    }
+}
+
+impl DemoEffect for Lissajous {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        let fade = (1.0 - FADE_RATE * dt).clamp(0.0, 1.0);
+        self.trail.iter_mut().for_each(|v| *v *= fade);
+        self.trace(dt, self.elapsed);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer.iter_mut().zip(&self.trail).for_each(|(pixel, &v)| {
+            // The hue tells the age while the brightness fades the trail out to black
+            let (r, g, b) = self.palette.rgb(v);
+            let scale = |c: u8| (c as f32 * v) as u8;
+            *pixel = rgb(scale(r), scale(g), scale(b));
+        });
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.trail = vec![0.0; self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_lissajous() -> Lissajous {
+        Lissajous::new(100, 100, Palette::Rainbow)
+    }
+
+    #[test]
+    fn curve_stays_on_screen() {
+        let lissajous = create_lissajous();
+        for step in 0..1000 {
+            let (ratio, phase) = Lissajous::shape(step as f32 * 0.7);
+            let (x, y) = lissajous.point(step as f32 * 0.1, ratio, phase);
+            assert!((0.0..100.0).contains(&x) && (0.0..100.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn ratio_drifts_around_base_ratio() {
+        let (start, _) = Lissajous::shape(0.0);
+        let (later, _) = Lissajous::shape(10.0);
+        assert_eq!(start, BASE_RATIO);
+        assert_ne!(later, BASE_RATIO);
+        assert!((later - BASE_RATIO).abs() <= RATIO_DRIFT);
+    }
+
+    #[test]
+    fn older_trail_is_dimmer() {
+        let mut lissajous = create_lissajous();
+        lissajous.update(0.3);
+        let fresh = lissajous.trail.iter().filter(|&&v| v == 1.0).count();
+        lissajous.update(0.3);
+        assert!(fresh > 0);
+        assert!(lissajous.trail.iter().any(|&v| v > 0.0 && v < 1.0));
+    }
+}
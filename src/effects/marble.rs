@@ -0,0 +1,182 @@
+//! Procedural marble and wood textures.
+//!
+//! Both textures start from a regular pattern that is pushed around by noise
+//! turbulence. Marble uses straight stripes, so the turbulence bends them into veins, while
+//! wood uses concentric rings around a point, so it bends them into growth rings with knots.
+//! The turbulence itself can also be shown on its own. Time drives the third noise
+//! dimension, so the veins slowly flow.
+use crate::common::{for_each_row, DemoBase, DemoEffect};
+use crate::noise::Noise;
+use crate::palette::Palette;
+use clap::{Args, ValueEnum};
+
+/// Number of noise octaves summed into the turbulence
+const OCTAVES: u32 = 5;
+
+/// Number of turbulence features across the smallest screen dimension
+const NOISE_SCALE: f32 = 3.0;
+
+/// Speed at which the turbulence evolves, in noise units per second
+const FLOW_SPEED: f32 = 0.1;
+
+/// Number of marble stripes across the smallest screen dimension
+const STRIPES: f32 = 4.0;
+
+/// Number of wood rings between the center and the edge of the screen
+const RINGS: f32 = 8.0;
+
+/// Default strength with which the turbulence distorts the pattern
+const DEFAULT_TURBULENCE: f32 = 5.0;
+
+/// Procedural textures that can be generated
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Material {
+    Marble,
+    Wood,
+    Turbulence,
+}
+
+/// User tunable marble parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Marble options")]
+#[group(id = "marble-options")]
+pub struct Config {
+    #[arg(
+        id = "material",
+        long = "material",
+        value_name = "MATERIAL",
+        value_enum,
+        default_value_t = Material::Marble,
+        help = "Procedural texture to generate"
+    )]
+    pub material: Material,
+
+    #[arg(
+        id = "turbulence",
+        long = "turbulence",
+        value_name = "STRENGTH",
+        default_value_t = DEFAULT_TURBULENCE,
+        help = "Strength with which the turbulence distorts the texture"
+    )]
+    pub turbulence: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            material: Material::Marble,
+            turbulence: DEFAULT_TURBULENCE,
+        }
+    }
+}
+
+/// Animated turbulence-based textures
+pub struct Marble {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the texture
+    palette: Palette,
+    /// Marble parameters
+    config: Config,
+    /// Noise generator providing the turbulence
+    noise: Noise,
+}
+
+impl Marble {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            config,
+            noise: Noise::new(fastrand::u64(..)),
+        }
+    }
+
+    /// Returns the texture value at a pixel in the [0,1] range.
+    fn texture(&self, x: usize, y: usize, time: f32) -> f32 {
+        let min_dim = self.base.width.min(self.base.height).max(1) as f32;
+        // Coordinates in units of the smallest screen dimension
+        let (u, v) = (x as f32 / min_dim, y as f32 / min_dim);
+        let turbulence =
+            self.noise
+                .turbulence(u * NOISE_SCALE, v * NOISE_SCALE, time * FLOW_SPEED, OCTAVES);
+
+        match self.config.material {
+            Material::Marble => {
+                let phase = (u + v) * STRIPES * std::f32::consts::PI;
+                (phase + self.config.turbulence * turbulence).sin() * 0.5 + 0.5
+            }
+            Material::Wood => {
+                let (center_x, center_y) = self.base.center();
+                let (dx, dy) = (
+                    (x as f32 - center_x) / min_dim,
+                    (y as f32 - center_y) / min_dim,
+                );
+                let dist = (dx * dx + dy * dy).sqrt() * 2.0;
+                // Only the fractional part of the ring count matters, giving sawtooth rings
+                (dist * RINGS + self.config.turbulence * 0.2 * turbulence).fract()
+            }
+            Material::Turbulence => (turbulence * 1.5).min(1.0),
+        }
+    }
+}
+
+impl DemoEffect for Marble {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.palette.color(self.texture(x, y, time));
+            }
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_marble(material: Material) -> Marble {
+        let mut marble = Marble::new(
+            64,
+            64,
+            Palette::BlackWhite,
+            Config {
+                material,
+                ..Config::default()
+            },
+        );
+        marble.noise = Noise::new(11);
+        marble
+    }
+
+    #[test]
+    fn textures_stay_in_unit_range() {
+        for material in [Material::Marble, Material::Wood, Material::Turbulence] {
+            let marble = create_marble(material);
+            for y in (0..64).step_by(3) {
+                for x in (0..64).step_by(3) {
+                    assert!((0.0..=1.0).contains(&marble.texture(x, y, 0.8)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wood_without_turbulence_has_concentric_rings() {
+        let mut marble = create_marble(Material::Wood);
+        marble.config.turbulence = 0.0;
+        assert_eq!(marble.texture(32, 10, 0.0), marble.texture(32, 54, 0.0));
+        assert_eq!(marble.texture(10, 32, 0.0), marble.texture(54, 32, 0.0));
+    }
+
+    #[test]
+    fn texture_flows_over_time() {
+        let marble = create_marble(Material::Marble);
+        let changed = (0..64).any(|x| marble.texture(x, 20, 0.0) != marble.texture(x, 20, 5.0));
+        assert!(changed);
+    }
+}
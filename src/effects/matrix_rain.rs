@@ -0,0 +1,267 @@
+//! Matrix-style digital rain.
+//!
+//! The screen is divided into a grid of glyph cells. Each column hosts a falling drop whose
+//! head leaves a trail of glyphs behind it, and every glyph fades out over time so the
+//! trails appear to dissolve. Glyphs come from a small atlas of mirrored characters built
+//! from the embedded font, which gives them the alien look of the film.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+use crate::font::{self, GLYPH_SIZE};
+use clap::Args;
+
+/// Characters mirrored to build the glyph atlas
+const ATLAS_CHARS: &str = "0123456789ZXCVBNMASDFGHJKL:+=*<>";
+
+/// Default fall speed in cells per second
+const DEFAULT_SPEED: f32 = 12.0;
+
+/// Default fraction of columns with a falling drop
+const DEFAULT_DENSITY: f32 = 0.7;
+
+/// Brightness lost by a glyph per second
+const FADE_RATE: f32 = 0.8;
+
+/// Probability per frame that a visible glyph changes into another one
+const MUTATION_PROBABILITY: f32 = 0.01;
+
+/// User tunable digital rain parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Matrix rain options")]
+#[group(id = "matrix-rain-options")]
+pub struct Config {
+    #[arg(
+        id = "rain-speed",
+        long = "rain-speed",
+        value_name = "SPEED",
+        default_value_t = DEFAULT_SPEED,
+        help = "Fall speed of the glyph columns in cells per second"
+    )]
+    pub speed: f32,
+
+    #[arg(
+        id = "rain-density",
+        long = "rain-density",
+        value_name = "DENSITY",
+        default_value_t = DEFAULT_DENSITY,
+        help = "Fraction of columns with a falling drop, between 0 and 1"
+    )]
+    pub density: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            speed: DEFAULT_SPEED,
+            density: DEFAULT_DENSITY,
+        }
+    }
+}
+
+/// A glyph cell of the grid
+#[derive(Debug, Clone, Copy, Default)]
+struct Cell {
+    /// Index of the glyph in the atlas
+    glyph: usize,
+    /// Brightness in the [0,1] range
+    brightness: f32,
+}
+
+/// A falling drop in a column
+#[derive(Debug, Clone, Copy)]
+struct Drop {
+    /// Row of the drop head, in cells
+    head: f32,
+    /// Fall speed multiplier so columns don't move in lockstep
+    speed: f32,
+    /// Whether the column currently has a falling drop
+    active: bool,
+}
+
+/// A digital rain effect
+pub struct MatrixRain {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Digital rain parameters
+    config: Config,
+    /// Mirrored glyph bitmaps
+    atlas: Vec<[u8; GLYPH_SIZE]>,
+    /// Number of glyph columns
+    columns: usize,
+    /// Number of glyph rows
+    rows: usize,
+    /// Glyph cells in row-major order
+    cells: Vec<Cell>,
+    /// Drop state of every column
+    drops: Vec<Drop>,
+    /// Random number generator for glyphs and drops
+    rng: fastrand::Rng,
+}
+
+impl MatrixRain {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        let columns = width.div_ceil(GLYPH_SIZE);
+        let rows = height.div_ceil(GLYPH_SIZE);
+        let mut rng = fastrand::Rng::new();
+        let drops = (0..columns)
+            .map(|_| Drop {
+                head: -(rng.f32() * rows as f32),
+                speed: 0.5 + rng.f32(),
+                active: rng.f32() < config.density,
+            })
+            .collect();
+
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            atlas: Self::build_atlas(),
+            columns,
+            rows,
+            cells: vec![Cell::default(); columns * rows],
+            drops,
+            rng,
+        }
+    }
+
+    /// Builds the glyph atlas by mirroring the font characters horizontally.
+    fn build_atlas() -> Vec<[u8; GLYPH_SIZE]> {
+        ATLAS_CHARS
+            .chars()
+            .map(|c| font::glyph(c).map(|row| row.reverse_bits()))
+            .collect()
+    }
+
+    /// Moves the drops down, leaving fresh glyphs behind their heads, and fades the grid.
+    fn advance(&mut self, dt: f32) {
+        for cell in self.cells.iter_mut() {
+            cell.brightness = (cell.brightness - FADE_RATE * dt).max(0.0);
+            if cell.brightness > 0.0 && self.rng.f32() < MUTATION_PROBABILITY {
+                cell.glyph = self.rng.usize(..self.atlas.len());
+            }
+        }
+
+        for x in 0..self.columns {
+            let drop = self.drops[x];
+            if !drop.active {
+                // Idle columns start a new drop every now and then
+                if self.rng.f32() < self.config.density * dt {
+                    self.drops[x] = Drop {
+                        head: 0.0,
+                        active: true,
+                        ..drop
+                    };
+                }
+                continue;
+            }
+
+            let head = drop.head + self.config.speed * drop.speed * dt;
+            // Light up every cell the head passed through during this frame
+            let from = drop.head.max(0.0) as usize;
+            let to = (head.max(0.0) as usize).min(self.rows);
+            for y in from..to {
+                self.cells[y * self.columns + x] = Cell {
+                    glyph: self.rng.usize(..self.atlas.len()),
+                    brightness: 1.0,
+                };
+            }
+
+            self.drops[x] = if head as usize >= self.rows {
+                Drop {
+                    head: 0.0,
+                    speed: 0.5 + self.rng.f32(),
+                    active: self.rng.f32() < self.config.density,
+                }
+            } else {
+                Drop { head, ..drop }
+            };
+        }
+    }
+}
+
+impl DemoEffect for MatrixRain {
+    fn update(&mut self, dt: f32) {
+        self.advance(dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let width = self.base.width;
+        for_each_row(buffer, width, |y, row| {
+            let cy = y / GLYPH_SIZE;
+            row.iter_mut().enumerate().for_each(|(x, pixel)| {
+                let cx = x / GLYPH_SIZE;
+                let cell = self.cells[cy * self.columns + cx];
+                let lit = self.atlas[cell.glyph][y % GLYPH_SIZE] & (1 << (x % GLYPH_SIZE));
+
+                *pixel = if lit == 0 || cell.brightness == 0.0 {
+                    rgb(0, 0, 0)
+                } else if cell.brightness > 0.95 {
+                    // Freshly written glyphs near the head glow white
+                    rgb(200, 255, 200)
+                } else {
+                    let level = cell.brightness * 255.0;
+                    rgb((level * 0.2) as u8, level as u8, (level * 0.3) as u8)
+                };
+            });
+        });
+    }
+
+    /// Starts the rain over on a grid matching the new size.
+    fn resize(&mut self, width: usize, height: usize) {
+        *self = Self::new(width, height, self.config.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_rain(density: f32) -> MatrixRain {
+        let config = Config {
+            density,
+            ..Config::default()
+        };
+        let mut rain = MatrixRain::new(80, 64, config);
+        rain.rng = fastrand::Rng::with_seed(3);
+        rain
+    }
+
+    #[test]
+    fn atlas_glyphs_are_mirrored_font_characters() {
+        let atlas = MatrixRain::build_atlas();
+        assert_eq!(atlas.len(), ATLAS_CHARS.len());
+        let original = font::glyph('7');
+        let mirrored = atlas[7];
+        for (o, m) in original.iter().zip(&mirrored) {
+            assert_eq!(o.reverse_bits(), *m);
+        }
+    }
+
+    #[test]
+    fn grid_covers_the_whole_screen() {
+        let rain = MatrixRain::new(81, 63, Config::default());
+        assert_eq!(rain.columns, 11);
+        assert_eq!(rain.rows, 8);
+    }
+
+    #[test]
+    fn glyphs_fade_out_over_time() {
+        let mut rain = create_rain(0.0);
+        rain.cells[0].brightness = 1.0;
+        rain.advance(0.5);
+        assert!(rain.cells[0].brightness < 1.0);
+        rain.advance(10.0);
+        assert_eq!(rain.cells[0].brightness, 0.0);
+    }
+
+    #[test]
+    fn falling_drop_lights_up_cells_behind_its_head() {
+        let mut rain = create_rain(0.0);
+        rain.drops[0] = Drop {
+            head: 0.0,
+            speed: 1.0,
+            active: true,
+        };
+        rain.advance(3.0 / DEFAULT_SPEED);
+        for y in 0..3 {
+            assert_eq!(rain.cells[y * rain.columns].brightness, 1.0);
+        }
+    }
+}
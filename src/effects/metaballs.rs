@@ -0,0 +1,220 @@
+//! The metaballs effect.
+//!
+//! Each blob contributes a field that falls off with the squared distance to its center.
+//! The fields of all blobs are summed at every pixel, and pixels whose total crosses a
+//! threshold are considered inside the surface. Because the fields add up, blobs that get
+//! close to each other melt together into a single smooth shape.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default number of blobs
+const DEFAULT_COUNT: usize = 6;
+
+/// Default blob radius as a fraction of the smallest screen dimension
+const DEFAULT_RADIUS: f32 = 0.12;
+
+/// Default blob speed multiplier
+const DEFAULT_SPEED: f32 = 1.0;
+
+/// Field strength at which a pixel is considered inside the surface
+const THRESHOLD: f32 = 1.0;
+
+/// User tunable metaballs parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Metaballs options")]
+#[group(id = "metaballs-options")]
+pub struct Config {
+    #[arg(
+        id = "blob-count",
+        long = "blob-count",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_COUNT,
+        help = "Number of metaballs"
+    )]
+    pub count: usize,
+
+    #[arg(
+        id = "blob-radius",
+        long = "blob-radius",
+        value_name = "RADIUS",
+        default_value_t = DEFAULT_RADIUS,
+        help = "Blob radius as a fraction of the smallest screen dimension"
+    )]
+    pub radius: f32,
+
+    #[arg(
+        id = "blob-speed",
+        long = "blob-speed",
+        value_name = "SPEED",
+        default_value_t = DEFAULT_SPEED,
+        help = "Speed multiplier applied to the blob motion"
+    )]
+    pub speed: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_COUNT,
+            radius: DEFAULT_RADIUS,
+            speed: DEFAULT_SPEED,
+        }
+    }
+}
+
+/// Motion parameters of a single blob
+///
+/// Blobs follow Lissajous paths so their motion is smooth and a pure function of time.
+#[derive(Debug, Clone, Copy)]
+struct Blob {
+    /// Horizontal and vertical angular frequencies
+    frequency: (f32, f32),
+    /// Horizontal and vertical phase offsets
+    phase: (f32, f32),
+}
+
+/// A metaballs renderer mapping the summed field through a palette
+pub struct Metaballs {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Metaballs parameters
+    config: Config,
+    /// Color palette used for rendering the field
+    palette: Palette,
+    /// Motion parameters of every blob
+    blobs: Vec<Blob>,
+}
+
+impl Metaballs {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let mut rng = fastrand::Rng::new();
+        let blobs = (0..config.count)
+            .map(|_| Blob {
+                frequency: (0.3 + rng.f32() * 0.7, 0.3 + rng.f32() * 0.7),
+                phase: (
+                    rng.f32() * std::f32::consts::TAU,
+                    rng.f32() * std::f32::consts::TAU,
+                ),
+            })
+            .collect();
+
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            palette,
+            blobs,
+        }
+    }
+
+    /// Returns the screen position of every blob at the given time.
+    fn positions(&self, time: f32) -> Vec<(f32, f32)> {
+        let (center_x, center_y) = self.base.center();
+        let t = time * self.config.speed;
+        self.blobs
+            .iter()
+            .map(|blob| {
+                let x = center_x + center_x * 0.8 * (t * blob.frequency.0 + blob.phase.0).sin();
+                let y = center_y + center_y * 0.8 * (t * blob.frequency.1 + blob.phase.1).cos();
+                (x, y)
+            })
+            .collect()
+    }
+
+    /// Computes the summed field strength of all blobs at a point.
+    fn field(&self, positions: &[(f32, f32)], x: f32, y: f32) -> f32 {
+        let min_dim = self.base.width.min(self.base.height) as f32;
+        let radius = self.config.radius * min_dim;
+        let radius_sq = radius * radius;
+
+        positions
+            .iter()
+            .map(|&(bx, by)| {
+                let dx = x - bx;
+                let dy = y - by;
+                // Offset the distance slightly to avoid dividing by zero at the blob center
+                radius_sq / (dx * dx + dy * dy + 1.0)
+            })
+            .sum()
+    }
+}
+
+impl DemoEffect for Metaballs {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let positions = self.positions(time);
+
+        for_each_row(buffer, self.base.width, |y, row| {
+            row.iter_mut().enumerate().for_each(|(x, pixel)| {
+                let field = self.field(&positions, x as f32, y as f32);
+                // Map the field onto [0,1], reaching the middle of the palette at the
+                // surface threshold
+                let v = (field / (2.0 * THRESHOLD)).min(1.0);
+                let (r, g, b) = self.palette.rgb(v);
+
+                // Fade out the glow surrounding the blobs
+                let glow = (field / THRESHOLD).min(1.0).powi(2);
+                *pixel = rgb(
+                    (r as f32 * glow) as u8,
+                    (g as f32 * glow) as u8,
+                    (b as f32 * glow) as u8,
+                );
+            });
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_metaballs(count: usize) -> Metaballs {
+        let config = Config {
+            count,
+            ..Config::default()
+        };
+        Metaballs::new(200, 100, Palette::Rainbow, config)
+    }
+
+    #[test]
+    fn metaballs_contain_configured_number_of_blobs() {
+        let metaballs = create_metaballs(9);
+        assert_eq!(metaballs.blobs.len(), 9);
+        assert_eq!(metaballs.positions(0.0).len(), 9);
+    }
+
+    #[test]
+    fn field_is_strongest_at_blob_center() {
+        let metaballs = create_metaballs(1);
+        let positions = [(50.0, 50.0)];
+        let center = metaballs.field(&positions, 50.0, 50.0);
+        let away = metaballs.field(&positions, 90.0, 50.0);
+        assert!(
+            center > THRESHOLD,
+            "Blob center should be inside the surface"
+        );
+        assert!(away < center, "Field should fall off with distance");
+    }
+
+    #[test]
+    fn fields_of_overlapping_blobs_add_up() {
+        let metaballs = create_metaballs(2);
+        let single = metaballs.field(&[(50.0, 50.0)], 60.0, 50.0);
+        let double = metaballs.field(&[(50.0, 50.0), (50.0, 50.0)], 60.0, 50.0);
+        assert!((double - 2.0 * single).abs() < 1e-4);
+    }
+
+    #[test]
+    fn blobs_stay_on_screen() {
+        let metaballs = create_metaballs(8);
+        for step in 0..100 {
+            for (x, y) in metaballs.positions(step as f32 * 0.37) {
+                assert!((0.0..=200.0).contains(&x));
+                assert!((0.0..=100.0).contains(&y));
+            }
+        }
+    }
+}
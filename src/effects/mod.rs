@@ -0,0 +1,1007 @@
+//! Demo effects rendered by the main loop, starting with the plasma itself.
+//!
+//! Every effect is listed in [`EFFECTS`] together with a constructor building it from the
+//! command line [`Settings`], so adding an effect to the list makes it selectable with the
+//! `--effect` flag without touching the main loop. [`registry`] turns the list into an
+//! [`EffectRegistry`] the main loop creates effects from.
+use crate::common::{ColorCycle, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::Args;
+use plasma::Shape;
+use registry::EffectRegistry;
+use std::rc::Rc;
+
+pub mod amigaball;
+pub mod attractor;
+pub mod boids;
+pub mod bumpmap;
+pub mod caustics;
+pub mod clouds;
+pub mod copperbars;
+pub mod dottunnel;
+pub mod feedback;
+pub mod fire;
+pub mod fireworks;
+pub mod floor;
+pub mod fractal;
+pub mod helix;
+pub mod hypno;
+pub mod kaleidoscope;
+pub mod lens;
+pub mod life;
+pub mod lightning;
+pub mod lissajous;
+pub mod marble;
+pub mod matrix_rain;
+pub mod metaballs;
+pub mod munching;
+pub mod noise_clouds;
+pub mod particles;
+pub mod plasma;
+pub mod plasma_globe;
+pub mod rain;
+pub mod registry;
+pub mod rotozoom;
+pub mod sand;
+pub mod scroller;
+pub mod shadebobs;
+pub mod smoke;
+pub mod snow;
+pub mod spirograph;
+pub mod starfield;
+pub mod static_noise;
+pub mod tunnel;
+pub mod twister;
+pub mod voronoi;
+pub mod water;
+pub mod wavepool;
+pub mod wireframe;
+
+/// Builds an effect of the given width and height from the command line settings
+pub type Constructor =
+    fn(usize, usize, &Settings) -> Result<Box<dyn DemoEffect>, Box<dyn std::error::Error>>;
+
+/// An effect built into the program
+pub struct Builtin {
+    /// Name selecting the effect on the command line
+    pub name: &'static str,
+    /// One line summary shown by `--list-effects`
+    pub description: &'static str,
+    /// Builds the effect from the command line settings
+    pub constructor: Constructor,
+}
+
+/// Parameters shared by the effects along with the options of every individual effect
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct Settings {
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value_t = Shape::Ripple,
+        help = "Plasma shape"
+    )]
+    pub shape: Shape,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = Palette::Rainbow,
+        help = "Color palette: rainbow, blue-cyan, hot, purple-pink, black-white, spectrum, sunset, ocean, lime, desert, neon, fire, pastel, viridis, magma, inferno, cividis, cosine:A:B:C:D, or gradient colors such as '#000000,#ff8000@0.7,#ffffff'"
+    )]
+    pub palette: Palette,
+
+    #[arg(
+        short = 'x',
+        long,
+        default_value_t = 10.0,
+        help = "Scale factor that controls the density/size of the plasma patterns"
+    )]
+    pub scale: f32,
+
+    #[arg(
+        long,
+        help = "Run the palette from its end to its start, as toggled with R"
+    )]
+    pub palette_reversed: bool,
+
+    #[arg(
+        long,
+        value_name = "DEGREES",
+        default_value_t = 0.0,
+        allow_negative_numbers = true,
+        help = "Turn the hues of the palette by this angle, as changed with H"
+    )]
+    pub palette_hue: f32,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u8).range(2..=5),
+        help = "Ripple centers orbiting the screen in the multi-ripple plasma shape, from 2 to 5"
+    )]
+    pub plasma_emitters: u8,
+
+    #[arg(
+        long,
+        value_name = "ENTRIES",
+        value_parser = clap::value_parser!(u32).range(4..),
+        help = "Approximate the plasma sines with a lookup table of this many entries, faster but less precise"
+    )]
+    pub sin_table: Option<u32>,
+
+    #[arg(
+        long,
+        help = "Color effects drawing palette indices through a rotating palette, like old color cycling demos"
+    )]
+    pub color_cycle: bool,
+
+    #[arg(
+        long,
+        value_name = "TURNS",
+        default_value_t = 0.25,
+        allow_negative_numbers = true,
+        help = "Palette turns per second with --color-cycle, negative to rotate backward"
+    )]
+    pub cycle_speed: f32,
+
+    #[command(flatten)]
+    pub superformula: plasma::Superformula,
+
+    #[command(flatten)]
+    pub starfield: starfield::Config,
+
+    #[command(flatten)]
+    pub tunnel: tunnel::Config,
+
+    #[command(flatten)]
+    pub rotozoom: rotozoom::Config,
+
+    #[command(flatten)]
+    pub metaballs: metaballs::Config,
+
+    #[command(flatten)]
+    pub fractal: fractal::Config,
+
+    #[command(flatten)]
+    pub scroller: scroller::Config,
+
+    #[command(flatten)]
+    pub life: life::Config,
+
+    #[command(flatten)]
+    pub matrix_rain: matrix_rain::Config,
+
+    #[command(flatten)]
+    pub particles: particles::Config,
+
+    #[command(flatten)]
+    pub fireworks: fireworks::Config,
+
+    #[command(flatten)]
+    pub attractor: attractor::Config,
+
+    #[command(flatten)]
+    pub boids: boids::Config,
+
+    #[command(flatten)]
+    pub bumpmap: bumpmap::Config,
+
+    #[command(flatten)]
+    pub wireframe: wireframe::Config,
+
+    #[command(flatten)]
+    pub twister: twister::Config,
+
+    #[command(flatten)]
+    pub shadebobs: shadebobs::Config,
+
+    #[command(flatten)]
+    pub kaleidoscope: kaleidoscope::Config,
+
+    #[command(flatten)]
+    pub noise_clouds: noise_clouds::Config,
+
+    #[command(flatten)]
+    pub lightning: lightning::Config,
+
+    #[command(flatten)]
+    pub voronoi: voronoi::Config,
+
+    #[command(flatten)]
+    pub spirograph: spirograph::Config,
+
+    #[command(flatten)]
+    pub floor: floor::Config,
+
+    #[command(flatten)]
+    pub static_noise: static_noise::Config,
+
+    #[command(flatten)]
+    pub plasma_globe: plasma_globe::Config,
+
+    #[command(flatten)]
+    pub wavepool: wavepool::Config,
+
+    #[command(flatten)]
+    pub snow: snow::Config,
+
+    #[command(flatten)]
+    pub rain: rain::Config,
+
+    #[command(flatten)]
+    pub feedback: feedback::Config,
+
+    #[command(flatten)]
+    pub munching: munching::Config,
+
+    #[command(flatten)]
+    pub hypno: hypno::Config,
+
+    #[command(flatten)]
+    pub marble: marble::Config,
+
+    #[command(flatten)]
+    pub smoke: smoke::Config,
+
+    #[command(flatten)]
+    pub clouds: clouds::Config,
+
+    #[command(flatten)]
+    pub caustics: caustics::Config,
+
+    #[command(flatten)]
+    pub sand: sand::Config,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            shape: Shape::Ripple,
+            palette: Palette::Rainbow,
+            scale: 10.0,
+            palette_reversed: false,
+            palette_hue: 0.0,
+            plasma_emitters: 3,
+            sin_table: None,
+            color_cycle: false,
+            cycle_speed: 0.25,
+            superformula: Default::default(),
+            starfield: Default::default(),
+            tunnel: Default::default(),
+            rotozoom: Default::default(),
+            metaballs: Default::default(),
+            fractal: Default::default(),
+            scroller: Default::default(),
+            life: Default::default(),
+            matrix_rain: Default::default(),
+            particles: Default::default(),
+            fireworks: Default::default(),
+            attractor: Default::default(),
+            boids: Default::default(),
+            bumpmap: Default::default(),
+            wireframe: Default::default(),
+            twister: Default::default(),
+            shadebobs: Default::default(),
+            kaleidoscope: Default::default(),
+            noise_clouds: Default::default(),
+            lightning: Default::default(),
+            voronoi: Default::default(),
+            spirograph: Default::default(),
+            floor: Default::default(),
+            static_noise: Default::default(),
+            plasma_globe: Default::default(),
+            wavepool: Default::default(),
+            snow: Default::default(),
+            rain: Default::default(),
+            feedback: Default::default(),
+            munching: Default::default(),
+            hypno: Default::default(),
+            marble: Default::default(),
+            smoke: Default::default(),
+            clouds: Default::default(),
+            caustics: Default::default(),
+            sand: Default::default(),
+        }
+    }
+}
+
+/// Every effect selectable with `--effect`, keyed by its command line name
+pub const EFFECTS: &[Builtin] = &[
+    Builtin {
+        name: "plasma",
+        description: "Animated plasma patterns",
+        constructor: |width, height, settings| {
+            Ok(Box::new(plasma::Plasma::from_settings(
+                width, height, settings,
+            )))
+        },
+    },
+    Builtin {
+        name: "fire",
+        description: "Classic demoscene fire",
+        constructor: |width, height, _settings| Ok(Box::new(fire::Fire::new(width, height))),
+    },
+    Builtin {
+        name: "starfield",
+        description: "3D starfield flying toward the camera",
+        constructor: |width, height, settings| {
+            Ok(Box::new(starfield::Starfield::new(
+                width,
+                height,
+                settings.starfield.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "tunnel",
+        description: "Texture-mapped tunnel",
+        constructor: |width, height, settings| {
+            Ok(Box::new(tunnel::Tunnel::new(
+                width,
+                height,
+                settings.tunnel.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "rotozoom",
+        description: "Rotating and zooming tile",
+        constructor: |width, height, settings| {
+            Ok(Box::new(rotozoom::Rotozoom::new(
+                width,
+                height,
+                settings.rotozoom.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "metaballs",
+        description: "Blobs melting together",
+        constructor: |width, height, settings| {
+            Ok(Box::new(metaballs::Metaballs::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.metaballs.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "copper-bars",
+        description: "Amiga style copper bars",
+        constructor: |width, height, _settings| {
+            Ok(Box::new(copperbars::CopperBars::new(width, height)))
+        },
+    },
+    Builtin {
+        name: "fractal",
+        description: "Mandelbrot zoom or morphing Julia set",
+        constructor: |width, height, settings| {
+            Ok(Box::new(fractal::FractalZoom::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.fractal.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "water",
+        description: "Interactive water ripples, click to disturb the surface",
+        constructor: |width, height, settings| {
+            Ok(Box::new(water::Water::new(
+                width,
+                height,
+                settings.palette.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "scroller",
+        description: "Sine-wave text scroller",
+        constructor: |width, height, settings| {
+            Ok(Box::new(scroller::Scroller::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.scroller.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "life",
+        description: "Conway's Game of Life",
+        constructor: |width, height, settings| {
+            Ok(Box::new(life::Life::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.life.clone(),
+            )?))
+        },
+    },
+    Builtin {
+        name: "matrix-rain",
+        description: "Matrix-style digital rain",
+        constructor: |width, height, settings| {
+            Ok(Box::new(matrix_rain::MatrixRain::new(
+                width,
+                height,
+                settings.matrix_rain.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "particles",
+        description: "Particle fountain",
+        constructor: |width, height, settings| {
+            Ok(Box::new(particles::Fountain::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.particles.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "fireworks",
+        description: "Fireworks bursting into palette-colored sparks",
+        constructor: |width, height, settings| {
+            Ok(Box::new(fireworks::Fireworks::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.fireworks.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "attractor",
+        description: "Strange attractor density plot",
+        constructor: |width, height, settings| {
+            Ok(Box::new(attractor::AttractorPlot::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.attractor.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "boids",
+        description: "Boids flocking simulation",
+        constructor: |width, height, settings| {
+            Ok(Box::new(boids::Boids::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.boids.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "lens",
+        description: "Magnifying lens gliding over the plasma",
+        constructor: |width, height, settings| {
+            Ok(Box::new(lens::Lens::new(
+                width,
+                height,
+                Box::new(plasma::Plasma::from_settings(width, height, settings)),
+            )))
+        },
+    },
+    Builtin {
+        name: "bump-map",
+        description: "Bump-mapped plasma lit by a moving light",
+        constructor: |width, height, settings| {
+            Ok(Box::new(bumpmap::BumpMap::from_settings(
+                width, height, settings,
+            )))
+        },
+    },
+    Builtin {
+        name: "wireframe",
+        description: "Spinning wireframe cube, icosahedron, or torus knot",
+        constructor: |width, height, settings| {
+            Ok(Box::new(wireframe::Wireframe::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.wireframe.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "twister",
+        description: "Classic demoscene twisting column",
+        constructor: |width, height, settings| {
+            Ok(Box::new(twister::Twister::new(
+                width,
+                height,
+                settings.twister.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "shadebobs",
+        description: "Additive bobs leaving glowing trails",
+        constructor: |width, height, settings| {
+            Ok(Box::new(shadebobs::Shadebobs::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.shadebobs.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "kaleidoscope",
+        description: "Mirrored wedges of the plasma",
+        constructor: |width, height, settings| {
+            Ok(Box::new(kaleidoscope::Kaleidoscope::new(
+                width,
+                height,
+                Box::new(plasma::Plasma::from_settings(width, height, settings)),
+                settings.kaleidoscope.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "noise-clouds",
+        description: "Billowing multi-octave noise clouds",
+        constructor: |width, height, settings| {
+            Ok(Box::new(noise_clouds::NoiseClouds::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.noise_clouds.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "lightning",
+        description: "Branching lightning bolts, click to strike",
+        constructor: |width, height, settings| {
+            Ok(Box::new(lightning::Lightning::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.lightning.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "voronoi",
+        description: "Drifting Voronoi cells with Worley noise shading",
+        constructor: |width, height, settings| {
+            Ok(Box::new(voronoi::Voronoi::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.voronoi.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "helix",
+        description: "Rotating DNA double helix",
+        constructor: |width, height, settings| {
+            Ok(Box::new(helix::Helix::new(
+                width,
+                height,
+                settings.palette.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "spirograph",
+        description: "Hypotrochoid curves with fading trails",
+        constructor: |width, height, settings| {
+            Ok(Box::new(spirograph::Spirograph::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.spirograph.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "floor",
+        description: "Mode 7 style infinite scrolling floor",
+        constructor: |width, height, settings| {
+            Ok(Box::new(floor::Floor::new(
+                width,
+                height,
+                settings.floor.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "static-noise",
+        description: "Old TV static with tearing and color glitches",
+        constructor: |width, height, settings| {
+            Ok(Box::new(static_noise::StaticNoise::new(
+                width,
+                height,
+                settings.static_noise.overlay.then(|| {
+                    Box::new(plasma::Plasma::from_settings(width, height, settings))
+                        as Box<dyn DemoEffect>
+                }),
+                settings.static_noise.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "plasma-globe",
+        description: "Electric filaments reaching for the mouse cursor",
+        constructor: |width, height, settings| {
+            Ok(Box::new(plasma_globe::PlasmaGlobe::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.plasma_globe.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "wave-pool",
+        description: "Interference between waves from moving emitters",
+        constructor: |width, height, settings| {
+            Ok(Box::new(wavepool::WavePool::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.wavepool.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "snow",
+        description: "Layered snowfall piling up on the ground",
+        constructor: |width, height, settings| {
+            Ok(Box::new(snow::Snow::new(
+                width,
+                height,
+                settings.snow.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "rain",
+        description: "Rainfall with splashes and lightning flashes",
+        constructor: |width, height, settings| {
+            Ok(Box::new(rain::Rain::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.rain.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "dot-tunnel",
+        description: "Twisting tunnel of dot rings",
+        constructor: |width, height, settings| {
+            Ok(Box::new(dottunnel::DotTunnel::new(
+                width,
+                height,
+                settings.palette.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "feedback",
+        description: "Video feedback spirals",
+        constructor: |width, height, settings| {
+            Ok(Box::new(feedback::Feedback::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.feedback.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "munching",
+        description: "Munching squares XOR pattern",
+        constructor: |width, height, settings| {
+            Ok(Box::new(munching::Munching::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.munching.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "hypno",
+        description: "Rotating hypnotic spirals and sunburst beams",
+        constructor: |width, height, settings| {
+            Ok(Box::new(hypno::Hypno::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.hypno.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "marble",
+        description: "Procedural marble and wood textures",
+        constructor: |width, height, settings| {
+            Ok(Box::new(marble::Marble::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.marble.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "smoke",
+        description: "Rising smoke from a small fluid simulation",
+        constructor: |width, height, settings| {
+            Ok(Box::new(smoke::Smoke::new(
+                width,
+                height,
+                settings.palette.clone(),
+                settings.smoke.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "sand",
+        description: "Falling sand and water, drag the pointer to paint",
+        constructor: |width, height, settings| {
+            Ok(Box::new(sand::Sand::new(
+                width,
+                height,
+                settings.sand.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "amiga-ball",
+        description: "Bouncing checkered Amiga ball",
+        constructor: |width, height, _settings| {
+            Ok(Box::new(amigaball::AmigaBall::new(width, height)))
+        },
+    },
+    Builtin {
+        name: "lissajous",
+        description: "Morphing Lissajous figures with fading trails",
+        constructor: |width, height, settings| {
+            Ok(Box::new(lissajous::Lissajous::new(
+                width,
+                height,
+                settings.palette.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "clouds",
+        description: "Diamond-square fractal clouds",
+        constructor: |width, height, settings| {
+            Ok(Box::new(clouds::Clouds::new(
+                width,
+                height,
+                settings.clouds.clone(),
+            )))
+        },
+    },
+    Builtin {
+        name: "caustics",
+        description: "Underwater light caustics",
+        constructor: |width, height, settings| {
+            Ok(Box::new(caustics::Caustics::new(
+                width,
+                height,
+                settings.caustics.clone(),
+            )))
+        },
+    },
+];
+
+/// Returns the command line names of all built-in effects.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    EFFECTS.iter().map(|effect| effect.name)
+}
+
+/// Builds the built-in effect `name` for a screen of `width * height` pixels, configured
+/// with `settings`.
+///
+/// Drawing the effect afterwards is a pure function of the time, which is what the
+/// benchmarks measure.
+///
+/// # Errors
+/// Fails if no built-in effect has that name or if the effect cannot be built.
+pub fn create(
+    name: &str,
+    width: usize,
+    height: usize,
+    settings: &Settings,
+) -> Result<Box<dyn DemoEffect>, Box<dyn std::error::Error>> {
+    let effect = EFFECTS
+        .iter()
+        .find(|effect| effect.name == name)
+        .ok_or_else(|| format!("unknown effect '{name}'"))?;
+    build(effect.constructor, width, height, settings)
+}
+
+/// Builds an effect with `constructor`, cycling its palette if `--color-cycle` was given.
+fn build(
+    constructor: Constructor,
+    width: usize,
+    height: usize,
+    settings: &Settings,
+) -> Result<Box<dyn DemoEffect>, Box<dyn std::error::Error>> {
+    let effect = constructor(width, height, settings)?;
+    if !settings.color_cycle {
+        return Ok(effect);
+    }
+    let base = DemoBase::new(width, height);
+    Ok(Box::new(ColorCycle::new(
+        base,
+        effect,
+        settings.cycle_speed,
+    )))
+}
+
+/// Returns a registry holding every built-in effect, configured with `settings`.
+pub fn registry(settings: &Settings) -> EffectRegistry {
+    let settings = Rc::new(settings.clone());
+    let mut registry = EffectRegistry::new();
+    for effect in EFFECTS {
+        let settings = Rc::clone(&settings);
+        let constructor = effect.constructor;
+        registry.register(effect.name, effect.description, move |width, height| {
+            build(constructor, width, height, &settings)
+        });
+    }
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::InputEvent;
+    use clap::Parser;
+    use minifb::Key;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        settings: Settings,
+    }
+
+    fn default_settings() -> Settings {
+        Cli::parse_from(["effects"]).settings
+    }
+
+    #[test]
+    fn default_settings_match_command_line_defaults() {
+        assert_eq!(Settings::default(), default_settings());
+    }
+
+    #[test]
+    fn effect_names_are_unique() {
+        let mut names: Vec<&str> = names().collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), EFFECTS.len());
+    }
+
+    #[test]
+    fn every_effect_renders_a_frame() {
+        let registry = registry(&default_settings());
+        for name in names() {
+            let mut effect = registry.create(name, 64, 48).unwrap();
+            let mut buffer = vec![0; 64 * 48];
+            effect.update(0.0);
+            effect.draw(&mut buffer, 0.0);
+            effect.update(0.1);
+            effect.draw(&mut buffer, 0.1);
+        }
+    }
+
+    #[test]
+    fn every_effect_renders_after_a_resize() {
+        let registry = registry(&default_settings());
+        for name in names() {
+            let mut effect = registry.create(name, 64, 48).unwrap();
+            effect.update(0.1);
+            effect.resize(40, 72);
+            let mut buffer = vec![0; 40 * 72];
+            effect.update(0.1);
+            effect.draw(&mut buffer, 0.1);
+        }
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        // Effects whose arrow keys change a setting saved in presets
+        const ADJUSTABLE: [&str; 9] = [
+            "plasma",
+            "lens",
+            "bump-map",
+            "kaleidoscope",
+            "spirograph",
+            "wave-pool",
+            "rain",
+            "hypno",
+            "caustics",
+        ];
+        let defaults = default_settings();
+        for builtin in EFFECTS {
+            let mut effect = (builtin.constructor)(64, 48, &defaults).unwrap();
+            effect.handle_input(InputEvent::Key(Key::Up));
+            effect.handle_input(InputEvent::Key(Key::Right));
+            let mut saved = defaults.clone();
+            effect.save_settings(&mut saved);
+            let adjusted = saved != defaults;
+            assert_eq!(
+                adjusted,
+                ADJUSTABLE.contains(&builtin.name),
+                "{}",
+                builtin.name
+            );
+
+            // An effect built from the saved settings saves them unchanged
+            let rebuilt = (builtin.constructor)(64, 48, &saved).unwrap();
+            let mut resaved = defaults.clone();
+            rebuilt.save_settings(&mut resaved);
+            assert_eq!(resaved, saved, "{}", builtin.name);
+        }
+    }
+
+    #[test]
+    fn every_effect_renders_the_same_frame_on_any_number_of_threads() {
+        let registry = registry(&default_settings());
+        for name in names() {
+            let effect = registry.create(name, 61, 47).unwrap();
+            let frame = |threads| {
+                crate::common::set_threads(threads);
+                let mut buffer = vec![0; 61 * 47];
+                effect.draw(&mut buffer, 0.7);
+                buffer
+            };
+            assert_eq!(frame(1), frame(4), "{name}");
+        }
+        crate::common::set_threads(0);
+    }
+
+    #[test]
+    fn registry_lists_every_builtin_effect() {
+        let registry = registry(&default_settings());
+        assert!(registry.names().eq(names()));
+    }
+
+    #[test]
+    fn create_builds_builtin_effects_by_name() {
+        let settings = default_settings();
+        let mut buffer = vec![0; 16 * 8];
+        create("plasma", 16, 8, &settings)
+            .unwrap()
+            .draw(&mut buffer, 0.0);
+        assert!(buffer.iter().any(|&pixel| pixel != 0));
+        assert!(create("nope", 16, 8, &settings).is_err());
+    }
+
+    #[test]
+    fn color_cycling_turns_the_plasma_palette() {
+        let draw = |speed: &str| {
+            let args = ["effects", "--color-cycle", "--cycle-speed", speed];
+            let settings = Cli::parse_from(args).settings;
+            let mut buffer = vec![0; 16 * 8];
+            create("plasma", 16, 8, &settings)
+                .unwrap()
+                .draw(&mut buffer, 1.0);
+            buffer
+        };
+        // After a whole turn the palette is back where it started
+        assert_eq!(draw("-1"), draw("0"));
+        assert_ne!(draw("0.5"), draw("0"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Munching squares, the XOR pattern from the PDP-1 days.
+//!
+//! Every pixel XORs its coordinates with a counter that ticks up over time. The result is
+//! masked down to a selection of bit planes and mapped through the palette. Each bit plane
+//! contributes squares of a different size, so masking planes out thins the pattern into
+//! different nested grids of squares that appear to eat each other as the counter grows.
+//!
+//! `Up`/`Down` rotate the bit-plane mask to bring other planes into view.
+use crate::common::{for_each_row, DemoBase, DemoEffect, InputEvent};
+use crate::effects::Settings;
+use crate::palette::Palette;
+use clap::Args;
+use minifb::Key;
+
+/// Default mask selecting which bit planes of the pattern are visible
+const DEFAULT_MASK: u8 = 0xff;
+
+/// Counter increments per second
+const SPEED: f32 = 40.0;
+
+/// User tunable munching squares parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Munching squares options")]
+#[group(id = "munching-options")]
+pub struct Config {
+    #[arg(
+        id = "munch-mask",
+        long = "munch-mask",
+        value_name = "MASK",
+        default_value_t = DEFAULT_MASK,
+        help = "Bit mask selecting the visible bit planes of the XOR pattern"
+    )]
+    pub mask: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { mask: DEFAULT_MASK }
+    }
+}
+
+/// The classic `x XOR y XOR t` pattern
+pub struct Munching {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the pattern
+    palette: Palette,
+    /// Bit planes of the pattern that are visible, adjustable at runtime
+    mask: u8,
+}
+
+impl Munching {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            // An empty mask would leave a blank screen
+            mask: if config.mask == 0 {
+                DEFAULT_MASK
+            } else {
+                config.mask
+            },
+        }
+    }
+
+    /// Returns the masked pattern value at a pixel for the given counter.
+    fn value(&self, x: usize, y: usize, tick: u8) -> u8 {
+        (x as u8 ^ y as u8 ^ tick) & self.mask
+    }
+}
+
+impl DemoEffect for Munching {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let tick = (time * SPEED) as u64 as u8;
+        let max = self.mask as f32;
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.palette.color(self.value(x, y, tick) as f32 / max);
+            }
+        });
+    }
+
+    /// Handles the bit-plane mask controls.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Up) => self.mask = self.mask.rotate_left(1),
+            InputEvent::Key(Key::Down) => self.mask = self.mask.rotate_right(1),
+            _ => {}
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.munching.mask = self.mask;
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_munching(mask: u8) -> Munching {
+        Munching::new(16, 16, Palette::Rainbow, Config { mask })
+    }
+
+    #[test]
+    fn pattern_is_xor_of_coordinates_and_counter() {
+        let munching = create_munching(0xff);
+        assert_eq!(munching.value(0b1100, 0b1010, 0b0001), 0b0111);
+    }
+
+    #[test]
+    fn mask_hides_bit_planes() {
+        let munching = create_munching(0b0100);
+        assert_eq!(munching.value(0b1100, 0b1010, 0b0001), 0b0100);
+        assert_eq!(munching.value(0b1000, 0b1010, 0b0001), 0);
+    }
+
+    #[test]
+    fn empty_mask_falls_back_to_default() {
+        assert_eq!(create_munching(0).mask, DEFAULT_MASK);
+    }
+
+    #[test]
+    fn keys_rotate_mask() {
+        let mut munching = create_munching(0b0000_0011);
+        munching.handle_input(InputEvent::Key(Key::Up));
+        assert_eq!(munching.mask, 0b0000_0110);
+        munching.handle_input(InputEvent::Key(Key::Down));
+        munching.handle_input(InputEvent::Key(Key::Down));
+        assert_eq!(munching.mask, 0b1000_0001);
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        let mut munching = create_munching(0b0000_0011);
+        munching.handle_input(InputEvent::Key(Key::Up));
+        let mut settings = Settings::default();
+        munching.save_settings(&mut settings);
+        assert_eq!(settings.munching.mask, 0b0000_0110);
+    }
+}
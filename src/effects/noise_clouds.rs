@@ -0,0 +1,140 @@
+//! Drifting clouds made of layered gradient noise.
+//!
+//! Every pixel samples several octaves of 3D noise, using its screen position for the
+//! first two dimensions and time for the third. Moving through the third dimension makes
+//! the clouds billow and morph in place, while a slow horizontal offset makes them drift.
+use crate::common::{for_each_row, DemoBase, DemoEffect};
+use crate::noise::Noise;
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default number of noise octaves layered on top of each other
+const DEFAULT_OCTAVES: u32 = 5;
+
+/// Default number of noise features across the smallest screen dimension
+const DEFAULT_SCALE: f32 = 4.0;
+
+/// Speed at which the clouds morph, in noise units per second
+const MORPH_SPEED: f32 = 0.15;
+
+/// Speed at which the clouds drift sideways, in noise units per second
+const DRIFT_SPEED: f32 = 0.1;
+
+/// User tunable noise clouds parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Noise clouds options")]
+#[group(id = "noise-clouds-options")]
+pub struct Config {
+    #[arg(
+        id = "octaves",
+        long = "octaves",
+        value_name = "OCTAVES",
+        default_value_t = DEFAULT_OCTAVES,
+        help = "Number of noise octaves layered in the clouds"
+    )]
+    pub octaves: u32,
+
+    #[arg(
+        id = "cloud-scale",
+        long = "cloud-scale",
+        value_name = "SCALE",
+        default_value_t = DEFAULT_SCALE,
+        help = "Number of cloud features across the screen"
+    )]
+    pub scale: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            octaves: DEFAULT_OCTAVES,
+            scale: DEFAULT_SCALE,
+        }
+    }
+}
+
+/// Animated multi-octave noise clouds
+pub struct NoiseClouds {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the clouds
+    palette: Palette,
+    /// Noise clouds parameters
+    config: Config,
+    /// Noise generator sampled for every pixel
+    noise: Noise,
+}
+
+impl NoiseClouds {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            config,
+            noise: Noise::new(fastrand::u64(..)),
+        }
+    }
+
+    /// Returns the cloud density at a pixel in the [0,1] range.
+    fn density(&self, x: usize, y: usize, time: f32) -> f32 {
+        let step = self.config.scale / self.base.width.min(self.base.height).max(1) as f32;
+        let v = self.noise.fbm(
+            x as f32 * step + time * DRIFT_SPEED,
+            y as f32 * step,
+            time * MORPH_SPEED,
+            self.config.octaves,
+        );
+        // Noise rarely reaches its extremes, so stretch it to use more of the palette
+        (v * 0.9 + 0.5).clamp(0.0, 1.0)
+    }
+}
+
+impl DemoEffect for NoiseClouds {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.palette.color(self.density(x, y, time));
+            }
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_clouds() -> NoiseClouds {
+        let mut clouds = NoiseClouds::new(64, 64, Palette::BlueCyan, Config::default());
+        clouds.noise = Noise::new(9);
+        clouds
+    }
+
+    #[test]
+    fn density_stays_in_unit_range() {
+        let clouds = create_clouds();
+        for y in 0..64 {
+            for x in 0..64 {
+                assert!((0.0..=1.0).contains(&clouds.density(x, y, 1.7)));
+            }
+        }
+    }
+
+    #[test]
+    fn clouds_change_over_time() {
+        let clouds = create_clouds();
+        let changed = (0..64).any(|x| clouds.density(x, 10, 0.0) != clouds.density(x, 10, 3.0));
+        assert!(changed);
+    }
+
+    #[test]
+    fn clouds_are_not_uniform() {
+        let clouds = create_clouds();
+        let mut buffer = vec![0; 64 * 64];
+        clouds.draw(&mut buffer, 0.5);
+        assert!(buffer.iter().any(|&p| p != buffer[0]));
+    }
+}
@@ -0,0 +1,382 @@
+//! A general purpose particle system and a fountain built on top of it.
+//!
+//! Particles are spawned by emitters with a randomized direction and speed, fall under
+//! gravity, and fade out as they approach the end of their lifetime. Each particle carries
+//! its own position in the palette so effects can color particles individually, and
+//! overlapping particles are blended additively to give dense areas a glowing core.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default maximum number of live particles
+const DEFAULT_COUNT: usize = 4000;
+
+/// Default downward acceleration in pixels per second squared
+const DEFAULT_GRAVITY: f32 = 300.0;
+
+/// Lifetime of a fountain particle in seconds
+const FOUNTAIN_LIFETIME: f32 = 2.5;
+
+/// Opening angle of the fountain jet in radians
+const FOUNTAIN_SPREAD: f32 = 0.35;
+
+/// User tunable particle fountain parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Particles options")]
+#[group(id = "particles-options")]
+pub struct Config {
+    #[arg(
+        id = "particle-count",
+        long = "particle-count",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_COUNT,
+        help = "Maximum number of live particles"
+    )]
+    pub count: usize,
+
+    #[arg(
+        id = "gravity",
+        long = "gravity",
+        value_name = "GRAVITY",
+        default_value_t = DEFAULT_GRAVITY,
+        help = "Downward acceleration in pixels per second squared"
+    )]
+    pub gravity: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_COUNT,
+            gravity: DEFAULT_GRAVITY,
+        }
+    }
+}
+
+/// A single particle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    /// Position in pixels
+    pub position: (f32, f32),
+    /// Velocity in pixels per second
+    pub velocity: (f32, f32),
+    /// Time since the particle was spawned, in seconds
+    pub age: f32,
+    /// Total lifetime of the particle, in seconds
+    pub lifetime: f32,
+    /// Position of the particle color in the palette, in the [0,1] range
+    pub color: f32,
+}
+
+impl Particle {
+    /// Returns the remaining fraction of the particle's life, from 1 at birth to 0 at death.
+    pub fn life(&self) -> f32 {
+        (1.0 - self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+}
+
+/// Spawns particles at a steady rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Emitter {
+    /// Position of the emitter in pixels
+    pub position: (f32, f32),
+    /// Direction particles are launched toward, in radians, where 0 points right
+    pub direction: f32,
+    /// Maximum deviation from `direction`, in radians
+    pub spread: f32,
+    /// Minimum and maximum launch speed in pixels per second
+    pub speed: (f32, f32),
+    /// Lifetime of the emitted particles, in seconds
+    pub lifetime: f32,
+    /// Number of particles emitted per second
+    pub rate: f32,
+    /// Fractional particles carried over between frames
+    pending: f32,
+}
+
+impl Emitter {
+    pub fn new(
+        position: (f32, f32),
+        direction: f32,
+        spread: f32,
+        speed: (f32, f32),
+        lifetime: f32,
+        rate: f32,
+    ) -> Self {
+        Self {
+            position,
+            direction,
+            spread,
+            speed,
+            lifetime,
+            rate,
+            pending: 0.0,
+        }
+    }
+}
+
+/// A pool of particles sharing the same gravity
+pub struct ParticleSystem {
+    /// Live particles
+    pub particles: Vec<Particle>,
+    /// Maximum number of live particles, new particles are dropped beyond it
+    pub capacity: usize,
+    /// Downward acceleration in pixels per second squared
+    pub gravity: f32,
+    /// Random number generator used to randomize emitted particles
+    rng: fastrand::Rng,
+    /// Seconds the particles are drawn behind their latest position
+    lag: f32,
+    /// Duration of the last update in seconds
+    last_dt: f32,
+}
+
+impl ParticleSystem {
+    pub fn new(capacity: usize, gravity: f32) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+            gravity,
+            rng: fastrand::Rng::new(),
+            lag: 0.0,
+            last_dt: 0.0,
+        }
+    }
+
+    /// Returns a uniformly distributed random value in [`min`, `max`).
+    pub fn random(&mut self, min: f32, max: f32) -> f32 {
+        min + self.rng.f32() * (max - min)
+    }
+
+    /// Adds a particle unless the system is full.
+    pub fn spawn(&mut self, particle: Particle) {
+        if self.particles.len() < self.capacity {
+            self.particles.push(particle);
+        }
+    }
+
+    /// Emits the particles an emitter produces over `dt` seconds.
+    pub fn emit(&mut self, emitter: &mut Emitter, dt: f32, color: f32) {
+        emitter.pending += emitter.rate * dt;
+        while emitter.pending >= 1.0 {
+            emitter.pending -= 1.0;
+            let angle = emitter.direction + self.random(-emitter.spread, emitter.spread);
+            let speed = self.random(emitter.speed.0, emitter.speed.1);
+            self.spawn(Particle {
+                position: emitter.position,
+                velocity: (angle.cos() * speed, angle.sin() * speed),
+                age: 0.0,
+                lifetime: emitter.lifetime,
+                color,
+            });
+        }
+    }
+
+    /// Moves every particle forward by `dt` seconds and removes the dead ones.
+    pub fn update(&mut self, dt: f32) {
+        (self.lag, self.last_dt) = (0.0, dt);
+        let gravity = self.gravity;
+        self.particles.retain_mut(|p| {
+            p.velocity.1 += gravity * dt;
+            p.position.0 += p.velocity.0 * dt;
+            p.position.1 += p.velocity.1 * dt;
+            p.age += dt;
+            p.age < p.lifetime
+        });
+    }
+
+    /// Draws the particles `alpha` of the way from their positions before the last update
+    /// to their latest ones, until the next update.
+    pub fn interpolate(&mut self, alpha: f32) {
+        self.lag = (1.0 - alpha.clamp(0.0, 1.0)) * self.last_dt;
+    }
+
+    /// Additively blends every particle into the buffer, fading them out with age.
+    pub fn render(&self, buffer: &mut [u32], base: &DemoBase, palette: &Palette) {
+        for p in &self.particles {
+            // Positions move by the updated velocity, so stepping back along it retraces
+            // the last update exactly
+            let x = p.position.0 - p.velocity.0 * self.lag;
+            let y = p.position.1 - p.velocity.1 * self.lag;
+            if x < 0.0 || y < 0.0 || x >= base.width as f32 || y >= base.height as f32 {
+                continue;
+            }
+
+            let i = y as usize * base.width + x as usize;
+            let (r, g, b) = palette.rgb(p.color);
+            let life = p.life();
+            let existing = buffer[i];
+            let add = |shift: u32, c: u8| {
+                let current = (existing >> shift) & 0xff;
+                (current + (c as f32 * life) as u32).min(255) as u8
+            };
+            buffer[i] = rgb(add(16, r), add(8, g), add(0, b));
+        }
+    }
+}
+
+/// A particle fountain spraying upward from the bottom of the screen
+pub struct Fountain {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the particles
+    palette: Palette,
+    /// Particles in flight
+    system: ParticleSystem,
+    /// Nozzle of the fountain
+    emitter: Emitter,
+    /// Seconds the fountain has been running, drives the color sweep
+    elapsed: f32,
+}
+
+impl Fountain {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            system: ParticleSystem::new(config.count, config.gravity),
+            emitter: Self::nozzle(base, config.gravity, config.count),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Builds the nozzle at the bottom center of a screen of the given size.
+    fn nozzle(base: DemoBase, gravity: f32, count: usize) -> Emitter {
+        // Launch fast enough to reach about 80% of the screen height under gravity
+        let apex = base.height as f32 * 0.8;
+        let launch = (2.0 * gravity.abs() * apex).sqrt();
+        Emitter::new(
+            (base.width as f32 * 0.5, base.height as f32 - 1.0),
+            -std::f32::consts::FRAC_PI_2,
+            FOUNTAIN_SPREAD,
+            (launch * 0.7, launch),
+            FOUNTAIN_LIFETIME,
+            // Emit just fast enough to keep the system full at steady state
+            count as f32 / FOUNTAIN_LIFETIME,
+        )
+    }
+}
+
+impl DemoEffect for Fountain {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        // Slowly sweep through the palette so successive jets change color
+        let color = (self.elapsed * 0.1).rem_euclid(1.0);
+        self.system.emit(&mut self.emitter, dt, color);
+        self.system.update(dt);
+    }
+
+    fn interpolate(&mut self, alpha: f32) {
+        self.system.interpolate(alpha);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+        self.system.render(buffer, &self.base, &self.palette);
+    }
+
+    /// Moves the nozzle back to the bottom center of the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.emitter = Self::nozzle(self.base, self.system.gravity, self.system.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn particle() -> Particle {
+        Particle {
+            position: (10.0, 10.0),
+            velocity: (0.0, 0.0),
+            age: 0.0,
+            lifetime: 1.0,
+            color: 0.0,
+        }
+    }
+
+    #[test]
+    fn gravity_accelerates_particles_downward() {
+        let mut system = ParticleSystem::new(10, 100.0);
+        system.spawn(particle());
+        system.update(0.5);
+        assert_eq!(system.particles[0].velocity.1, 50.0);
+        assert!(system.particles[0].position.1 > 10.0);
+    }
+
+    #[test]
+    fn particles_die_after_their_lifetime() {
+        let mut system = ParticleSystem::new(10, 0.0);
+        system.spawn(particle());
+        system.update(0.6);
+        assert_eq!(system.particles.len(), 1);
+        assert!((system.particles[0].life() - 0.4).abs() < 1e-6);
+        system.update(0.6);
+        assert!(system.particles.is_empty());
+    }
+
+    #[test]
+    fn spawn_respects_capacity() {
+        let mut system = ParticleSystem::new(2, 0.0);
+        for _ in 0..5 {
+            system.spawn(particle());
+        }
+        assert_eq!(system.particles.len(), 2);
+    }
+
+    #[test]
+    fn emitter_spawns_particles_at_its_rate() {
+        let mut system = ParticleSystem::new(100, 0.0);
+        let mut emitter = Emitter::new((0.0, 0.0), 0.0, 0.1, (1.0, 2.0), 1.0, 10.0);
+        system.emit(&mut emitter, 0.25, 0.0);
+        assert_eq!(system.particles.len(), 2);
+        // The leftover half particle is emitted on the next frame
+        system.emit(&mut emitter, 0.05, 0.0);
+        assert_eq!(system.particles.len(), 3);
+    }
+
+    #[test]
+    fn interpolation_draws_between_the_last_two_updates() {
+        let base = DemoBase::new(20, 20);
+        let mut system = ParticleSystem::new(10, 0.0);
+        system.spawn(Particle {
+            position: (2.5, 10.5),
+            velocity: (10.0, 0.0),
+            lifetime: 10.0,
+            color: 1.0,
+            ..particle()
+        });
+        system.update(1.0);
+        system.interpolate(0.5);
+        let mut buffer = vec![rgb(0, 0, 0); base.pixel_count()];
+        system.render(&mut buffer, &base, &Palette::BlackWhite);
+        // Halfway between x = 2.5 and x = 12.5
+        assert_ne!(buffer[10 * 20 + 7], rgb(0, 0, 0));
+        assert_eq!(buffer[10 * 20 + 12], rgb(0, 0, 0));
+        // The next update draws the latest positions again
+        system.update(0.0);
+        let mut buffer = vec![rgb(0, 0, 0); base.pixel_count()];
+        system.render(&mut buffer, &base, &Palette::BlackWhite);
+        assert_ne!(buffer[10 * 20 + 12], rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn render_blends_particles_additively() {
+        let base = DemoBase::new(20, 20);
+        let mut system = ParticleSystem::new(10, 0.0);
+        system.spawn(particle());
+        let mut single = vec![rgb(0, 0, 0); base.pixel_count()];
+        system.render(&mut single, &base, &Palette::BlackWhite);
+
+        system.spawn(Particle {
+            color: 0.5,
+            ..particle()
+        });
+        let mut double = vec![rgb(0, 0, 0); base.pixel_count()];
+        system.render(&mut double, &base, &Palette::BlackWhite);
+
+        assert!(double[10 * 20 + 10] > single[10 * 20 + 10]);
+    }
+}
@@ -0,0 +1,1051 @@
+//! A module for generating animated plasma effects with various patterns and color palettes.
+//!
+//! This module provides functionality to create smooth, colorful plasma animations using
+//! different geometric patterns (ripples, spirals, circles, etc.) and color schemes.
+//! The plasma effect is generated by combining mathematical functions with color
+//! transformations to create fluid, psychedelic patterns.
+//!
+//! # Example
+//! ```
+//! use plasma::common::DemoEffect;
+//! use plasma::palette::Palette;
+//! use plasma::effects::plasma::{Plasma, Shape};
+//!
+//! let plasma = Plasma::new(800, 600, Shape::Ripple, Palette::Rainbow, 10.0);
+//! let mut buffer = vec![0u32; 800 * 600];
+//! plasma.draw(&mut buffer, 0.0);
+//! ```
+use crate::common::{for_each_row, DemoBase, DemoEffect, InputEvent, ShaderSource, SinTable};
+use crate::effects::Settings;
+use crate::noise::Noise;
+use crate::palette::{Crossfade, Palette};
+use crate::simd::{self, f32x8, LANES};
+use clap::{Args, ValueEnum};
+use minifb::Key;
+use std::f32::consts::TAU;
+
+/// Scale factor change for increasing or decreasing the plasma pattern density
+const SCALE_DELTA: f32 = 10.0;
+
+/// Hue rotation in degrees applied by one press of `H`
+pub(crate) const HUE_STEP: f32 = 15.0;
+
+/// Number of points of the star shape
+const STAR_POINTS: f32 = 5.0;
+
+/// How far the rings of the star shape reach in and out along its points, relative to
+/// their radius
+const STAR_DEPTH: f32 = 0.3;
+
+/// Fewest and most ripple centers of the multi-ripple shape
+const EMITTERS: std::ops::RangeInclusive<usize> = 2..=5;
+
+/// Turns per second of the first ripple center around the screen, every following one
+/// orbiting a little faster
+const ORBIT_SPEED: f32 = 0.05;
+
+/// Noise cycles across half the smallest dimension of the display in the warp shape
+const WARP_FREQUENCY: f32 = 1.5;
+
+/// Farthest the warp shape moves the sample points, relative to half the smallest
+/// dimension of the display
+const WARP_STRENGTH: f32 = 0.35;
+
+/// Octaves of noise warping the sample points
+const WARP_OCTAVES: u32 = 3;
+
+/// Speed at which the warping noise evolves, in noise cycles per second
+const WARP_SPEED: f32 = 0.15;
+
+/// Seed of the warping noise, fixed so the pattern only depends on time
+const WARP_SEED: u64 = 7;
+
+/// Default symmetry of the superformula shape, the number of its lobes
+const DEFAULT_M: f32 = 5.0;
+
+/// Default exponents of the superformula shape, which draw a starfish
+const DEFAULT_N: [f32; 3] = [2.0, 7.0, 7.0];
+
+/// Lobes the superformula symmetry swings by around its configured value
+const M_SWING: f32 = 1.0;
+
+/// Fraction of their configured values the superformula exponents swing by
+const N_SWING: f32 = 0.4;
+
+/// Smallest superformula exponent, keeping the radius finite
+const MIN_EXPONENT: f32 = 0.1;
+
+/// Half the square root of 3, the distance from the center of a hexagon of unit radius to
+/// the middle of its sides
+const HEX_APOTHEM: f32 = 0.866_025_4;
+
+/// Parameters of the superformula shape, which slowly swing around these values
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Superformula shape options")]
+#[group(id = "superformula-options")]
+pub struct Superformula {
+    #[arg(
+        id = "superformula-m",
+        long = "superformula-m",
+        value_name = "M",
+        default_value_t = DEFAULT_M,
+        help = "Rotational symmetry of the superformula shape, the number of its lobes"
+    )]
+    pub m: f32,
+
+    #[arg(
+        id = "superformula-n1",
+        long = "superformula-n1",
+        value_name = "N1",
+        default_value_t = DEFAULT_N[0],
+        help = "Overall exponent of the superformula shape, low values pinching the lobes"
+    )]
+    pub n1: f32,
+
+    #[arg(
+        id = "superformula-n2",
+        long = "superformula-n2",
+        value_name = "N2",
+        default_value_t = DEFAULT_N[1],
+        help = "Exponent of the cosine term of the superformula shape"
+    )]
+    pub n2: f32,
+
+    #[arg(
+        id = "superformula-n3",
+        long = "superformula-n3",
+        value_name = "N3",
+        default_value_t = DEFAULT_N[2],
+        help = "Exponent of the sine term of the superformula shape"
+    )]
+    pub n3: f32,
+}
+
+impl Default for Superformula {
+    fn default() -> Self {
+        Self {
+            m: DEFAULT_M,
+            n1: DEFAULT_N[0],
+            n2: DEFAULT_N[1],
+            n3: DEFAULT_N[2],
+        }
+    }
+}
+
+impl Superformula {
+    /// Returns the parameters at `time`, each swinging around its configured value at its
+    /// own slow pace.
+    fn at(&self, time: f32) -> Self {
+        let swing = |speed: f32, phase: f32| (time * speed + phase).sin();
+        let exponent = |n: f32, speed: f32, phase: f32| {
+            (n * (1.0 + N_SWING * swing(speed, phase))).max(MIN_EXPONENT)
+        };
+        Self {
+            m: self.m + M_SWING * swing(0.07, 0.0),
+            n1: exponent(self.n1, 0.13, 1.0),
+            n2: exponent(self.n2, 0.11, 2.0),
+            n3: exponent(self.n3, 0.17, 3.0),
+        }
+    }
+
+    /// Returns the radius of the superformula curve at `angle`, with `a = b = 1`:
+    /// `(|cos(m * angle / 4)|^n2 + |sin(m * angle / 4)|^n3)^(-1 / n1)`.
+    fn radius(&self, angle: f32) -> f32 {
+        let (sin, cos) = (self.m * angle * 0.25).sin_cos();
+        let n1 = self.n1.max(MIN_EXPONENT);
+        let sum =
+            cos.abs().powf(self.n2.max(MIN_EXPONENT)) + sin.abs().powf(self.n3.max(MIN_EXPONENT));
+        sum.powf(-1.0 / n1)
+    }
+}
+
+/// Defines the available shape patterns for the plasma effect
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Shape {
+    /// Rings spreading out from the center
+    Ripple,
+    /// Three arms winding around the center
+    Spiral,
+    /// Rings crossed by waves turning around the center
+    Circle,
+    /// A grid of waves along both axes
+    Square,
+    /// Rings measured with the Manhattan distance, forming nested diamonds
+    Diamond,
+    /// Rings whose radius swells along five slowly turning points
+    Star,
+    /// Rings measured with the hexagonal distance, forming nested hexagons
+    Hexagon,
+    /// Bands running outward from both axes, meeting in the shape of a cross
+    Cross,
+    /// Ripples spreading from several centers orbiting the screen, interfering where they
+    /// meet
+    MultiRipple,
+    /// Ripples seen through animated noise, which bends their rings into swirls
+    Warp,
+    /// Rings following the superformula of Gielis, morphing between flowers and starfish
+    Superformula,
+}
+
+/// Quantities of the pattern shared by every pixel of a frame
+struct Frame {
+    /// Half of the smallest dimension, the unit of distances
+    min_dim: f32,
+    /// Animation time in seconds
+    time: f32,
+    /// Ripple centers of the multi-ripple shape relative to the center of the display, in
+    /// pixels
+    centers: Vec<(f32, f32)>,
+    /// Parameters of the superformula shape at the time of the frame
+    superformula: Superformula,
+}
+
+/// A plasma effect generator that creates colorful animated patterns
+pub struct Plasma {
+    /// Dimensions of the plasma effect
+    base: DemoBase,
+    /// The geometric shape used to generate the plasma pattern
+    shape: Shape,
+    /// Color palette used for rendering the plasma effect, fading in when changed
+    palette: Crossfade,
+    /// Scale factor that controls the density/size of the plasma patterns
+    scale: f32,
+    /// Number of ripple centers of the multi-ripple shape
+    emitters: usize,
+    /// Noise moving the sample points of the warp shape
+    noise: Noise,
+    /// Configured parameters of the superformula shape
+    superformula: Superformula,
+    /// Lookup table replacing the computed sines, if any
+    sines: Option<SinTable>,
+    /// Distance of every pixel from the center, relative to half the smallest dimension
+    distances: Vec<f32>,
+    /// Angle of every pixel around the center in radians
+    angles: Vec<f32>,
+}
+
+impl Plasma {
+    pub fn new(width: usize, height: usize, shape: Shape, palette: Palette, scale: f32) -> Self {
+        let mut plasma = Self {
+            base: DemoBase::new(width, height),
+            shape,
+            palette: Crossfade::new(palette),
+            scale,
+            emitters: 3,
+            noise: Noise::new(WARP_SEED),
+            superformula: Superformula::default(),
+            sines: None,
+            distances: Vec::new(),
+            angles: Vec::new(),
+        };
+        plasma.resize(width, height);
+        plasma
+    }
+
+    /// Builds a plasma from the command line settings, approximating its sines with a
+    /// lookup table if one was requested, with its palette reversed or turned the way a
+    /// preset left it.
+    pub fn from_settings(width: usize, height: usize, settings: &Settings) -> Self {
+        let mut plasma = Self::new(
+            width,
+            height,
+            settings.shape.clone(),
+            settings.palette.clone(),
+            settings.scale,
+        );
+        plasma.sines = settings.sin_table.map(|size| SinTable::new(size as usize));
+        plasma.set_emitters(settings.plasma_emitters as usize);
+        plasma.superformula = settings.superformula.clone();
+        plasma
+            .palette
+            .set_look(settings.palette_reversed, settings.palette_hue);
+        plasma
+    }
+
+    /// Approximates the sines of the pattern with `table` instead of computing them, which
+    /// is faster on weak hardware at the cost of precision.
+    pub fn use_sin_table(&mut self, table: SinTable) {
+        self.sines = Some(table);
+    }
+
+    /// Increases the scale factor of the plasma patterns by SCALE_DELTA.
+    pub fn increase_scale(&mut self) {
+        self.scale += SCALE_DELTA;
+    }
+
+    /// Decreases the scale factor of the plasma patterns by SCALE_DELTA.
+    pub fn decrease_scale(&mut self) {
+        self.scale -= SCALE_DELTA;
+    }
+
+    /// Sets the number of ripple centers of the multi-ripple shape, kept between 2 and 5.
+    pub fn set_emitters(&mut self, emitters: usize) {
+        self.emitters = emitters.clamp(*EMITTERS.start(), *EMITTERS.end());
+    }
+
+    /// Fades into the next color palette in the sequence.
+    pub fn next_palette(&mut self) {
+        self.palette.next();
+    }
+
+    /// Cycles to the next shape pattern in the sequence.
+    pub fn next_shape(&mut self) {
+        self.step_shape(1);
+    }
+
+    /// Cycles to the previous shape pattern in the sequence.
+    pub fn prev_shape(&mut self) {
+        self.step_shape(Shape::value_variants().len() - 1);
+    }
+
+    /// Moves `steps` shapes forward in the order the shapes are declared, wrapping around
+    /// after the last one.
+    fn step_shape(&mut self, steps: usize) {
+        let shapes = Shape::value_variants();
+        let index = shapes.iter().position(|shape| *shape == self.shape);
+        self.shape = shapes[(index.unwrap_or(0) + steps) % shapes.len()].clone();
+    }
+
+    /// Returns the sine of `x`, from the lookup table if there is one.
+    fn sin(&self, x: f32) -> f32 {
+        match &self.sines {
+            Some(table) => table.sin(x),
+            None => x.sin(),
+        }
+    }
+
+    /// Returns the sines of eight values, from the lookup table if there is one.
+    fn sin_lanes(&self, x: f32x8) -> f32x8 {
+        match &self.sines {
+            Some(table) => simd::map(x, |x| table.sin(x)),
+            None => x.sin(),
+        }
+    }
+
+    fn ripple(&self, dist: f32, time: f32) -> f32 {
+        // Ripple pattern: sin(dist * 10.0 - time * 2.0)
+        self.sin(dist * self.scale - time * 2.0)
+    }
+
+    fn spiral(&self, dist: f32, time: f32, angle: f32) -> f32 {
+        // Spiral pattern: sin(dist * 10.0 + angle * 3.0 + time)
+        self.sin(dist * self.scale + angle * 3.0 + time)
+    }
+
+    fn circle(&self, dist: f32, time: f32, angle: f32) -> f32 {
+        // Circle pattern: sin(dist * 10.0 + time) + sin(angle * 2.0 + time)
+        self.sin(dist * self.scale + time) + self.sin(angle * 2.0 + time)
+    }
+
+    fn square(&self, px: f32, py: f32, min_dim: f32, time: f32) -> f32 {
+        // Square pattern: sin(px / min_dim * 10.0 + time) * sin(py / min_dim * 10.0 + time)
+        self.sin((px / min_dim) * self.scale + time) * self.sin((py / min_dim) * self.scale + time)
+    }
+
+    fn diamond(&self, px: f32, py: f32, min_dim: f32, time: f32) -> f32 {
+        // Diamond pattern: sin((|px| + |py|) / min_dim * 10.0 - time * 2.0)
+        self.sin((px.abs() + py.abs()) / min_dim * self.scale - time * 2.0)
+    }
+
+    fn star(&self, dist: f32, time: f32, angle: f32) -> f32 {
+        // Star pattern: sin(dist * (1.0 + 0.3 * sin(angle * 5.0 + time * 0.5)) * 10.0 - time * 2.0)
+        let swell = 1.0 + STAR_DEPTH * self.sin(angle * STAR_POINTS + time * 0.5);
+        self.sin(dist * swell * self.scale - time * 2.0)
+    }
+
+    fn hexagon(&self, px: f32, py: f32, min_dim: f32, time: f32) -> f32 {
+        // Hexagon pattern: sin(max(|py|, |px| * sqrt(3) / 2 + |py| / 2) / min_dim * 10.0 - time * 2.0)
+        let (ax, ay) = (px.abs(), py.abs());
+        let dist = ay.max(ax * HEX_APOTHEM + ay * 0.5);
+        self.sin(dist / min_dim * self.scale - time * 2.0)
+    }
+
+    fn cross(&self, px: f32, py: f32, min_dim: f32, time: f32) -> f32 {
+        // Cross pattern: sin(min(|px|, |py|) / min_dim * 10.0 - time * 2.0)
+        self.sin(px.abs().min(py.abs()) / min_dim * self.scale - time * 2.0)
+    }
+
+    fn multi_ripple(
+        &self,
+        px: f32,
+        py: f32,
+        min_dim: f32,
+        time: f32,
+        centers: &[(f32, f32)],
+    ) -> f32 {
+        // Multi-ripple pattern: the average of sin(dist_i * 10.0 - time * 2.0) over the
+        // distances from every center
+        let sum: f32 = centers
+            .iter()
+            .map(|&(cx, cy)| {
+                let dist = (px - cx).hypot(py - cy) / min_dim;
+                self.sin(dist * self.scale - time * 2.0)
+            })
+            .sum();
+        sum / centers.len() as f32
+    }
+
+    fn warp(&self, px: f32, py: f32, min_dim: f32, time: f32) -> f32 {
+        // Warp pattern: the ripple pattern at (px, py) / min_dim moved by two octave sums of
+        // noise, one for every axis
+        let (x, y) = (px / min_dim, py / min_dim);
+        let (u, v, z) = (x * WARP_FREQUENCY, y * WARP_FREQUENCY, time * WARP_SPEED);
+        let dx = self.noise.fbm(u, v, z, WARP_OCTAVES) * WARP_STRENGTH;
+        // A second sample far away from the first keeps the axes independent
+        let dy = self.noise.fbm(u + 5.2, v + 1.3, z, WARP_OCTAVES) * WARP_STRENGTH;
+        let dist = (x + dx).hypot(y + dy);
+        self.sin(dist * self.scale - time * 2.0)
+    }
+
+    fn superformula(&self, dist: f32, time: f32, angle: f32, curve: &Superformula) -> f32 {
+        // Superformula pattern: sin(dist / radius(angle) * 10.0 - time * 2.0)
+        self.sin(dist / curve.radius(angle) * self.scale - time * 2.0)
+    }
+
+    /// Returns the ripple centers of the multi-ripple shape at `time`, relative to the
+    /// center of the display, in pixels.
+    ///
+    /// Every center orbits at its own pace on an ellipse spanning most of the display,
+    /// while its distance from the middle swells and shrinks.
+    fn centers(&self, time: f32) -> Vec<(f32, f32)> {
+        let (half_width, half_height) = self.base.center();
+        (0..self.emitters)
+            .map(|i| {
+                let i = i as f32;
+                let turns = time * ORBIT_SPEED * (1.0 + 0.3 * i) + i / self.emitters as f32;
+                let radius = 0.6 + 0.25 * (time * 0.37 + i * 1.7).sin();
+                let (sin, cos) = (turns * TAU).sin_cos();
+                (cos * radius * half_width, sin * radius * half_height)
+            })
+            .collect()
+    }
+
+    /// Computes the distance and angle of every pixel from the center of the display,
+    /// which only change with the resolution.
+    fn compute_maps(&mut self) {
+        let DemoBase { width, height } = self.base;
+        // Calculate the center coordinates of the display area
+        let (center_x, center_y) = self.base.center();
+        // Calculate half of the smallest dimension for scaling patterns
+        let min_dim = width.min(height) as f32 * 0.5;
+
+        self.distances.clear();
+        self.angles.clear();
+        for y in 0..height {
+            let py = y as f32 - center_y;
+            for x in 0..width {
+                let px = x as f32 - center_x;
+                self.distances.push((px * px + py * py).sqrt() / min_dim);
+                self.angles.push(py.atan2(px));
+            }
+        }
+    }
+
+    /// Returns the quantities shared by every pixel of the frame at `time`.
+    fn frame(&self, time: f32) -> Frame {
+        let DemoBase { width, height } = self.base;
+        let centers = match self.shape {
+            Shape::MultiRipple => self.centers(time),
+            _ => Vec::new(),
+        };
+        Frame {
+            min_dim: width.min(height) as f32 * 0.5,
+            time,
+            centers,
+            superformula: self.superformula.at(time),
+        }
+    }
+
+    /// Evaluates the plasma pattern at the pixel `index`, `(px, py)` away from the center
+    /// of the display.
+    fn value(&self, index: usize, px: f32, py: f32, frame: &Frame) -> f32 {
+        let (min_dim, time) = (frame.min_dim, frame.time);
+        let (dist, angle) = (self.distances[index], self.angles[index]);
+        match self.shape {
+            Shape::Ripple => self.ripple(dist, time),
+            Shape::Spiral => self.spiral(dist, time, angle),
+            Shape::Circle => self.circle(dist, time, angle),
+            Shape::Square => self.square(px, py, min_dim, time),
+            Shape::Diamond => self.diamond(px, py, min_dim, time),
+            Shape::Star => self.star(dist, time, angle),
+            Shape::Hexagon => self.hexagon(px, py, min_dim, time),
+            Shape::Cross => self.cross(px, py, min_dim, time),
+            Shape::MultiRipple => self.multi_ripple(px, py, min_dim, time, &frame.centers),
+            Shape::Warp => self.warp(px, py, min_dim, time),
+            Shape::Superformula => self.superformula(dist, time, angle, &frame.superformula),
+        }
+    }
+
+    /// Evaluates the plasma pattern at eight consecutive pixels of a row at once, from
+    /// the pixel `index` on, the way [`Plasma::value`] does for a single pixel.
+    fn values(&self, index: usize, px: f32x8, py: f32, frame: &Frame) -> f32x8 {
+        let (min_dim, time) = (frame.min_dim, frame.time);
+        let dist = || simd::load(&self.distances[index..]) * self.scale;
+        let angle = || simd::load(&self.angles[index..]);
+        // Distances in pixels scaled like the distances from the center
+        let (ax, ay) = (
+            px.abs() * (self.scale / min_dim),
+            py.abs() * (self.scale / min_dim),
+        );
+        match self.shape {
+            Shape::Ripple => self.sin_lanes(dist() - time * 2.0),
+            Shape::Spiral => self.sin_lanes(dist() + angle() * 3.0 + time),
+            Shape::Circle => self.sin_lanes(dist() + time) + self.sin_lanes(angle() * 2.0 + time),
+            Shape::Square => {
+                let row = self.sin((py / min_dim) * self.scale + time);
+                self.sin_lanes(px * (self.scale / min_dim) + time) * row
+            }
+            Shape::Diamond => self.sin_lanes(ax + ay - time * 2.0),
+            Shape::Star => {
+                let swell = self.sin_lanes(angle() * STAR_POINTS + time * 0.5) * STAR_DEPTH + 1.0;
+                self.sin_lanes(dist() * swell - time * 2.0)
+            }
+            Shape::Hexagon => {
+                let dist = (ax * HEX_APOTHEM + ay * 0.5).max(f32x8::splat(ay));
+                self.sin_lanes(dist - time * 2.0)
+            }
+            Shape::Cross => self.sin_lanes(ax.min(f32x8::splat(ay)) - time * 2.0),
+            Shape::MultiRipple => {
+                let mut sum = f32x8::splat(0.0);
+                for &(cx, cy) in &frame.centers {
+                    let (dx, dy) = (px - cx, py - cy);
+                    let dist = (dx * dx + dy * dy).sqrt() * (self.scale / min_dim);
+                    sum += self.sin_lanes(dist - time * 2.0);
+                }
+                sum * (1.0 / frame.centers.len() as f32)
+            }
+            // Noise has no vectorized form, so every lane is warped on its own
+            Shape::Warp => simd::map(px, |px| self.warp(px, py, min_dim, time)),
+            Shape::Superformula => {
+                let inverse = simd::map(angle(), |angle| 1.0 / frame.superformula.radius(angle));
+                self.sin_lanes(dist() * inverse - time * 2.0)
+            }
+        }
+    }
+
+    /// Evaluates the plasma pattern at every pixel and stores the mapped values in `out`.
+    ///
+    /// The pattern value handed to `map` is normalized to the [0,1] range. Rows are
+    /// evaluated in parallel, eight pixels at a time, and the pixels left at the end of
+    /// every row one by one.
+    fn evaluate<T: Send>(&self, out: &mut [T], time: f32, map: impl Fn(f32) -> T + Sync) {
+        // Calculate the center coordinates of the display area
+        let (center_x, center_y) = self.base.center();
+        let frame = self.frame(time);
+        // Normalize the plasma value from [-1,1] to [0,1] range for color mapping
+        let normalize = |v: f32| v * 0.5 + 0.5;
+
+        for_each_row(out, self.base.width, |y, row| {
+            // Calculate the y-coordinate relative to the center of the display
+            let py = y as f32 - center_y;
+            let start = y * self.base.width;
+
+            let split = row.len() / LANES * LANES;
+            let (body, tail) = row.split_at_mut(split);
+            for (i, items) in body.chunks_exact_mut(LANES).enumerate() {
+                // Calculate the x-coordinates relative to the center of the display
+                let px = simd::ramp((i * LANES) as f32 - center_x);
+                let values = self.values(start + i * LANES, px, py, &frame);
+                for (item, v) in items.iter_mut().zip(values.to_array()) {
+                    *item = map(normalize(v));
+                }
+            }
+            for (x, item) in (split..).zip(tail) {
+                let px = x as f32 - center_x;
+                *item = map(normalize(self.value(start + x, px, py, &frame)));
+            }
+        });
+    }
+
+    /// Computes the plasma value of every pixel, normalized to the [0,1] range.
+    ///
+    /// This lets other effects reuse the plasma pattern as a scalar field, e.g. as a
+    /// heightmap.
+    pub fn field(&self, values: &mut [f32], time: f32) {
+        self.evaluate(values, time, |v| v);
+    }
+}
+
+impl DemoEffect for Plasma {
+    /// Advances the fade between palettes, the pattern itself being a function of time.
+    fn update(&mut self, dt: f32) {
+        self.palette.update(dt);
+    }
+
+    /// Renders the plasma effect into the provided pixel buffer.
+    ///
+    /// Each pixel in the buffer is updated with a color value based on the current
+    /// shape, palette, and time parameters. The color values are packed into 32-bit
+    /// ARGB format.
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        self.evaluate(buffer, time, |v| self.palette.color(v));
+    }
+
+    /// Renders the plasma values as indices into the palette, for cycling its colors.
+    fn draw_indices(&self, indices: &mut [u8], time: f32) -> Option<&Crossfade> {
+        self.evaluate(indices, time, |v| (v * 255.0).round() as u8);
+        Some(&self.palette)
+    }
+
+    /// Handles the interactive plasma controls.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Space) => self.next_palette(),
+            InputEvent::Key(Key::R) => self.palette.reverse(),
+            InputEvent::Key(Key::H) => self.palette.rotate_hue(HUE_STEP),
+            InputEvent::ShiftKey(Key::H) => self.palette.rotate_hue(-HUE_STEP),
+            InputEvent::Key(Key::Key2) => self.set_emitters(2),
+            InputEvent::Key(Key::Key3) => self.set_emitters(3),
+            InputEvent::Key(Key::Key4) => self.set_emitters(4),
+            InputEvent::Key(Key::Key5) => self.set_emitters(5),
+            InputEvent::Key(Key::Up) => self.decrease_scale(),
+            InputEvent::Key(Key::Down) => self.increase_scale(),
+            InputEvent::Key(Key::Left) => self.prev_shape(),
+            InputEvent::Key(Key::Right) => self.next_shape(),
+            _ => {}
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.compute_maps();
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.shape = self.shape.clone();
+        settings.palette = self.palette.palette().clone();
+        settings.palette_reversed = self.palette.is_reversed();
+        settings.palette_hue = self.palette.hue();
+        settings.scale = self.scale;
+        settings.plasma_emitters = self.emitters as u8;
+        settings.superformula = self.superformula.clone();
+    }
+
+    /// Returns the shader drawing every shape but the circle, whose values reach past
+    /// the ends of the palette, which palettes extend in their own ways, and the warp,
+    /// whose noise is only computed on the CPU.
+    fn shader_source(&self, time: f32) -> Option<ShaderSource> {
+        if matches!(self.shape, Shape::Circle | Shape::Warp) {
+            return None;
+        }
+        let shapes = Shape::value_variants();
+        let shape = shapes.iter().position(|shape| *shape == self.shape)?;
+        let frame = self.frame(time);
+        let curve = &frame.superformula;
+        let mut params = vec![
+            shape as f32,
+            self.scale,
+            frame.centers.len() as f32,
+            0.0,
+            curve.m,
+            curve.n1,
+            curve.n2,
+            curve.n3,
+        ];
+        params.extend(frame.centers.iter().flat_map(|&(x, y)| [x, y]));
+        Some(ShaderSource {
+            code: include_str!("plasma.wgsl"),
+            params,
+            palette: ShaderSource::sample_palette(|v| self.palette.color(v)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_plasma() -> Plasma {
+        Plasma::new(800, 600, Shape::Ripple, Palette::Rainbow, 0.0)
+    }
+
+    #[test]
+    fn shaders_pass_the_shape_scale_and_palette() {
+        let mut plasma = create_plasma();
+        plasma.shape = Shape::Square;
+        let shader = plasma.shader_source(2.0).unwrap();
+        assert_eq!(shader.params[..4], [3.0, plasma.scale, 0.0, 0.0]);
+        assert_eq!(shader.palette[0], plasma.palette.color(0.0));
+        assert_eq!(shader.palette[255], plasma.palette.color(1.0));
+        for shape in [Shape::Circle, Shape::Warp] {
+            plasma.shape = shape;
+            assert_eq!(plasma.shader_source(2.0), None);
+        }
+    }
+
+    #[test]
+    fn shaders_pass_the_superformula_of_the_frame() {
+        let mut plasma = create_plasma();
+        plasma.shape = Shape::Superformula;
+        let shader = plasma.shader_source(2.0).unwrap();
+        let curve = plasma.superformula.at(2.0);
+        assert_eq!(shader.params[4..8], [curve.m, curve.n1, curve.n2, curve.n3]);
+    }
+
+    #[test]
+    fn shaders_pass_the_ripple_centers() {
+        let mut plasma = create_plasma();
+        plasma.shape = Shape::MultiRipple;
+        plasma.set_emitters(4);
+        let shader = plasma.shader_source(2.0).unwrap();
+        assert_eq!(shader.params[..3], [8.0, plasma.scale, 4.0]);
+        let centers = plasma.centers(2.0);
+        assert_eq!(
+            shader.params[8..],
+            centers
+                .iter()
+                .flat_map(|&(x, y)| [x, y])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn vectorized_pixels_match_the_scalar_math() {
+        for shape in Shape::value_variants() {
+            let plasma = Plasma::new(37, 11, shape.clone(), Palette::Rainbow, 10.0);
+            let mut values = vec![0.0; 37 * 11];
+            plasma.field(&mut values, 12.5);
+            let (center_x, center_y) = plasma.base.center();
+            for (i, &v) in values.iter().enumerate() {
+                let (px, py) = ((i % 37) as f32 - center_x, (i / 37) as f32 - center_y);
+                let expected = plasma.value(i, px, py, &plasma.frame(12.5)) * 0.5 + 0.5;
+                assert!((v - expected).abs() < 1e-4, "{shape:?} at pixel {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn indices_follow_the_plasma_values() {
+        let plasma = Plasma::new(16, 8, Shape::Spiral, Palette::Hot, 10.0);
+        let (mut values, mut indices) = (vec![0.0; 16 * 8], vec![0; 16 * 8]);
+        plasma.field(&mut values, 2.0);
+        let palette = plasma.draw_indices(&mut indices, 2.0).unwrap();
+        assert_eq!(*palette.palette(), Palette::Hot);
+        for (&index, &v) in indices.iter().zip(&values) {
+            assert_eq!(index, (v * 255.0).round() as u8);
+        }
+    }
+
+    #[test]
+    fn maps_hold_the_distance_and_angle_of_every_pixel() {
+        let mut plasma = create_plasma();
+        plasma.resize(4, 2);
+        assert_eq!(plasma.distances.len(), 8);
+        // One pixel right of the center, half the smallest dimension being one pixel
+        assert_eq!((plasma.distances[7], plasma.angles[7]), (1.0, 0.0));
+        assert_eq!(plasma.angles[2], -std::f32::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn sine_tables_approximate_the_pattern() {
+        let exact = Plasma::new(64, 48, Shape::Circle, Palette::Rainbow, 10.0);
+        let mut approximate = Plasma::new(64, 48, Shape::Circle, Palette::Rainbow, 10.0);
+        approximate.use_sin_table(SinTable::new(1024));
+        let (mut expected, mut values) = (vec![0.0; 64 * 48], vec![0.0; 64 * 48]);
+        exact.field(&mut expected, 3.0);
+        approximate.field(&mut values, 3.0);
+        for (v, e) in values.iter().zip(&expected) {
+            assert!((v - e).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn tone_keys_reverse_and_turn_the_palette() {
+        let mut plasma = create_plasma();
+        plasma.handle_input(InputEvent::Key(Key::R));
+        assert!(plasma.palette.is_reversed());
+        assert_eq!(plasma.palette.rgb(1.0), Palette::Rainbow.rgb(0.0));
+        plasma.handle_input(InputEvent::Key(Key::H));
+        plasma.handle_input(InputEvent::Key(Key::H));
+        assert_eq!(plasma.palette.hue(), 2.0 * HUE_STEP);
+        plasma.handle_input(InputEvent::ShiftKey(Key::H));
+        assert_eq!(plasma.palette.hue(), HUE_STEP);
+        // The tone carries over to the next palette
+        plasma.next_palette();
+        assert!(plasma.palette.is_reversed());
+        assert_eq!(plasma.palette.hue(), HUE_STEP);
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        let mut plasma = create_plasma();
+        plasma.handle_input(InputEvent::Key(Key::Space));
+        plasma.handle_input(InputEvent::Key(Key::Right));
+        plasma.handle_input(InputEvent::Key(Key::Down));
+        plasma.handle_input(InputEvent::Key(Key::R));
+        plasma.handle_input(InputEvent::ShiftKey(Key::H));
+        let mut settings = Settings::default();
+        plasma.save_settings(&mut settings);
+        assert_eq!(settings.shape, Shape::Spiral);
+        assert_eq!(settings.palette, Palette::Rainbow.next());
+        assert_eq!(settings.scale, SCALE_DELTA);
+        assert!(settings.palette_reversed);
+        assert_eq!(settings.palette_hue, 360.0 - HUE_STEP);
+
+        // The palette comes back reversed and turned when built from the saved settings
+        let rebuilt = Plasma::from_settings(800, 600, &settings);
+        assert!(rebuilt.palette.is_reversed());
+        assert_eq!(rebuilt.palette.hue(), 360.0 - HUE_STEP);
+    }
+
+    #[test]
+    fn scale_increases_by_scale_delta_when_increased() {
+        let mut plasma = create_plasma();
+        let initial_scale = plasma.scale;
+        plasma.increase_scale();
+        assert_eq!(plasma.scale, initial_scale + SCALE_DELTA);
+    }
+
+    #[test]
+    fn scale_decreases_by_scale_delta_when_decreased() {
+        let mut plasma = create_plasma();
+        let initial_scale = plasma.scale;
+        plasma.decrease_scale();
+        assert_eq!(plasma.scale, initial_scale - SCALE_DELTA);
+    }
+
+    #[test]
+    fn distance_shapes_follow_their_metrics() {
+        let plasma = |shape| Plasma::new(16, 16, shape, Palette::Rainbow, 10.0);
+        let value = |plasma: &Plasma, px: f32, py: f32| {
+            let index = ((py + 8.0) * 16.0 + px + 8.0) as usize;
+            plasma.value(index, px, py, &plasma.frame(1.0))
+        };
+        // Points the same Manhattan distance away share a ring of the diamond
+        let diamond = plasma(Shape::Diamond);
+        assert_eq!(value(&diamond, 4.0, 0.0), value(&diamond, -1.0, 3.0));
+        // The corners of the hexagon lie along the horizontal axis
+        let hexagon = plasma(Shape::Hexagon);
+        assert_eq!(
+            value(&hexagon, 0.0, 3.0),
+            hexagon.sin(3.0 / 8.0 * 10.0 - 2.0)
+        );
+        assert_eq!(
+            value(&hexagon, 2.0, 0.0),
+            hexagon.sin(2.0 * HEX_APOTHEM / 8.0 * 10.0 - 2.0)
+        );
+        // Points along either axis lie on the center band of the cross
+        let cross = plasma(Shape::Cross);
+        assert_eq!(value(&cross, 0.0, 5.0), value(&cross, -3.0, 0.0));
+        assert_eq!(value(&cross, 2.0, 6.0), value(&cross, -7.0, -2.0));
+        // Rings of the star reach farther out along its points
+        let star = plasma(Shape::Star);
+        let dist = 4.0 / 8.0;
+        let swell = 1.0 + STAR_DEPTH * star.sin(0.5);
+        assert_eq!(value(&star, 4.0, 0.0), star.sin(dist * swell * 10.0 - 2.0));
+    }
+
+    #[test]
+    fn ripple_centers_orbit_and_sum_their_fields() {
+        let mut plasma = Plasma::new(40, 20, Shape::MultiRipple, Palette::Rainbow, 10.0);
+        assert_eq!(plasma.centers(0.0).len(), 3);
+        plasma.handle_input(InputEvent::Key(Key::Key5));
+        assert_eq!(plasma.centers(0.0).len(), 5);
+        plasma.set_emitters(9);
+        assert_eq!(plasma.emitters, *EMITTERS.end());
+        plasma.handle_input(InputEvent::Key(Key::Key2));
+        // The centers start evenly spread around the orbit and move over time
+        let centers = plasma.centers(0.0);
+        assert!(centers[0].0 > 0.0 && centers[1].0 < 0.0);
+        assert!(centers[0].1.abs() < 1e-3 && centers[1].1.abs() < 1e-3);
+        assert_ne!(plasma.centers(5.0), centers);
+        // Pixels on either center share the field of both ripples
+        let frame = plasma.frame(0.0);
+        let at = |(cx, cy): (f32, f32)| {
+            let index = ((cy + 10.0) as usize).min(19) * 40 + ((cx + 20.0) as usize).min(39);
+            plasma.value(index, cx, cy, &frame)
+        };
+        let between = (centers[0].0 - centers[1].0).hypot(centers[0].1 - centers[1].1) / 10.0;
+        let expected = (0.0f32.sin() + (between * 10.0).sin()) / 2.0;
+        assert!((at(centers[0]) - expected).abs() < 1e-5);
+        assert!((at(centers[1]) - expected).abs() < 1e-5);
+        let mut settings = Settings::default();
+        plasma.save_settings(&mut settings);
+        assert_eq!(settings.plasma_emitters, 2);
+    }
+
+    #[test]
+    fn warp_bends_the_ripples_with_evolving_noise() {
+        let (mut warp, mut ripple) = (vec![0.0; 32 * 32], vec![0.0; 32 * 32]);
+        let plasma = Plasma::new(32, 32, Shape::Warp, Palette::Rainbow, 10.0);
+        plasma.field(&mut warp, 3.0);
+        Plasma::new(32, 32, Shape::Ripple, Palette::Rainbow, 10.0).field(&mut ripple, 3.0);
+        assert!(warp.iter().zip(&ripple).any(|(w, r)| (w - r).abs() > 0.1));
+        assert!(warp.iter().all(|v| (0.0..=1.0).contains(v)));
+        // The same time always gives the same picture, while the noise moves on with it
+        let mut again = vec![0.0; 32 * 32];
+        plasma.field(&mut again, 3.0);
+        assert_eq!(again, warp);
+        plasma.field(&mut again, 4.0);
+        assert_ne!(again, warp);
+    }
+
+    #[test]
+    fn superformula_curves_repeat_with_their_symmetry() {
+        let curve = Superformula {
+            m: 4.0,
+            n1: 1.0,
+            n2: 2.0,
+            n3: 2.0,
+        };
+        // With n2 = n3 the curve repeats every 1 / m of a turn
+        for angle in [0.0, 0.3, 1.1] {
+            let turned = angle + std::f32::consts::TAU / 4.0;
+            assert!((curve.radius(angle) - curve.radius(turned)).abs() < 1e-4);
+        }
+        assert_eq!(curve.radius(0.0), 1.0);
+        // Between two lobes the curve reaches to 1 / (2 * 0.5) again for n1 = 1
+        assert!((curve.radius(std::f32::consts::FRAC_PI_4) - 1.0).abs() < 1e-5);
+        let star = Superformula::default();
+        assert!(star.radius(std::f32::consts::TAU / 10.0) > 2.0 * star.radius(0.0));
+    }
+
+    #[test]
+    fn superformula_parameters_swing_slowly_from_the_settings() {
+        let mut settings = Settings::default();
+        settings.superformula.m = 8.0;
+        settings.shape = Shape::Superformula;
+        let plasma = Plasma::from_settings(32, 32, &settings);
+        let start = plasma.frame(0.0).superformula;
+        assert_eq!(start.m, 8.0);
+        let later = plasma.frame(1.0).superformula;
+        assert!(later != start && (later.m - start.m).abs() < 0.1);
+        let swung = plasma.frame(20.0).superformula;
+        assert!((swung.m - 8.0).abs() <= M_SWING && swung.n1 >= MIN_EXPONENT);
+        let mut saved = Settings::default();
+        plasma.save_settings(&mut saved);
+        assert_eq!(saved.superformula, settings.superformula);
+    }
+
+    #[test]
+    fn shape_cycles_forward_through_all_variants() {
+        let mut plasma = create_plasma();
+
+        assert_eq!(plasma.shape, Shape::Ripple);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Spiral);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Circle);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Square);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Diamond);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Star);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Hexagon);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Cross);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::MultiRipple);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Warp);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Superformula);
+        plasma.next_shape();
+        assert_eq!(plasma.shape, Shape::Ripple);
+    }
+
+    #[test]
+    fn shape_cycles_backward_through_all_variants() {
+        let mut plasma = create_plasma();
+
+        assert_eq!(plasma.shape, Shape::Ripple);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Superformula);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Warp);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::MultiRipple);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Cross);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Hexagon);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Star);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Diamond);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Square);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Circle);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Spiral);
+        plasma.prev_shape();
+        assert_eq!(plasma.shape, Shape::Ripple);
+    }
+
+    #[test]
+    fn shape_returns_to_initial_after_complete_cycle_in_both_directions() {
+        let mut plasma = create_plasma();
+        let initial_shape = plasma.shape.clone();
+
+        // Do a full cycle with next_shape
+        for _ in 0..Shape::value_variants().len() {
+            plasma.next_shape();
+        }
+        assert_eq!(
+            plasma.shape, initial_shape,
+            "Shape should return to initial after full next cycle"
+        );
+
+        // Do a full cycle with prev_shape
+        for _ in 0..Shape::value_variants().len() {
+            plasma.prev_shape();
+        }
+        assert_eq!(
+            plasma.shape, initial_shape,
+            "Shape should return to initial after full prev cycle"
+        );
+    }
+
+    #[test]
+    fn palette_cycles_through_all_variants() {
+        let mut plasma = create_plasma();
+
+        assert_eq!(*plasma.palette.palette(), Palette::Rainbow);
+        plasma.next_palette();
+        assert_eq!(*plasma.palette.palette(), Palette::BlueCyan);
+        plasma.next_palette();
+        assert_eq!(*plasma.palette.palette(), Palette::Hot);
+        plasma.next_palette();
+        assert_eq!(*plasma.palette.palette(), Palette::PurplePink);
+        plasma.next_palette();
+        assert_eq!(*plasma.palette.palette(), Palette::BlackWhite);
+        plasma.next_palette();
+        assert_eq!(plasma.palette.palette().name(), "spectrum");
+    }
+
+    #[test]
+    fn palette_returns_to_initial_after_complete_cycle() {
+        let mut plasma = create_plasma();
+        let initial_palette = plasma.palette.palette().clone();
+
+        // Do a full cycle
+        for _ in 0..Palette::BUILTINS.len() {
+            plasma.next_palette();
+        }
+
+        assert_eq!(
+            *plasma.palette.palette(),
+            initial_palette,
+            "Palette should return to initial after full cycle"
+        );
+    }
+
+    #[test]
+    fn each_palette_transition_changes_to_different_variant() {
+        let mut plasma = create_plasma();
+        let mut previous_palette = plasma.palette.palette().clone();
+
+        for _ in 0..5 {
+            plasma.next_palette();
+            assert_ne!(
+                *plasma.palette.palette(),
+                previous_palette,
+                "Each palette transition should result in a different variant"
+            );
+            previous_palette = plasma.palette.palette().clone();
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Plasma patterns drawn on the GPU, the way `Plasma::value` computes them on the CPU.
+//
+// Parameters: 0 the index of the shape in the order `Shape` declares them, 1 the scale,
+// 2 the number of ripple centers, 4 to 7 the superformula parameters m, n1, n2 and n3 at
+// the time of the frame, and from 8 on the ripple centers relative to the center of the
+// frame, x and y in turn.
+
+// Returns the rings spreading out at the distance `dist` from the center.
+fn ring(dist: f32) -> f32 {
+    return sin(dist * param(1u) - frame.time * 2.0);
+}
+
+// Returns the radius of the superformula curve at `angle`.
+fn superformula_radius(angle: f32) -> f32 {
+    let turn = param(4u) * angle * 0.25;
+    return pow(pow(abs(cos(turn)), param(6u)) + pow(abs(sin(turn)), param(7u)), -1.0 / param(5u));
+}
+
+fn shade(pixel: vec2<f32>) -> vec3<f32> {
+    let p = pixel - frame.resolution * 0.5;
+    let min_dim = min(frame.resolution.x, frame.resolution.y) * 0.5;
+    let scale = param(1u);
+    let time = frame.time;
+    let dist = length(p) / min_dim;
+    let angle = atan2(p.y, p.x);
+    let a = abs(p) / min_dim;
+
+    var v: f32;
+    switch u32(param(0u)) {
+        // Ripple
+        case 0u: {
+            v = ring(dist);
+        }
+        // Spiral
+        case 1u: {
+            v = sin(dist * scale + angle * 3.0 + time);
+        }
+        // Square
+        case 3u: {
+            v = sin(p.x / min_dim * scale + time) * sin(p.y / min_dim * scale + time);
+        }
+        // Diamond
+        case 4u: {
+            v = ring(a.x + a.y);
+        }
+        // Star
+        case 5u: {
+            v = ring(dist * (1.0 + 0.3 * sin(angle * 5.0 + time * 0.5)));
+        }
+        // Hexagon
+        case 6u: {
+            v = ring(max(a.y, a.x * 0.8660254 + a.y * 0.5));
+        }
+        // Cross
+        case 7u: {
+            v = ring(min(a.x, a.y));
+        }
+        // Multi-ripple
+        case 8u: {
+            let centers = u32(param(2u));
+            var sum = 0.0;
+            for (var i = 0u; i < centers; i++) {
+                let center = vec2<f32>(param(8u + i * 2u), param(9u + i * 2u));
+                sum += ring(distance(p, center) / min_dim);
+            }
+            v = sum / f32(centers);
+        }
+        // Superformula
+        default: {
+            v = ring(dist / superformula_radius(angle));
+        }
+    }
+    return palette_color(v * 0.5 + 0.5);
+}
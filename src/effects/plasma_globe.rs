@@ -0,0 +1,234 @@
+//! A plasma globe with electric filaments.
+//!
+//! Filaments leave the electrode in the middle of the globe and crawl outward to the glass.
+//! The angle of every filament wanders along its length by sampling gradient noise, so the
+//! filaments twist and flicker over time. Filaments whose tips point toward the mouse
+//! cursor bend toward it, as if a finger were touching the glass. Each filament is stamped
+//! additively into a glow buffer, making overlapping filaments burn brighter.
+use crate::common::{DemoBase, DemoEffect, InputEvent};
+use crate::noise::Noise;
+use crate::palette::Palette;
+use clap::Args;
+use std::f32::consts::{PI, TAU};
+
+/// Default number of filaments
+const DEFAULT_FILAMENTS: usize = 10;
+
+/// Radius of the glass as a fraction of the smallest screen dimension
+const GLOBE_RADIUS: f32 = 0.45;
+
+/// Radius of the central electrode relative to the globe radius
+const ELECTRODE_RADIUS: f32 = 0.12;
+
+/// Number of points along each filament
+const FILAMENT_POINTS: usize = 120;
+
+/// Largest angle a filament wanders away from its base direction
+const WANDER: f32 = 0.9;
+
+/// Brightness added by a single filament at its core
+const GLOW_STRENGTH: f32 = 0.6;
+
+/// Radius of the glow around each filament point in pixels
+const GLOW_RADIUS: i32 = 2;
+
+/// User tunable plasma globe parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Plasma globe options")]
+#[group(id = "plasma-globe-options")]
+pub struct Config {
+    #[arg(
+        id = "filaments",
+        long = "filaments",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_FILAMENTS,
+        help = "Number of electric filaments in the plasma globe"
+    )]
+    pub filaments: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            filaments: DEFAULT_FILAMENTS,
+        }
+    }
+}
+
+/// Glowing filaments wandering inside a glass globe
+pub struct PlasmaGlobe {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the glow
+    palette: Palette,
+    /// Number of filaments
+    filaments: usize,
+    /// Noise driving the filament paths
+    noise: Noise,
+    /// Angle of the mouse cursor around the center, when it is inside the globe
+    touch: Option<f32>,
+}
+
+impl PlasmaGlobe {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            filaments: config.filaments,
+            noise: Noise::new(fastrand::u64(..)),
+            touch: None,
+        }
+    }
+
+    /// Returns the radius of the glass in pixels.
+    fn radius(&self) -> f32 {
+        self.base.width.min(self.base.height) as f32 * GLOBE_RADIUS
+    }
+
+    /// Returns the angle of a filament at `t` along its length, 0 at the electrode and 1 at
+    /// the glass.
+    fn angle(&self, filament: usize, t: f32, time: f32) -> f32 {
+        let base = filament as f32 / self.filaments.max(1) as f32 * TAU;
+        let wander = self
+            .noise
+            .fbm(filament as f32 * 7.3, t * 2.0, time * 0.8, 3)
+            * WANDER;
+        let mut angle = base + wander + time * 0.1;
+
+        if let Some(touch) = self.touch {
+            // Shortest signed angle from the filament to the touch point
+            let delta = (touch - angle + PI).rem_euclid(TAU) - PI;
+            // Only filaments already leaning toward the touch point get pulled in, and the
+            // pull grows toward the tip
+            let pull = ((PI * 0.5 - delta.abs()) / (PI * 0.5)).max(0.0);
+            angle += delta * pull * t;
+        }
+        angle
+    }
+
+    /// Adds the glow of a single point to the glow buffer.
+    fn stamp(&self, glow: &mut [f32], x: f32, y: f32, strength: f32) {
+        let (width, height) = (self.base.width as i32, self.base.height as i32);
+        let (cx, cy) = (x as i32, y as i32);
+        for oy in -GLOW_RADIUS..=GLOW_RADIUS {
+            for ox in -GLOW_RADIUS..=GLOW_RADIUS {
+                let (px, py) = (cx + ox, cy + oy);
+                if px < 0 || py < 0 || px >= width || py >= height {
+                    continue;
+                }
+                let falloff = 1.0 / (1.0 + (ox * ox + oy * oy) as f32);
+                glow[(py * width + px) as usize] += strength * falloff;
+            }
+        }
+    }
+}
+
+impl DemoEffect for PlasmaGlobe {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let mut glow = vec![0.0; self.base.pixel_count()];
+        let (center_x, center_y) = self.base.center();
+        let radius = self.radius();
+        let inner = radius * ELECTRODE_RADIUS;
+
+        for filament in 0..self.filaments {
+            // Flicker every filament independently
+            let flicker = 0.75 + 0.25 * (time * 13.0 + filament as f32 * 2.1).sin();
+            for i in 0..FILAMENT_POINTS {
+                let t = i as f32 / (FILAMENT_POINTS - 1) as f32;
+                let r = inner + (radius - inner) * t;
+                let angle = self.angle(filament, t, time);
+                let (x, y) = (center_x + r * angle.cos(), center_y + r * angle.sin());
+                self.stamp(&mut glow, x, y, GLOW_STRENGTH * flicker);
+            }
+        }
+
+        for (i, (pixel, &glow)) in buffer.iter_mut().zip(&glow).enumerate() {
+            let dx = (i % self.base.width) as f32 - center_x;
+            let dy = (i / self.base.width) as f32 - center_y;
+            let dist = (dx * dx + dy * dy).sqrt();
+            // Faint haze filling the globe, brightest around the electrode
+            let haze = if dist < radius {
+                0.15 * (1.0 - dist / radius) + if dist < inner { 0.5 } else { 0.0 }
+            } else {
+                0.0
+            };
+            *pixel = self.palette.color((glow + haze).min(1.0));
+        }
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        if let InputEvent::Mouse { x, y, .. } = event {
+            let (center_x, center_y) = self.base.center();
+            let (dx, dy) = (x - center_x, y - center_y);
+            self.touch = ((dx * dx + dy * dy).sqrt() < self.radius()).then(|| dy.atan2(dx));
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_globe() -> PlasmaGlobe {
+        let mut globe = PlasmaGlobe::new(100, 100, Palette::PurplePink, Config::default());
+        globe.noise = Noise::new(21);
+        globe
+    }
+
+    #[test]
+    fn mouse_inside_globe_is_tracked() {
+        let mut globe = create_globe();
+        globe.handle_input(InputEvent::Mouse {
+            x: 80.0,
+            y: 50.0,
+            pressed: false,
+        });
+        assert_eq!(globe.touch, Some(0.0));
+        globe.handle_input(InputEvent::Mouse {
+            x: 99.0,
+            y: 99.0,
+            pressed: false,
+        });
+        assert_eq!(globe.touch, None);
+    }
+
+    #[test]
+    fn filaments_bend_toward_the_touch_point() {
+        let mut globe = create_globe();
+        let untouched = globe.angle(0, 1.0, 0.0);
+        globe.touch = Some(untouched + 0.5);
+        let touched = globe.angle(0, 1.0, 0.0);
+        assert!((touched - (untouched + 0.5)).abs() < 0.5);
+        assert!(touched > untouched);
+    }
+
+    #[test]
+    fn touch_does_not_move_the_filament_root() {
+        let mut globe = create_globe();
+        let root = globe.angle(3, 0.0, 1.0);
+        globe.touch = Some(root + 0.5);
+        assert_eq!(globe.angle(3, 0.0, 1.0), root);
+    }
+
+    #[test]
+    fn glow_is_additive() {
+        let globe = create_globe();
+        let mut glow = vec![0.0; 100 * 100];
+        globe.stamp(&mut glow, 50.0, 50.0, 0.4);
+        globe.stamp(&mut glow, 50.0, 50.0, 0.4);
+        assert_eq!(glow[50 * 100 + 50], 0.8);
+    }
+
+    #[test]
+    fn outside_the_globe_is_dark() {
+        let globe = create_globe();
+        let mut buffer = vec![0; 100 * 100];
+        globe.draw(&mut buffer, 0.0);
+        assert_eq!(buffer[0], Palette::PurplePink.color(0.0));
+    }
+}
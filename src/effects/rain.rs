@@ -0,0 +1,298 @@
+//! Rainfall splashing on the ground.
+//!
+//! Raindrops are drawn as slanted streaks falling at slightly different speeds. When a drop
+//! reaches the ground it bursts into a few splash particles handled by the shared
+//! [`ParticleSystem`]. Optional lightning flashes light up the whole sky for a moment
+//! before fading back to darkness.
+//!
+//! `Up` and `Down` make the rain heavier or lighter.
+use crate::common::{rgb, DemoBase, DemoEffect, InputEvent};
+use crate::effects::particles::{Particle, ParticleSystem};
+use crate::effects::Settings;
+use crate::palette::Palette;
+use clap::Args;
+use minifb::Key;
+
+/// Default number of raindrops spawned per second
+const DEFAULT_INTENSITY: f32 = 300.0;
+
+/// Amount the intensity changes per key press
+const INTENSITY_STEP: f32 = 50.0;
+
+/// Heaviest rain that can be reached at runtime
+const MAX_INTENSITY: f32 = 2000.0;
+
+/// Falling speed of the drops in screen heights per second
+const FALL_SPEED: f32 = 1.5;
+
+/// Horizontal drift of the drops relative to their falling speed
+const SLANT: f32 = 0.15;
+
+/// Number of splash particles created by a landing drop
+const SPLASH_PARTICLES: usize = 4;
+
+/// Downward acceleration of splash particles in pixels per second squared
+const SPLASH_GRAVITY: f32 = 400.0;
+
+/// Chance per second of a lightning flash
+const FLASH_RATE: f32 = 0.15;
+
+/// Fraction of the flash brightness lost per second
+const FLASH_DECAY: f32 = 3.0;
+
+/// User tunable rain parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Rain options")]
+#[group(id = "rain-options")]
+pub struct Config {
+    #[arg(
+        id = "rain-intensity",
+        long = "rain-intensity",
+        value_name = "INTENSITY",
+        default_value_t = DEFAULT_INTENSITY,
+        value_parser = parse_intensity,
+        help = "Number of raindrops falling per second, from 0 to 2000"
+    )]
+    pub intensity: f32,
+
+    #[arg(
+        id = "rain-lightning",
+        long = "rain-lightning",
+        help = "Light up the sky with occasional lightning flashes"
+    )]
+    pub lightning: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            intensity: DEFAULT_INTENSITY,
+            lightning: false,
+        }
+    }
+}
+
+/// Parses a rain intensity from 0 to [`MAX_INTENSITY`].
+fn parse_intensity(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(intensity) if (0.0..=MAX_INTENSITY).contains(&intensity) => Ok(intensity),
+        _ => Err(format!(
+            "invalid intensity '{spec}', expected 0 to {MAX_INTENSITY}"
+        )),
+    }
+}
+
+/// A falling raindrop
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Drop {
+    x: f32,
+    y: f32,
+    /// Falling speed in pixels per second
+    speed: f32,
+}
+
+/// Rain streaks with splashes and optional lightning
+pub struct Rain {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the splashes
+    palette: Palette,
+    /// Rain parameters, the intensity is adjustable at runtime
+    config: Config,
+    /// Drops currently falling
+    drops: Vec<Drop>,
+    /// Splash particles thrown up by landing drops
+    splashes: ParticleSystem,
+    /// Fractional drops carried over between frames
+    pending: f32,
+    /// Brightness of the current lightning flash in [0,1]
+    flash: f32,
+    /// Random number generator used to place drops
+    rng: fastrand::Rng,
+}
+
+impl Rain {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            config,
+            drops: Vec::new(),
+            splashes: ParticleSystem::new(4000, SPLASH_GRAVITY),
+            pending: 0.0,
+            flash: 0.0,
+            rng: fastrand::Rng::new(),
+        }
+    }
+
+    /// Returns the vertical length of a drop streak, faster drops leave longer streaks.
+    fn streak(&self, drop: &Drop) -> f32 {
+        drop.speed * 0.02
+    }
+
+    /// Spawns new drops, moves the existing ones, and splashes those reaching the ground.
+    fn advance(&mut self, dt: f32) {
+        let (width, height) = (self.base.width as f32, self.base.height as f32);
+
+        self.pending += self.config.intensity * dt;
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+            let speed = FALL_SPEED * height * (0.8 + self.rng.f32() * 0.4);
+            // Spawn a bit to the left so slanted drops also cover the right edge
+            self.drops.push(Drop {
+                x: self.rng.f32() * width * (1.0 + SLANT) - width * SLANT,
+                y: 0.0,
+                speed,
+            });
+        }
+
+        let mut landed = Vec::new();
+        self.drops.retain_mut(|drop| {
+            drop.x += drop.speed * SLANT * dt;
+            drop.y += drop.speed * dt;
+            if drop.y >= height - 1.0 {
+                landed.push(drop.x);
+                false
+            } else {
+                true
+            }
+        });
+
+        for x in landed {
+            for _ in 0..SPLASH_PARTICLES {
+                let angle = self.splashes.random(-2.6, -0.5);
+                let speed = self.splashes.random(20.0, 80.0);
+                self.splashes.spawn(Particle {
+                    position: (x, height - 1.0),
+                    velocity: (angle.cos() * speed, angle.sin() * speed),
+                    age: 0.0,
+                    lifetime: 0.4,
+                    color: 0.8,
+                });
+            }
+        }
+        self.splashes.update(dt);
+
+        self.flash = (self.flash - FLASH_DECAY * dt).max(0.0);
+        if self.config.lightning && self.rng.f32() < FLASH_RATE * dt {
+            self.flash = 1.0;
+        }
+    }
+}
+
+impl DemoEffect for Rain {
+    fn update(&mut self, dt: f32) {
+        self.advance(dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let sky = |base: f32| (base + (255.0 - base) * self.flash * 0.7) as u8;
+        buffer.fill(rgb(sky(10.0), sky(12.0), sky(24.0)));
+
+        let streak_color = rgb(140, 160, 200);
+        for drop in &self.drops {
+            let length = self.streak(drop);
+            let from = ((drop.x - length * SLANT) as i32, (drop.y - length) as i32);
+            let to = (drop.x as i32, drop.y as i32);
+            self.base.draw_line(buffer, from, to, streak_color);
+        }
+        self.splashes.render(buffer, &self.base, &self.palette);
+    }
+
+    /// Makes the rain heavier or lighter.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Up) => {
+                self.config.intensity = (self.config.intensity + INTENSITY_STEP).min(MAX_INTENSITY)
+            }
+            InputEvent::Key(Key::Down) => {
+                self.config.intensity = (self.config.intensity - INTENSITY_STEP).max(0.0)
+            }
+            _ => {}
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.rain.intensity = self.config.intensity;
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_rain(config: Config) -> Rain {
+        let mut rain = Rain::new(100, 100, Palette::BlueCyan, config);
+        rain.rng = fastrand::Rng::with_seed(8);
+        rain
+    }
+
+    #[test]
+    fn drops_spawn_at_configured_rate() {
+        let config = Config {
+            intensity: 100.0,
+            ..Config::default()
+        };
+        let mut rain = create_rain(config);
+        rain.advance(0.05);
+        assert_eq!(rain.drops.len(), 5);
+    }
+
+    #[test]
+    fn landing_drops_splash() {
+        let config = Config {
+            intensity: 0.0,
+            ..Config::default()
+        };
+        let mut rain = create_rain(config);
+        rain.drops.push(Drop {
+            x: 50.0,
+            y: 98.5,
+            speed: 150.0,
+        });
+        rain.advance(0.01);
+        assert!(rain.drops.is_empty());
+        assert_eq!(rain.splashes.particles.len(), SPLASH_PARTICLES);
+    }
+
+    #[test]
+    fn keys_adjust_intensity_within_bounds() {
+        let mut rain = create_rain(Config::default());
+        rain.handle_input(InputEvent::Key(Key::Up));
+        assert_eq!(rain.config.intensity, DEFAULT_INTENSITY + INTENSITY_STEP);
+        for _ in 0..100 {
+            rain.handle_input(InputEvent::Key(Key::Down));
+        }
+        assert_eq!(rain.config.intensity, 0.0);
+    }
+
+    #[test]
+    fn flashes_only_happen_when_enabled() {
+        let mut rain = create_rain(Config::default());
+        for _ in 0..1000 {
+            rain.advance(0.1);
+        }
+        assert_eq!(rain.flash, 0.0);
+    }
+
+    #[test]
+    fn flash_fades_out() {
+        let mut rain = create_rain(Config::default());
+        rain.flash = 1.0;
+        rain.advance(0.1);
+        assert!(rain.flash < 1.0 && rain.flash > 0.0);
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        let mut rain = create_rain(Config::default());
+        rain.handle_input(InputEvent::Key(Key::Up));
+        let mut settings = Settings::default();
+        rain.save_settings(&mut settings);
+        assert_eq!(settings.rain.intensity, DEFAULT_INTENSITY + INTENSITY_STEP);
+    }
+}
@@ -0,0 +1,132 @@
+//! Lookup of effects by name.
+//!
+//! The registry maps effect names to factories building a fresh effect for a given screen
+//! size. The main loop only deals with names, so it can start, list, and switch between
+//! effects without knowing how any of them is constructed.
+use crate::common::DemoEffect;
+use std::error::Error;
+
+/// Builds an effect of the given width and height
+pub type Factory = Box<dyn Fn(usize, usize) -> Result<Box<dyn DemoEffect>, Box<dyn Error>>>;
+
+/// A named effect factory
+struct Entry {
+    /// Name the effect is looked up by
+    name: String,
+    /// One line summary of the effect
+    description: String,
+    /// Builds the effect
+    factory: Factory,
+}
+
+/// Effects that can be created by name
+#[derive(Default)]
+pub struct EffectRegistry {
+    /// Registered effects in registration order
+    entries: Vec<Entry>,
+}
+
+impl EffectRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an effect under `name`, replacing any effect already registered with that
+    /// name.
+    pub fn register<F>(&mut self, name: &str, description: &str, factory: F)
+    where
+        F: Fn(usize, usize) -> Result<Box<dyn DemoEffect>, Box<dyn Error>> + 'static,
+    {
+        let entry = Entry {
+            name: name.to_string(),
+            description: description.to_string(),
+            factory: Box::new(factory),
+        };
+        match self.entries.iter_mut().find(|e| e.name == name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Builds the effect registered under `name` for a screen of the given size.
+    ///
+    /// # Errors
+    /// Fails if no effect has that name or if the effect cannot be built.
+    pub fn create(
+        &self,
+        name: &str,
+        width: usize,
+        height: usize,
+    ) -> Result<Box<dyn DemoEffect>, Box<dyn Error>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| format!("unknown effect '{name}'"))?;
+        (entry.factory)(width, height)
+    }
+
+    /// Returns the names of all registered effects in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.name.as_str())
+    }
+
+    /// Returns the name and description of all registered effects in registration order.
+    pub fn descriptions(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.description.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Solid(u32);
+
+    impl DemoEffect for Solid {
+        fn draw(&self, buffer: &mut [u32], _time: f32) {
+            buffer.fill(self.0);
+        }
+    }
+
+    fn solid(color: u32) -> impl Fn(usize, usize) -> Result<Box<dyn DemoEffect>, Box<dyn Error>> {
+        move |_, _| Ok(Box::new(Solid(color)))
+    }
+
+    #[test]
+    fn names_keep_registration_order() {
+        let mut registry = EffectRegistry::new();
+        registry.register("b", "second letter", solid(2));
+        registry.register("a", "first letter", solid(1));
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["b", "a"]);
+    }
+
+    #[test]
+    fn create_builds_the_named_effect() {
+        let mut registry = EffectRegistry::new();
+        registry.register("one", "", solid(1));
+        registry.register("two", "", solid(2));
+        let mut buffer = [0; 4];
+        registry.create("two", 2, 2).unwrap().draw(&mut buffer, 0.0);
+        assert_eq!(buffer, [2; 4]);
+    }
+
+    #[test]
+    fn registering_a_name_again_replaces_the_effect() {
+        let mut registry = EffectRegistry::new();
+        registry.register("solid", "old", solid(1));
+        registry.register("solid", "new", solid(2));
+        assert_eq!(
+            registry.descriptions().collect::<Vec<_>>(),
+            [("solid", "new")]
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        let registry = EffectRegistry::new();
+        assert!(registry.create("missing", 2, 2).is_err());
+    }
+}
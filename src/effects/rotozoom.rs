@@ -0,0 +1,205 @@
+//! The rotozoomer effect.
+//!
+//! A procedurally generated tile is repeated over an infinite plane which is rotated and
+//! zoomed over time. Rather than transforming every pixel independently, the texture
+//! coordinates are stepped incrementally along each row and column, the same trick the
+//! original demoscene versions used to run in real time.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+use clap::{Args, ValueEnum};
+
+/// Width and height of the square procedural tile, must be a power of two
+const TILE_SIZE: usize = 256;
+
+/// Default rotation speed in radians per second
+const DEFAULT_ROTATION_SPEED: f32 = 0.5;
+
+/// Default zoom amplitude
+const DEFAULT_ZOOM_AMPLITUDE: f32 = 0.8;
+
+/// Base zoom level around which the zoom oscillates
+const BASE_ZOOM: f32 = 1.0;
+
+/// Procedural tiles that can be rotated and zoomed
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Tile {
+    Checkerboard,
+    Xor,
+}
+
+/// User tunable rotozoomer parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Rotozoom options")]
+#[group(id = "rotozoom-options")]
+pub struct Config {
+    #[arg(
+        id = "rotozoom-tile",
+        long = "rotozoom-tile",
+        value_name = "TILE",
+        value_enum,
+        default_value_t = Tile::Checkerboard,
+        help = "Tile that is rotated and zoomed"
+    )]
+    pub tile: Tile,
+
+    #[arg(
+        id = "rotation-speed",
+        long = "rotation-speed",
+        value_name = "ROTATION_SPEED",
+        default_value_t = DEFAULT_ROTATION_SPEED,
+        help = "Rotation speed in radians per second"
+    )]
+    pub rotation_speed: f32,
+
+    #[arg(
+        id = "zoom-amplitude",
+        long = "zoom-amplitude",
+        value_name = "ZOOM_AMPLITUDE",
+        default_value_t = DEFAULT_ZOOM_AMPLITUDE,
+        help = "How far the zoom oscillates around its base level"
+    )]
+    pub zoom_amplitude: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tile: Tile::Checkerboard,
+            rotation_speed: DEFAULT_ROTATION_SPEED,
+            zoom_amplitude: DEFAULT_ZOOM_AMPLITUDE,
+        }
+    }
+}
+
+/// A rotozoomer rendering a rotated and scaled tile
+pub struct Rotozoom {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Rotozoomer parameters
+    config: Config,
+    /// Colored tile repeated over the plane
+    tile: Vec<u32>,
+}
+
+impl Rotozoom {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        let tile = Self::generate_tile(&config.tile);
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            tile,
+        }
+    }
+
+    /// Generates a `TILE_SIZE` x `TILE_SIZE` colored tile.
+    fn generate_tile(tile: &Tile) -> Vec<u32> {
+        (0..TILE_SIZE * TILE_SIZE)
+            .map(|i| {
+                let (x, y) = (i % TILE_SIZE, i / TILE_SIZE);
+                match tile {
+                    Tile::Checkerboard => {
+                        if ((x / 32) + (y / 32)) % 2 == 0 {
+                            rgb(240, 240, 240)
+                        } else {
+                            rgb(40, 40, 160)
+                        }
+                    }
+                    Tile::Xor => {
+                        let v = (x ^ y) as u8;
+                        rgb(v, v / 2, 255 - v)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the rotation angle and zoom factor at the given time.
+    fn transform(&self, time: f32) -> (f32, f32) {
+        let angle = time * self.config.rotation_speed;
+        let zoom = BASE_ZOOM + self.config.zoom_amplitude * (time * 0.7).sin();
+        (angle, zoom)
+    }
+}
+
+impl DemoEffect for Rotozoom {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let (angle, zoom) = self.transform(time);
+        // A zoom close to zero would blow up the texture step, so keep it strictly positive
+        let zoom = zoom.max(0.05);
+        let (center_x, center_y) = self.base.center();
+
+        // Texture space step when moving one pixel right or down on screen
+        let (du_dx, dv_dx) = (angle.cos() / zoom, angle.sin() / zoom);
+        let (du_dy, dv_dy) = (-dv_dx, du_dx);
+        let mask = (TILE_SIZE - 1) as i32;
+
+        for_each_row(buffer, self.base.width, |y, row| {
+            let py = y as f32 - center_y;
+            // Texture coordinates of the leftmost pixel of this row
+            let mut u = -center_x * du_dx + py * du_dy;
+            let mut v = -center_x * dv_dx + py * dv_dy;
+
+            for pixel in row.iter_mut() {
+                let tx = (u.floor() as i32 & mask) as usize;
+                let ty = (v.floor() as i32 & mask) as usize;
+                *pixel = self.tile[ty * TILE_SIZE + tx];
+                u += du_dx;
+                v += dv_dx;
+            }
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_covers_full_texture() {
+        assert_eq!(
+            Rotozoom::generate_tile(&Tile::Checkerboard).len(),
+            TILE_SIZE * TILE_SIZE
+        );
+        assert_eq!(
+            Rotozoom::generate_tile(&Tile::Xor).len(),
+            TILE_SIZE * TILE_SIZE
+        );
+    }
+
+    #[test]
+    fn transform_starts_unrotated_at_base_zoom() {
+        let rotozoom = Rotozoom::new(64, 64, Config::default());
+        let (angle, zoom) = rotozoom.transform(0.0);
+        assert_eq!(angle, 0.0);
+        assert_eq!(zoom, BASE_ZOOM);
+    }
+
+    #[test]
+    fn rotation_speed_controls_angle() {
+        let config = Config {
+            rotation_speed: 2.0,
+            ..Config::default()
+        };
+        let rotozoom = Rotozoom::new(64, 64, config);
+        let (angle, _) = rotozoom.transform(1.5);
+        assert_eq!(angle, 3.0);
+    }
+
+    #[test]
+    fn unrotated_frame_samples_tile_directly() {
+        let config = Config {
+            rotation_speed: 0.0,
+            zoom_amplitude: 0.0,
+            ..Config::default()
+        };
+        let rotozoom = Rotozoom::new(64, 64, config);
+        let mut buffer = vec![0; 64 * 64];
+        rotozoom.draw(&mut buffer, 0.0);
+
+        // With no rotation and unit zoom, the screen center maps onto texel (0, 0)
+        assert_eq!(buffer[32 * 64 + 32], rotozoom.tile[0]);
+    }
+}
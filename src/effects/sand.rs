@@ -0,0 +1,343 @@
+//! A falling sand cellular automaton.
+//!
+//! The screen is divided into small cells that each hold one element. Every step the grid
+//! is swept from the bottom up: sand falls straight down or slides diagonally, sinking
+//! through water on the way, water does the same but also spreads sideways to find its
+//! level, and walls never move. The horizontal sweep direction alternates between steps so
+//! the piles don't lean to one side. A spout trickles sand from the top of the screen, and
+//! dragging the pointer paints the selected element.
+//!
+//! `1` selects sand, `2` water, `3` wall, and `4` the eraser.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect, InputEvent};
+use crate::effects::Settings;
+use clap::{Args, ValueEnum};
+use minifb::Key;
+
+/// Size of a cell in pixels
+const CELL_SIZE: usize = 2;
+
+/// Radius of the brush painting elements, in cells
+const BRUSH_RADIUS: isize = 3;
+
+/// Probability that the spout drops a grain of sand on a given step
+const SPOUT_RATE: f32 = 0.6;
+
+/// Materials a cell can hold
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Element {
+    /// Nothing, painted by the eraser
+    #[value(name = "eraser")]
+    Empty,
+    /// Grains falling into piles
+    Sand,
+    /// Liquid spreading sideways to find its level
+    Water,
+    /// Solid cells that never move
+    Wall,
+}
+
+/// User tunable falling sand parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Sand options")]
+#[group(id = "sand-options")]
+pub struct Config {
+    #[arg(
+        id = "sand-brush",
+        long = "sand-brush",
+        value_name = "ELEMENT",
+        value_enum,
+        default_value_t = Element::Sand,
+        help = "Element painted by the pointer at first"
+    )]
+    pub brush: Element,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            brush: Element::Sand,
+        }
+    }
+}
+
+impl Element {
+    /// Returns the color of the element.
+    fn color(self) -> u32 {
+        match self {
+            Element::Empty => rgb(0, 0, 0),
+            Element::Sand => rgb(220, 190, 120),
+            Element::Water => rgb(40, 100, 220),
+            Element::Wall => rgb(120, 120, 130),
+        }
+    }
+
+    /// Returns whether the element moves out of the way of a falling one.
+    fn displaced_by(self, falling: Element) -> bool {
+        match falling {
+            Element::Sand => matches!(self, Element::Empty | Element::Water),
+            Element::Water => self == Element::Empty,
+            Element::Empty | Element::Wall => false,
+        }
+    }
+}
+
+/// Sand and water poured onto the screen
+pub struct Sand {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Number of cells per row
+    columns: usize,
+    /// Number of rows
+    rows: usize,
+    /// Element held by every cell
+    cells: Vec<Element>,
+    /// Element painted by the pointer
+    brush: Element,
+    /// Whether the sweep of the next step runs from left to right
+    left_to_right: bool,
+    /// Random number generator used for the spout and diagonal moves
+    rng: fastrand::Rng,
+}
+
+impl Sand {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        let (columns, rows) = (width.div_ceil(CELL_SIZE), height.div_ceil(CELL_SIZE));
+        Self {
+            base: DemoBase::new(width, height),
+            columns,
+            rows,
+            cells: vec![Element::Empty; columns * rows],
+            brush: config.brush,
+            left_to_right: true,
+            rng: fastrand::Rng::new(),
+        }
+    }
+
+    /// Returns the element at a cell, treating everything outside the grid as a wall.
+    fn get(&self, x: isize, y: isize) -> Element {
+        if x < 0 || y < 0 || x as usize >= self.columns || y as usize >= self.rows {
+            return Element::Wall;
+        }
+        self.cells[y as usize * self.columns + x as usize]
+    }
+
+    /// Fills a disc of cells around the given cell with an element.
+    fn paint(&mut self, x: isize, y: isize, element: Element) {
+        for dy in -BRUSH_RADIUS..=BRUSH_RADIUS {
+            for dx in -BRUSH_RADIUS..=BRUSH_RADIUS {
+                let (cx, cy) = (x + dx, y + dy);
+                let inside = dx * dx + dy * dy <= BRUSH_RADIUS * BRUSH_RADIUS;
+                if inside
+                    && cx >= 0
+                    && cy >= 0
+                    && (cx as usize) < self.columns
+                    && (cy as usize) < self.rows
+                {
+                    self.cells[cy as usize * self.columns + cx as usize] = element;
+                }
+            }
+        }
+    }
+
+    /// Returns the cell the element at `(x, y)` moves to, if it can move at all.
+    fn destination(&mut self, x: isize, y: isize) -> Option<(isize, isize)> {
+        let element = self.get(x, y);
+        if matches!(element, Element::Empty | Element::Wall) {
+            return None;
+        }
+
+        // Try both diagonals, in random order so piles grow symmetrically
+        let side = if self.rng.bool() { 1 } else { -1 };
+        let mut moves = vec![(x, y + 1), (x + side, y + 1), (x - side, y + 1)];
+        if element == Element::Water {
+            moves.extend([(x + side, y), (x - side, y)]);
+        }
+        moves
+            .into_iter()
+            .find(|&(mx, my)| self.get(mx, my).displaced_by(element))
+    }
+
+    /// Advances the automaton by one step.
+    fn step(&mut self) {
+        if self.columns > 0 && self.rng.f32() < SPOUT_RATE {
+            let x = (self.columns / 2 + self.rng.usize(..3))
+                .saturating_sub(1)
+                .min(self.columns - 1);
+            if self.cells[x] == Element::Empty {
+                self.cells[x] = Element::Sand;
+            }
+        }
+
+        // Cells that already moved this step must not move again as the sweep reaches them
+        let mut moved = vec![false; self.cells.len()];
+        let columns: Vec<usize> = if self.left_to_right {
+            (0..self.columns).collect()
+        } else {
+            (0..self.columns).rev().collect()
+        };
+        for y in (0..self.rows).rev() {
+            for &x in &columns {
+                let from = y * self.columns + x;
+                if moved[from] {
+                    continue;
+                }
+                if let Some((tx, ty)) = self.destination(x as isize, y as isize) {
+                    let to = ty as usize * self.columns + tx as usize;
+                    self.cells.swap(from, to);
+                    moved[to] = true;
+                }
+            }
+        }
+        self.left_to_right = !self.left_to_right;
+    }
+}
+
+impl DemoEffect for Sand {
+    /// Advances the grains by one step per update, regardless of the tick length.
+    fn update(&mut self, _dt: f32) {
+        self.step();
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.cells[(y / CELL_SIZE) * self.columns + x / CELL_SIZE].color();
+            }
+        });
+    }
+
+    /// Handles the element selection keys and paints with the mouse.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Key1) => self.brush = Element::Sand,
+            InputEvent::Key(Key::Key2) => self.brush = Element::Water,
+            InputEvent::Key(Key::Key3) => self.brush = Element::Wall,
+            InputEvent::Key(Key::Key4) => self.brush = Element::Empty,
+            InputEvent::Mouse { x, y, pressed } if pressed && x >= 0.0 && y >= 0.0 => {
+                let (x, y) = (x as usize / CELL_SIZE, y as usize / CELL_SIZE);
+                self.paint(x as isize, y as isize, self.brush);
+            }
+            _ => {}
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.sand.brush = self.brush;
+    }
+
+    /// Keeps the grains that still fit on the resized grid.
+    fn resize(&mut self, width: usize, height: usize) {
+        let (columns, rows) = (width.div_ceil(CELL_SIZE), height.div_ceil(CELL_SIZE));
+        let mut cells = vec![Element::Empty; columns * rows];
+        // Keep the pile resting on the floor by aligning the old and new grids at the bottom
+        for y in 0..rows.min(self.rows) {
+            for x in 0..columns.min(self.columns) {
+                cells[(rows - 1 - y) * columns + x] =
+                    self.cells[(self.rows - 1 - y) * self.columns + x];
+            }
+        }
+
+        self.base = DemoBase::new(width, height);
+        self.columns = columns;
+        self.rows = rows;
+        self.cells = cells;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_sand() -> Sand {
+        let mut sand = Sand::new(16, 16, Config::default());
+        sand.rng = fastrand::Rng::with_seed(3);
+        sand
+    }
+
+    fn set(sand: &mut Sand, x: usize, y: usize, element: Element) {
+        sand.cells[y * sand.columns + x] = element;
+    }
+
+    fn count(sand: &Sand, element: Element) -> usize {
+        sand.cells.iter().filter(|&&e| e == element).count()
+    }
+
+    #[test]
+    fn sand_falls_into_empty_space() {
+        let mut sand = create_sand();
+        set(&mut sand, 3, 2, Element::Sand);
+        assert_eq!(sand.destination(3, 2), Some((3, 3)));
+    }
+
+    #[test]
+    fn sand_rests_on_walls_and_other_grains() {
+        let mut sand = create_sand();
+        set(&mut sand, 3, 2, Element::Sand);
+        for x in 2..=4 {
+            set(&mut sand, x, 3, Element::Wall);
+        }
+        assert_eq!(sand.destination(3, 2), None);
+        // The bottom of the grid counts as a wall
+        set(&mut sand, 3, 7, Element::Sand);
+        assert_eq!(sand.destination(3, 7), None);
+    }
+
+    #[test]
+    fn sand_sinks_through_water() {
+        let mut sand = create_sand();
+        set(&mut sand, 3, 2, Element::Sand);
+        set(&mut sand, 3, 3, Element::Water);
+        assert_eq!(sand.destination(3, 2), Some((3, 3)));
+        assert!(!sand.get(3, 3).displaced_by(Element::Water));
+    }
+
+    #[test]
+    fn water_spreads_sideways() {
+        let mut sand = create_sand();
+        for x in 0..8 {
+            set(&mut sand, x, 7, Element::Wall);
+        }
+        set(&mut sand, 3, 6, Element::Water);
+        let (x, y) = sand.destination(3, 6).unwrap();
+        assert_eq!(y, 6);
+        assert!(x == 2 || x == 4);
+    }
+
+    #[test]
+    fn steps_conserve_elements() {
+        let mut sand = create_sand();
+        sand.paint(4, 2, Element::Water);
+        let before = count(&sand, Element::Water);
+        for _ in 0..20 {
+            sand.step();
+        }
+        assert_eq!(count(&sand, Element::Water), before);
+    }
+
+    #[test]
+    fn number_keys_select_the_brush() {
+        let mut sand = create_sand();
+        let click = InputEvent::Mouse {
+            x: 8.0,
+            y: 8.0,
+            pressed: true,
+        };
+        sand.handle_input(InputEvent::Key(Key::Key3));
+        sand.handle_input(click);
+        assert_eq!(sand.get(4, 4), Element::Wall);
+        sand.handle_input(InputEvent::Key(Key::Key4));
+        sand.handle_input(click);
+        assert_eq!(sand.get(4, 4), Element::Empty);
+    }
+
+    #[test]
+    fn saved_settings_keep_the_brush() {
+        let mut sand = create_sand();
+        sand.handle_input(InputEvent::Key(Key::Key2));
+        let mut settings = Settings::default();
+        sand.save_settings(&mut settings);
+        assert_eq!(settings.sand.brush, Element::Water);
+        let sand = Sand::new(16, 16, settings.sand);
+        assert_eq!(sand.brush, Element::Water);
+    }
+}
@@ -0,0 +1,175 @@
+//! A sine-wave text scroller.
+//!
+//! A message rendered with the embedded bitmap font scrolls from right to left across the
+//! screen. Every pixel column of the text is displaced vertically by a sine wave that
+//! travels along the message, and the text is colored by sweeping the active palette
+//! across the screen.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::font::{self, GLYPH_SIZE};
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default message scrolled across the screen
+const DEFAULT_TEXT: &str = "GREETINGS FROM THE PLASMA DEMO!";
+
+/// Size of a font pixel in screen pixels
+const PIXEL_SCALE: usize = 4;
+
+/// Horizontal scrolling speed in screen pixels per second
+const SCROLL_SPEED: f32 = 120.0;
+
+/// Number of wave periods visible across the screen
+const WAVE_PERIODS: f32 = 1.5;
+
+/// Angular speed of the wave in radians per second
+const WAVE_SPEED: f32 = 3.0;
+
+/// User tunable scroller parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Scroller options")]
+#[group(id = "scroller-options")]
+pub struct Config {
+    #[arg(
+        id = "text",
+        long = "text",
+        value_name = "TEXT",
+        default_value = DEFAULT_TEXT,
+        help = "Message scrolled across the screen"
+    )]
+    pub text: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            text: DEFAULT_TEXT.to_string(),
+        }
+    }
+}
+
+/// A sine-wave text scroller
+pub struct Scroller {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Scroller parameters
+    config: Config,
+    /// Color palette used for the text
+    palette: Palette,
+}
+
+impl Scroller {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            palette,
+        }
+    }
+
+    /// Returns the width of the rendered message in screen pixels.
+    fn text_width(&self) -> usize {
+        self.config.text.chars().count() * GLYPH_SIZE * PIXEL_SCALE
+    }
+
+    /// Returns the horizontal screen position of the start of the message.
+    ///
+    /// The message enters from the right edge, leaves through the left edge, and then
+    /// starts over.
+    fn offset(&self, time: f32) -> f32 {
+        let travel = (self.base.width + self.text_width()) as f32;
+        self.base.width as f32 - (time * SCROLL_SPEED) % travel
+    }
+
+    /// Returns the vertical displacement of the text at a screen column.
+    fn wave(&self, x: usize, time: f32) -> f32 {
+        let amplitude = (self.base.height / 2).saturating_sub(GLYPH_SIZE * PIXEL_SCALE) as f32;
+        let phase = x as f32 / self.base.width as f32 * WAVE_PERIODS * std::f32::consts::TAU;
+        amplitude * (phase + time * WAVE_SPEED).sin()
+    }
+}
+
+impl DemoEffect for Scroller {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+
+        let chars: Vec<char> = self.config.text.chars().collect();
+        let offset = self.offset(time);
+        let glyph_height = GLYPH_SIZE * PIXEL_SCALE;
+        let (width, height) = (self.base.width, self.base.height);
+
+        for x in 0..width {
+            // Locate the font column displayed in this screen column
+            let text_x = x as f32 - offset;
+            if text_x < 0.0 {
+                continue;
+            }
+            let text_x = text_x as usize / PIXEL_SCALE;
+            let Some(&c) = chars.get(text_x / GLYPH_SIZE) else {
+                continue;
+            };
+
+            let top = height as f32 * 0.5 - glyph_height as f32 * 0.5 + self.wave(x, time);
+            let color = self
+                .palette
+                .color((x as f32 / width as f32 + time * 0.2).rem_euclid(1.0));
+
+            for dy in 0..glyph_height {
+                let y = top as isize + dy as isize;
+                if y < 0 || y as usize >= height {
+                    continue;
+                }
+                if font::is_set(c, text_x % GLYPH_SIZE, dy / PIXEL_SCALE) {
+                    buffer[y as usize * width + x] = color;
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_scroller(text: &str) -> Scroller {
+        let config = Config {
+            text: text.to_string(),
+        };
+        Scroller::new(320, 200, Palette::Rainbow, config)
+    }
+
+    #[test]
+    fn text_enters_from_the_right_edge() {
+        let scroller = create_scroller("HI");
+        assert_eq!(scroller.offset(0.0), 320.0);
+    }
+
+    #[test]
+    fn text_wraps_around_after_leaving_the_screen() {
+        let scroller = create_scroller("HI");
+        let travel = (320 + scroller.text_width()) as f32;
+        let period = travel / SCROLL_SPEED;
+        assert!((scroller.offset(period + 0.5) - scroller.offset(0.5)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn wave_keeps_text_on_screen() {
+        let scroller = create_scroller("HI");
+        let half_text = (GLYPH_SIZE * PIXEL_SCALE) as f32 * 0.5;
+        for x in 0..320 {
+            let top = 100.0 - half_text + scroller.wave(x, 1.3);
+            assert!(top >= 0.0 && top + 2.0 * half_text <= 200.0);
+        }
+    }
+
+    #[test]
+    fn empty_text_draws_a_black_screen() {
+        let scroller = create_scroller("");
+        let mut buffer = vec![1; 320 * 200];
+        scroller.draw(&mut buffer, 1.0);
+        assert!(buffer.iter().all(|&p| p == rgb(0, 0, 0)));
+    }
+}
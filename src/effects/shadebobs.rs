@@ -0,0 +1,179 @@
+//! The shadebobs effect.
+//!
+//! A handful of soft round bobs travel along Lissajous curves. Instead of being drawn
+//! directly, each bob adds its brightness into a persistent intensity buffer that saturates
+//! at full brightness and slowly decays, so the bobs leave glowing trails that pile up
+//! wherever the paths cross. The intensity buffer is mapped through the palette on display.
+use crate::common::{DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::Args;
+
+/// Default fraction of the accumulated intensity lost per second
+const DEFAULT_DECAY: f32 = 0.6;
+
+/// Number of bobs travelling across the screen
+const BOB_COUNT: usize = 5;
+
+/// Radius of a bob as a fraction of the smallest screen dimension
+const BOB_RADIUS: f32 = 0.08;
+
+/// Intensity added per second at the center of a bob
+const BOB_STRENGTH: f32 = 1.5;
+
+/// User tunable shadebobs parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Shadebobs options")]
+#[group(id = "shadebobs-options")]
+pub struct Config {
+    #[arg(
+        id = "bob-decay",
+        long = "bob-decay",
+        value_name = "DECAY",
+        default_value_t = DEFAULT_DECAY,
+        help = "Fraction of the accumulated intensity lost per second"
+    )]
+    pub decay: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            decay: DEFAULT_DECAY,
+        }
+    }
+}
+
+/// Additive bobs accumulating in a persistent intensity buffer
+pub struct Shadebobs {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used to display the intensity buffer
+    palette: Palette,
+    /// Shadebobs parameters
+    config: Config,
+    /// Accumulated intensity of every pixel in [0,1]
+    intensity: Vec<f32>,
+    /// Seconds the bobs have been moving along their paths
+    elapsed: f32,
+}
+
+impl Shadebobs {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            config,
+            intensity: vec![0.0; base.pixel_count()],
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns the center of the given bob at `time`.
+    ///
+    /// Every bob follows its own Lissajous curve so the paths keep crossing each other.
+    fn position(&self, bob: usize, time: f32) -> (f32, f32) {
+        let (center_x, center_y) = self.base.center();
+        let k = bob as f32;
+        let x = ((1.0 + k * 0.3) * time * 0.9 + k).sin();
+        let y = ((1.5 + k * 0.2) * time * 0.7 + k * 2.0).cos();
+        (center_x + x * center_x * 0.8, center_y + y * center_y * 0.8)
+    }
+
+    /// Fades the intensity buffer and stamps every bob into it.
+    fn accumulate(&mut self, time: f32, dt: f32) {
+        let fade = (1.0 - self.config.decay * dt).clamp(0.0, 1.0);
+        self.intensity.iter_mut().for_each(|v| *v *= fade);
+
+        let radius = (self.base.width.min(self.base.height) as f32 * BOB_RADIUS).max(1.0);
+        let width = self.base.width as i32;
+        let height = self.base.height as i32;
+        for bob in 0..BOB_COUNT {
+            let (bx, by) = self.position(bob, time);
+            let x0 = ((bx - radius) as i32).max(0);
+            let x1 = ((bx + radius) as i32 + 1).min(width);
+            let y0 = ((by - radius) as i32).max(0);
+            let y1 = ((by + radius) as i32 + 1).min(height);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let dx = x as f32 - bx;
+                    let dy = y as f32 - by;
+                    // Soft quadratic falloff toward the rim of the bob
+                    let falloff = 1.0 - (dx * dx + dy * dy) / (radius * radius);
+                    if falloff > 0.0 {
+                        let v = &mut self.intensity[y as usize * self.base.width + x as usize];
+                        *v = (*v + falloff * BOB_STRENGTH * dt).min(1.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl DemoEffect for Shadebobs {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        self.accumulate(self.elapsed, dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer
+            .iter_mut()
+            .zip(&self.intensity)
+            .for_each(|(pixel, &v)| *pixel = self.palette.color(v));
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.intensity = vec![0.0; self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_shadebobs() -> Shadebobs {
+        Shadebobs::new(100, 100, Palette::Hot, Config::default())
+    }
+
+    #[test]
+    fn bobs_stay_on_screen() {
+        let shadebobs = create_shadebobs();
+        for step in 0..100 {
+            for bob in 0..BOB_COUNT {
+                let (x, y) = shadebobs.position(bob, step as f32 * 0.37);
+                assert!((0.0..100.0).contains(&x) && (0.0..100.0).contains(&y));
+            }
+        }
+    }
+
+    #[test]
+    fn bobs_add_intensity_under_their_center() {
+        let mut shadebobs = create_shadebobs();
+        shadebobs.accumulate(0.0, 0.1);
+        let (x, y) = shadebobs.position(0, 0.0);
+        assert!(shadebobs.intensity[y as usize * 100 + x as usize] > 0.0);
+    }
+
+    #[test]
+    fn intensity_saturates_at_full_brightness() {
+        let mut shadebobs = create_shadebobs();
+        for _ in 0..100 {
+            shadebobs.accumulate(0.0, 1.0);
+        }
+        assert!(shadebobs.intensity.iter().all(|&v| v <= 1.0));
+        assert!(shadebobs.intensity.contains(&1.0));
+    }
+
+    #[test]
+    fn intensity_decays_over_time() {
+        let mut shadebobs = create_shadebobs();
+        shadebobs.intensity.fill(0.8);
+        shadebobs.config.decay = 0.5;
+        shadebobs.accumulate(0.0, 1.0);
+        // The corner is never covered by a bob, so it only decays
+        assert_eq!(shadebobs.intensity[0], 0.4);
+    }
+}
@@ -0,0 +1,364 @@
+//! Rising smoke from a tiny fluid simulation.
+//!
+//! Smoke density and velocity live on a coarse grid that is upsampled to the screen. Every
+//! step, dense cells are pushed upward by buoyancy, a slowly changing noise field stirs the
+//! flow sideways, and the velocity is smoothed a little. Density and velocity are then
+//! advected semi-Lagrangian style: each cell traces its velocity backward and takes the
+//! bilinearly interpolated value found there. Backward tracing stays stable however large
+//! the step, which is what makes the method popular for real-time smoke. Smoke rises from
+//! the bottom of the screen or from the pointer.
+use crate::common::{for_each_row, DemoBase, DemoEffect, InputEvent};
+use crate::noise::Noise;
+use crate::palette::Palette;
+use clap::{Args, ValueEnum};
+
+/// Size of a simulation cell in pixels
+const CELL_SIZE: usize = 4;
+
+/// Upward acceleration per unit of density, in cells per second squared
+const BUOYANCY: f32 = 40.0;
+
+/// Strength of the noise stirring the flow sideways, in cells per second squared
+const STIR: f32 = 60.0;
+
+/// Fraction of the smoke density that disappears per second
+const DISSIPATION: f32 = 0.4;
+
+/// Fraction of the velocity lost per second
+const DRAG: f32 = 0.5;
+
+/// Fraction of every cell's velocity replaced by the average of its neighbors each step,
+/// acts as viscosity and lets the flow spread beyond the smoke
+const VISCOSITY: f32 = 0.5;
+
+/// Density added to a source cell per second
+const EMISSION: f32 = 6.0;
+
+/// Radius of the pointer source in cells
+const SOURCE_RADIUS: isize = 2;
+
+/// Longest step simulated at once, avoids huge jumps after a stall
+const MAX_STEP: f32 = 0.05;
+
+/// Where the smoke comes from
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Source {
+    Bottom,
+    Mouse,
+}
+
+/// User tunable smoke parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Smoke options")]
+#[group(id = "smoke-options")]
+pub struct Config {
+    #[arg(
+        id = "smoke-source",
+        long = "smoke-source",
+        value_name = "SOURCE",
+        value_enum,
+        default_value_t = Source::Bottom,
+        help = "Where the smoke is emitted from"
+    )]
+    pub source: Source,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source: Source::Bottom,
+        }
+    }
+}
+
+/// A coarse grid holding one value per simulation cell
+#[derive(Debug, Clone, PartialEq)]
+struct Grid {
+    /// Number of cells per row
+    width: usize,
+    /// Number of rows
+    height: usize,
+    /// Cell values in row-major order
+    cells: Vec<f32>,
+}
+
+impl Grid {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0.0; width * height],
+        }
+    }
+
+    /// Returns the bilinearly interpolated value at a point given in cell units.
+    ///
+    /// Points outside the grid are clamped to its border.
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let x = x.clamp(0.0, (self.width - 1) as f32);
+        let y = y.clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+        let at = |x: usize, y: usize| self.cells[y * self.width + x];
+        let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * fx;
+        let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * fx;
+        top + (bottom - top) * fy
+    }
+
+    /// Blends every cell with the average of its four neighbors.
+    fn smooth(&mut self, amount: f32) {
+        let previous = self.cells.clone();
+        let (width, height) = (self.width, self.height);
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            let (x, y) = (i % width, i / width);
+            // Neighbors outside the grid are replaced by the cell itself
+            let left = if x > 0 { previous[i - 1] } else { previous[i] };
+            let right = if x + 1 < width {
+                previous[i + 1]
+            } else {
+                previous[i]
+            };
+            let up = if y > 0 {
+                previous[i - width]
+            } else {
+                previous[i]
+            };
+            let down = if y + 1 < height {
+                previous[i + width]
+            } else {
+                previous[i]
+            };
+            let average = (left + right + up + down) * 0.25;
+            *cell += (average - *cell) * amount;
+        }
+    }
+}
+
+/// Smoke rising through a semi-Lagrangian fluid
+pub struct Smoke {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used to shade the smoke by density
+    palette: Palette,
+    /// Smoke parameters
+    config: Config,
+    /// Amount of smoke in every cell
+    density: Grid,
+    /// Horizontal velocity of every cell in cells per second
+    vx: Grid,
+    /// Vertical velocity of every cell in cells per second
+    vy: Grid,
+    /// Noise field stirring the flow
+    noise: Noise,
+    /// Latest pointer position in cells, if the pointer is over the window
+    pointer: Option<(usize, usize)>,
+    /// Seconds the smoke has been flowing, drives the turbulence stirring it
+    elapsed: f32,
+}
+
+impl Smoke {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let (columns, rows) = (
+            width.div_ceil(CELL_SIZE).max(2),
+            height.div_ceil(CELL_SIZE).max(2),
+        );
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            config,
+            density: Grid::new(columns, rows),
+            vx: Grid::new(columns, rows),
+            vy: Grid::new(columns, rows),
+            noise: Noise::new(fastrand::u64(..)),
+            pointer: None,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Adds smoke at the active source.
+    fn emit(&mut self, dt: f32) {
+        let (columns, rows) = (self.density.width, self.density.height);
+        match self.config.source {
+            Source::Bottom => {
+                // Leave the outer columns empty so the plume doesn't cling to the walls
+                let start = (rows - 1) * columns;
+                for x in columns / 4..columns - columns / 4 {
+                    self.density.cells[start + x] += EMISSION * dt;
+                }
+            }
+            Source::Mouse => {
+                let Some((px, py)) = self.pointer else {
+                    return;
+                };
+                for dy in -SOURCE_RADIUS..=SOURCE_RADIUS {
+                    for dx in -SOURCE_RADIUS..=SOURCE_RADIUS {
+                        let (x, y) = (px as isize + dx, py as isize + dy);
+                        let inside = dx * dx + dy * dy <= SOURCE_RADIUS * SOURCE_RADIUS;
+                        if inside
+                            && x >= 0
+                            && y >= 0
+                            && (x as usize) < columns
+                            && (y as usize) < rows
+                        {
+                            self.density.cells[y as usize * columns + x as usize] += EMISSION * dt;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies buoyancy, stirring, and drag to the velocity field.
+    fn accelerate(&mut self, dt: f32, time: f32) {
+        let columns = self.density.width;
+        let drag = (1.0 - DRAG * dt).max(0.0);
+        for (i, &density) in self.density.cells.iter().enumerate() {
+            let (x, y) = ((i % columns) as f32, (i / columns) as f32);
+            let stir = self.noise.sample(x * 0.1, y * 0.1, time * 0.5);
+            self.vx.cells[i] = (self.vx.cells[i] + stir * STIR * dt) * drag;
+            // Screen coordinates grow downward, so rising means a negative velocity
+            self.vy.cells[i] = (self.vy.cells[i] - density * BUOYANCY * dt) * drag;
+        }
+        self.vx.smooth(VISCOSITY);
+        self.vy.smooth(VISCOSITY);
+    }
+
+    /// Moves a field along the velocity field by tracing every cell backward.
+    fn advect(&self, field: &Grid, dt: f32) -> Grid {
+        let mut next = Grid::new(field.width, field.height);
+        for (i, cell) in next.cells.iter_mut().enumerate() {
+            let (x, y) = ((i % field.width) as f32, (i / field.width) as f32);
+            *cell = field.sample(x - self.vx.cells[i] * dt, y - self.vy.cells[i] * dt);
+        }
+        next
+    }
+
+    /// Advances the simulation by `dt` seconds.
+    fn step(&mut self, dt: f32, time: f32) {
+        self.emit(dt);
+        self.accelerate(dt, time);
+
+        let fade = (1.0 - DISSIPATION * dt).max(0.0);
+        let mut density = self.advect(&self.density, dt);
+        density.cells.iter_mut().for_each(|d| *d *= fade);
+        let vx = self.advect(&self.vx, dt);
+        let vy = self.advect(&self.vy, dt);
+        self.density = density;
+        self.vx = vx;
+        self.vy = vy;
+    }
+}
+
+impl DemoEffect for Smoke {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        self.step(dt.min(MAX_STEP), self.elapsed);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let scale = 1.0 / CELL_SIZE as f32;
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                // Sample at pixel centers so the upsampled smoke lines up with the cells
+                let density = self.density.sample(
+                    (x as f32 + 0.5) * scale - 0.5,
+                    (y as f32 + 0.5) * scale - 0.5,
+                );
+                *pixel = self.palette.color(density.clamp(0.0, 1.0));
+            }
+        });
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        if let InputEvent::Mouse { x, y, .. } = event {
+            self.pointer = (x >= 0.0 && y >= 0.0)
+                .then_some((x as usize / CELL_SIZE, y as usize / CELL_SIZE))
+                .filter(|&(x, y)| x < self.density.width && y < self.density.height);
+        }
+    }
+
+    /// Starts over with clear air on a grid matching the new size.
+    fn resize(&mut self, width: usize, height: usize) {
+        *self = Self::new(width, height, self.palette.clone(), self.config.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_smoke(source: Source) -> Smoke {
+        let mut smoke = Smoke::new(64, 64, Palette::BlackWhite, Config { source });
+        smoke.noise = Noise::new(13);
+        smoke
+    }
+
+    fn total(grid: &Grid) -> f32 {
+        grid.cells.iter().sum()
+    }
+
+    #[test]
+    fn grid_sampling_interpolates_between_cells() {
+        let mut grid = Grid::new(2, 2);
+        grid.cells = vec![0.0, 1.0, 2.0, 3.0];
+        assert_eq!(grid.sample(0.5, 0.0), 0.5);
+        assert_eq!(grid.sample(0.5, 0.5), 1.5);
+        assert_eq!(grid.sample(-3.0, 9.0), 2.0);
+    }
+
+    #[test]
+    fn smoothing_spreads_values_to_neighbors() {
+        let mut grid = Grid::new(3, 3);
+        grid.cells[4] = 1.0;
+        grid.smooth(1.0);
+        assert_eq!(grid.cells[4], 0.0);
+        assert_eq!(grid.cells[1], 0.25);
+    }
+
+    #[test]
+    fn bottom_source_fills_the_last_row() {
+        let mut smoke = create_smoke(Source::Bottom);
+        smoke.emit(0.1);
+        let columns = smoke.density.width;
+        let last_row = &smoke.density.cells[(smoke.density.height - 1) * columns..];
+        assert!(last_row.iter().any(|&d| d > 0.0));
+        assert_eq!(total(&smoke.density), last_row.iter().sum::<f32>());
+    }
+
+    #[test]
+    fn mouse_source_needs_the_pointer() {
+        let mut smoke = create_smoke(Source::Mouse);
+        smoke.emit(0.1);
+        assert_eq!(total(&smoke.density), 0.0);
+        smoke.handle_input(InputEvent::Mouse {
+            x: 32.0,
+            y: 32.0,
+            pressed: false,
+        });
+        smoke.emit(0.1);
+        assert!(smoke.density.cells[8 * smoke.density.width + 8] > 0.0);
+    }
+
+    #[test]
+    fn smoke_rises() {
+        let mut smoke = create_smoke(Source::Bottom);
+        for step in 0..40 {
+            smoke.step(0.03, step as f32 * 0.03);
+        }
+        let columns = smoke.density.width;
+        let upper_half: f32 = smoke.density.cells[..smoke.density.height / 2 * columns]
+            .iter()
+            .sum();
+        assert!(upper_half > 0.0);
+        assert!(smoke.vy.cells.iter().any(|&v| v < 0.0));
+    }
+
+    #[test]
+    fn smoke_dissipates_without_a_source() {
+        let mut smoke = create_smoke(Source::Mouse);
+        smoke.density.cells.fill(1.0);
+        smoke.step(0.05, 0.0);
+        assert!(total(&smoke.density) < smoke.density.cells.len() as f32);
+    }
+}
@@ -0,0 +1,287 @@
+//! Falling snow piling up on the ground.
+//!
+//! Snowflakes live on a few depth layers. Flakes on nearer layers are larger, brighter, and
+//! fall faster than distant ones, giving the scene some parallax. A gusting wind made of
+//! gradient noise pushes the flakes sideways, and flakes on the nearest layer land on a
+//! growing snow bank along the bottom edge of the screen.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::noise::Noise;
+use clap::Args;
+
+/// Default number of snowflakes
+const DEFAULT_COUNT: usize = 400;
+
+/// Default strength of the wind in pixels per second
+const DEFAULT_WIND: f32 = 30.0;
+
+/// Number of depth layers, the last layer is the nearest to the viewer
+const LAYERS: usize = 3;
+
+/// Falling speed of the farthest layer in screen heights per second
+const FALL_SPEED: f32 = 0.08;
+
+/// Highest the snow bank can grow, as a fraction of the screen height
+const MAX_BANK: f32 = 0.2;
+
+/// Height added to the bank by a single landing flake, in pixels
+const FLAKE_VOLUME: f32 = 0.6;
+
+/// User tunable snow parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Snow options")]
+#[group(id = "snow-options")]
+pub struct Config {
+    #[arg(
+        id = "snow-count",
+        long = "snow-count",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_COUNT,
+        help = "Number of snowflakes"
+    )]
+    pub count: usize,
+
+    #[arg(
+        id = "wind",
+        long = "wind",
+        value_name = "WIND",
+        default_value_t = DEFAULT_WIND,
+        help = "Strength of the wind pushing the snowflakes"
+    )]
+    pub wind: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_COUNT,
+            wind: DEFAULT_WIND,
+        }
+    }
+}
+
+/// A single snowflake
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Flake {
+    x: f32,
+    y: f32,
+    /// Depth layer of the flake, higher layers are nearer
+    layer: usize,
+}
+
+/// Snowflakes drifting down onto a snow bank
+pub struct Snow {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Snow parameters
+    config: Config,
+    /// Flakes currently falling
+    flakes: Vec<Flake>,
+    /// Height of the snow bank at every column in pixels
+    bank: Vec<f32>,
+    /// Noise driving the wind gusts
+    noise: Noise,
+    /// Seconds the snow has been falling, drives the wind
+    elapsed: f32,
+    /// Random number generator used to place flakes
+    rng: fastrand::Rng,
+}
+
+impl Snow {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        let mut rng = fastrand::Rng::new();
+        let flakes = (0..config.count)
+            .map(|_| Flake {
+                x: rng.f32() * width as f32,
+                y: rng.f32() * height as f32,
+                layer: rng.usize(0..LAYERS),
+            })
+            .collect();
+
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            flakes,
+            bank: vec![0.0; width],
+            noise: Noise::new(rng.u64(..)),
+            elapsed: 0.0,
+            rng,
+        }
+    }
+
+    /// Returns the horizontal wind speed at a given height and time.
+    fn wind(&self, y: f32, time: f32) -> f32 {
+        let gust = self.noise.sample(time * 0.2, y * 0.01, 0.5);
+        self.config.wind * ((time * 0.3).sin() * 0.5 + gust)
+    }
+
+    /// Drops a flake at a random position above the screen.
+    fn respawn(&mut self, index: usize) {
+        self.flakes[index] = Flake {
+            x: self.rng.f32() * self.base.width as f32,
+            y: -self.rng.f32() * 10.0,
+            layer: self.rng.usize(0..LAYERS),
+        };
+    }
+
+    /// Moves every flake, landing the nearest ones on the snow bank.
+    fn advance(&mut self, dt: f32, time: f32) {
+        let (width, height) = (self.base.width as f32, self.base.height as f32);
+        let max_bank = height * MAX_BANK;
+
+        for i in 0..self.flakes.len() {
+            let flake = self.flakes[i];
+            let depth = (flake.layer + 1) as f32;
+            let wind = self.wind(flake.y, time) * depth / LAYERS as f32;
+            let x = (flake.x + wind * dt).rem_euclid(width);
+            let y = flake.y + FALL_SPEED * height * depth * dt;
+            self.flakes[i] = Flake { x, y, ..flake };
+
+            let column = (x as usize).min(self.base.width - 1);
+            let ground = height - self.bank[column];
+            if flake.layer == LAYERS - 1 && y >= ground {
+                self.land(column, max_bank);
+                self.respawn(i);
+            } else if y >= height {
+                self.respawn(i);
+            }
+        }
+    }
+
+    /// Grows the snow bank where a flake lands, spilling onto lower neighbors.
+    fn land(&mut self, column: usize, max_bank: f32) {
+        let mut target = column;
+        // Snow slides down the bank until it rests on a column no lower than its neighbors
+        for neighbor in [
+            column.saturating_sub(1),
+            (column + 1).min(self.bank.len() - 1),
+        ] {
+            if self.bank[neighbor] + 1.0 < self.bank[target] {
+                target = neighbor;
+            }
+        }
+        self.bank[target] = (self.bank[target] + FLAKE_VOLUME).min(max_bank);
+    }
+}
+
+impl DemoEffect for Snow {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        self.advance(dt, self.elapsed);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        // Night sky getting lighter toward the horizon
+        for (y, row) in buffer.chunks_exact_mut(self.base.width).enumerate() {
+            let t = y as f32 / self.base.height as f32;
+            row.fill(rgb(
+                (10.0 + 30.0 * t) as u8,
+                (15.0 + 35.0 * t) as u8,
+                (40.0 + 50.0 * t) as u8,
+            ));
+        }
+
+        for (x, &bank) in self.bank.iter().enumerate() {
+            let top = self.base.height as i32 - bank as i32;
+            for y in top..self.base.height as i32 {
+                self.base.plot(buffer, x as i32, y, rgb(235, 240, 255));
+            }
+        }
+
+        for flake in &self.flakes {
+            let brightness = 140 + 115 * flake.layer as u8 / (LAYERS - 1) as u8;
+            let color = rgb(brightness, brightness, brightness);
+            let size = flake.layer as i32;
+            for dy in 0..size.max(1) {
+                for dx in 0..size.max(1) {
+                    self.base
+                        .plot(buffer, flake.x as i32 + dx, flake.y as i32 + dy, color);
+                }
+            }
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.bank.resize(width, 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_snow() -> Snow {
+        let config = Config {
+            count: 0,
+            wind: 0.0,
+        };
+        let mut snow = Snow::new(100, 100, config);
+        snow.rng = fastrand::Rng::with_seed(5);
+        snow
+    }
+
+    #[test]
+    fn nearer_flakes_fall_faster() {
+        let mut snow = create_snow();
+        snow.flakes = vec![
+            Flake {
+                x: 10.0,
+                y: 10.0,
+                layer: 0,
+            },
+            Flake {
+                x: 20.0,
+                y: 10.0,
+                layer: LAYERS - 1,
+            },
+        ];
+        snow.advance(0.1, 0.0);
+        assert!(snow.flakes[1].y > snow.flakes[0].y);
+    }
+
+    #[test]
+    fn near_flakes_land_on_the_bank() {
+        let mut snow = create_snow();
+        snow.flakes = vec![Flake {
+            x: 50.0,
+            y: 99.5,
+            layer: LAYERS - 1,
+        }];
+        snow.advance(0.1, 0.0);
+        assert!(snow.bank[49..=51].iter().sum::<f32>() > 0.0);
+        assert!(
+            snow.flakes[0].y < 0.0,
+            "Landed flakes respawn above the screen"
+        );
+    }
+
+    #[test]
+    fn far_flakes_fall_behind_the_bank() {
+        let mut snow = create_snow();
+        snow.flakes = vec![Flake {
+            x: 50.0,
+            y: 99.5,
+            layer: 0,
+        }];
+        snow.advance(0.5, 0.0);
+        assert!(snow.bank.iter().all(|&h| h == 0.0));
+    }
+
+    #[test]
+    fn bank_height_is_capped() {
+        let mut snow = create_snow();
+        for _ in 0..1000 {
+            snow.land(50, 20.0);
+        }
+        assert!(snow.bank.iter().all(|&h| h <= 20.0));
+    }
+
+    #[test]
+    fn snow_slides_off_steep_piles() {
+        let mut snow = create_snow();
+        snow.bank[50] = 5.0;
+        snow.land(50, 20.0);
+        assert_eq!(snow.bank[50], 5.0);
+        assert_eq!(snow.bank[49], FLAKE_VOLUME);
+    }
+}
@@ -0,0 +1,265 @@
+//! An animated spirograph tracing hypotrochoids.
+//!
+//! A small circle of radius `r` rolls inside a large circle of radius `R` while a pen
+//! attached at distance `d` from the small circle's center draws its path. The pen advances
+//! along the curve every frame and the traced line is stamped into a persistent trail
+//! buffer that slowly fades, so the oldest loops of the pattern vanish as new ones appear.
+//!
+//! `Up`/`Down` adjust the radius ratio `r / R` and `Left`/`Right` the pen offset `d / r`.
+use crate::common::{DemoBase, DemoEffect, InputEvent};
+use crate::effects::Settings;
+use crate::palette::Palette;
+use clap::Args;
+use minifb::Key;
+
+/// Default ratio between the rolling and the fixed circle radii
+const DEFAULT_RATIO: f32 = 0.37;
+
+/// Default pen distance from the rolling circle center, relative to its radius
+const DEFAULT_PEN_OFFSET: f32 = 0.8;
+
+/// Amount the ratio or pen offset changes per key press
+const PARAMETER_STEP: f32 = 0.01;
+
+/// Angle travelled by the rolling circle per second
+const PEN_SPEED: f32 = 6.0;
+
+/// Largest angle between two consecutive traced points, keeps the curve smooth
+const MAX_STEP: f32 = 0.02;
+
+/// Fraction of the trail brightness lost per second
+const FADE_RATE: f32 = 0.25;
+
+/// User tunable spirograph parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Spirograph options")]
+#[group(id = "spirograph-options")]
+pub struct Config {
+    #[arg(
+        id = "radius-ratio",
+        long = "radius-ratio",
+        value_name = "RATIO",
+        default_value_t = DEFAULT_RATIO,
+        value_parser = parse_ratio,
+        help = "Ratio between the rolling and fixed circle radii, from 0 to 1"
+    )]
+    pub ratio: f32,
+
+    #[arg(
+        id = "pen-offset",
+        long = "pen-offset",
+        value_name = "OFFSET",
+        default_value_t = DEFAULT_PEN_OFFSET,
+        value_parser = parse_pen_offset,
+        help = "Pen distance from the rolling circle center relative to its radius, 0 or more"
+    )]
+    pub pen_offset: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            ratio: DEFAULT_RATIO,
+            pen_offset: DEFAULT_PEN_OFFSET,
+        }
+    }
+}
+
+/// Parses a radius ratio from 0 to 1.
+fn parse_ratio(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err(format!("invalid ratio '{spec}', expected 0 to 1")),
+    }
+}
+
+/// Parses a pen offset, which must not be negative.
+fn parse_pen_offset(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(offset) if offset >= 0.0 && offset.is_finite() => Ok(offset),
+        _ => Err(format!("invalid offset '{spec}', expected 0 or more")),
+    }
+}
+
+/// A spirograph pen leaving a fading trail
+pub struct Spirograph {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the trail
+    palette: Palette,
+    /// Spirograph parameters, adjustable at runtime
+    config: Config,
+    /// Brightness of the trail at every pixel in [0,1]
+    trail: Vec<f32>,
+    /// Current angle of the rolling circle around the fixed one
+    angle: f32,
+}
+
+impl Spirograph {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            config,
+            trail: vec![0.0; base.pixel_count()],
+            angle: 0.0,
+        }
+    }
+
+    /// Returns the pen position on screen at the given angle.
+    fn pen(&self, angle: f32) -> (f32, f32) {
+        let (center_x, center_y) = self.base.center();
+        // Scale the fixed circle so the curve always fits on screen
+        let outer = center_x.min(center_y) * 0.9;
+        let ratio = self.config.ratio.clamp(PARAMETER_STEP, 1.0);
+        let inner = outer * ratio;
+        let pen = inner * self.config.pen_offset;
+        let fit = (outer / ((outer - inner).abs() + pen.abs()).max(f32::EPSILON)).min(1.0);
+
+        let rolling = (outer - inner) / inner * angle;
+        (
+            center_x + ((outer - inner) * angle.cos() + pen * rolling.cos()) * fit,
+            center_y + ((outer - inner) * angle.sin() - pen * rolling.sin()) * fit,
+        )
+    }
+
+    /// Moves the pen forward and stamps the traced path into the trail.
+    fn trace(&mut self, dt: f32) {
+        let travel = PEN_SPEED * dt;
+        let steps = (travel / MAX_STEP).ceil() as usize;
+        for _ in 0..steps {
+            let from = self.pen(self.angle);
+            self.angle += travel / steps as f32;
+            let to = self.pen(self.angle);
+            self.stamp(from, to);
+        }
+    }
+
+    /// Marks the pixels of a line at full brightness in the trail buffer.
+    fn stamp(&mut self, from: (f32, f32), to: (f32, f32)) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+            if x >= 0.0 && y >= 0.0 {
+                let (x, y) = (x as usize, y as usize);
+                if x < self.base.width && y < self.base.height {
+                    self.trail[y * self.base.width + x] = 1.0;
+                }
+            }
+        }
+    }
+
+    /// Changes the curve parameters and starts a fresh drawing.
+    fn adjust(&mut self, ratio: f32, pen_offset: f32) {
+        self.config.ratio = (self.config.ratio + ratio).clamp(PARAMETER_STEP, 1.0);
+        self.config.pen_offset = (self.config.pen_offset + pen_offset).max(0.0);
+        self.trail.fill(0.0);
+    }
+}
+
+impl DemoEffect for Spirograph {
+    fn update(&mut self, dt: f32) {
+        let fade = (1.0 - FADE_RATE * dt).clamp(0.0, 1.0);
+        self.trail.iter_mut().for_each(|v| *v *= fade);
+        self.trace(dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer
+            .iter_mut()
+            .zip(&self.trail)
+            .for_each(|(pixel, &v)| *pixel = self.palette.color(v));
+    }
+
+    /// Handles the interactive spirograph controls.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Up) => self.adjust(PARAMETER_STEP, 0.0),
+            InputEvent::Key(Key::Down) => self.adjust(-PARAMETER_STEP, 0.0),
+            InputEvent::Key(Key::Right) => self.adjust(0.0, PARAMETER_STEP),
+            InputEvent::Key(Key::Left) => self.adjust(0.0, -PARAMETER_STEP),
+            _ => {}
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.spirograph.ratio = self.config.ratio;
+        settings.spirograph.pen_offset = self.config.pen_offset;
+    }
+
+    /// Clears the accumulated picture, which no longer lines up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.trail = vec![0.0; self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_spirograph() -> Spirograph {
+        Spirograph::new(100, 100, Palette::Rainbow, Config::default())
+    }
+
+    #[test]
+    fn curve_stays_on_screen() {
+        let spirograph = create_spirograph();
+        for step in 0..1000 {
+            let (x, y) = spirograph.pen(step as f32 * 0.1);
+            assert!((0.0..100.0).contains(&x) && (0.0..100.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn pen_starts_on_the_right_of_center() {
+        let spirograph = create_spirograph();
+        let (x, y) = spirograph.pen(0.0);
+        assert!(x > 50.0);
+        assert_eq!(y, 50.0);
+    }
+
+    #[test]
+    fn tracing_leaves_a_trail() {
+        let mut spirograph = create_spirograph();
+        spirograph.trace(0.5);
+        assert!(spirograph.angle > 0.0);
+        assert!(spirograph.trail.contains(&1.0));
+    }
+
+    #[test]
+    fn keys_adjust_parameters_and_clear_trail() {
+        let mut spirograph = create_spirograph();
+        spirograph.trace(0.5);
+        spirograph.handle_input(InputEvent::Key(Key::Up));
+        spirograph.handle_input(InputEvent::Key(Key::Left));
+        assert_eq!(spirograph.config.ratio, DEFAULT_RATIO + PARAMETER_STEP);
+        assert_eq!(
+            spirograph.config.pen_offset,
+            DEFAULT_PEN_OFFSET - PARAMETER_STEP
+        );
+        assert!(spirograph.trail.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn ratio_never_reaches_zero() {
+        let mut spirograph = create_spirograph();
+        for _ in 0..100 {
+            spirograph.handle_input(InputEvent::Key(Key::Down));
+        }
+        assert_eq!(spirograph.config.ratio, PARAMETER_STEP);
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        let mut spirograph = create_spirograph();
+        spirograph.handle_input(InputEvent::Key(Key::Up));
+        spirograph.handle_input(InputEvent::Key(Key::Right));
+        let mut settings = Settings::default();
+        spirograph.save_settings(&mut settings);
+        assert_eq!(settings.spirograph, spirograph.config);
+    }
+}
@@ -0,0 +1,201 @@
+//! A 3D starfield flying toward the camera.
+//!
+//! Stars are scattered through a unit volume in front of the viewer and move toward the
+//! camera every frame. Each star is projected onto the screen with a perspective divide,
+//! so stars accelerate and brighten as they get closer before being respawned far away.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use clap::Args;
+
+/// Default number of stars in the field
+const DEFAULT_COUNT: usize = 500;
+
+/// Default distance travelled by the camera per second, in units of the field depth
+const DEFAULT_SPEED: f32 = 0.5;
+
+/// Closest depth a star can reach before it is respawned
+const NEAR_PLANE: f32 = 0.01;
+
+/// User tunable starfield parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Starfield options")]
+#[group(id = "starfield-options")]
+pub struct Config {
+    #[arg(
+        id = "star-count",
+        long = "star-count",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_COUNT,
+        help = "Number of stars in the starfield"
+    )]
+    pub count: usize,
+
+    #[arg(
+        id = "star-speed",
+        long = "star-speed",
+        value_name = "SPEED",
+        default_value_t = DEFAULT_SPEED,
+        help = "Speed at which the stars fly toward the camera"
+    )]
+    pub speed: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_COUNT,
+            speed: DEFAULT_SPEED,
+        }
+    }
+}
+
+/// A single star in camera space
+#[derive(Debug, Clone, Copy)]
+struct Star {
+    /// Horizontal position in [-1, 1]
+    x: f32,
+    /// Vertical position in [-1, 1]
+    y: f32,
+    /// Depth in (0, 1], where 1 is the far end of the field
+    z: f32,
+}
+
+/// A perspective-projected starfield
+pub struct Starfield {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Starfield parameters
+    config: Config,
+    /// Stars currently in flight
+    stars: Vec<Star>,
+    /// Random number generator used to place stars
+    rng: fastrand::Rng,
+}
+
+impl Starfield {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        let mut rng = fastrand::Rng::new();
+        let stars = (0..config.count)
+            .map(|_| {
+                let z = rng.f32().max(NEAR_PLANE);
+                Self::spawn(&mut rng, z)
+            })
+            .collect();
+
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+            stars,
+            rng,
+        }
+    }
+
+    /// Creates a star at a random position on the plane at depth `z`.
+    fn spawn(rng: &mut fastrand::Rng, z: f32) -> Star {
+        Star {
+            x: rng.f32() * 2.0 - 1.0,
+            y: rng.f32() * 2.0 - 1.0,
+            z,
+        }
+    }
+
+    /// Projects a star onto the screen, returning `None` if it falls outside the display.
+    fn project(&self, star: &Star) -> Option<(usize, usize)> {
+        let (center_x, center_y) = self.base.center();
+        let sx = center_x + star.x / star.z * center_x;
+        let sy = center_y + star.y / star.z * center_y;
+
+        if sx < 0.0 || sy < 0.0 {
+            return None;
+        }
+        let (sx, sy) = (sx as usize, sy as usize);
+        (sx < self.base.width && sy < self.base.height).then_some((sx, sy))
+    }
+
+    /// Moves every star toward the camera, respawning those that pass the near plane or
+    /// leave the screen.
+    fn advance(&mut self, dt: f32) {
+        for i in 0..self.stars.len() {
+            self.stars[i].z -= self.config.speed * dt;
+            if self.stars[i].z <= NEAR_PLANE || self.project(&self.stars[i]).is_none() {
+                self.stars[i] = Self::spawn(&mut self.rng, 1.0);
+            }
+        }
+    }
+}
+
+impl DemoEffect for Starfield {
+    fn update(&mut self, dt: f32) {
+        self.advance(dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+        for star in &self.stars {
+            if let Some((x, y)) = self.project(star) {
+                // Stars get brighter as they approach the camera
+                let brightness = ((1.0 - star.z) * 255.0) as u8;
+                buffer[y * self.base.width + x] = rgb(brightness, brightness, brightness);
+            }
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_starfield() -> Starfield {
+        let mut starfield = Starfield::new(320, 200, Config::default());
+        starfield.rng = fastrand::Rng::with_seed(7);
+        starfield
+    }
+
+    #[test]
+    fn starfield_contains_configured_number_of_stars() {
+        let config = Config {
+            count: 42,
+            ..Config::default()
+        };
+        let starfield = Starfield::new(320, 200, config);
+        assert_eq!(starfield.stars.len(), 42);
+    }
+
+    #[test]
+    fn stars_move_toward_camera() {
+        let mut starfield = create_starfield();
+        starfield.stars = vec![Star {
+            x: 0.0,
+            y: 0.0,
+            z: 0.5,
+        }];
+        starfield.advance(0.1);
+        assert!(starfield.stars[0].z < 0.5, "Star depth should decrease");
+    }
+
+    #[test]
+    fn stars_respawn_at_far_plane_after_passing_camera() {
+        let mut starfield = create_starfield();
+        starfield.stars = vec![Star {
+            x: 0.0,
+            y: 0.0,
+            z: NEAR_PLANE,
+        }];
+        starfield.advance(0.1);
+        assert_eq!(starfield.stars[0].z, 1.0, "Star should respawn far away");
+    }
+
+    #[test]
+    fn center_star_projects_to_screen_center() {
+        let starfield = create_starfield();
+        let star = Star {
+            x: 0.0,
+            y: 0.0,
+            z: 0.5,
+        };
+        assert_eq!(starfield.project(&star), Some((160, 100)));
+    }
+}
@@ -0,0 +1,306 @@
+//! Old television static with channel glitches.
+//!
+//! Every frame starts from fresh white noise, or from another effect mixed with noise when
+//! used as an overlay. A darker bar rolls slowly down the screen like a badly synced
+//! signal, and every so often a band of scanlines is torn sideways or has its red channel
+//! shifted out of alignment with the others.
+use crate::common::{rgb, DemoBase, DemoEffect, InputEvent, Layer};
+use crate::effects::Settings;
+use clap::Args;
+
+/// Default strength of the noise mixed over the wrapped effect
+const DEFAULT_AMOUNT: f32 = 0.35;
+
+/// Chance per frame that a band of scanlines is torn sideways
+const TEAR_CHANCE: f32 = 0.08;
+
+/// Chance per frame that a band of scanlines has its colors misaligned
+const GLITCH_CHANCE: f32 = 0.05;
+
+/// Speed of the rolling bar in screen heights per second
+const ROLL_SPEED: f32 = 0.15;
+
+/// Height of the rolling bar as a fraction of the screen height
+const ROLL_HEIGHT: f32 = 0.12;
+
+/// User tunable static parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Static options")]
+#[group(id = "static-noise-options")]
+pub struct Config {
+    #[arg(
+        id = "static-overlay",
+        long = "static-overlay",
+        help = "Overlay the static on top of the plasma instead of showing it alone"
+    )]
+    pub overlay: bool,
+
+    #[arg(
+        id = "static-amount",
+        long = "static-amount",
+        value_name = "AMOUNT",
+        default_value_t = DEFAULT_AMOUNT,
+        help = "Strength of the static mixed over the plasma when used as an overlay"
+    )]
+    pub amount: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            overlay: false,
+            amount: DEFAULT_AMOUNT,
+        }
+    }
+}
+
+/// A horizontal band of scanlines affected by a glitch
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Band {
+    /// First scanline of the band
+    top: usize,
+    /// Number of scanlines in the band
+    height: usize,
+    /// Horizontal displacement applied inside the band
+    shift: i32,
+}
+
+/// White noise with tearing, a rolling bar, and color glitches
+pub struct StaticNoise {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Effect shown through the static, if any
+    inner: Option<Layer>,
+    /// Strength of the noise mixed over the inner effect
+    amount: f32,
+    /// Seed of the noise drawn in the current frame
+    seed: u64,
+    /// Band torn in the current frame, if any
+    tear_band: Option<Band>,
+    /// Band with misaligned colors in the current frame, if any
+    glitch_band: Option<Band>,
+    /// Random number generator driving the noise and glitches
+    rng: fastrand::Rng,
+}
+
+impl StaticNoise {
+    pub fn new(
+        width: usize,
+        height: usize,
+        inner: Option<Box<dyn DemoEffect>>,
+        config: Config,
+    ) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            inner: inner.map(|effect| Layer::new(base, effect)),
+            amount: config.amount.clamp(0.0, 1.0),
+            seed: 0,
+            tear_band: None,
+            glitch_band: None,
+            rng: fastrand::Rng::new(),
+        }
+    }
+
+    /// Picks a random band of scanlines to glitch with probability `chance`.
+    fn random_band(&mut self, chance: f32) -> Option<Band> {
+        if self.base.height == 0 || self.rng.f32() >= chance {
+            return None;
+        }
+        let height = self.rng.usize(1..=(self.base.height / 8).max(1));
+        let max_shift = (self.base.width / 10).max(1) as i32;
+        Some(Band {
+            top: self.rng.usize(0..self.base.height),
+            height,
+            shift: self.rng.i32(-max_shift..=max_shift),
+        })
+    }
+
+    /// Returns the brightness multiplier of scanline `y` due to the rolling bar.
+    fn roll(&self, y: usize, time: f32) -> f32 {
+        let height = self.base.height as f32;
+        let bar = (time * ROLL_SPEED).fract() * height;
+        let distance = (y as f32 - bar).rem_euclid(height);
+        if distance < height * ROLL_HEIGHT {
+            0.6
+        } else {
+            1.0
+        }
+    }
+
+    /// Shifts every pixel of a band horizontally, wrapping around the scanline.
+    fn tear(&self, buffer: &mut [u32], band: Band) {
+        let width = self.base.width;
+        let bottom = (band.top + band.height).min(self.base.height);
+        for row in buffer[band.top * width..bottom * width].chunks_exact_mut(width) {
+            let shift = band.shift.rem_euclid(width as i32) as usize;
+            row.rotate_right(shift);
+        }
+    }
+
+    /// Shifts the red channel of a band out of alignment with green and blue.
+    fn misalign(&self, buffer: &mut [u32], source: &[u32], band: Band) {
+        let width = self.base.width;
+        let bottom = (band.top + band.height).min(self.base.height);
+        for y in band.top..bottom {
+            for x in 0..width {
+                let sx = (x as i32 + band.shift).clamp(0, width as i32 - 1) as usize;
+                let red = source[y * width + sx] & 0x00ff_0000;
+                let pixel = &mut buffer[y * width + x];
+                *pixel = (*pixel & !0x00ff_0000) | red;
+            }
+        }
+    }
+}
+
+impl DemoEffect for StaticNoise {
+    /// Rolls the noise and the glitches of the next frame.
+    fn update(&mut self, dt: f32) {
+        if let Some(layer) = &mut self.inner {
+            layer.update(dt);
+        }
+        self.seed = self.rng.u64(..);
+        self.tear_band = self.random_band(TEAR_CHANCE);
+        self.glitch_band = self.random_band(GLITCH_CHANCE);
+    }
+
+    fn interpolate(&mut self, alpha: f32) {
+        if let Some(layer) = &mut self.inner {
+            layer.interpolate(alpha);
+        }
+    }
+
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        match &self.inner {
+            Some(layer) => buffer.copy_from_slice(&layer.render(time)),
+            None => buffer.fill(rgb(0, 0, 0)),
+        }
+        // Pure static replaces the picture entirely
+        let amount = if self.inner.is_some() {
+            self.amount
+        } else {
+            1.0
+        };
+
+        let width = self.base.width;
+        let mut rng = fastrand::Rng::with_seed(self.seed);
+        for (y, row) in buffer.chunks_exact_mut(width).enumerate() {
+            let roll = self.roll(y, time);
+            for pixel in row.iter_mut() {
+                let noise = rng.u8(..) as f32;
+                let channel = |shift: u32| {
+                    let c = ((*pixel >> shift) & 0xff) as f32;
+                    ((c + (noise - c) * amount) * roll) as u8
+                };
+                *pixel = rgb(channel(16), channel(8), channel(0));
+            }
+        }
+
+        if let Some(band) = self.tear_band {
+            self.tear(buffer, band);
+        }
+        if let Some(band) = self.glitch_band {
+            let source = buffer.to_vec();
+            self.misalign(buffer, &source, band);
+        }
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        if let Some(layer) = &mut self.inner {
+            layer.handle_input(event);
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        if let Some(layer) = &mut self.inner {
+            layer.resize(self.base);
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        if let Some(layer) = &self.inner {
+            layer.save_settings(settings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inner effect filling the screen with a single color
+    struct Solid(u32);
+
+    impl DemoEffect for Solid {
+        fn draw(&self, buffer: &mut [u32], _time: f32) {
+            buffer.fill(self.0);
+        }
+    }
+
+    fn create_static(inner: Option<Box<dyn DemoEffect>>, amount: f32) -> StaticNoise {
+        let config = Config {
+            overlay: inner.is_some(),
+            amount,
+        };
+        let mut noise = StaticNoise::new(16, 16, inner, config);
+        noise.rng = fastrand::Rng::with_seed(3);
+        noise
+    }
+
+    #[test]
+    fn standalone_static_is_noisy() {
+        let mut noise = create_static(None, DEFAULT_AMOUNT);
+        let mut buffer = vec![0; 16 * 16];
+        noise.update(0.0);
+        noise.draw(&mut buffer, 0.0);
+        assert!(buffer.iter().any(|&p| p != buffer[0]));
+    }
+
+    #[test]
+    fn overlay_without_noise_shows_inner_effect() {
+        let noise = create_static(Some(Box::new(Solid(rgb(200, 100, 50)))), 0.0);
+        let mut buffer = vec![0; 16 * 16];
+        // Pick a time where the rolling bar is off the first scanline
+        noise.draw(&mut buffer, 3.0);
+        assert_eq!(buffer[0], rgb(200, 100, 50));
+    }
+
+    #[test]
+    fn rolling_bar_darkens_scanlines() {
+        let noise = create_static(None, DEFAULT_AMOUNT);
+        let darkened = (0..16).filter(|&y| noise.roll(y, 0.0) < 1.0).count();
+        assert!(darkened > 0 && darkened < 16);
+    }
+
+    #[test]
+    fn tear_shifts_band_with_wraparound() {
+        let noise = create_static(None, DEFAULT_AMOUNT);
+        let mut buffer: Vec<u32> = (0..16 * 16).collect();
+        let band = Band {
+            top: 2,
+            height: 1,
+            shift: 3,
+        };
+        noise.tear(&mut buffer, band);
+        assert_eq!(buffer[2 * 16 + 3], 2 * 16);
+        assert_eq!(buffer[2 * 16], 2 * 16 + 13);
+        assert_eq!(buffer[0], 0, "Rows outside the band are untouched");
+    }
+
+    #[test]
+    fn misalign_only_moves_red_channel() {
+        let noise = create_static(None, DEFAULT_AMOUNT);
+        let mut source = vec![rgb(0, 0, 0); 16 * 16];
+        source[5] = rgb(255, 255, 255);
+        let mut buffer = source.clone();
+        let band = Band {
+            top: 0,
+            height: 1,
+            shift: 1,
+        };
+        noise.misalign(&mut buffer, &source, band);
+        assert_eq!(buffer[4], rgb(255, 0, 0));
+        assert_eq!(buffer[5], rgb(0, 255, 255));
+    }
+}
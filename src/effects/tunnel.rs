@@ -0,0 +1,226 @@
+//! A texture-mapped tunnel effect.
+//!
+//! The distance and angle of every pixel relative to the screen center are computed once
+//! when the tunnel is created and stored in lookup tables. Each frame only has to offset
+//! those tables by the current time to find the texel to display, which makes the camera
+//! appear to fly down an endlessly rotating tube.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect, ShaderSource};
+use clap::{Args, ValueEnum};
+use std::f32::consts::PI;
+
+/// Width and height of the square procedural texture, must be a power of two
+const TEXTURE_SIZE: usize = 256;
+
+/// Controls how quickly the texture repeats along the tunnel depth
+const DEPTH_RATIO: f32 = 32.0;
+
+/// Texture lookups per second along the tunnel depth
+const FORWARD_SPEED: f32 = 64.0;
+
+/// Texture lookups per second around the tunnel circumference
+const ROTATION_SPEED: f32 = 16.0;
+
+/// Procedural textures that can be mapped onto the tunnel walls
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Texture {
+    Xor,
+    Checker,
+    Bricks,
+}
+
+/// User tunable tunnel parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Tunnel options")]
+#[group(id = "tunnel-options")]
+pub struct Config {
+    #[arg(
+        id = "tunnel-texture",
+        long = "tunnel-texture",
+        value_name = "TEXTURE",
+        value_enum,
+        default_value_t = Texture::Xor,
+        help = "Texture mapped onto the tunnel walls"
+    )]
+    pub texture: Texture,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            texture: Texture::Xor,
+        }
+    }
+}
+
+/// A tunnel effect driven by precomputed distance and angle tables
+pub struct Tunnel {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Texture row sampled by each pixel, derived from its distance to the center
+    distances: Vec<usize>,
+    /// Texture column sampled by each pixel, derived from its angle around the center
+    angles: Vec<usize>,
+    /// Brightness of each pixel, fading toward the far end of the tunnel
+    shades: Vec<u8>,
+    /// Kind of texture mapped onto the tunnel walls
+    kind: Texture,
+    /// Grayscale texture mapped onto the tunnel walls
+    texture: Vec<u8>,
+}
+
+impl Tunnel {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        let mut tunnel = Self {
+            base: DemoBase::new(width, height),
+            distances: Vec::new(),
+            angles: Vec::new(),
+            shades: Vec::new(),
+            texture: Self::generate_texture(&config.texture),
+            kind: config.texture,
+        };
+        tunnel.resize(width, height);
+        tunnel
+    }
+
+    /// Generates a grayscale texture of `TEXTURE_SIZE` x `TEXTURE_SIZE` texels.
+    fn generate_texture(texture: &Texture) -> Vec<u8> {
+        let mut texels = vec![0; TEXTURE_SIZE * TEXTURE_SIZE];
+        for (i, texel) in texels.iter_mut().enumerate() {
+            let (x, y) = (i % TEXTURE_SIZE, i / TEXTURE_SIZE);
+            *texel = match texture {
+                Texture::Xor => (x ^ y) as u8,
+                Texture::Checker => {
+                    if ((x / 32) + (y / 32)) % 2 == 0 {
+                        255
+                    } else {
+                        64
+                    }
+                }
+                Texture::Bricks => {
+                    // Every other row of bricks is shifted by half a brick
+                    let row = y / 16;
+                    let offset = if row % 2 == 0 { 0 } else { 16 };
+                    let mortar = y % 16 == 0 || (x + offset) % 32 == 0;
+                    if mortar {
+                        200
+                    } else {
+                        96
+                    }
+                }
+            };
+        }
+        texels
+    }
+}
+
+impl DemoEffect for Tunnel {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let shift_depth = (time * FORWARD_SPEED) as usize;
+        let shift_angle = (time * ROTATION_SPEED) as usize;
+
+        let width = self.base.width;
+        for_each_row(buffer, width, |y, row| {
+            for (i, pixel) in (y * width..).zip(row) {
+                let u = (self.distances[i] + shift_depth) % TEXTURE_SIZE;
+                let v = (self.angles[i] + shift_angle) % TEXTURE_SIZE;
+                let texel = self.texture[u * TEXTURE_SIZE + v] as u32;
+                let level = (texel * self.shades[i] as u32 / 255) as u8;
+                // Tint the texture blue so the tunnel does not look flat gray
+                *pixel = rgb(level / 2, level / 2 + level / 4, level);
+            }
+        });
+    }
+
+    /// Returns the shader sampling the texture the way `draw` does.
+    fn shader_source(&self, _time: f32) -> Option<ShaderSource> {
+        let textures = Texture::value_variants();
+        let texture = textures.iter().position(|texture| *texture == self.kind)?;
+        Some(ShaderSource {
+            code: include_str!("tunnel.wgsl"),
+            params: vec![texture as f32, DEPTH_RATIO, FORWARD_SPEED, ROTATION_SPEED],
+            palette: Vec::new(),
+        })
+    }
+
+    /// Precomputes the texture coordinates and shading of every pixel.
+    fn resize(&mut self, width: usize, height: usize) {
+        let base = DemoBase::new(width, height);
+        let (center_x, center_y) = base.center();
+        let size = TEXTURE_SIZE as f32;
+
+        let mut distances = Vec::with_capacity(base.pixel_count());
+        let mut angles = Vec::with_capacity(base.pixel_count());
+        let mut shades = Vec::with_capacity(base.pixel_count());
+        for y in 0..height {
+            for x in 0..width {
+                let px = x as f32 - center_x;
+                let py = y as f32 - center_y;
+                // Offset by a fraction of a pixel to avoid dividing by zero at the center
+                let radius = (px * px + py * py).sqrt().max(0.5);
+                let depth = DEPTH_RATIO * size / radius;
+                // Map the angle from [-PI, PI] to [0, TEXTURE_SIZE)
+                let angle = 0.5 * size * (py.atan2(px) / PI + 1.0);
+
+                distances.push(depth as usize % TEXTURE_SIZE);
+                angles.push(angle as usize % TEXTURE_SIZE);
+                shades.push(
+                    (radius / center_x.max(center_y))
+                        .min(1.0)
+                        .mul_add(255.0, 0.5) as u8,
+                );
+            }
+        }
+
+        self.base = base;
+        self.distances = distances;
+        self.angles = angles;
+        self.shades = shades;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_tables_cover_every_pixel() {
+        let tunnel = Tunnel::new(64, 48, Config::default());
+        assert_eq!(tunnel.distances.len(), 64 * 48);
+        assert_eq!(tunnel.angles.len(), 64 * 48);
+        assert_eq!(tunnel.shades.len(), 64 * 48);
+    }
+
+    #[test]
+    fn lookup_tables_stay_within_texture_bounds() {
+        let tunnel = Tunnel::new(64, 48, Config::default());
+        assert!(tunnel.distances.iter().all(|&d| d < TEXTURE_SIZE));
+        assert!(tunnel.angles.iter().all(|&a| a < TEXTURE_SIZE));
+    }
+
+    #[test]
+    fn shading_is_darker_near_center() {
+        let tunnel = Tunnel::new(64, 48, Config::default());
+        let center = tunnel.shades[24 * 64 + 32];
+        let corner = tunnel.shades[0];
+        assert!(
+            center < corner,
+            "The far end of the tunnel should be darker"
+        );
+    }
+
+    #[test]
+    fn xor_texture_matches_bit_pattern() {
+        let texels = Tunnel::generate_texture(&Texture::Xor);
+        assert_eq!(texels[3 * TEXTURE_SIZE + 5], 3 ^ 5);
+    }
+
+    #[test]
+    fn each_texture_produces_distinct_texels() {
+        let xor = Tunnel::generate_texture(&Texture::Xor);
+        let checker = Tunnel::generate_texture(&Texture::Checker);
+        let bricks = Tunnel::generate_texture(&Texture::Bricks);
+        assert_ne!(xor, checker);
+        assert_ne!(checker, bricks);
+        assert_ne!(bricks, xor);
+    }
+}
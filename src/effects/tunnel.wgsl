@@ -0,0 +1,43 @@
+// Tunnel drawn on the GPU, the way `Tunnel::draw` samples its lookup tables on the CPU.
+//
+// Parameters: 0 the index of the texture in the order `Texture` declares them, 1 the
+// depth ratio, 2 the forward speed and 3 the rotation speed.
+
+// Width and height of the texture
+const SIZE: u32 = 256u;
+
+// Returns the brightness of the texel in column `x` and row `y` of the texture.
+fn texel(x: u32, y: u32) -> u32 {
+    switch u32(param(0u)) {
+        // Xor
+        case 0u: {
+            return x ^ y;
+        }
+        // Checker
+        case 1u: {
+            return select(64u, 255u, (x / 32u + y / 32u) % 2u == 0u);
+        }
+        // Bricks, every other row shifted by half a brick
+        default: {
+            let offset = (y / 16u) % 2u * 16u;
+            return select(96u, 200u, y % 16u == 0u || (x + offset) % 32u == 0u);
+        }
+    }
+}
+
+fn shade(pixel: vec2<f32>) -> vec3<f32> {
+    let center = frame.resolution * 0.5;
+    let p = pixel - center;
+    let size = f32(SIZE);
+    // Offset by a fraction of a pixel to avoid dividing by zero at the center
+    let radius = max(length(p), 0.5);
+    let depth = u32(param(1u) * size / radius);
+    let angle = u32(0.5 * size * (atan2(p.y, p.x) / PI + 1.0));
+
+    let u = (depth + u32(frame.time * param(2u))) % SIZE;
+    let v = (angle + u32(frame.time * param(3u))) % SIZE;
+    let light = u32(min(radius / max(center.x, center.y), 1.0) * 255.0 + 0.5);
+    let level = texel(v, u) * light / 255u;
+    // Tint the texture blue so the tunnel does not look flat gray
+    return vec3<f32>(vec3<u32>(level / 2u, level / 2u + level / 4u, level)) / 255.0;
+}
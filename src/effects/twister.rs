@@ -0,0 +1,195 @@
+//! The classic demoscene twister.
+//!
+//! A square column is twisted around its vertical axis. Every scanline picks a rotation angle
+//! from a few stacked sine waves, projects the four corners of the column cross section onto
+//! the screen, and fills the faces facing the viewer. Faces are shaded by how wide they
+//! appear, so faces turned toward the viewer are brighter than those seen edge-on.
+use crate::common::{for_each_row, rgb, DemoBase, DemoEffect};
+use clap::Args;
+use std::f32::consts::FRAC_PI_2;
+
+/// Default amount of twist applied per scanline
+const DEFAULT_FREQUENCY: f32 = 0.01;
+
+/// Default width of the column as a fraction of the screen width
+const DEFAULT_WIDTH: f32 = 0.3;
+
+/// Base color of each of the four faces of the column
+const FACE_COLORS: [(u8, u8, u8); 4] =
+    [(255, 64, 64), (64, 255, 96), (64, 128, 255), (255, 224, 64)];
+
+/// User tunable twister parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Twister options")]
+#[group(id = "twister-options")]
+pub struct Config {
+    #[arg(
+        id = "twist-frequency",
+        long = "twist-frequency",
+        value_name = "FREQUENCY",
+        default_value_t = DEFAULT_FREQUENCY,
+        help = "Amount of twist applied along the column"
+    )]
+    pub frequency: f32,
+
+    #[arg(
+        id = "twister-width",
+        long = "twister-width",
+        value_name = "WIDTH",
+        default_value_t = DEFAULT_WIDTH,
+        value_parser = parse_width,
+        help = "Width of the twister column as a fraction of the screen width, above 0 and up to 1"
+    )]
+    pub width: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            frequency: DEFAULT_FREQUENCY,
+            width: DEFAULT_WIDTH,
+        }
+    }
+}
+
+/// Parses a column width above 0 and up to 1.
+fn parse_width(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(width) if width > 0.0 && width <= 1.0 => Ok(width),
+        _ => Err(format!(
+            "invalid width '{spec}', expected above 0 and up to 1"
+        )),
+    }
+}
+
+/// A twisting column of shaded faces
+pub struct Twister {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Twister parameters
+    config: Config,
+}
+
+impl Twister {
+    pub fn new(width: usize, height: usize, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            config,
+        }
+    }
+
+    /// Returns the rotation angle of the column at the given scanline.
+    fn angle(&self, y: usize, time: f32) -> f32 {
+        let y = y as f32;
+        let twist = self.config.frequency * (time * 0.8).sin() * 2.0;
+        time + y * twist + (y * 0.013 + time * 1.3).sin() * 0.5
+    }
+
+    /// Returns the screen columns of the four corners of the cross section at `angle`.
+    fn corners(&self, angle: f32) -> [f32; 4] {
+        let (center_x, _) = self.base.center();
+        let radius = self.config.width * self.base.width as f32 * 0.5;
+        [0.0, 1.0, 2.0, 3.0].map(|k: f32| center_x + (angle + k * FRAC_PI_2).sin() * radius)
+    }
+}
+
+impl DemoEffect for Twister {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let (center_x, _) = self.base.center();
+        // Widest a single face can appear, reached when it faces the viewer
+        let face_width = (self.config.width * center_x * std::f32::consts::SQRT_2).max(1.0);
+
+        for_each_row(buffer, self.base.width, |y, row| {
+            // Dark blue backdrop fading toward the bottom
+            let shade = (y * 64 / self.base.height.max(1)) as u8;
+            row.fill(rgb(0, 0, shade));
+
+            let corners = self.corners(self.angle(y, time));
+            for (face, &(r, g, b)) in FACE_COLORS.iter().enumerate() {
+                let left = corners[face];
+                let right = corners[(face + 1) % 4];
+                // Faces whose corners appear in reverse order are facing away
+                if right <= left {
+                    continue;
+                }
+                let light = ((right - left) / face_width).min(1.0);
+                let color = rgb(
+                    (r as f32 * light) as u8,
+                    (g as f32 * light) as u8,
+                    (b as f32 * light) as u8,
+                );
+                let start = (left.max(0.0) as usize).min(self.base.width);
+                let end = (right.max(0.0) as usize).min(self.base.width);
+                // Corners that are not finite may leave the face with no columns at all
+                if start < end {
+                    row[start..end].fill(color);
+                }
+            }
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_are_spread_around_center() {
+        let twister = Twister::new(200, 100, Config::default());
+        let corners = twister.corners(0.0);
+        assert_eq!(corners[0], 100.0);
+        assert!((corners[1] - 130.0).abs() < 1e-3);
+        assert!((corners[3] - 70.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn at_most_two_faces_are_visible() {
+        let twister = Twister::new(200, 100, Config::default());
+        for step in 0..32 {
+            let corners = twister.corners(step as f32 * 0.2);
+            let visible = (0..4)
+                .filter(|&f| corners[(f + 1) % 4] > corners[f])
+                .count();
+            assert!(visible <= 2);
+        }
+    }
+
+    #[test]
+    fn background_is_visible_beside_the_column() {
+        let twister = Twister::new(200, 100, Config::default());
+        let mut buffer = vec![0; 200 * 100];
+        twister.draw(&mut buffer, 1.0);
+        assert_eq!(buffer[0], rgb(0, 0, 0));
+        assert_ne!(buffer[50 * 200 + 100], rgb(0, 0, 32));
+    }
+
+    #[test]
+    fn widths_outside_the_screen_are_rejected() {
+        assert_eq!(parse_width("0.5"), Ok(0.5));
+        for spec in ["0", "-0.1", "1.5", "inf", "-inf", "NaN", "wide"] {
+            assert!(parse_width(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn non_finite_widths_draw_without_panicking() {
+        for width in [f32::INFINITY, f32::NEG_INFINITY, f32::NAN] {
+            let config = Config {
+                width,
+                ..Config::default()
+            };
+            let twister = Twister::new(16, 8, config);
+            twister.draw(&mut vec![0; 16 * 8], 1.0);
+        }
+    }
+
+    #[test]
+    fn twist_varies_angle_between_scanlines() {
+        let twister = Twister::new(200, 100, Config::default());
+        assert_ne!(twister.angle(0, 1.0), twister.angle(50, 1.0));
+    }
+}
@@ -0,0 +1,225 @@
+//! Animated Voronoi cells.
+//!
+//! A set of seed points drifts around the screen, bouncing off its edges. Every pixel
+//! belongs to the cell of its nearest seed and is shaded through the palette using either
+//! the distance to that seed, the index of the cell, or the difference between the
+//! distances to the two nearest seeds. That last mode (F2 - F1 in Worley noise terms) is
+//! zero on cell borders, which outlines the cells with dark veins.
+use crate::common::{for_each_row, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::{Args, ValueEnum};
+
+/// Default number of seed points
+const DEFAULT_SEED_COUNT: usize = 16;
+
+/// Fastest speed of a seed, as a fraction of the smallest screen dimension per second
+const MAX_SPEED: f32 = 0.15;
+
+/// Ways of shading the pixels of each cell
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Shading {
+    /// Distance to the nearest seed
+    Distance,
+    /// Flat color per cell
+    Cells,
+    /// Difference between the distances to the two nearest seeds
+    Worley,
+}
+
+/// User tunable Voronoi parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Voronoi options")]
+#[group(id = "voronoi-options")]
+pub struct Config {
+    #[arg(
+        id = "seed-count",
+        long = "seed-count",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_SEED_COUNT,
+        help = "Number of Voronoi seed points"
+    )]
+    pub count: usize,
+
+    #[arg(
+        id = "voronoi-shading",
+        long = "voronoi-shading",
+        value_name = "SHADING",
+        value_enum,
+        default_value_t = Shading::Distance,
+        help = "How the Voronoi cells are shaded"
+    )]
+    pub shading: Shading,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            count: DEFAULT_SEED_COUNT,
+            shading: Shading::Distance,
+        }
+    }
+}
+
+/// A moving Voronoi seed
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Seed {
+    position: (f32, f32),
+    velocity: (f32, f32),
+}
+
+/// Voronoi cells around drifting seeds
+pub struct Voronoi {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used to shade the cells
+    palette: Palette,
+    /// How the cells are shaded
+    shading: Shading,
+    /// Seeds defining the cells
+    seeds: Vec<Seed>,
+}
+
+impl Voronoi {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        let mut rng = fastrand::Rng::new();
+        let speed = width.min(height) as f32 * MAX_SPEED;
+        let seeds = (0..config.count.max(1))
+            .map(|_| Seed {
+                position: (rng.f32() * width as f32, rng.f32() * height as f32),
+                velocity: (
+                    (rng.f32() * 2.0 - 1.0) * speed,
+                    (rng.f32() * 2.0 - 1.0) * speed,
+                ),
+            })
+            .collect();
+
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            shading: config.shading,
+            seeds,
+        }
+    }
+
+    /// Moves every seed, bouncing those that reach the edge of the screen.
+    fn advance(&mut self, dt: f32) {
+        let (width, height) = (self.base.width as f32, self.base.height as f32);
+        for seed in &mut self.seeds {
+            seed.position.0 += seed.velocity.0 * dt;
+            seed.position.1 += seed.velocity.1 * dt;
+            if !(0.0..width).contains(&seed.position.0) {
+                seed.velocity.0 = -seed.velocity.0;
+                seed.position.0 = seed.position.0.clamp(0.0, width);
+            }
+            if !(0.0..height).contains(&seed.position.1) {
+                seed.velocity.1 = -seed.velocity.1;
+                seed.position.1 = seed.position.1.clamp(0.0, height);
+            }
+        }
+    }
+
+    /// Returns the index of the nearest seed along with the distances to the nearest and
+    /// second nearest seeds.
+    fn nearest(&self, x: f32, y: f32) -> (usize, f32, f32) {
+        let mut best = (0, f32::MAX, f32::MAX);
+        for (i, seed) in self.seeds.iter().enumerate() {
+            let (dx, dy) = (seed.position.0 - x, seed.position.1 - y);
+            let dist = dx * dx + dy * dy;
+            if dist < best.1 {
+                best = (i, dist, best.1);
+            } else if dist < best.2 {
+                best.2 = dist;
+            }
+        }
+        (best.0, best.1.sqrt(), best.2.sqrt())
+    }
+
+    /// Returns the palette position of a pixel in the [0,1] range.
+    fn shade(&self, x: f32, y: f32) -> f32 {
+        let (cell, f1, f2) = self.nearest(x, y);
+        // Typical distance between neighboring seeds
+        let spacing = (self.base.pixel_count() as f32 / self.seeds.len() as f32).sqrt();
+        let v = match self.shading {
+            Shading::Distance => f1 / spacing,
+            Shading::Cells => cell as f32 / self.seeds.len() as f32,
+            Shading::Worley => (f2 - f1) / spacing,
+        };
+        v.clamp(0.0, 1.0)
+    }
+}
+
+impl DemoEffect for Voronoi {
+    fn update(&mut self, dt: f32) {
+        self.advance(dt);
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.palette.color(self.shade(x as f32, y as f32));
+            }
+        });
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_voronoi(shading: Shading) -> Voronoi {
+        let mut voronoi = Voronoi::new(100, 100, Palette::Rainbow, Config::default());
+        voronoi.shading = shading;
+        voronoi.seeds = vec![
+            Seed {
+                position: (25.0, 50.0),
+                velocity: (10.0, 0.0),
+            },
+            Seed {
+                position: (75.0, 50.0),
+                velocity: (0.0, -10.0),
+            },
+        ];
+        voronoi
+    }
+
+    #[test]
+    fn nearest_finds_closest_seed() {
+        let voronoi = create_voronoi(Shading::Distance);
+        assert_eq!(voronoi.nearest(30.0, 50.0), (0, 5.0, 45.0));
+        assert_eq!(voronoi.nearest(90.0, 50.0).0, 1);
+    }
+
+    #[test]
+    fn distance_shading_is_darkest_at_seed() {
+        let voronoi = create_voronoi(Shading::Distance);
+        assert_eq!(voronoi.shade(25.0, 50.0), 0.0);
+        assert!(voronoi.shade(40.0, 50.0) > 0.0);
+    }
+
+    #[test]
+    fn worley_shading_vanishes_on_cell_border() {
+        let voronoi = create_voronoi(Shading::Worley);
+        assert_eq!(voronoi.shade(50.0, 50.0), 0.0);
+        assert!(voronoi.shade(25.0, 50.0) > 0.0);
+    }
+
+    #[test]
+    fn cell_shading_is_flat_within_a_cell() {
+        let voronoi = create_voronoi(Shading::Cells);
+        assert_eq!(voronoi.shade(10.0, 10.0), voronoi.shade(40.0, 90.0));
+        assert_ne!(voronoi.shade(10.0, 10.0), voronoi.shade(90.0, 10.0));
+    }
+
+    #[test]
+    fn seeds_bounce_off_edges() {
+        let mut voronoi = create_voronoi(Shading::Distance);
+        voronoi.seeds[0].position = (99.0, 50.0);
+        voronoi.advance(1.0);
+        assert_eq!(voronoi.seeds[0].velocity.0, -10.0);
+        assert!(voronoi.seeds[0].position.0 <= 100.0);
+    }
+}
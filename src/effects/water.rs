@@ -0,0 +1,204 @@
+//! Interactive water ripple simulation.
+//!
+//! The classic two-buffer height field algorithm: every update each cell's new height is
+//! half the sum of its four neighbors in the current buffer minus its height in the
+//! previous buffer, then damped slightly. Swapping the buffers afterward makes disturbances
+//! spread outward as rings that bounce off the edges and slowly die down. Clicking the
+//! window drops a stone into the water, and raindrops fall at random in the meantime.
+use crate::common::{for_each_row, DemoBase, DemoEffect, InputEvent};
+use crate::palette::Palette;
+
+/// Right shift applied to damp the waves each step, higher values damp less
+const DAMPING_SHIFT: i32 = 5;
+
+/// Height added to the water surface by a drop
+const DROP_STRENGTH: i32 = 512;
+
+/// Radius of a drop in pixels
+const DROP_RADIUS: usize = 3;
+
+/// Probability that a random raindrop falls on a given update
+const RAIN_PROBABILITY: f32 = 0.03;
+
+/// Height difference that maps onto the full palette range
+const SHADE_RANGE: f32 = 256.0;
+
+/// A water surface disturbed by clicks and raindrops
+pub struct Water {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for shading the surface
+    palette: Palette,
+    /// Height field of the current step
+    current: Vec<i32>,
+    /// Height field of the previous step, overwritten with the next step
+    previous: Vec<i32>,
+    /// Random number generator used for raindrops
+    rng: fastrand::Rng,
+}
+
+impl Water {
+    pub fn new(width: usize, height: usize, palette: Palette) -> Self {
+        let base = DemoBase::new(width, height);
+        Self {
+            base,
+            palette,
+            current: vec![0; base.pixel_count()],
+            previous: vec![0; base.pixel_count()],
+            rng: fastrand::Rng::new(),
+        }
+    }
+
+    /// Disturbs the surface with a circular drop centered on the given pixel.
+    fn drop_at(&mut self, x: usize, y: usize) {
+        let (width, height) = (self.base.width, self.base.height);
+        let radius_sq = (DROP_RADIUS * DROP_RADIUS) as isize;
+
+        for dy in -(DROP_RADIUS as isize)..=DROP_RADIUS as isize {
+            for dx in -(DROP_RADIUS as isize)..=DROP_RADIUS as isize {
+                let (px, py) = (x as isize + dx, y as isize + dy);
+                let inside = dx * dx + dy * dy <= radius_sq;
+                if inside && px >= 0 && py >= 0 && (px as usize) < width && (py as usize) < height {
+                    self.current[py as usize * width + px as usize] = DROP_STRENGTH;
+                }
+            }
+        }
+    }
+
+    /// Advances the simulation by one step.
+    fn step(&mut self) {
+        let width = self.base.width;
+        let height = self.base.height;
+
+        // Border cells are left untouched, acting as walls the waves bounce off
+        for y in 1..height.saturating_sub(1) {
+            for x in 1..width.saturating_sub(1) {
+                let i = y * width + x;
+                let neighbors = self.current[i - 1]
+                    + self.current[i + 1]
+                    + self.current[i - width]
+                    + self.current[i + width];
+                let next = neighbors / 2 - self.previous[i];
+                self.previous[i] = next - (next >> DAMPING_SHIFT);
+            }
+        }
+        std::mem::swap(&mut self.current, &mut self.previous);
+    }
+}
+
+impl DemoEffect for Water {
+    /// Advances the ripples by one step per update, regardless of the tick length.
+    fn update(&mut self, _dt: f32) {
+        if self.base.width < 2 || self.base.height < 2 {
+            return;
+        }
+
+        if self.rng.f32() < RAIN_PROBABILITY {
+            let x = self.rng.usize(..self.base.width);
+            let y = self.rng.usize(..self.base.height);
+            self.drop_at(x, y);
+        }
+        self.step();
+    }
+
+    fn draw(&self, buffer: &mut [u32], _time: f32) {
+        let width = self.base.width;
+        for_each_row(buffer, width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                // Shade by the slope of the surface so the waves look lit from the side
+                let i = y * width + x;
+                let right = if x + 1 < width { i + 1 } else { i };
+                let slope = (self.current[i] - self.current[right]) as f32;
+                let v = (0.5 + slope / SHADE_RANGE).clamp(0.0, 1.0);
+                *pixel = self.palette.color(v);
+            }
+        });
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        if let InputEvent::Mouse { x, y, pressed } = event {
+            if pressed && x >= 0.0 && y >= 0.0 {
+                let (x, y) = (x as usize, y as usize);
+                if x < self.base.width && y < self.base.height {
+                    self.drop_at(x, y);
+                }
+            }
+        }
+    }
+
+    /// Calms the water, since the ripples no longer line up with the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+        self.current = vec![0; self.base.pixel_count()];
+        self.previous = vec![0; self.base.pixel_count()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_water() -> Water {
+        Water::new(64, 64, Palette::BlueCyan)
+    }
+
+    #[test]
+    fn surface_starts_flat() {
+        let water = create_water();
+        assert!(water.current.iter().all(|&h| h == 0));
+    }
+
+    #[test]
+    fn ripples_spread_outward_from_drop() {
+        let mut water = create_water();
+        water.drop_at(32, 32);
+        for _ in 0..20 {
+            water.step();
+        }
+        assert!(
+            water.current[32 * 64 + 42..32 * 64 + 52]
+                .iter()
+                .any(|&h| h != 0),
+            "The ripple should have reached cells away from the drop"
+        );
+    }
+
+    #[test]
+    fn ripples_die_down_over_time() {
+        let mut water = create_water();
+        water.drop_at(32, 32);
+        let initial: i64 = water.current.iter().map(|&h| h.abs() as i64).sum();
+        for _ in 0..2000 {
+            water.step();
+        }
+        let remaining: i64 = water.current.iter().map(|&h| h.abs() as i64).sum();
+        assert!(remaining < initial / 10, "Damping should calm the water");
+    }
+
+    #[test]
+    fn clicks_outside_the_window_are_ignored() {
+        let mut water = create_water();
+        water.handle_input(InputEvent::Mouse {
+            x: -5.0,
+            y: 10.0,
+            pressed: true,
+        });
+        water.handle_input(InputEvent::Mouse {
+            x: 10.0,
+            y: 100.0,
+            pressed: true,
+        });
+        assert!(water.current.iter().all(|&h| h == 0));
+    }
+
+    #[test]
+    fn clicks_drop_stones_into_the_water() {
+        let mut water = create_water();
+        water.handle_input(InputEvent::Mouse {
+            x: 10.0,
+            y: 20.0,
+            pressed: true,
+        });
+        assert_eq!(water.current[20 * 64 + 10], DROP_STRENGTH);
+    }
+}
@@ -0,0 +1,192 @@
+//! Interference between circular waves.
+//!
+//! Several emitters drift across a pool, each sending out circular waves. The wave heights
+//! of all emitters are summed at every pixel, so waves reinforce each other where crests
+//! meet and cancel out where a crest meets a trough, drawing shifting interference fringes.
+//!
+//! `Up` adds an emitter and `Down` removes one.
+use crate::common::{for_each_row, DemoBase, DemoEffect, InputEvent};
+use crate::effects::Settings;
+use crate::palette::Palette;
+use clap::Args;
+use minifb::Key;
+
+/// Default number of wave emitters
+const DEFAULT_EMITTERS: usize = 3;
+
+/// Largest number of emitters that can be added at runtime
+const MAX_EMITTERS: usize = 12;
+
+/// Number of wave crests per smallest screen dimension
+const WAVE_NUMBER: f32 = 12.0;
+
+/// Angular frequency of the waves in radians per second
+const WAVE_SPEED: f32 = 4.0;
+
+/// User tunable wave pool parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Wave pool options")]
+#[group(id = "wavepool-options")]
+pub struct Config {
+    #[arg(
+        id = "emitters",
+        long = "emitters",
+        value_name = "COUNT",
+        default_value_t = DEFAULT_EMITTERS,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_EMITTERS as u64),
+        help = "Number of wave emitters in the pool, from 1 to 12"
+    )]
+    pub emitters: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            emitters: DEFAULT_EMITTERS,
+        }
+    }
+}
+
+/// Circular waves from moving emitters interfering with each other
+pub struct WavePool {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used to display the wave heights
+    palette: Palette,
+    /// Number of active emitters
+    emitters: usize,
+}
+
+impl WavePool {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            emitters: config.emitters.clamp(1, MAX_EMITTERS),
+        }
+    }
+
+    /// Returns the position of an emitter at the given time.
+    fn emitter(&self, index: usize, time: f32) -> (f32, f32) {
+        let (center_x, center_y) = self.base.center();
+        let k = index as f32;
+        (
+            center_x + center_x * 0.6 * (time * (0.3 + k * 0.07) + k * 1.7).sin(),
+            center_y + center_y * 0.6 * (time * (0.23 + k * 0.05) + k * 2.9).cos(),
+        )
+    }
+
+    /// Returns the summed wave height at a pixel, normalized to the [0,1] range.
+    fn height(&self, x: f32, y: f32, emitters: &[(f32, f32)], time: f32) -> f32 {
+        let k = WAVE_NUMBER / self.base.width.min(self.base.height).max(1) as f32;
+        let sum: f32 = emitters
+            .iter()
+            .map(|&(ex, ey)| {
+                let d = ((x - ex).powi(2) + (y - ey).powi(2)).sqrt();
+                (d * k * std::f32::consts::TAU - time * WAVE_SPEED).sin()
+            })
+            .sum();
+        sum / emitters.len() as f32 * 0.5 + 0.5
+    }
+}
+
+impl DemoEffect for WavePool {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let emitters: Vec<_> = (0..self.emitters).map(|i| self.emitter(i, time)).collect();
+        for_each_row(buffer, self.base.width, |y, row| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let v = self.height(x as f32, y as f32, &emitters, time);
+                *pixel = self.palette.color(v);
+            }
+        });
+    }
+
+    /// Adds or removes emitters.
+    fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(Key::Up) => self.emitters = (self.emitters + 1).min(MAX_EMITTERS),
+            InputEvent::Key(Key::Down) => self.emitters = self.emitters.saturating_sub(1).max(1),
+            _ => {}
+        }
+    }
+
+    fn save_settings(&self, settings: &mut Settings) {
+        settings.wavepool.emitters = self.emitters;
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_pool() -> WavePool {
+        WavePool::new(100, 100, Palette::BlueCyan, Config::default())
+    }
+
+    #[test]
+    fn keys_add_and_remove_emitters() {
+        let mut pool = create_pool();
+        pool.handle_input(InputEvent::Key(Key::Up));
+        assert_eq!(pool.emitters, DEFAULT_EMITTERS + 1);
+        pool.handle_input(InputEvent::Key(Key::Down));
+        pool.handle_input(InputEvent::Key(Key::Down));
+        assert_eq!(pool.emitters, DEFAULT_EMITTERS - 1);
+    }
+
+    #[test]
+    fn emitter_count_stays_within_bounds() {
+        let mut pool = create_pool();
+        for _ in 0..20 {
+            pool.handle_input(InputEvent::Key(Key::Down));
+        }
+        assert_eq!(pool.emitters, 1);
+        for _ in 0..20 {
+            pool.handle_input(InputEvent::Key(Key::Up));
+        }
+        assert_eq!(pool.emitters, MAX_EMITTERS);
+    }
+
+    #[test]
+    fn height_is_normalized() {
+        let pool = create_pool();
+        let emitters = [(10.0, 10.0), (60.0, 40.0)];
+        for i in 0..100 {
+            let v = pool.height(i as f32, 99.0 - i as f32, &emitters, 0.3);
+            assert!((0.0..=1.0).contains(&v));
+        }
+    }
+
+    #[test]
+    fn waves_interfere_constructively_between_equal_emitters() {
+        let pool = create_pool();
+        // Equidistant from both emitters, so both waves always arrive in phase
+        let emitters = [(20.0, 50.0), (80.0, 50.0)];
+        let single = pool.height(50.0, 50.0, &emitters[..1], 0.0);
+        let both = pool.height(50.0, 50.0, &emitters, 0.0);
+        assert!((single - both).abs() < 1e-5);
+    }
+
+    #[test]
+    fn emitters_stay_in_the_pool() {
+        let pool = create_pool();
+        for step in 0..200 {
+            for i in 0..MAX_EMITTERS {
+                let (x, y) = pool.emitter(i, step as f32 * 0.5);
+                assert!((0.0..100.0).contains(&x) && (0.0..100.0).contains(&y));
+            }
+        }
+    }
+
+    #[test]
+    fn saved_settings_follow_the_interactive_controls() {
+        let mut pool = create_pool();
+        pool.handle_input(InputEvent::Key(Key::Up));
+        let mut settings = Settings::default();
+        pool.save_settings(&mut settings);
+        assert_eq!(settings.wavepool.emitters, DEFAULT_EMITTERS + 1);
+    }
+}
@@ -0,0 +1,262 @@
+//! A spinning wireframe mesh rendered with a tiny software 3D pipeline.
+//!
+//! Vertices are rotated around all three axes with rotation matrices, pushed away from the
+//! camera, and projected onto the screen with a perspective divide. Edges are then drawn as
+//! Bresenham lines and colored through the palette by their depth, so the near side of the
+//! mesh stands out from the far side.
+use crate::common::{rgb, DemoBase, DemoEffect};
+use crate::palette::Palette;
+use clap::{Args, ValueEnum};
+
+/// A point or direction in 3D space
+type Vec3 = [f32; 3];
+
+/// A 3x3 matrix stored in row-major order
+type Mat3 = [[f32; 3]; 3];
+
+/// Distance between the camera and the center of the mesh
+const CAMERA_DISTANCE: f32 = 4.0;
+
+/// Rotation speeds around the x, y, and z axes in radians per second
+const SPIN: Vec3 = [0.7, 1.1, 0.3];
+
+/// Number of segments used to approximate the torus knot
+const KNOT_SEGMENTS: usize = 180;
+
+/// Meshes that can be spun
+#[derive(Debug, PartialEq, Clone, ValueEnum)]
+pub enum Mesh {
+    Cube,
+    Icosahedron,
+    TorusKnot,
+}
+
+/// User tunable wireframe parameters
+#[derive(Debug, Clone, PartialEq, Args)]
+#[command(next_help_heading = "Wireframe options")]
+#[group(id = "wireframe-options")]
+pub struct Config {
+    #[arg(
+        id = "mesh",
+        long = "mesh",
+        value_name = "MESH",
+        value_enum,
+        default_value_t = Mesh::Icosahedron,
+        help = "Mesh spinning on screen"
+    )]
+    pub mesh: Mesh,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            mesh: Mesh::Icosahedron,
+        }
+    }
+}
+
+/// Returns the rotation matrix for the given angles around the x, y, and z axes.
+fn rotation([ax, ay, az]: Vec3) -> Mat3 {
+    let (sx, cx) = ax.sin_cos();
+    let (sy, cy) = ay.sin_cos();
+    let (sz, cz) = az.sin_cos();
+    let rx = [[1.0, 0.0, 0.0], [0.0, cx, -sx], [0.0, sx, cx]];
+    let ry = [[cy, 0.0, sy], [0.0, 1.0, 0.0], [-sy, 0.0, cy]];
+    let rz = [[cz, -sz, 0.0], [sz, cz, 0.0], [0.0, 0.0, 1.0]];
+    multiply(&rz, &multiply(&ry, &rx))
+}
+
+/// Multiplies two 3x3 matrices.
+fn multiply(a: &Mat3, b: &Mat3) -> Mat3 {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    m
+}
+
+/// Applies a matrix to a vector.
+fn transform(m: &Mat3, v: Vec3) -> Vec3 {
+    [0, 1, 2].map(|i| m[i][0] * v[0] + m[i][1] * v[1] + m[i][2] * v[2])
+}
+
+/// A mesh made of vertices connected by edges
+#[derive(Debug, Clone, PartialEq)]
+struct Model {
+    /// Vertex positions, roughly within the unit sphere
+    vertices: Vec<Vec3>,
+    /// Pairs of vertex indices connected by an edge
+    edges: Vec<(usize, usize)>,
+}
+
+impl Model {
+    /// Builds the vertices and edges of a mesh.
+    fn build(mesh: &Mesh) -> Self {
+        match mesh {
+            Mesh::Cube => {
+                let vertices: Vec<Vec3> = (0..8)
+                    .map(|i| [0, 1, 2].map(|axis| if i >> axis & 1 == 1 { 0.6 } else { -0.6 }))
+                    .collect();
+                // Cube corners sharing an edge differ in exactly one coordinate
+                Self::connect(vertices, 1.2)
+            }
+            Mesh::Icosahedron => {
+                let phi = (1.0 + 5.0_f32.sqrt()) / 2.0;
+                let mut vertices = Vec::new();
+                for a in [-1.0, 1.0] {
+                    for b in [-phi, phi] {
+                        vertices.push([0.0, a, b]);
+                        vertices.push([a, b, 0.0]);
+                        vertices.push([b, 0.0, a]);
+                    }
+                }
+                // Scale down so the mesh fits in the unit sphere, edges are then 2 * scale long
+                let scale = 1.0 / (1.0 + phi * phi).sqrt();
+                let vertices = vertices.into_iter().map(|v| v.map(|c| c * scale)).collect();
+                Self::connect(vertices, 2.0 * scale)
+            }
+            Mesh::TorusKnot => {
+                // A (2,3) torus knot, also known as the trefoil
+                let vertices = (0..KNOT_SEGMENTS)
+                    .map(|i| {
+                        let t = i as f32 / KNOT_SEGMENTS as f32 * std::f32::consts::TAU;
+                        let r = (3.0 * t).cos() + 2.0;
+                        [
+                            r * (2.0 * t).cos() / 3.0,
+                            r * (2.0 * t).sin() / 3.0,
+                            -(3.0 * t).sin() / 3.0,
+                        ]
+                    })
+                    .collect();
+                let edges = (0..KNOT_SEGMENTS)
+                    .map(|i| (i, (i + 1) % KNOT_SEGMENTS))
+                    .collect();
+                Self { vertices, edges }
+            }
+        }
+    }
+
+    /// Connects every pair of vertices separated by the given edge length.
+    fn connect(vertices: Vec<Vec3>, length: f32) -> Self {
+        let mut edges = Vec::new();
+        for i in 0..vertices.len() {
+            for j in i + 1..vertices.len() {
+                let d: f32 = (0..3)
+                    .map(|k| (vertices[i][k] - vertices[j][k]).powi(2))
+                    .sum();
+                if (d.sqrt() - length).abs() < 1e-3 {
+                    edges.push((i, j));
+                }
+            }
+        }
+        Self { vertices, edges }
+    }
+}
+
+/// A spinning wireframe renderer
+pub struct Wireframe {
+    /// Dimensions of the effect
+    base: DemoBase,
+    /// Color palette used for the edges
+    palette: Palette,
+    /// Mesh being rendered
+    model: Model,
+}
+
+impl Wireframe {
+    pub fn new(width: usize, height: usize, palette: Palette, config: Config) -> Self {
+        Self {
+            base: DemoBase::new(width, height),
+            palette,
+            model: Model::build(&config.mesh),
+        }
+    }
+
+    /// Projects a camera space point onto the screen.
+    fn project(&self, [x, y, z]: Vec3) -> (i32, i32) {
+        let (center_x, center_y) = self.base.center();
+        let focal = center_x.min(center_y) * 2.5;
+        let depth = z + CAMERA_DISTANCE;
+        (
+            (center_x + x * focal / depth) as i32,
+            (center_y + y * focal / depth) as i32,
+        )
+    }
+}
+
+impl DemoEffect for Wireframe {
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        buffer.fill(rgb(0, 0, 0));
+
+        let matrix = rotation(SPIN.map(|speed| speed * time));
+        let rotated: Vec<Vec3> = self
+            .model
+            .vertices
+            .iter()
+            .map(|&v| transform(&matrix, v))
+            .collect();
+
+        for &(a, b) in &self.model.edges {
+            // Map the edge depth from [-1,1] onto the palette, nearest edges at the top
+            let depth = (rotated[a][2] + rotated[b][2]) * 0.5;
+            let color = self.palette.color((0.5 - depth * 0.5).clamp(0.0, 1.0));
+            let from = self.project(rotated[a]);
+            let to = self.project(rotated[b]);
+            self.base.draw_line(buffer, from, to, color);
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.base = DemoBase::new(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_has_twelve_edges() {
+        let model = Model::build(&Mesh::Cube);
+        assert_eq!(model.vertices.len(), 8);
+        assert_eq!(model.edges.len(), 12);
+    }
+
+    #[test]
+    fn icosahedron_has_thirty_edges() {
+        let model = Model::build(&Mesh::Icosahedron);
+        assert_eq!(model.vertices.len(), 12);
+        assert_eq!(model.edges.len(), 30);
+    }
+
+    #[test]
+    fn torus_knot_is_a_closed_loop() {
+        let model = Model::build(&Mesh::TorusKnot);
+        assert_eq!(model.edges.len(), KNOT_SEGMENTS);
+        assert_eq!(model.edges.last(), Some(&(KNOT_SEGMENTS - 1, 0)));
+    }
+
+    #[test]
+    fn rotation_preserves_length() {
+        let m = rotation([0.3, 1.2, -0.7]);
+        let v = transform(&m, [1.0, 2.0, 3.0]);
+        let length: f32 = v.iter().map(|c| c * c).sum::<f32>().sqrt();
+        assert!((length - 14.0_f32.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn zero_rotation_is_identity() {
+        assert_eq!(
+            transform(&rotation([0.0; 3]), [1.0, 2.0, 3.0]),
+            [1.0, 2.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn origin_projects_to_screen_center() {
+        let wireframe = Wireframe::new(200, 100, Palette::Rainbow, Config::default());
+        assert_eq!(wireframe.project([0.0, 0.0, 0.0]), (100, 50));
+    }
+}
@@ -0,0 +1,404 @@
+//! Animated GIF encoding.
+//!
+//! GIF images hold at most 256 colors, so every frame is reduced to its own palette with
+//! the median cut algorithm: the colors of the frame are split into boxes along their
+//! widest channel until there are 256 boxes, and each box is replaced by its average
+//! color. Frames using 256 colors or fewer keep their exact colors. The palette indices
+//! are then compressed with the variable code size LZW scheme of the format.
+use super::FrameWriter;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Largest number of colors in a GIF palette
+const MAX_COLORS: usize = 256;
+
+/// Number of bits per palette index, which is also the initial LZW code size
+const INDEX_BITS: u8 = 8;
+
+/// Largest LZW code, after which the dictionary is cleared
+const MAX_CODE: u16 = 4095;
+
+/// Largest amount of data in a GIF sub-block
+const SUB_BLOCK_SIZE: usize = 255;
+
+/// Writes frames of packed ARGB pixels as a looping animated GIF
+pub struct GifEncoder<W: Write> {
+    /// Destination of the encoded image
+    writer: W,
+    /// Width of every frame in pixels
+    width: usize,
+    /// Height of every frame in pixels
+    height: usize,
+    /// Frames shown per second
+    fps: f32,
+    /// Number of frames written so far
+    frames: usize,
+}
+
+impl<W: Write> GifEncoder<W> {
+    /// Writes the header of an animation of the given size, looping forever.
+    pub fn new(mut writer: W, width: usize, height: usize, fps: f32) -> io::Result<Self> {
+        writer.write_all(b"GIF89a")?;
+        // Logical screen without a global palette
+        writer.write_all(&(width as u16).to_le_bytes())?;
+        writer.write_all(&(height as u16).to_le_bytes())?;
+        writer.write_all(&[0, 0, 0])?;
+        // Application extension asking viewers to loop forever
+        writer.write_all(&[0x21, 0xff, 11])?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        writer.write_all(&[3, 1, 0, 0, 0])?;
+        Ok(Self {
+            writer,
+            width,
+            height,
+            fps,
+            frames: 0,
+        })
+    }
+
+    /// Appends a frame of `width * height` pixels to the animation.
+    pub fn add_frame(&mut self, pixels: &[u32]) -> io::Result<()> {
+        // Frame delays are counted in hundredths of a second, so round the end time of
+        // every frame rather than its duration to keep rounding errors from piling up
+        let end = |frame: usize| (frame as f32 * 100.0 / self.fps).round() as u16;
+        let delay = end(self.frames + 1) - end(self.frames);
+        self.frames += 1;
+
+        let (palette, indices) = quantize(pixels);
+        let w = &mut self.writer;
+        // Graphic control extension holding the frame delay
+        w.write_all(&[0x21, 0xf9, 4, 0])?;
+        w.write_all(&delay.to_le_bytes())?;
+        w.write_all(&[0, 0])?;
+        // Image descriptor covering the whole screen with a local palette of 256 colors
+        w.write_all(&[0x2c, 0, 0, 0, 0])?;
+        w.write_all(&(self.width as u16).to_le_bytes())?;
+        w.write_all(&(self.height as u16).to_le_bytes())?;
+        w.write_all(&[0x80 | (INDEX_BITS - 1)])?;
+        for i in 0..MAX_COLORS {
+            w.write_all(&palette.get(i).copied().unwrap_or_default())?;
+        }
+        w.write_all(&[INDEX_BITS])?;
+        for block in lzw_encode(&indices).chunks(SUB_BLOCK_SIZE) {
+            w.write_all(&[block.len() as u8])?;
+            w.write_all(block)?;
+        }
+        w.write_all(&[0])
+    }
+
+    /// Terminates the animation and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.write_all(&[0x3b])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> FrameWriter for GifEncoder<W> {
+    fn write_frame(&mut self, pixels: &[u32]) -> io::Result<()> {
+        self.add_frame(pixels)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        GifEncoder::finish(*self).map(drop)
+    }
+}
+
+/// A box of colors split by the median cut algorithm
+struct ColorBox {
+    /// Distinct colors in the box along with their number of pixels
+    colors: Vec<([u8; 3], u32)>,
+}
+
+impl ColorBox {
+    /// Returns the channel with the widest range of values and the size of that range.
+    fn widest_channel(&self) -> (usize, u8) {
+        (0..3)
+            .map(|c| {
+                let values = self.colors.iter().map(|(color, _)| color[c]);
+                let (min, max) = values.fold((u8::MAX, 0), |(lo, hi), v| (lo.min(v), hi.max(v)));
+                (c, max.saturating_sub(min))
+            })
+            .max_by_key(|&(_, range)| range)
+            .unwrap_or((0, 0))
+    }
+
+    /// Splits the box along its widest channel, leaving half of the pixels in each box.
+    fn split(mut self) -> (ColorBox, ColorBox) {
+        let (channel, _) = self.widest_channel();
+        self.colors
+            .sort_unstable_by_key(|(color, _)| color[channel]);
+        let total: u32 = self.colors.iter().map(|&(_, count)| count).sum();
+        let mut seen = 0;
+        let median = self
+            .colors
+            .iter()
+            .position(|&(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        // Both halves must keep at least one color
+        let at = (median + 1).clamp(1, self.colors.len() - 1);
+        let upper = self.colors.split_off(at);
+        (self, ColorBox { colors: upper })
+    }
+
+    /// Returns the average color of the pixels in the box.
+    fn average(&self) -> [u8; 3] {
+        let total: u64 = self.colors.iter().map(|&(_, count)| count as u64).sum();
+        let mut sum = [0u64; 3];
+        for &(color, count) in &self.colors {
+            for c in 0..3 {
+                sum[c] += color[c] as u64 * count as u64;
+            }
+        }
+        sum.map(|s| (s / total.max(1)) as u8)
+    }
+}
+
+/// Reduces a frame to a palette of at most 256 colors and the palette index of every
+/// pixel.
+///
+/// Frames with few enough colors keep them exactly, others get the palette chosen by the
+/// median cut algorithm.
+pub fn quantize(pixels: &[u32]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let rgb = |pixel: u32| {
+        let [_, r, g, b] = pixel.to_be_bytes();
+        [r, g, b]
+    };
+    let mut histogram: HashMap<[u8; 3], u32> = HashMap::new();
+    for &pixel in pixels {
+        *histogram.entry(rgb(pixel)).or_default() += 1;
+    }
+
+    if histogram.len() <= MAX_COLORS {
+        let palette: Vec<[u8; 3]> = histogram.keys().copied().collect();
+        let index: HashMap<[u8; 3], u8> =
+            (0..palette.len()).map(|i| (palette[i], i as u8)).collect();
+        let indices = pixels.iter().map(|&pixel| index[&rgb(pixel)]).collect();
+        return (palette, indices);
+    }
+
+    let mut boxes = vec![ColorBox {
+        colors: histogram
+            .iter()
+            .map(|(&color, &count)| (color, count))
+            .collect(),
+    }];
+    while boxes.len() < MAX_COLORS {
+        // Split the box spanning the widest range of any channel
+        let Some((widest, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.colors.len() > 1)
+            .max_by_key(|(_, b)| b.widest_channel().1)
+        else {
+            break;
+        };
+        let (lower, upper) = boxes.swap_remove(widest).split();
+        boxes.push(lower);
+        boxes.push(upper);
+    }
+    let palette: Vec<[u8; 3]> = boxes.iter().map(ColorBox::average).collect();
+
+    // Colors differing only in their lowest bits share the nearest palette entry, which
+    // is searched once for each of them
+    let mut nearest: Vec<Option<u8>> = vec![None; 1 << 15];
+    let indices = pixels
+        .iter()
+        .map(|&pixel| {
+            let color = rgb(pixel);
+            let key = color
+                .iter()
+                .fold(0, |key, &c| (key << 5) | (c >> 3) as usize);
+            *nearest[key].get_or_insert_with(|| {
+                let distance = |entry: &[u8; 3]| -> u32 {
+                    (0..3)
+                        .map(|c| (color[c] as i32 - entry[c] as i32).pow(2) as u32)
+                        .sum()
+                };
+                (0..palette.len())
+                    .min_by_key(|&i| distance(&palette[i]))
+                    .unwrap_or(0) as u8
+            })
+        })
+        .collect();
+    (palette, indices)
+}
+
+/// Packs codes of varying bit lengths into bytes, least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    /// Completed bytes
+    bytes: Vec<u8>,
+    /// Bits waiting to fill the next byte
+    buffer: u32,
+    /// Number of bits in `buffer`
+    count: u8,
+}
+
+impl BitWriter {
+    /// Appends the lowest `size` bits of `code`.
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Flushes the remaining bits and returns the bytes.
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Compresses palette indices with the LZW variant used by GIF.
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << INDEX_BITS;
+    let end = clear + 1;
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = INDEX_BITS + 1;
+    let mut bits = BitWriter::default();
+
+    bits.write(clear, size);
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(current) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = dictionary.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+        bits.write(current, size);
+        if next <= MAX_CODE {
+            dictionary.insert((current, index), next);
+            // Grow the code size once the new code no longer fits
+            if next == 1 << size && size < 12 {
+                size += 1;
+            }
+            next += 1;
+        } else {
+            bits.write(clear, size);
+            dictionary.clear();
+            next = end + 1;
+            size = INDEX_BITS + 1;
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(current) = prefix {
+        bits.write(current, size);
+    }
+    bits.write(end, size);
+    bits.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::rgb;
+
+    /// Decodes a GIF LZW stream, mirroring the encoder.
+    fn lzw_decode(data: &[u8]) -> Vec<u8> {
+        let clear = 1u16 << INDEX_BITS;
+        let end = clear + 1;
+        let (mut position, mut size) = (0usize, INDEX_BITS + 1);
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let reset = |table: &mut Vec<Vec<u8>>| {
+            *table = (0..=255u8).map(|i| vec![i]).collect();
+            table.extend([vec![], vec![]]);
+        };
+        reset(&mut table);
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = Vec::new();
+        loop {
+            let mut code = 0u16;
+            for bit in 0..size as usize {
+                let byte = data[(position + bit) / 8];
+                code |= (((byte >> ((position + bit) % 8)) & 1) as u16) << bit;
+            }
+            position += size as usize;
+            if code == clear {
+                reset(&mut table);
+                size = INDEX_BITS + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+            let entry = match (table.get(code as usize), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => [prev.clone(), vec![prev[0]]].concat(),
+                (None, None) => panic!("invalid code"),
+            };
+            output.extend(&entry);
+            if let Some(prev) = previous {
+                table.push([prev, vec![entry[0]]].concat());
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        let indices: Vec<u8> = (0..20_000u32).map(|i| (i * i / 7 % 251) as u8).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&indices)), indices);
+        let flat = vec![3; 50_000];
+        assert_eq!(lzw_decode(&lzw_encode(&flat)), flat);
+    }
+
+    #[test]
+    fn few_colors_are_kept_exactly() {
+        let pixels = [rgb(1, 2, 3), rgb(200, 100, 50), rgb(1, 2, 3)];
+        let (palette, indices) = quantize(&pixels);
+        assert_eq!(palette.len(), 2);
+        assert_eq!(palette[indices[1] as usize], [200, 100, 50]);
+        assert_eq!(indices[0], indices[2]);
+    }
+
+    #[test]
+    fn many_colors_are_reduced_to_a_full_palette() {
+        let pixels: Vec<u32> = (0..4096u32)
+            .map(|i| rgb((i % 64 * 4) as u8, (i / 64 * 4) as u8, 128))
+            .collect();
+        let (palette, indices) = quantize(&pixels);
+        assert_eq!(palette.len(), MAX_COLORS);
+        // Every pixel ends up close to its original color
+        for (&pixel, &index) in pixels.iter().zip(&indices) {
+            let [_, r, g, _] = pixel.to_be_bytes();
+            let [pr, pg, _] = palette[index as usize];
+            assert!(r.abs_diff(pr) <= 8 && g.abs_diff(pg) <= 8);
+        }
+    }
+
+    #[test]
+    fn animation_is_framed_by_header_and_trailer() {
+        let mut encoder = GifEncoder::new(Vec::new(), 2, 2, 30.0).unwrap();
+        for _ in 0..3 {
+            encoder.add_frame(&[rgb(255, 0, 0); 4]).unwrap();
+        }
+        let gif = encoder.finish().unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif[6..10], [2, 0, 2, 0]);
+        assert_eq!(gif.last(), Some(&0x3b));
+        // Delays of 3, 4, and 3 hundredths of a second add up to a tenth of a second
+        let delays: Vec<u16> = (0..gif.len() - 6)
+            .filter(|&i| gif[i..i + 3] == [0x21, 0xf9, 4])
+            .map(|i| u16::from_le_bytes([gif[i + 4], gif[i + 5]]))
+            .collect();
+        assert_eq!(delays, [3, 4, 3]);
+    }
+}
@@ -0,0 +1,280 @@
+//! Saving rendered frames to image files.
+//!
+//! Single frames are written as PNG images by a small built-in encoder. The image data is
+//! stored in uncompressed deflate blocks, trading file size for an encoder simple enough
+//! to live in a few functions. Animations are written by a [`FrameWriter`]: [`gif`]
+//! encodes them as GIF images, [`video`] hands them to ffmpeg, [`raw`] streams them
+//! uncompressed, and a [`PngSequence`] saves every frame as a numbered PNG image.
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod gif;
+pub mod raw;
+pub mod video;
+
+/// Destination of the frames of a recording
+pub trait FrameWriter {
+    /// Appends a frame of packed ARGB pixels to the recording.
+    fn write_frame(&mut self, pixels: &[u32]) -> io::Result<()>;
+
+    /// Completes the recording once the last frame has been written.
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+/// Bytes every PNG file starts with
+pub(crate) const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest amount of data held by an uncompressed deflate block
+const STORED_BLOCK_SIZE: usize = 65535;
+
+/// Lookup table of the CRC-32 checksum used by PNG chunks
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 checksum of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Computes the Adler-32 checksum of `data` closing a zlib stream.
+fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    let (mut a, mut b) = (1, 0);
+    for &byte in data {
+        a = (a + byte as u32) % MODULUS;
+        b = (b + a) % MODULUS;
+    }
+    (b << 16) | a
+}
+
+/// Wraps `data` into a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary, with the check bits set so the
+    // header is a multiple of 31
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(STORED_BLOCK_SIZE).peekable();
+    if blocks.peek().is_none() {
+        stream.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        stream.push(last as u8);
+        stream.extend(len.to_le_bytes());
+        stream.extend((!len).to_le_bytes());
+        stream.extend(block);
+    }
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+/// Appends a PNG chunk of the given type to `png`.
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Encodes a frame of packed ARGB pixels as an RGB PNG image, dropping the alpha channel.
+pub fn encode_png(pixels: &[u32], width: usize, height: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    // 8 bits per channel, truecolor, default compression and filtering, no interlacing
+    header.extend([8, 2, 0, 0, 0]);
+
+    let mut scanlines = Vec::with_capacity(height * (1 + width * 3));
+    for row in pixels.chunks_exact(width.max(1)).take(height) {
+        // Every scanline starts with its filter type, none here
+        scanlines.push(0);
+        for &pixel in row {
+            scanlines.extend(&pixel.to_be_bytes()[1..]);
+        }
+    }
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Writes a frame of packed ARGB pixels to a PNG file.
+pub fn save_png(path: &Path, pixels: &[u32], width: usize, height: usize) -> io::Result<()> {
+    std::fs::write(path, encode_png(pixels, width, height))
+}
+
+/// Writes every frame of a recording as a PNG file numbered from 1, such as
+/// `frame_0001.png`
+pub struct PngSequence {
+    /// Directory receiving the frames
+    directory: PathBuf,
+    /// Width of the frames in pixels
+    width: usize,
+    /// Height of the frames in pixels
+    height: usize,
+    /// Number of frames written so far
+    frames: usize,
+}
+
+impl PngSequence {
+    /// Prepares to write frames of the given size into `directory`, creating it if it
+    /// does not exist yet.
+    pub fn new(directory: &Path, width: usize, height: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            width,
+            height,
+            frames: 0,
+        })
+    }
+}
+
+impl FrameWriter for PngSequence {
+    fn write_frame(&mut self, pixels: &[u32]) -> io::Result<()> {
+        self.frames += 1;
+        let path = self.directory.join(format!("frame_{:04}.png", self.frames));
+        save_png(&path, pixels, self.width, self.height)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Formats a time as `YYYYMMDD-HHMMSS-mmm` in UTC.
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, day_seconds) = (seconds / 86400, seconds % 86400);
+
+    // Convert days since the epoch to a civil date, counting years from March so leap
+    // days fall at the end of the year
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        day_seconds / 3600,
+        day_seconds / 60 % 60,
+        day_seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Saves a frame as a PNG screenshot named after the effect and the current time.
+///
+/// The directory is created if it does not exist yet. Returns the path of the new file.
+pub fn screenshot(
+    directory: &Path,
+    effect: &str,
+    pixels: &[u32],
+    width: usize,
+    height: usize,
+) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(directory)?;
+    let name = format!("{effect}-{}.png", format_timestamp(SystemTime::now()));
+    let path = directory.join(name);
+    save_png(&path, pixels, width, height)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn checksums_match_reference_values() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn png_holds_header_and_pixels() {
+        let png = encode_png(&[0xff11_2233, 0xff44_5566], 2, 1);
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..24], [0, 0, 0, 2, 0, 0, 0, 1]);
+        // The scanline follows the zlib header and the stored block header
+        let idat = 8 + 25 + 8;
+        assert_eq!(&png[idat - 4..idat], b"IDAT");
+        assert_eq!(
+            png[idat + 7..idat + 14],
+            [0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66]
+        );
+        assert_eq!(png[png.len() - 8..png.len() - 4], *b"IEND");
+    }
+
+    #[test]
+    fn large_images_are_split_into_stored_blocks() {
+        let data = vec![7; STORED_BLOCK_SIZE + 10];
+        let stream = zlib_stored(&data);
+        assert_eq!(stream.len(), 2 + 2 * 5 + data.len() + 4);
+        // Only the second block is marked as the last one
+        assert_eq!(stream[2], 0);
+        assert_eq!(stream[2 + 5 + STORED_BLOCK_SIZE], 1);
+    }
+
+    #[test]
+    fn timestamps_use_the_civil_date() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+        assert_eq!(format_timestamp(time), "20240229-123456-789");
+    }
+
+    #[test]
+    fn sequences_number_the_frames() {
+        let directory = std::env::temp_dir().join(format!("plasma-frames-{}", std::process::id()));
+        let mut frames = Box::new(PngSequence::new(&directory, 2, 1).unwrap());
+        for _ in 0..3 {
+            frames.write_frame(&[0, 0]).unwrap();
+        }
+        frames.finish().unwrap();
+        let mut names: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            names,
+            ["frame_0001.png", "frame_0002.png", "frame_0003.png"]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Uncompressed frames streamed to another program.
+//!
+//! Frames are written back to back, either as raw BGRA pixels, which the reader must be
+//! told the size and rate of, or as a YUV4MPEG2 stream whose header describes them. Both
+//! can be piped straight into ffmpeg, for example with
+//! `plasma --pipe-raw y4m | ffmpeg -i - out.mp4`.
+use super::FrameWriter;
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+/// Layout of the streamed frames
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum RawFormat {
+    /// Four bytes per pixel in blue, green, red, alpha order
+    Bgra,
+    /// YUV4MPEG2 stream with full resolution 4:4:4 chroma
+    Y4m,
+}
+
+/// Writes frames of packed ARGB pixels uncompressed into a stream
+pub struct RawWriter<W: Write> {
+    /// Stream receiving the frames
+    writer: W,
+    /// Layout of the frames
+    format: RawFormat,
+    /// Scratch buffer holding a converted frame
+    bytes: Vec<u8>,
+}
+
+impl<W: Write> RawWriter<W> {
+    /// Starts a stream of frames of the given size shown `fps` times per second, writing
+    /// the stream header if the format has one.
+    pub fn new(
+        mut writer: W,
+        format: RawFormat,
+        width: usize,
+        height: usize,
+        fps: f32,
+    ) -> io::Result<Self> {
+        if format == RawFormat::Y4m {
+            let (numerator, denominator) = frame_rate(fps);
+            writeln!(
+                writer,
+                "YUV4MPEG2 W{width} H{height} F{numerator}:{denominator} Ip A1:1 C444"
+            )?;
+        }
+        Ok(Self {
+            writer,
+            format,
+            bytes: Vec::new(),
+        })
+    }
+}
+
+impl<W: Write> FrameWriter for RawWriter<W> {
+    fn write_frame(&mut self, pixels: &[u32]) -> io::Result<()> {
+        self.bytes.clear();
+        match self.format {
+            RawFormat::Bgra => {
+                for &pixel in pixels {
+                    let [a, r, g, b] = pixel.to_be_bytes();
+                    self.bytes.extend([b, g, r, a]);
+                }
+            }
+            RawFormat::Y4m => {
+                self.bytes.extend(b"FRAME\n");
+                // The planes follow each other: every luma sample, then blue and red chroma
+                for plane in 0..3 {
+                    self.bytes
+                        .extend(pixels.iter().map(|&pixel| yuv(pixel)[plane]));
+                }
+            }
+        }
+        self.writer.write_all(&self.bytes)
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Converts a frame rate into the fraction stored by YUV4MPEG2 headers, keeping three
+/// decimals.
+fn frame_rate(fps: f32) -> (u32, u32) {
+    let (mut numerator, mut denominator) = ((fps * 1000.0).round() as u32, 1000);
+    let (mut a, mut b) = (numerator, denominator);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    if a > 1 {
+        numerator /= a;
+        denominator /= a;
+    }
+    (numerator, denominator)
+}
+
+/// Converts a packed ARGB pixel into studio range BT.601 luma and chroma.
+fn yuv(pixel: u32) -> [u8; 3] {
+    let [_, r, g, b] = pixel.to_be_bytes().map(i32::from);
+    let y = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    [y as u8, u as u8, v as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bgra_frames_are_written_back_to_back() {
+        let mut writer = RawWriter::new(Vec::new(), RawFormat::Bgra, 1, 1, 30.0).unwrap();
+        writer.write_frame(&[0xff11_2233]).unwrap();
+        writer.write_frame(&[0x8044_5566]).unwrap();
+        assert_eq!(
+            writer.writer,
+            [0x33, 0x22, 0x11, 0xff, 0x66, 0x55, 0x44, 0x80]
+        );
+    }
+
+    #[test]
+    fn y4m_stream_starts_with_a_header() {
+        let mut writer = RawWriter::new(Vec::new(), RawFormat::Y4m, 2, 1, 29.97).unwrap();
+        writer.write_frame(&[0xffff_ffff, 0xff00_0000]).unwrap();
+        let header = b"YUV4MPEG2 W2 H1 F2997:100 Ip A1:1 C444\n";
+        assert_eq!(&writer.writer[..header.len()], header);
+        assert_eq!(
+            &writer.writer[header.len()..],
+            b"FRAME\n\xeb\x10\x80\x80\x80\x80"
+        );
+    }
+
+    #[test]
+    fn frame_rates_are_reduced_fractions() {
+        assert_eq!(frame_rate(30.0), (30, 1));
+        assert_eq!(frame_rate(12.5), (25, 2));
+    }
+}
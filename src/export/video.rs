@@ -0,0 +1,126 @@
+//! Video encoding through ffmpeg.
+//!
+//! Rather than linking a video codec, the frames are piped as raw 24-bit RGB into an
+//! `ffmpeg` process, which picks the container and codec from the extension of the output
+//! file. ffmpeg must be installed and found on the `PATH`.
+use super::FrameWriter;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+/// Writes frames of packed ARGB pixels into a video file encoded by ffmpeg
+pub struct VideoEncoder {
+    /// Running ffmpeg process
+    child: Child,
+    /// Pipe receiving the raw frames
+    stdin: ChildStdin,
+    /// Scratch buffer holding a frame converted to RGB
+    rgb: Vec<u8>,
+}
+
+impl VideoEncoder {
+    /// Starts ffmpeg to encode frames of the given size into `path`, replacing any
+    /// existing file.
+    ///
+    /// # Errors
+    /// Fails if ffmpeg cannot be started.
+    pub fn new(path: &Path, width: usize, height: usize, fps: f32) -> io::Result<Self> {
+        Self::spawn(command(path, width, height, fps))
+    }
+
+    /// Starts the encoder process built by `command`.
+    fn spawn(mut command: Command) -> io::Result<Self> {
+        let mut child = command.stdin(Stdio::piped()).spawn().map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                io::Error::new(e.kind(), "ffmpeg is required to record videos")
+            } else {
+                e
+            }
+        })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        Ok(Self {
+            child,
+            stdin,
+            rgb: Vec::new(),
+        })
+    }
+}
+
+impl FrameWriter for VideoEncoder {
+    fn write_frame(&mut self, pixels: &[u32]) -> io::Result<()> {
+        self.rgb.clear();
+        for &pixel in pixels {
+            self.rgb.extend(&pixel.to_be_bytes()[1..]);
+        }
+        self.stdin.write_all(&self.rgb)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        let Self {
+            mut child, stdin, ..
+        } = *self;
+        // Closing the pipe tells ffmpeg the video is complete
+        drop(stdin);
+        let status = child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("ffmpeg failed with {status}")))
+        }
+    }
+}
+
+/// Builds the ffmpeg command line reading raw frames from its standard input.
+fn command(path: &Path, width: usize, height: usize, fps: f32) -> Command {
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-y", "-loglevel", "error"])
+        .args(["-f", "rawvideo", "-pixel_format", "rgb24"])
+        .args(["-video_size", &format!("{width}x{height}")])
+        .args(["-framerate", &fps.to_string()])
+        .args(["-i", "-"])
+        // Most codecs need even dimensions, and most players need 4:2:0 chroma
+        .args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(path);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffmpeg_reads_raw_frames_of_the_given_size() {
+        let command = command(Path::new("out.mp4"), 320, 200, 25.0);
+        let args: Vec<_> = command.get_args().map(|a| a.to_str().unwrap()).collect();
+        assert_eq!(command.get_program(), "ffmpeg");
+        assert!(args.windows(2).any(|w| w == ["-video_size", "320x200"]));
+        assert!(args.windows(2).any(|w| w == ["-framerate", "25"]));
+        assert_eq!(args.last(), Some(&"out.mp4"));
+    }
+
+    #[test]
+    fn frames_are_piped_as_rgb() {
+        let mut command = Command::new("cat");
+        command.stdout(Stdio::piped());
+        let mut encoder = VideoEncoder::spawn(command).unwrap();
+        encoder.write_frame(&[0xff11_2233, 0xff44_5566]).unwrap();
+        drop(encoder.stdin);
+        let output = encoder.child.wait_with_output().unwrap();
+        assert_eq!(output.stdout, [0x11, 0x22, 0x33, 0x44, 0x55, 0x66]);
+    }
+
+    #[test]
+    fn missing_program_is_reported() {
+        let error = VideoEncoder::spawn(Command::new("plasma-no-such-program"));
+        assert_eq!(
+            error.err().unwrap().to_string(),
+            "ffmpeg is required to record videos"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! On-screen display of the frame rate and of what is being shown.
+//!
+//! The [`Hud`] keeps the duration of the most recent frames and writes a few lines of
+//! text in the top left corner of finished frames: the frame rate achieved, next to the
+//! one aimed for with `--fps`, the median frame time along with its 95th and 99th
+//! percentiles, and the effect with the plasma shape, palette, and scale. The text uses
+//! the embedded [`font`](crate::font) over a darkened box, and grows with the frame so it
+//! stays readable on large screens.
+use crate::effects::Settings;
+use crate::font::{self, GLYPH_SIZE};
+use crate::preset::value_name;
+use std::collections::VecDeque;
+
+/// Number of frames the statistics are computed over
+const HISTORY: usize = 120;
+
+/// Frame height in pixels above which every font pixel is drawn as a larger square
+const PIXEL_SCALE_HEIGHT: usize = 540;
+
+/// Gap between the text and the edges of its box in font pixels
+const MARGIN: usize = 4;
+
+/// Color of the text
+const TEXT_COLOR: u32 = 0xffff_ffff;
+
+/// Frame statistics shown over the frames
+#[derive(Debug, Clone, Default)]
+pub struct Hud {
+    /// Duration of the most recent frames in seconds, oldest first
+    frame_times: VecDeque<f32>,
+    /// Frames per second the main loop is held to, if any
+    target_rate: Option<f32>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shows the frame rate the main loop is held to next to the achieved one.
+    pub fn set_target_rate(&mut self, target_rate: Option<f32>) {
+        self.target_rate = target_rate;
+    }
+
+    /// Records the time a frame took in seconds, forgetting the oldest frame once the
+    /// last 120 are kept.
+    pub fn record(&mut self, frame_time: f32) {
+        if self.frame_times.len() == HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// Returns the average number of frames per second over the recorded frames, or 0
+    /// before any frame took time.
+    pub fn frame_rate(&self) -> f32 {
+        let total: f32 = self.frame_times.iter().sum();
+        if total > 0.0 {
+            self.frame_times.len() as f32 / total
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the time in seconds that the given fraction of the recorded frames took at
+    /// most, such as 0.5 for the median, or 0 before any frame was recorded.
+    pub fn percentile(&self, fraction: f32) -> f32 {
+        let mut times: Vec<f32> = self.frame_times.iter().copied().collect();
+        if times.is_empty() {
+            return 0.0;
+        }
+        times.sort_unstable_by(f32::total_cmp);
+        let index = (fraction.clamp(0.0, 1.0) * (times.len() - 1) as f32).round() as usize;
+        times[index]
+    }
+
+    /// Returns the lines of text describing the frames and the effect `name` configured
+    /// with `settings`.
+    pub fn lines(&self, name: &str, settings: &Settings) -> Vec<String> {
+        let milliseconds = |fraction| self.percentile(fraction) * 1000.0;
+        let frame_rate = match self.target_rate {
+            Some(target) => format!("{:.1} fps of {target}", self.frame_rate()),
+            None => format!("{:.1} fps", self.frame_rate()),
+        };
+        vec![
+            frame_rate,
+            format!(
+                "frame {:.1} ms, p95 {:.1} ms, p99 {:.1} ms",
+                milliseconds(0.5),
+                milliseconds(0.95),
+                milliseconds(0.99)
+            ),
+            format!("effect {name}"),
+            format!(
+                "shape {}, palette {}",
+                value_name(&settings.shape),
+                settings.palette.name()
+            ),
+            format!("scale {:.1}", settings.scale),
+        ]
+    }
+
+    /// Writes the statistics and the effect `name` configured with `settings` over a
+    /// frame of `width * height` pixels.
+    pub fn draw(
+        &self,
+        buffer: &mut [u32],
+        width: usize,
+        height: usize,
+        name: &str,
+        settings: &Settings,
+    ) {
+        draw_text(buffer, width, height, &self.lines(name, settings));
+    }
+}
+
+/// Writes `lines` in the top left corner of a frame of `width * height` pixels over a box
+/// darkening the frame, cropping whatever does not fit.
+fn draw_text(buffer: &mut [u32], width: usize, height: usize, lines: &[String]) {
+    let scale = 1 + height / PIXEL_SCALE_HEIGHT;
+    let columns = lines
+        .iter()
+        .map(|line| line.len())
+        .max()
+        .unwrap_or_default();
+    let box_width = ((columns * GLYPH_SIZE + 2 * MARGIN) * scale).min(width);
+    let box_height = ((lines.len() * GLYPH_SIZE + 2 * MARGIN) * scale).min(height);
+    for row in buffer.chunks_exact_mut(width).take(box_height) {
+        for pixel in &mut row[..box_width] {
+            // Halving every channel keeps the effect visible behind the text
+            *pixel = 0xff00_0000 | (*pixel >> 1) & 0x007f_7f7f;
+        }
+    }
+    for y in 0..box_height {
+        let Some(line) = (y / scale).checked_sub(MARGIN).map(|y| y / GLYPH_SIZE) else {
+            continue;
+        };
+        let Some(line) = lines.get(line) else {
+            continue;
+        };
+        let glyph_y = (y / scale - MARGIN) % GLYPH_SIZE;
+        for x in 0..box_width {
+            let Some(column) = (x / scale).checked_sub(MARGIN) else {
+                continue;
+            };
+            let Some(c) = line.chars().nth(column / GLYPH_SIZE) else {
+                continue;
+            };
+            if font::is_set(c, column % GLYPH_SIZE, glyph_y) {
+                buffer[y * width + x] = TEXT_COLOR;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_cover_the_recent_frames() {
+        let mut hud = Hud::new();
+        assert_eq!((hud.frame_rate(), hud.percentile(0.5)), (0.0, 0.0));
+        for _ in 0..HISTORY {
+            hud.record(1.0);
+        }
+        for frame in 0..99 {
+            hud.record(if frame == 0 { 0.1 } else { 0.01 });
+        }
+        hud.record(0.1);
+        assert_eq!(hud.percentile(0.5), 0.01);
+        assert_eq!(hud.percentile(0.99), 1.0);
+        assert_eq!(hud.percentile(1.0), 1.0);
+        // 100 frames of the last 120 took 1.18 seconds and the 20 others 20 seconds
+        assert!((hud.frame_rate() - 120.0 / 21.18).abs() < 1e-3);
+    }
+
+    #[test]
+    fn lines_name_the_effect_and_its_settings() {
+        let mut hud = Hud::new();
+        hud.record(0.02);
+        let lines = hud.lines("plasma", &Settings::default());
+        assert_eq!(lines[0], "50.0 fps");
+        assert_eq!(lines[1], "frame 20.0 ms, p95 20.0 ms, p99 20.0 ms");
+        assert_eq!(lines[2], "effect plasma");
+        assert_eq!(lines[3], "shape ripple, palette rainbow");
+        assert_eq!(lines[4], "scale 10.0");
+        hud.set_target_rate(Some(60.0));
+        assert_eq!(
+            hud.lines("plasma", &Settings::default())[0],
+            "50.0 fps of 60"
+        );
+    }
+
+    #[test]
+    fn text_is_drawn_over_a_darkened_box() {
+        let (width, height) = (400, 100);
+        let mut buffer = vec![0xff80_8080; width * height];
+        draw_text(&mut buffer, width, height, &["#".to_string()]);
+        // Outside of the box
+        assert_eq!(buffer[width * 50 + 300], 0xff80_8080);
+        // Margin of the box
+        assert_eq!(buffer[0], 0xff40_4040);
+        // The top row of '#' sets its second and third columns
+        assert_eq!(buffer[MARGIN * width + MARGIN + 1], TEXT_COLOR);
+        assert_eq!(buffer[MARGIN * width + MARGIN], 0xff40_4040);
+    }
+
+    #[test]
+    fn text_is_cropped_to_small_frames() {
+        let mut buffer = vec![0; 6 * 5];
+        let mut hud = Hud::new();
+        hud.record(0.02);
+        hud.draw(&mut buffer, 6, 5, "plasma", &Settings::default());
+        assert!(buffer.iter().all(|&pixel| pixel >> 24 == 0xff));
+    }
+}
@@ -0,0 +1,164 @@
+//! Reading images from PNG files.
+//!
+//! Images are decoded with the `png` crate, so palettes can be taken from grayscale,
+//! truecolor, and indexed pictures with or without alpha at any bit depth, interlaced or
+//! not. Samples are cut down to 8 bits, and alpha is dropped as every effect draws opaque
+//! pixels.
+use crate::common::rgb;
+use png::{Decoder, Transformations};
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+
+/// A decoded image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// Packed ARGB pixels, row by row from the top
+    pub pixels: Vec<u32>,
+}
+
+impl Image {
+    /// Reads an image from a PNG file.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a valid PNG image.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let data = std::fs::read(path)?;
+        Ok(decode_png(&data).map_err(|e| format!("{}: {e}", path.display()))?)
+    }
+
+    /// Returns the pixels of the top row.
+    pub fn top_row(&self) -> &[u32] {
+        &self.pixels[..self.width]
+    }
+}
+
+/// Decodes a PNG image.
+///
+/// # Errors
+/// Fails if the data is not a PNG image, is truncated or corrupted, or is too large to
+/// decode in memory.
+pub fn decode_png(data: &[u8]) -> Result<Image, String> {
+    let mut decoder = Decoder::new(Cursor::new(data));
+    // Palettes and bit depths below 8 are expanded, and 16-bit samples cut down to 8 bits
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let size = reader.output_buffer_size().ok_or("image too large")?;
+    let mut buffer = vec![0; size];
+    let frame = reader.next_frame(&mut buffer).map_err(|e| e.to_string())?;
+    let channels = frame.color_type.samples();
+    let pixels = buffer[..frame.buffer_size()]
+        .chunks_exact(channels)
+        .map(|pixel| match *pixel {
+            [gray] | [gray, _] => rgb(gray, gray, gray),
+            [r, g, b, ..] => rgb(r, g, b),
+            [] => unreachable!("pixels have at least one sample"),
+        })
+        .collect();
+    Ok(Image {
+        width: frame.width as usize,
+        height: frame.height as usize,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::encode_png;
+    use png::{BitDepth, ColorType, Encoder};
+
+    /// Returns a PNG image of the given size, color type, and bit depth holding `data`.
+    fn png(
+        (width, height): (u32, u32),
+        color: ColorType,
+        depth: BitDepth,
+        palette: Option<&[u8]>,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut png = Vec::new();
+        let mut encoder = Encoder::new(&mut png, width, height);
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        if let Some(palette) = palette {
+            encoder.set_palette(palette);
+        }
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+        png
+    }
+
+    #[test]
+    fn encoded_frames_decode_to_the_same_pixels() {
+        let pixels: Vec<u32> = (0..12).map(|i| rgb(i * 20, 255 - i * 7, i)).collect();
+        let image = decode_png(&encode_png(&pixels, 4, 3)).unwrap();
+        assert_eq!((image.width, image.height), (4, 3));
+        assert_eq!(image.pixels, pixels);
+        assert_eq!(image.top_row(), &pixels[..4]);
+    }
+
+    #[test]
+    fn indexed_and_low_depth_images_are_expanded() {
+        let plte: &[u8] = &[255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let image = png(
+            (4, 1),
+            ColorType::Indexed,
+            BitDepth::Two,
+            Some(plte),
+            &[0b0001_1000],
+        );
+        assert_eq!(
+            decode_png(&image).unwrap().pixels,
+            [
+                rgb(255, 0, 0),
+                rgb(0, 255, 0),
+                rgb(0, 0, 255),
+                rgb(255, 0, 0)
+            ]
+        );
+        let image = png(
+            (3, 1),
+            ColorType::Grayscale,
+            BitDepth::One,
+            None,
+            &[0b1010_0000],
+        );
+        assert_eq!(
+            decode_png(&image).unwrap().pixels,
+            [rgb(255, 255, 255), 0xff00_0000, rgb(255, 255, 255)]
+        );
+        // 16-bit RGBA keeps the high byte of every color and drops the alpha
+        let image = png(
+            (1, 1),
+            ColorType::Rgba,
+            BitDepth::Sixteen,
+            None,
+            &[1, 2, 3, 4, 5, 6, 7, 8],
+        );
+        assert_eq!(decode_png(&image).unwrap().pixels, [rgb(1, 3, 5)]);
+        let image = png(
+            (1, 1),
+            ColorType::GrayscaleAlpha,
+            BitDepth::Eight,
+            None,
+            &[9, 0],
+        );
+        assert_eq!(decode_png(&image).unwrap().pixels, [rgb(9, 9, 9)]);
+    }
+
+    #[test]
+    fn invalid_images_are_rejected() {
+        assert!(decode_png(b"GIF89a").is_err());
+        let image = encode_png(&[rgb(1, 2, 3); 6], 3, 2);
+        assert!(decode_png(&image[..image.len() / 2]).is_err());
+        // The checksums of the chunks catch corrupted data
+        let mut corrupted = image.clone();
+        corrupted[20] ^= 1;
+        assert!(decode_png(&corrupted).is_err());
+    }
+}
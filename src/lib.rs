@@ -0,0 +1,37 @@
+//! Old school demo effects rendered into plain pixel buffers.
+//!
+//! Every effect implements [`common::DemoEffect`] and draws into a slice of packed ARGB
+//! pixels, so it can be shown by any frontend able to display such a buffer. The
+//! [`effects`] module lists all effects and builds them by name, while [`palette`] holds
+//! the color palettes they share. The passes in [`post`] rework finished frames, [`hud`]
+//! writes frame statistics over them, and [`export`] saves them as images, which
+//! [`image`] reads back. [`preset`] saves effects along with their parameters,
+//! [`sequence`] plays scripted demos made of several effects, and [`options`] reads
+//! command line options out of TOML files. The [`backends`] show frames somewhere other
+//! than a window, such as a terminal, and [`timing`] paces them.
+//!
+//! # Example
+//! ```
+//! use plasma::effects;
+//!
+//! let registry = effects::registry(&effects::Settings::default());
+//! let mut effect = registry.create("fire", 320, 200).unwrap();
+//! let mut buffer = vec![0u32; 320 * 200];
+//! effect.update(1.0 / 60.0);
+//! effect.draw(&mut buffer, 0.0);
+//! ```
+pub mod backends;
+pub mod common;
+pub mod effects;
+pub mod export;
+pub mod font;
+pub mod hud;
+pub mod image;
+pub mod noise;
+pub mod options;
+pub mod palette;
+pub mod post;
+pub mod preset;
+pub mod sequence;
+pub mod simd;
+pub mod timing;
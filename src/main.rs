@@ -5,116 +5,1673 @@
 //!
 //! # Controls
 //! - `Space`: Cycle through color palettes
+//! - `R`: Reverse the direction of the palette
+//! - `H/Shift+H`: Turn the hues of the palette forward or back
 //! - `Left/Right`: Change pattern shape
 //! - `Up/Down`: Adjust pattern scale
+//! - `2-5`: Set the number of ripple centers of the multi-ripple shape
+//! - `Tab/Shift+Tab`: Switch to the next or previous effect
+//! - `S`: Save a PNG screenshot into the `--screenshot-dir` directory
+//! - `F1-F8`: Recall a preset
+//! - `Shift+F1-F8`: Save the running effect and its parameters as a preset
+//! - `+/-`: Raise or lower the render scale
+//! - `F`: Show or hide the frame rate, frame times, and current settings
+//! - `P`: Pause or resume the animation
+//! - `,/.`: Scrub the animation backward or forward while held, or move it by one
+//!   simulation step while paused
+//! - `Home`: Rewind the animation to its start
+//! - `[/]`: Halve or double the speed of the animation, from 1/16 to 16 times
+//! - `G/B/C/V`: Raise the gamma, brightness, contrast, or saturation, with Shift to
+//!   lower it
 //! - `Escape/Q`: Exit program
 //!
+//! Scrubbing and rewinding move the animation time, which effects drawn from it follow,
+//! while simulations such as particles or smoke go on from where they are.
+//!
+//! The plasma controls only apply while the plasma effect is running. In the water
+//! effect, clicking the window drops a stone into the water, and in the lightning effect
+//! it strikes a bolt at the pointer. The plasma globe filaments reach for the pointer, and
+//! with `--smoke-source mouse` the smoke pours out of it.
+//! In the sand effect, dragging the pointer paints the element picked with `1` (sand),
+//! `2` (water), `3` (wall), or `4` (eraser), starting with the one given by
+//! `--sand-brush`.
+//!
+//! `H` turns the hues by 15 degrees a press. The palette stays reversed and turned as
+//! `Space` moves on to the next one, in the plasma and bump-map effects alike.
+//!
+//! A few other effects reuse the arrow keys:
+//!
+//! - `spirograph`: `Up/Down` adjust the radius ratio, `Left/Right` the pen offset
+//! - `wave-pool`: `Up/Down` add or remove emitters
+//! - `rain`: `Up/Down` make the rain heavier or lighter
+//! - `munching`: `Up/Down` rotate the bit-plane mask
+//! - `hypno`: `Up/Down` add or remove arms, `Left/Right` change the rotation speed
+//! - `caustics`: `Up/Down` make the light brighter or dimmer
+//!
+//! Besides the built-in palettes, `--palette` takes gradient colors written as hex codes,
+//! each optionally followed by `@` and its position from 0 to 1, such as
+//! `--palette '#000000,#ff8000@0.7,#ffffff'`. Colors without a position are spread evenly
+//! between their neighbors. `--palette-file FILE` reads the colors from a file instead,
+//! separated by commas, spaces, or new lines, or written as a JSON array of strings:
+//!
+//! ```json
+//! ["#000000", "#ff8000@0.7", "#ffffff"]
+//! ```
+//!
+//! Gradients blend their colors in the perceptual OKLab color space, so the ramps between
+//! them look even instead of turning dark or grayish half way.
+//!
+//! `--palette-file` also reads the palettes of other pixel-art tools: JASC-PAL `.pal`
+//! files, as saved by Paint Shop Pro and Aseprite, and GIMP `.gpl` palettes, whose colors
+//! are spread evenly along the gradient, as well as GIMP `.ggr` gradients. Their colors
+//! blend into each other, or form flat bands with `--palette-lookup nearest`, which suits
+//! palettes made for pixel art.
+//!
+//! `--palette-image FILE` takes the colors from the top row of a PNG image instead, from
+//! its left edge for the lowest values to its right edge for the highest, so gradients can
+//! be painted in any image editor. `--palette-lookup` applies to them as well.
+//!
+//! Cosine palettes compute every channel as `a + b * cos(2 * pi * (c * v + d))` and come
+//! built in as `spectrum`, `sunset`, `ocean`, `lime`, `desert`, and `neon`. Others are
+//! written with the offset, amplitude, frequency, and phase of the red, green, and blue
+//! channels, such as `--palette cosine:0.8,0.5,0.4:0.2,0.4,0.2:2,1,1:0,0.25,0.25`.
+//!
+//! Gradient palettes come built in as `fire`, `pastel`, and the perceptually uniform
+//! `viridis`, `magma`, `inferno`, and `cividis` colormaps of matplotlib, whose lightness
+//! rises steadily from the lowest values to the highest, so they read well in print and
+//! with color vision deficiencies.
+//!
+//! Space cycles through the built-in palettes, fading into the next one over half a
+//! second, and presets remember custom palettes along with their colors.
+//!
+//! `--color-cycle` brings back the color cycling of old demos: the plasma draws indices
+//! into a palette of 256 colors, and the palette rotates under the still indices, making
+//! the colors flow through the picture. `--cycle-speed TURNS` sets how many times per
+//! second the palette goes around, backward when negative, such as `--cycle-speed -0.5`.
+//! Effects choosing their own colors are not affected.
+//!
+//! Presets are kept in `plasma/presets.toml` under the XDG config directory, usually
+//! `~/.config`. A preset remembers the effect along with its shape, palette, scale,
+//! multi-ripple emitters, and superformula parameters, including changes made with the
+//! controls above such as a reversed palette or turned hues. It also remembers the
+//! parameters other effects change with their own keys, such as the hypno arms or the
+//! rain intensity.
+//!
+//! Several effects can be stacked with `--layer NAME:MODE[:OPACITY]`, repeated once per
+//! layer from bottom to top. The blend mode is one of `add`, `multiply`, `screen`, or
+//! `alpha`, and the optional opacity between 0 and 1 weakens the layer. For example
+//! `--layer plasma:alpha --layer starfield:screen` flies the stars over the plasma. The
+//! stack starts in place of `--effect` and is reached again with Tab as the `layers`
+//! effect.
+//!
+//! Options can also be read from a TOML file given with `--config FILE`. Every key names
+//! a long option without its dashes, and tables only group related options:
+//!
+//! ```toml
+//! effect = "starfield"
+//! width = 800
+//! post = ["bloom", "crt"]
+//!
+//! [starfield]
+//! star-count = 800
+//! ```
+//!
+//! Arrays repeat the option once per item, so `layer = ["plasma:alpha", "fire:add"]`
+//! stacks two layers like `--layer plasma:alpha --layer fire:add` would. Options given
+//! on the command line override the values of the file. While the program runs, changes
+//! saved to the file are applied right away. The running effect is rebuilt with the new
+//! options, or replaced when the file selects another effect, while the window keeps its
+//! size.
+//!
+//! The `superformula` plasma shape draws rings following the superformula of Johan Gielis,
+//! `r = (|cos(m * angle / 4)|^n2 + |sin(m * angle / 4)|^n3)^(-1 / n1)`. Its symmetry `m`
+//! and exponents `n1`, `n2`, and `n3` slowly swing around the values of the
+//! `--superformula-m`, `--superformula-n1`, `--superformula-n2`, and `--superformula-n3`
+//! options, which default to the starfish of `5`, `2`, `7`, and `7`, and may be kept in the
+//! configuration file like any other option:
+//!
+//! ```toml
+//! shape = "superformula"
+//!
+//! [superformula]
+//! superformula-m = 6
+//! superformula-n1 = 1
+//! superformula-n2 = 1
+//! superformula-n3 = 6
+//! ```
+//!
+//! A scripted demo playing several effects one after the other is started with
+//! `--sequence FILE`. The file lists one `[[scene]]` table per scene in TOML:
+//!
+//! ```toml
+//! [[scene]]
+//! effect = "plasma"
+//! duration = 10
+//! palette = "hot"
+//!
+//! [[scene]]
+//! effect = "starfield"
+//! duration = 8
+//! transition = "crossfade"
+//! transition-time = 2
+//! ```
+//!
+//! Every scene names its `effect` and its `duration` in seconds. A scene starts when the
+//! previous one ends unless it gives its own `start` time, and enters with a `transition`:
+//! `cut` (the default), `fade` through black, or `crossfade`, lasting `transition-time`
+//! seconds. Any other key sets the command line option of the same name for the effect.
+//! The demo starts over after the last scene, and Tab reaches it again as the `sequence`
+//! effect.
+//!
+//! The finished frames can be post-processed with `--post`, followed by a comma separated
+//! list of passes applied in order: `blur`, `bloom`, `scanlines`, `vignette`, `chromatic`
+//! (chromatic aberration), and `crt` (barrel distortion of a curved screen). For example
+//! `--post bloom,scanlines,crt` imitates an old monitor.
+//!
+//! Last comes the color grading: `--gamma`, `--brightness`, `--contrast`, and
+//! `--saturation` adjust the finished frames, such as `--gamma 1.4 --saturation 1.2` for
+//! brighter midtones and livelier colors. While running, `G`, `B`, `C`, and `V` raise the
+//! gamma, brightness, contrast, and saturation, and the same keys with Shift lower them.
+//! Grading goes through tables of 256 entries per channel, so it costs next to nothing.
+//!
+//! `--color-depth` then brings the frames down to the colors of a retro display: `rgb565`
+//! (16-bit high color), `rgb332` (8-bit color), `cga` (black, cyan, magenta, and white),
+//! `ega` (16 colors), or `gameboy` (four shades of green). A 4x4 Bayer matrix dithers the
+//! shades in between, so gradients turn into the cross-hatched patterns of old games.
+//!
+//! Effects computing every pixel on their own, such as the plasma, render the rows of a
+//! frame in parallel on every CPU core. `--threads N` picks another number of threads, and
+//! `--threads 1` renders on the main thread only.
+//!
+//! The plasma computes its sines eight pixels at a time with vector instructions. On
+//! hardware without fast floating point, `--sin-table ENTRIES` reads them from a lookup
+//! table of that many samples instead, such as `--sin-table 1024`, trading a little
+//! precision for speed. Smaller tables are faster to cache but show banding sooner.
+//!
+//! `--render-scale SCALE` renders the effects at a fraction of the window resolution and
+//! enlarges the frames to fill the window, so slow machines keep a smooth animation:
+//! `--render-scale 0.5` computes a quarter of the pixels. `+` and `-` change the scale
+//! while running, and `--upscale bilinear` smooths the enlarged pixels instead of
+//! repeating them.
+//!
+//! While running, `--fps N` holds the animation to at most N frames per second, sleeping
+//! between frames and spinning for the last moment so they come out on time without
+//! keeping the CPU busy. The window otherwise shows at most 250 frames per second, as
+//! minifb cannot wait for the display itself, and `--no-vsync` lifts that limit to
+//! measure how fast an effect renders. Terminals and wallpapers keep their own lower
+//! frame rates. The HUD shown with `F` reports the rate achieved next to the `--fps` one.
+//!
+//! Simulations such as particles, smoke, or the game of life advance in fixed steps of
+//! one over `--tick-rate` seconds, 60 per second by default, however fast frames are
+//! shown, so they run at the same speed on every machine. Frames usually fall between two
+//! steps, and `--interpolate` draws the particles of the fountain between their last two
+//! positions instead of the latest one, which smooths their motion when the window shows
+//! more frames than the tick rate. Recordings and screenshots step the simulations the
+//! same way, so they match what the window shows.
+//!
+//! `--record-gif FILE` renders the effect offscreen for `--duration` seconds at `--fps`
+//! frames per second and saves the frames as an animated GIF, without opening a window.
+//! Every frame gets its own palette of 256 colors, and the post-processing passes are
+//! applied as usual.
+//!
+//! `--record FILE` does the same for videos, piping the frames into `ffmpeg`, which must be
+//! installed. The extension of the file picks the format, such as `out.mp4` or `out.webm`.
+//! Recordings are rendered at the window size unless `--resolution WIDTHxHEIGHT` gives
+//! another one, for example `--record out.mp4 --resolution 1920x1080 --fps 60`.
+//!
+//! `--export-frames DIR` saves every frame as a numbered PNG file instead, from
+//! `frame_0001.png` on, to assemble videos with other tools. Frames are rendered at a fixed
+//! step of one over `--fps` seconds, and `--frames COUNT` records an exact number of frames
+//! rather than `--duration` seconds, for example `--export-frames out/ --frames 300`.
+//!
+//! `--pipe-raw` streams the frames uncompressed to the standard output instead, as BGRA
+//! pixels by default or as a YUV4MPEG2 stream with `--pipe-raw y4m`, whose header carries
+//! the frame size and rate: `plasma --pipe-raw y4m --duration 5 | ffmpeg -i - out.mp4`.
+//!
+//! Recordings never open a window, so they also work on servers and CI machines without a
+//! display. `--headless` renders the same way when nothing is recorded, saving the last
+//! frame as a PNG screenshot in `--screenshot-dir` to check that an effect still renders.
+//!
+//! `--backend winit` opens the window with winit and softbuffer instead of minifb, for
+//! desktops where the default window misbehaves, Wayland compositors in particular. The
+//! window keeps its size in logical pixels on HiDPI screens, while effects render at its
+//! physical resolution, so frames stay sharp instead of being enlarged by the scale factor
+//! of the monitor. The backend comes with the default `winit` feature, and builds made with
+//! `--no-default-features` leave it out.
+//!
+//! `--backend wgpu` draws into such a window on the GPU with wgpu. The plasma, tunnel and
+//! fractal effects come with WGSL fragment shaders, which the GPU runs at the physical
+//! resolution of the window, keeping them smooth on 4K screens at high refresh rates. The
+//! other effects, the plasma shapes `circle` and `warp`, and the deepest part of every
+//! Mandelbrot zoom, beyond the precision of the GPU, are rendered on the CPU as usual and
+//! copied into the window by the GPU. So are all frames going through `--post`, color
+//! grading, `--color-depth` or the HUD, which work on the pixels of the CPU. The backend
+//! comes with the default `wgpu` feature. `WGPU_BACKEND=vulkan` and the other variables
+//! read by wgpu choose the graphics API.
+//!
+//! `--backend terminal` shows the effect in the terminal instead of a window, drawing two
+//! pixels per character with half blocks in 24-bit color, which most terminals support.
+//! The frames are shrunk to fit the terminal as it is resized. The keyboard controls are
+//! the same as in the window.
+//!
+//! `--backend sixel` draws the frames as Sixel images instead, at full pixel resolution in
+//! terminals supporting them such as xterm, mlterm and WezTerm. Every frame gets its own
+//! palette of 256 colors, and frames larger than the terminal are shrunk to fit it.
+//!
+//! `--backend braille` draws monochrome frames with Braille patterns, eight dots per
+//! character, dithering the brightness of the effect. The output is plain text, so it
+//! stays sharp and light enough for slow SSH connections and terminals without colors.
+//!
+//! `--backend fbdev` draws straight into the Linux framebuffer at the resolution of the
+//! screen, for kiosks and boards such as the Raspberry Pi running without a desktop. It
+//! uses `/dev/fb0` unless the `FRAMEBUFFER` environment variable names another device,
+//! and reads the keys from the console, so Escape or Q quits.
+//!
+//! `--backend led` drives an RGB LED matrix through a microcontroller speaking the
+//! Adalight protocol, such as an Arduino or WLED, on the serial device `--led-device`.
+//! Frames are shrunk to `--led-width` by `--led-height` LEDs, gamma corrected with
+//! `--led-gamma`, and dimmed to `--led-brightness` to keep the current drawn by the panel
+//! in check. `--led-serpentine` suits panels whose rows are wired back and forth.
+//!
+//! `--wallpaper` animates the background of the X11 desktop instead, drawing into the
+//! root window at the resolution of the screen behind the other windows. Frames are
+//! limited to 15 per second to keep the machine responsive. Desktops that paint their own
+//! background over the root window, such as GNOME, hide it, while plain window managers
+//! such as i3 or Openbox show it.
+//!
+//! `--screensaver` starts with a random effect and exits as soon as a key is pressed or
+//! the mouse moves. `--window-id ID` draws into an existing X11 window, such as the one
+//! XScreenSaver hands over with its `-window-id` option, which plasma understands as well
+//! as `-root`, so it can be listed in `~/.xscreensaver` as `plasma --screensaver -root`.
+//!
+//! `--fullscreen` covers a monitor with a borderless window instead, rendering at its
+//! native resolution. `--monitor N` picks another monitor than the first one, counting
+//! from 0 in the order the display server lists them, and `--monitor all` spans a single
+//! window across every monitor. With `--resolution`, frames are rendered at that size and
+//! scaled up to fill the screen, with black bars keeping their aspect ratio, which eases
+//! the load of heavy effects on large screens. Monitors are listed through X11.
+//!
+//! The window can be resized at any time. Effects are rendered at the new resolution, and
+//! simulations tied to the screen size start over or keep what still fits.
+//!
 //! # Command Line Arguments
 //! ```text
 //! Options:
-//!   -w, --width <WIDTH>      Screen width in pixels [default: 512]
-//!   -h, --height <HEIGHT>    Screen height in pixels [default: 512]
+//!   -e, --effect <EFFECT>    Demo effect to run [default: plasma]
+//!   -w, --width <WIDTH>      Initial screen width in pixels [default: 512]
+//!   -h, --height <HEIGHT>    Initial screen height in pixels [default: 512]
+//!       --backend <BACKEND>  Where the frames are shown [default: window]
+//!       --wallpaper          Animate the X11 desktop background instead of opening a window
+//!       --screensaver        Run as a screensaver, starting with a random effect and exiting on any input
+//!       --window-id <ID>     Draw into the existing X11 window with this id
+//!       --fullscreen         Cover a whole monitor with a borderless window
+//!       --monitor <N|all>    Monitor covered in fullscreen, counting from 0, or all of them [default: 0]
+//!       --layer <LAYER>      Effect stacked on top of the previous layers
+//!       --sequence <FILE>    Play the scripted demo described in a sequence file
+//!       --post <POST>        Post-processing passes applied to every frame, in order
+//!       --color-depth <DEPTH>
+//!                            Imitate the colors of a retro display with ordered dithering
+//!       --threads <N>        Threads rendering every frame, 0 for one per CPU core [default: 0]
+//!       --render-scale <SCALE>
+//!                            Fraction of the window resolution effects render at, enlarged to fill it [default: 1]
+//!       --upscale <UPSCALE>  How frames rendered below the window resolution are enlarged [default: nearest]
+//!       --no-vsync           Show frames as soon as they are rendered instead of waiting for the display
+//!       --tick-rate <HZ>     Updates per second of the simulated effects, whatever the frame rate [default: 60]
+//!       --interpolate        Draw simulated particles between their last two updates for smoother motion
+//!       --reset-time         Restart the animation clock when switching effects
+//!       --config <FILE>      Read options from a TOML file, overridden by the command line
+//!       --screenshot-dir <DIR>
+//!                            Directory receiving the PNG screenshots taken with S [default: .]
+//!       --record-gif <FILE>  Record an animated GIF offscreen instead of opening a window
+//!       --record <FILE>      Record a video with ffmpeg offscreen instead of opening a window
+//!       --export-frames <DIR>
+//!                            Save every frame of the recording as a numbered PNG file in a directory
+//!       --pipe-raw [<FORMAT>]
+//!                            Stream the frames of the recording uncompressed to the standard output
+//!       --frames <COUNT>     Number of frames to record, instead of --duration times --fps
+//!       --headless           Render offscreen without opening a window, saving the last frame
+//!       --resolution <WIDTHxHEIGHT>
+//!                            Size of the recording or fullscreen frames in pixels [default: --width x --height]
+//!       --duration <SECONDS> Length of the recording in seconds [default: 10]
+//!       --fps <FPS>          Frames per second of the recording [default: 30], or most frames shown per second while running
+//!       --list-effects       List the available effects and exit
+//!       --palette-file <FILE>
+//!                            Read the color palette from a file of hex colors or gradient stops, a JASC or GIMP palette, or a GIMP gradient
+//!       --palette-image <FILE>
+//!                            Read the color palette from the top row of a PNG image, from left to right
+//!       --palette-lookup <LOOKUP>
+//!                            How the colors of the palette file or image map onto the effects [default: interpolate]
 //!   -s, --shape <SHAPE>      Initial plasma shape [default: ripple]
 //!   -p, --palette <PALETTE>  Initial color palette [default: rainbow]
 //!   -x, --scale <SCALE>      Pattern scale factor [default: 10.0]
+//!       --palette-reversed   Run the palette from its end to its start, as toggled with R
+//!       --palette-hue <DEGREES>
+//!                            Turn the hues of the palette by this angle, as changed with H [default: 0.0]
+//!       --plasma-emitters <COUNT>
+//!                            Ripple centers orbiting the screen in the multi-ripple plasma shape, from 2 to 5 [default: 3]
+//!       --sin-table <ENTRIES>
+//!                            Approximate the plasma sines with a lookup table of this many entries
+//!       --color-cycle        Rotate the palette of the plasma like old color cycling demos
+//!       --cycle-speed <TURNS>
+//!                            Palette turns per second with --color-cycle [default: 0.25]
 //! ```
-use crate::plasma::Plasma;
-use clap::Parser;
-use minifb::{Key, Window, WindowOptions};
+//!
+//! Effects with tunable parameters list their own options under a dedicated heading in
+//! the `--help` output, and so does the color grading.
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, ValueEnum};
+use minifb::Key;
+use notify::{EventKind, RecursiveMode, Watcher};
+use plasma::backends::{self, led, window::WindowBackend, BackendKind, Event, Filter, Monitor};
+use plasma::common::{self, BlendMode, Compositor, DemoBase, DemoEffect, InputEvent};
+use plasma::effects::{self, registry::EffectRegistry, Settings};
+use plasma::export::raw::{RawFormat, RawWriter};
+use plasma::export::{self, gif::GifEncoder, video::VideoEncoder, FrameWriter, PngSequence};
+use plasma::hud::Hud;
+use plasma::options;
+use plasma::palette::{Lookup, Palette};
+use plasma::post::{ColorDepth, ColorGrade, Grading, Pass, PostEffect};
+use plasma::preset::{self, Preset, Presets};
+use plasma::sequence::{Sequence, Sequencer};
+use plasma::timing::{Clock, FixedTimestep, FramePacer};
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
 use std::time::Instant;
 
-mod plasma;
+/// Change of the render scale per press of `+` or `-`
+const RENDER_SCALE_STEP: f32 = 0.125;
+
+/// Smallest render scale reachable with `-`
+const MIN_RENDER_SCALE: f32 = 0.125;
+
+/// Animation time in seconds skipped per press of `,` or `.` while running
+const SCRUB_TIME: f32 = 0.5;
+
+/// Frames per second of recordings without `--fps`
+const RECORDING_FPS: f32 = 30.0;
 
 #[doc(hidden)]
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    disable_help_flag = true,
+    args_override_self = true
+)]
 struct PlasmaArgs {
-    #[arg(short, long, default_value_t = 512, help = "Screen width in pixels")]
+    #[arg(
+        short,
+        long,
+        default_value = "plasma",
+        value_parser = PossibleValuesParser::new(effects::names()),
+        help = "Demo effect to run"
+    )]
+    effect: String,
+
+    // The short help flag is disabled since -h selects the screen height
+    #[arg(long, action = clap::ArgAction::Help, help = "Print help")]
+    help: Option<bool>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 512,
+        help = "Initial screen width in pixels"
+    )]
     width: usize,
 
-    #[arg(short, long, default_value_t = 512, help = "Screen height in pixels")]
+    #[arg(
+        short,
+        long,
+        default_value_t = 512,
+        help = "Initial screen height in pixels"
+    )]
     height: usize,
 
     #[arg(
-        short,
         long,
         value_enum,
-        default_value_t = plasma::Shape::Ripple,
-        help = "Plasma shape"
+        default_value_t = BackendKind::Window,
+        help = "Where the frames are shown"
     )]
-    shape: plasma::Shape,
+    backend: BackendKind,
+
+    #[arg(
+        long,
+        conflicts_with = "backend",
+        help = "Animate the X11 desktop background instead of opening a window"
+    )]
+    wallpaper: bool,
+
+    #[arg(
+        long,
+        help = "Run as a screensaver, starting with a random effect and exiting on any input"
+    )]
+    screensaver: bool,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        value_parser = backends::parse_window_id,
+        conflicts_with_all = ["backend", "wallpaper"],
+        help = "Draw into the existing X11 window with this id"
+    )]
+    window_id: Option<u64>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["backend", "wallpaper", "window_id"],
+        help = "Cover a whole monitor with a borderless window"
+    )]
+    fullscreen: bool,
+
+    #[arg(
+        long,
+        value_name = "N|all",
+        value_parser = parse_monitor,
+        conflicts_with_all = ["backend", "wallpaper", "window_id"],
+        help = "Monitor covered in fullscreen, counting from 0, or all of them [default: 0]"
+    )]
+    monitor: Option<MonitorArg>,
+
+    #[command(flatten)]
+    led: led::Config,
+
+    #[arg(
+        long = "layer",
+        value_name = "LAYER",
+        value_parser = parse_layer,
+        help = "Effect stacked on top of the previous layers, as NAME:MODE[:OPACITY]"
+    )]
+    layers: Vec<LayerArg>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "layers",
+        help = "Play the scripted demo described in a sequence file"
+    )]
+    sequence: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Post-processing passes applied to every frame, in order"
+    )]
+    post: Vec<Pass>,
+
+    #[command(flatten)]
+    grade: ColorGrade,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "DEPTH",
+        help = "Imitate the colors of a retro display with ordered dithering"
+    )]
+    color_depth: Option<ColorDepth>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "Threads rendering every frame, 0 for one per CPU core"
+    )]
+    threads: usize,
+
+    #[arg(
+        long,
+        value_name = "SCALE",
+        default_value_t = 1.0,
+        value_parser = parse_render_scale,
+        help = "Fraction of the window resolution effects render at, enlarged to fill it"
+    )]
+    render_scale: f32,
 
     #[arg(
-        short,
         long,
         value_enum,
-        default_value_t = plasma::Palette::Rainbow,
-        help = "Plasma color palette"
+        default_value_t = Filter::Nearest,
+        help = "How frames rendered below the window resolution are enlarged"
+    )]
+    upscale: Filter,
+
+    #[arg(
+        long,
+        help = "Show frames as soon as they are rendered instead of waiting for the display"
+    )]
+    no_vsync: bool,
+
+    #[arg(
+        long,
+        value_name = "HZ",
+        default_value_t = 60.0,
+        value_parser = parse_tick_rate,
+        help = "Updates per second of the simulated effects, whatever the frame rate"
+    )]
+    tick_rate: f32,
+
+    #[arg(
+        long,
+        help = "Draw simulated particles between their last two updates for smoother motion"
+    )]
+    interpolate: bool,
+
+    #[arg(
+        long,
+        help = "Restart the animation clock when switching effects with Tab"
+    )]
+    reset_time: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read options from a TOML file, overridden by the command line"
     )]
-    palette: plasma::Palette,
+    config: Option<PathBuf>,
 
     #[arg(
-        short = 'x',
         long,
+        value_name = "DIR",
+        default_value = ".",
+        help = "Directory receiving the PNG screenshots taken with S"
+    )]
+    screenshot_dir: PathBuf,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Record an animated GIF offscreen instead of opening a window"
+    )]
+    record_gif: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "record_gif",
+        help = "Record a video with ffmpeg offscreen instead of opening a window"
+    )]
+    record: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["record_gif", "record"],
+        help = "Save every frame of the recording as a numbered PNG file in a directory"
+    )]
+    export_frames: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "bgra",
+        conflicts_with_all = ["record_gif", "record", "export_frames"],
+        help = "Stream the frames of the recording uncompressed to the standard output"
+    )]
+    pipe_raw: Option<RawFormat>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of frames to record, instead of --duration times --fps"
+    )]
+    frames: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Render offscreen without opening a window, saving the last frame"
+    )]
+    headless: bool,
+
+    #[arg(
+        long,
+        value_name = "WIDTHxHEIGHT",
+        value_parser = parse_resolution,
+        help = "Size of the recording or fullscreen frames in pixels [default: --width x --height]"
+    )]
+    resolution: Option<(usize, usize)>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
         default_value_t = 10.0,
-        help = "Scale factor that controls the density/size of the plasma patterns"
+        help = "Length of the recording in seconds"
+    )]
+    duration: f32,
+
+    #[arg(
+        long,
+        value_parser = parse_frame_rate,
+        help = "Frames per second of the recording [default: 30], or most frames shown per second while running"
+    )]
+    fps: Option<f32>,
+
+    #[arg(long, help = "List the available effects and exit")]
+    list_effects: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_parser = parse_palette_file,
+        conflicts_with = "palette",
+        group = "palette_source",
+        help = "Read the color palette from a file of hex colors or gradient stops, a JASC or GIMP palette, or a GIMP gradient"
+    )]
+    palette_file: Option<Palette>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_parser = parse_palette_image,
+        conflicts_with = "palette",
+        group = "palette_source",
+        help = "Read the color palette from the top row of a PNG image, from left to right"
+    )]
+    palette_image: Option<Palette>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "LOOKUP",
+        default_value_t = Lookup::Interpolate,
+        requires = "palette_source",
+        help = "How the colors of the palette file or image map onto the effects"
     )]
-    scale: f32,
+    palette_lookup: Lookup,
+
+    #[command(flatten)]
+    settings: effects::Settings,
+}
+
+/// An effect stacked by the compositor
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq)]
+struct LayerArg {
+    /// Name of the effect
+    name: String,
+    /// How the effect is blended with the layers below
+    mode: BlendMode,
+    /// Weight of the layer in the [0,1] range
+    opacity: f32,
+}
+
+/// Parses a layer given as `NAME:MODE[:OPACITY]`.
+#[doc(hidden)]
+fn parse_layer(spec: &str) -> Result<LayerArg, String> {
+    let mut parts = spec.split(':');
+    let name = parts.next().unwrap_or_default();
+    if !effects::names().any(|effect| effect == name) {
+        return Err(format!("unknown effect '{name}'"));
+    }
+    let mode = parts
+        .next()
+        .ok_or("missing blend mode, expected NAME:MODE")?;
+    let mode = BlendMode::from_str(mode, true)?;
+    let opacity = match parts.next() {
+        Some(opacity) => opacity
+            .parse::<f32>()
+            .ok()
+            .filter(|opacity| (0.0..=1.0).contains(opacity))
+            .ok_or_else(|| format!("invalid opacity '{opacity}', expected 0 to 1"))?,
+        None => 1.0,
+    };
+    if parts.next().is_some() {
+        return Err("too many fields, expected NAME:MODE[:OPACITY]".to_string());
+    }
+    Ok(LayerArg {
+        name: name.to_string(),
+        mode,
+        opacity,
+    })
+}
+
+/// Monitors covered by a fullscreen window
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MonitorArg {
+    /// A single monitor, by position in the list of the display server
+    Index(usize),
+    /// The area spanning every monitor
+    All,
+}
+
+impl MonitorArg {
+    /// Returns the area of the desktop covered by the selected monitors.
+    fn select(self, monitors: &[Monitor]) -> Result<Monitor, String> {
+        match self {
+            MonitorArg::Index(index) => monitors.get(index).copied().ok_or_else(|| {
+                format!("no monitor {index}, there are {} monitors", monitors.len())
+            }),
+            MonitorArg::All => Monitor::span(monitors).ok_or_else(|| "no monitors found".into()),
+        }
+    }
+}
+
+/// Parses a monitor given as its position, or `all`.
+#[doc(hidden)]
+fn parse_monitor(spec: &str) -> Result<MonitorArg, String> {
+    match spec {
+        "all" => Ok(MonitorArg::All),
+        index => index
+            .parse()
+            .map(MonitorArg::Index)
+            .map_err(|_| format!("invalid monitor '{index}', expected a number or 'all'")),
+    }
+}
+
+/// Parses a render scale, a fraction of the window resolution above 0 and up to 1.
+#[doc(hidden)]
+fn parse_render_scale(spec: &str) -> Result<f32, String> {
+    spec.parse::<f32>()
+        .ok()
+        .filter(|scale| *scale > 0.0 && *scale <= 1.0)
+        .ok_or_else(|| format!("invalid render scale '{spec}', expected more than 0 and up to 1"))
+}
+
+/// Parses a `--tick-rate` value, which must be a positive number of updates per second.
+#[doc(hidden)]
+fn parse_tick_rate(spec: &str) -> Result<f32, String> {
+    spec.parse::<f32>()
+        .ok()
+        .filter(|rate| *rate > 0.0 && rate.is_finite())
+        .ok_or_else(|| format!("invalid tick rate '{spec}', expected a positive number"))
+}
+
+/// Parses an `--fps` value, which must be a positive number of frames per second.
+#[doc(hidden)]
+fn parse_frame_rate(spec: &str) -> Result<f32, String> {
+    spec.parse::<f32>()
+        .ok()
+        .filter(|rate| *rate > 0.0 && rate.is_finite())
+        .ok_or_else(|| format!("invalid frame rate '{spec}', expected a positive number"))
+}
+
+/// Returns the resolution effects render at for an output of `width * height` pixels,
+/// never empty.
+#[doc(hidden)]
+fn render_size((width, height): (usize, usize), scale: f32) -> (usize, usize) {
+    let scaled = |size: usize| ((size as f32 * scale).round() as usize).max(1);
+    (scaled(width), scaled(height))
+}
+
+/// Parses the size of a recording given as `WIDTHxHEIGHT`.
+#[doc(hidden)]
+fn parse_resolution(spec: &str) -> Result<(usize, usize), String> {
+    let (width, height) = spec
+        .split_once('x')
+        .ok_or("expected WIDTHxHEIGHT, for example 1280x720")?;
+    let size = |value: &str| {
+        value
+            .parse::<usize>()
+            .ok()
+            .filter(|&size| size > 0)
+            .ok_or_else(|| format!("invalid size '{value}', expected a positive number"))
+    };
+    Ok((size(width)?, size(height)?))
+}
+
+/// Converts the contents of a configuration file into command line options.
+///
+/// Every key names a long option, and tables only group related options, so
+/// `[starfield]` followed by `star-count = 500` is the same as `star-count = 500` at the
+/// top.
+#[doc(hidden)]
+fn config_arguments(source: &str) -> Result<Vec<String>, String> {
+    let document: toml::Table =
+        toml::from_str(source).map_err(|e| e.to_string().trim_end().to_string())?;
+    // Pairs at the top come before the tables in a TOML file
+    let (tables, top): (toml::Table, toml::Table) = document
+        .into_iter()
+        .partition(|(_, value)| value.is_table());
+    let mut arguments = options::arguments(&top, &["config"])?;
+    for table in tables.values().filter_map(toml::Value::as_table) {
+        arguments.extend(options::arguments(table, &["config"])?);
+    }
+    Ok(arguments)
+}
+
+/// Parses the command line, preceded by the options of the `--config` file if any.
+#[doc(hidden)]
+fn parse_args() -> Result<PlasmaArgs, Box<dyn Error>> {
+    let args = PlasmaArgs::parse_from(xscreensaver_options(std::env::args_os()));
+    match &args.config {
+        Some(path) => args_with_config(path, &fs::read_to_string(path)?),
+        None => Ok(with_palette_file(args)),
+    }
+}
+
+/// Reads the palette of a `--palette-file` option.
+#[doc(hidden)]
+fn parse_palette_file(path: &str) -> Result<Palette, String> {
+    Palette::load(Path::new(path)).map_err(|e| e.to_string())
+}
+
+/// Reads the palette of a `--palette-image` option.
+#[doc(hidden)]
+fn parse_palette_image(path: &str) -> Result<Palette, String> {
+    Palette::from_image(Path::new(path)).map_err(|e| e.to_string())
+}
+
+/// Makes the palette read from `--palette-file` or `--palette-image`, if any, the palette
+/// of the effects, looked up the way `--palette-lookup` tells.
+#[doc(hidden)]
+fn with_palette_file(mut args: PlasmaArgs) -> PlasmaArgs {
+    let palette = args.palette_file.take().or(args.palette_image.take());
+    if let Some(palette) = palette {
+        args.settings.palette = palette.with_lookup(args.palette_lookup);
+    }
+    args
+}
+
+/// Parses the command line preceded by the options read from the configuration file at
+/// `path`, whose contents are `source`.
+#[doc(hidden)]
+fn args_with_config(path: &Path, source: &str) -> Result<PlasmaArgs, Box<dyn Error>> {
+    let options = config_arguments(source).map_err(|e| format!("{}: {e}", path.display()))?;
+    // Options given later win, so the command line overrides the file
+    let mut command_line = xscreensaver_options(std::env::args_os()).into_iter();
+    let program = command_line.next().unwrap_or_default();
+    let args = std::iter::once(program)
+        .chain(options.into_iter().map(Into::into))
+        .chain(command_line);
+    Ok(with_palette_file(PlasmaArgs::try_parse_from(args)?))
+}
+
+/// Spells the options XScreenSaver passes to screensavers the way clap expects them:
+/// `-window-id ID` becomes `--window-id ID` and `-root` becomes `--wallpaper`.
+#[doc(hidden)]
+fn xscreensaver_options(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    args.into_iter()
+        .map(|arg| match arg.to_str() {
+            Some("-window-id") => "--window-id".into(),
+            Some("-root") => "--wallpaper".into(),
+            _ => arg,
+        })
+        .collect()
+}
+
+/// Watches a file from a background thread, sending its contents whenever it changes.
+#[doc(hidden)]
+fn watch(path: PathBuf) -> notify::Result<Receiver<String>> {
+    let path = fs::canonicalize(path)?;
+    let (sender, receiver) = mpsc::channel();
+    let (events, changes) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(events)?;
+    // Editors often save by replacing the file, which would end a watch on the file itself
+    let dir = path.parent().unwrap_or(&path);
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    thread::spawn(move || {
+        // The watcher stops once dropped, so the thread keeps it
+        let _watcher = watcher;
+        for event in changes.iter().flatten() {
+            let changed = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+            if !changed || !event.paths.contains(&path) {
+                continue;
+            }
+            // Editors may briefly leave the file missing while saving it
+            if let Ok(source) = fs::read_to_string(&path) {
+                if sender.send(source).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Prints an error, which clap errors already start with `error:`.
+#[doc(hidden)]
+fn report(e: &(dyn Error + 'static)) {
+    match e.downcast_ref::<clap::Error>() {
+        Some(e) => eprint!("{}", e),
+        None => eprintln!("error: {}", e),
+    }
+}
+
+/// Prints the name and description of every registered effect.
+#[doc(hidden)]
+fn list_effects(registry: &EffectRegistry) {
+    let width = registry.names().map(str::len).max().unwrap_or_default();
+    for (name, description) in registry.descriptions() {
+        println!("{name:<width$}  {description}");
+    }
+}
+
+/// Returns the index of the effect selected by pressing Tab, or Shift+Tab when `backward`.
+#[doc(hidden)]
+fn cycle(current: usize, count: usize, backward: bool) -> usize {
+    if backward {
+        (current + count - 1) % count
+    } else {
+        (current + 1) % count
+    }
+}
+
+/// Builds the effect selected by pressing Tab, or Shift+Tab when `backward`, returning
+/// its index along with the effect.
+///
+/// Effects failing to build are reported and skipped, and `None` is returned when no
+/// effect other than the current one builds, which then keeps running.
+#[doc(hidden)]
+fn switch_effect(
+    setup: &Setup,
+    current: usize,
+    backward: bool,
+    width: usize,
+    height: usize,
+) -> Option<(usize, Box<dyn DemoEffect>)> {
+    let count = setup.names.len();
+    let mut next = current;
+    for _ in 1..count {
+        next = cycle(next, count, backward);
+        match setup.registry.create(&setup.names[next], width, height) {
+            Ok(effect) => return Some((next, effect)),
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+    None
+}
+
+/// Returns the preset slot of a function key, numbered from 1.
+#[doc(hidden)]
+fn preset_slot(key: Key) -> Option<usize> {
+    const KEYS: [Key; preset::SLOTS] = [
+        Key::F1,
+        Key::F2,
+        Key::F3,
+        Key::F4,
+        Key::F5,
+        Key::F6,
+        Key::F7,
+        Key::F8,
+    ];
+    KEYS.iter().position(|&k| k == key).map(|i| i + 1)
+}
+
+/// Saves the running effect along with its current parameters into a preset slot.
+#[doc(hidden)]
+fn save_preset(
+    slot: usize,
+    name: &str,
+    effect: &dyn DemoEffect,
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>> {
+    if !effects::names().any(|builtin| builtin == name) {
+        return Err(format!("the '{name}' effect cannot be saved as a preset").into());
+    }
+    let path = preset::default_path().ok_or("no config directory to keep presets in")?;
+    let mut settings = settings.clone();
+    effect.save_settings(&mut settings);
+    let mut presets = Presets::load(&path)?;
+    presets.set(slot, Preset::new(name, &settings));
+    presets.save(&path)
+}
+
+/// Builds the effect saved in a preset slot, returning its name along with the effect.
+#[doc(hidden)]
+fn load_preset(
+    slot: usize,
+    settings: &Settings,
+    width: usize,
+    height: usize,
+) -> Result<(String, Box<dyn DemoEffect>), Box<dyn Error>> {
+    let path = preset::default_path().ok_or("no config directory to keep presets in")?;
+    let presets = Presets::load(&path)?;
+    let preset = presets
+        .get(slot)
+        .ok_or_else(|| format!("no preset saved with Shift+F{slot}"))?;
+    let mut settings = settings.clone();
+    preset.apply(&mut settings)?;
+    let effect = effects::create(&preset.effect, width, height, &settings)?;
+    Ok((preset.effect.clone(), effect))
+}
+
+/// Effects and passes built from the parsed command line
+#[doc(hidden)]
+struct Setup {
+    /// Parsed command line
+    args: PlasmaArgs,
+    /// Every effect reachable with Tab, including the `--layer` and `--sequence` ones
+    registry: EffectRegistry,
+    /// Names of the registered effects in Tab order
+    names: Vec<String>,
+    /// Name of the effect shown at startup
+    selected: String,
+    /// Post-processing passes applied to every frame
+    post: Vec<Box<dyn PostEffect>>,
+}
+
+impl Setup {
+    fn new(args: PlasmaArgs) -> Result<Self, Box<dyn Error>> {
+        let mut registry = effects::registry(&args.settings);
+        let mut selected = args.effect.clone();
+        if !args.layers.is_empty() {
+            let sources = effects::registry(&args.settings);
+            let layers = args.layers.clone();
+            registry.register(
+                "layers",
+                "Effects stacked with --layer",
+                move |width, height| {
+                    let mut compositor = Compositor::new(width, height);
+                    for layer in &layers {
+                        let effect = sources.create(&layer.name, width, height)?;
+                        compositor.push(effect, layer.mode, layer.opacity);
+                    }
+                    Ok(Box::new(compositor))
+                },
+            );
+            selected = "layers".to_string();
+        }
+        if let Some(path) = &args.sequence {
+            let sequence = Sequence::load(path)?;
+            registry.register(
+                "sequence",
+                "Scripted demo from --sequence",
+                move |width, height| Ok(Box::new(Sequencer::new(width, height, &sequence)?)),
+            );
+            selected = "sequence".to_string();
+        }
+        Ok(Self {
+            names: registry.names().map(String::from).collect(),
+            post: args.post.iter().map(|pass| pass.create()).collect(),
+            args,
+            registry,
+            selected,
+        })
+    }
+
+    /// Returns the position of the named effect in Tab order.
+    fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+}
+
+/// Renders the selected effect offscreen for the length of the recording, without
+/// opening a window.
+///
+/// The frames are saved as an animated GIF, a video, or a PNG sequence, or streamed to the
+/// standard output when recording, and otherwise the last frame is saved as a screenshot.
+#[doc(hidden)]
+fn headless(setup: &Setup) -> Result<(), Box<dyn Error>> {
+    let PlasmaArgs {
+        duration,
+        fps,
+        frames,
+        ..
+    } = setup.args;
+    let (width, height) = setup
+        .args
+        .resolution
+        .unwrap_or((setup.args.width, setup.args.height));
+    let fps = fps.unwrap_or(RECORDING_FPS);
+    let mut effect = setup.registry.create(&setup.selected, width, height)?;
+    let mut encoder: Option<(Box<dyn FrameWriter>, &Path)> =
+        if let Some(path) = &setup.args.record_gif {
+            let file = io::BufWriter::new(fs::File::create(path)?);
+            Some((Box::new(GifEncoder::new(file, width, height, fps)?), path))
+        } else if let Some(path) = &setup.args.record {
+            Some((Box::new(VideoEncoder::new(path, width, height, fps)?), path))
+        } else if let Some(directory) = &setup.args.export_frames {
+            Some((
+                Box::new(PngSequence::new(directory, width, height)?),
+                directory,
+            ))
+        } else if let Some(format) = setup.args.pipe_raw {
+            let stdout = io::BufWriter::new(io::stdout().lock());
+            let writer = RawWriter::new(stdout, format, width, height, fps)?;
+            Some((Box::new(writer), Path::new("-")))
+        } else {
+            None
+        };
+    let mut buffer = vec![0; width * height];
+    let frames = frames.unwrap_or((duration * fps).round() as usize);
+    let mut timestep = FixedTimestep::new(setup.args.tick_rate);
+
+    for frame in 0..frames {
+        // The first frame shows the effect as it starts, and the simulation then advances
+        // in the same fixed steps as in a window
+        let dt = if frame == 0 { 0.0 } else { 1.0 / fps };
+        for _ in 0..timestep.advance(dt) {
+            effect.update(timestep.step());
+        }
+        if setup.args.interpolate {
+            effect.interpolate(timestep.alpha());
+        }
+        effect.draw(&mut buffer, frame as f32 / fps);
+        for pass in &setup.post {
+            pass.apply(&mut buffer, DemoBase::new(width, height));
+        }
+        setup
+            .args
+            .grade
+            .apply(&mut buffer, DemoBase::new(width, height));
+        if let Some(depth) = setup.args.color_depth {
+            depth.apply(&mut buffer, DemoBase::new(width, height));
+        }
+        if let Some((encoder, _)) = &mut encoder {
+            encoder.write_frame(&buffer)?;
+        }
+    }
+    let path = match encoder {
+        // The standard output only carries frames
+        Some((encoder, _)) if setup.args.pipe_raw.is_some() => return Ok(encoder.finish()?),
+        Some((encoder, path)) => {
+            encoder.finish()?;
+            path.to_path_buf()
+        }
+        None => export::screenshot(
+            &setup.args.screenshot_dir,
+            &setup.selected,
+            &buffer,
+            width,
+            height,
+        )?,
+    };
+    println!("saved {}", path.display());
+    Ok(())
 }
 
 #[doc(hidden)]
-fn run(mut plasma: Plasma, width: usize, height: usize) -> Result<(), Box<dyn std::error::Error>> {
-    let mut window = Window::new("Plasma", width, height, WindowOptions::default())?;
+fn run(args: PlasmaArgs) -> Result<(), Box<dyn Error>> {
+    // Size of the frames shown by the backend, and of the frames effects render
+    let mut output = (args.width, args.height);
+    let mut render_scale = args.render_scale;
+    let (mut width, mut height) = render_size(output, render_scale);
+    common::set_threads(args.threads);
+    let mut setup = Setup::new(args)?;
+    if setup.args.headless
+        || setup.args.record_gif.is_some()
+        || setup.args.record.is_some()
+        || setup.args.export_frames.is_some()
+        || setup.args.pipe_raw.is_some()
+    {
+        return headless(&setup);
+    }
+    let reloads = setup.args.config.clone().map(watch).transpose()?;
+    let mut current = if setup.args.screensaver {
+        fastrand::usize(..setup.names.len())
+    } else {
+        setup.position(&setup.selected).unwrap_or_default()
+    };
+    let mut effect = setup
+        .registry
+        .create(&setup.names[current], width, height)?;
+
+    let kind = if setup.args.wallpaper {
+        BackendKind::Wallpaper
+    } else {
+        setup.args.backend
+    };
+    let mut backend = match setup.args.window_id {
+        Some(id) => backends::embed(id)?,
+        None if setup.args.fullscreen || setup.args.monitor.is_some() => {
+            let selection = setup.args.monitor.unwrap_or(MonitorArg::Index(0));
+            let monitor = selection.select(&backends::monitors()?)?;
+            let resolution = setup.args.resolution;
+            Box::new(WindowBackend::fullscreen("Plasma", monitor, resolution)?)
+        }
+        None => backends::create(kind, "Plasma", output.0, output.1, &setup.args.led)?,
+    };
+    if setup.args.no_vsync {
+        backend.disable_vsync();
+    }
+    let mut pacer = setup.args.fps.map(FramePacer::new);
+    // Screensavers stop once the pointer leaves the position it was first seen at
+    let mut pointer = None;
 
-    let start_time = Instant::now();
-    let mut last_key_time = Instant::now();
+    let mut timestep = FixedTimestep::new(setup.args.tick_rate);
+    let mut hud = Hud::new();
+    hud.set_target_rate(setup.args.fps);
+    let mut clock = Clock::new();
+    let mut grade = setup.args.grade;
+    let mut last_frame_time = Instant::now();
     let mut buffer = vec![0; width * height];
+    // Frame enlarged to the output size when rendering below it, or carrying the HUD
+    let mut frame = Vec::new();
+    let mut show_hud = false;
+    // Whether the last frame was drawn by the shader of the effect, leaving the buffer
+    let mut drawn_on_gpu = false;
+
+    while backend.is_open() {
+        // Apply the latest version of the configuration file, keeping the running effect
+        // unless the file selects another one, along with the frame rate, thread count,
+        // and render scale
+        if let Some(source) = reloads.as_ref().and_then(|r| r.try_iter().last()) {
+            let path = setup.args.config.clone().unwrap_or_default();
+            let reloaded = args_with_config(&path, &source).and_then(Setup::new);
+            match reloaded {
+                Ok(reloaded) => {
+                    let name = if reloaded.selected != setup.selected {
+                        &reloaded.selected
+                    } else {
+                        &setup.names[current]
+                    };
+                    let index = reloaded.position(name).unwrap_or_default();
+                    match reloaded
+                        .registry
+                        .create(&reloaded.names[index], width, height)
+                    {
+                        Ok(reloaded_effect) => {
+                            let args = &reloaded.args;
+                            common::set_threads(args.threads);
+                            pacer = args.fps.map(FramePacer::new);
+                            hud.set_target_rate(args.fps);
+                            render_scale = args.render_scale;
+                            grade = args.grade;
+                            (setup, current, effect) = (reloaded, index, reloaded_effect);
+                        }
+                        Err(e) => eprintln!("error: {}", e),
+                    }
+                }
+                Err(e) => report(e.as_ref()),
+            }
+        }
 
-    // Minimum time (in seconds) between key presses
-    // Oddly, the minifb functions set_key_repeat() and set_key_delay() don't work as expected so
-    // we resorted to manual key delay handling.
-    const KEY_DELAY: f32 = 0.15;
+        // Render at the resolution of the backend scaled by the render scale, skipping
+        // sizes with no pixels while minimized
+        let (new_width, new_height) = backend.size();
+        if new_width > 0 && new_height > 0 {
+            output = (new_width, new_height);
+        }
+        if render_size(output, render_scale) != (width, height) {
+            (width, height) = render_size(output, render_scale);
+            buffer = vec![0; width * height];
+            effect.resize(width, height);
+        }
 
-    while window.is_open() {
         let current_time = Instant::now();
-        let key_elapsed = current_time.duration_since(last_key_time).as_secs_f32();
-
-        if key_elapsed >= KEY_DELAY {
-            if let Some(key) = window.get_keys().first() {
-                match key {
-                    Key::Escape => std::process::exit(0),
-                    Key::Q => std::process::exit(0),
-                    Key::Space => plasma.next_palette(),
-                    Key::Up => plasma.decrease_scale(),
-                    Key::Down => plasma.increase_scale(),
-                    Key::Left => plasma.prev_shape(),
-                    Key::Right => plasma.next_shape(),
-                    _ => {}
+        for event in backend.poll_events() {
+            if setup.args.screensaver {
+                let input = match event {
+                    Event::Key { .. } => true,
+                    Event::Mouse { x, y, pressed } => {
+                        pressed || *pointer.get_or_insert((x, y)) != (x, y)
+                    }
+                };
+                if input {
+                    return Ok(());
+                }
+            }
+            let (key, shift) = match event {
+                Event::Key { key, shift } => (key, shift),
+                Event::Mouse { x, y, pressed } => {
+                    // Effects expect positions among the pixels they render
+                    let x = x * width as f32 / output.0 as f32;
+                    let y = y * height as f32 / output.1 as f32;
+                    effect.handle_input(InputEvent::Mouse { x, y, pressed });
+                    continue;
+                }
+            };
+            match key {
+                Key::Escape | Key::Q => return Ok(()),
+                Key::S => {
+                    if drawn_on_gpu {
+                        // The buffer was left alone while the GPU drew the frame
+                        effect.draw(&mut buffer, clock.time());
+                    }
+                    let directory = &setup.args.screenshot_dir;
+                    let name = &setup.names[current];
+                    match export::screenshot(directory, name, &buffer, width, height) {
+                        Ok(path) => println!("saved {}", path.display()),
+                        Err(e) => eprintln!("error: taking a screenshot: {}", e),
+                    }
+                }
+                Key::F => show_hud = !show_hud,
+                Key::P => clock.toggle_pause(),
+                Key::Period if clock.is_paused() => {
+                    clock.step(timestep.step());
+                    effect.update(timestep.step());
+                }
+                Key::Comma if clock.is_paused() => clock.step(-timestep.step()),
+                // Scrubbing only moves the time, as simulations cannot run backward
+                Key::Period => clock.step(SCRUB_TIME * clock.scale()),
+                Key::Comma => clock.step(-SCRUB_TIME * clock.scale()),
+                Key::Home => clock.reset(),
+                Key::G | Key::B | Key::C | Key::V => {
+                    let grading = match key {
+                        Key::G => Grading::Gamma,
+                        Key::B => Grading::Brightness,
+                        Key::C => Grading::Contrast,
+                        _ => Grading::Saturation,
+                    };
+                    grade.step(grading, if shift { -1.0 } else { 1.0 });
                 }
-                last_key_time = current_time;
+                Key::LeftBracket => clock.slow_down(),
+                Key::RightBracket => clock.speed_up(),
+                Key::Equal | Key::NumPadPlus => {
+                    render_scale = (render_scale + RENDER_SCALE_STEP).min(1.0);
+                }
+                Key::Minus | Key::NumPadMinus => {
+                    render_scale = (render_scale - RENDER_SCALE_STEP).max(MIN_RENDER_SCALE);
+                }
+                Key::Tab => {
+                    if let Some(switched) = switch_effect(&setup, current, shift, width, height) {
+                        (current, effect) = switched;
+                        if setup.args.reset_time {
+                            clock.reset();
+                        }
+                    }
+                }
+                key => match preset_slot(key) {
+                    Some(slot) if shift => {
+                        let name = &setup.names[current];
+                        let saved = save_preset(slot, name, &*effect, &setup.args.settings);
+                        if let Err(e) = saved {
+                            eprintln!("error: {}", e);
+                        }
+                    }
+                    Some(slot) => match load_preset(slot, &setup.args.settings, width, height) {
+                        Ok((name, preset)) => {
+                            current = setup.position(&name).unwrap_or(current);
+                            effect = preset;
+                        }
+                        Err(e) => eprintln!("error: {}", e),
+                    },
+                    None if shift => effect.handle_input(InputEvent::ShiftKey(key)),
+                    None => effect.handle_input(InputEvent::Key(key)),
+                },
             }
         }
 
-        let time = start_time.elapsed().as_secs_f32();
-        plasma.draw(&mut buffer, time);
-        window.update_with_buffer(&buffer, width, height)?;
+        let dt = current_time.duration_since(last_frame_time).as_secs_f32();
+        last_frame_time = current_time;
+        hud.record(dt);
+        let dt = clock.advance(dt);
+        for _ in 0..timestep.advance(dt) {
+            effect.update(timestep.step());
+        }
+        if setup.args.interpolate {
+            effect.interpolate(timestep.alpha());
+        }
+
+        // Effects having a shader are drawn on the GPU by backends able to, unless the
+        // frame goes through passes reading its pixels or carries the HUD
+        let gpu = backend.draws_shaders()
+            && setup.post.is_empty()
+            && grade.is_identity()
+            && setup.args.color_depth.is_none()
+            && !show_hud;
+        let shader = gpu.then(|| effect.shader_source(clock.time())).flatten();
+        drawn_on_gpu = shader.is_some();
+        if let Some(shader) = shader {
+            if let Some(pacer) = &mut pacer {
+                pacer.wait();
+            }
+            backend.draw_shader(&shader, width, height, clock.time())?;
+            continue;
+        }
+
+        effect.draw(&mut buffer, clock.time());
+        for pass in &setup.post {
+            pass.apply(&mut buffer, DemoBase::new(width, height));
+        }
+        grade.apply(&mut buffer, DemoBase::new(width, height));
+        if let Some(depth) = setup.args.color_depth {
+            depth.apply(&mut buffer, DemoBase::new(width, height));
+        }
+        if let Some(pacer) = &mut pacer {
+            pacer.wait();
+        }
+        if (width, height) == output && !show_hud {
+            backend.present(&buffer, width, height)?;
+        } else {
+            // The HUD is drawn at the output size to keep the text sharp, and outside of
+            // the buffer so screenshots leave it out
+            frame.resize(output.0 * output.1, 0);
+            let filter = setup.args.upscale;
+            backends::upscale(&buffer, (width, height), &mut frame, output, filter);
+            if show_hud {
+                let mut settings = setup.args.settings.clone();
+                effect.save_settings(&mut settings);
+                let name = &setup.names[current];
+                hud.draw(&mut frame, output.0, output.1, name, &settings);
+            }
+            backend.present(&frame, output.0, output.1)?;
+        }
     }
     Ok(())
 }
 
 #[doc(hidden)]
 fn main() {
-    let args = PlasmaArgs::parse();
-    let plasma = plasma::Plasma::new(
-        args.width,
-        args.height,
-        args.shape,
-        args.palette,
-        args.scale,
-    );
-
-    if let Err(e) = run(plasma, args.width, args.height) {
+    let args = match parse_args() {
+        Ok(args) => args,
+        // Errors of clap come with their own prefix and usage, and so does --help
+        Err(e) => match e.downcast::<clap::Error>() {
+            Ok(e) => e.exit(),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+    if args.list_effects {
+        list_effects(&effects::registry(&args.settings));
+        return;
+    }
+    if let Err(e) = run(args) {
         eprintln!("error: {}", e);
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn command_line_arguments_are_well_formed() {
+        PlasmaArgs::command().debug_assert();
+    }
+
+    #[test]
+    fn layers_parse_name_mode_and_opacity() {
+        let layer = parse_layer("starfield:screen:0.5").unwrap();
+        assert_eq!(
+            layer,
+            LayerArg {
+                name: "starfield".to_string(),
+                mode: BlendMode::Screen,
+                opacity: 0.5,
+            }
+        );
+        assert_eq!(parse_layer("plasma:add").unwrap().opacity, 1.0);
+        assert!(parse_layer("plasma").is_err());
+        assert!(parse_layer("plasma:blur").is_err());
+        assert!(parse_layer("nothing:add").is_err());
+        assert!(parse_layer("plasma:add:2").is_err());
+    }
+
+    #[test]
+    fn render_scales_shrink_the_output_size() {
+        assert_eq!(parse_render_scale("0.5"), Ok(0.5));
+        assert!(parse_render_scale("0").is_err());
+        assert!(parse_render_scale("2").is_err());
+        assert_eq!(render_size((640, 480), 0.5), (320, 240));
+        assert_eq!(render_size((640, 1), 0.25), (160, 1));
+    }
+
+    #[test]
+    fn tick_rates_must_be_positive() {
+        assert_eq!(parse_tick_rate("120"), Ok(120.0));
+        assert!(parse_tick_rate("0").is_err());
+        assert!(parse_tick_rate("inf").is_err());
+    }
+
+    #[test]
+    fn frame_rates_must_be_positive() {
+        assert_eq!(parse_frame_rate("30"), Ok(30.0));
+        assert!(parse_frame_rate("0").is_err());
+        assert!(parse_frame_rate("-60").is_err());
+        assert!(parse_frame_rate("nan").is_err());
+        assert!(parse_frame_rate("inf").is_err());
+    }
+
+    #[test]
+    fn resolutions_parse_width_and_height() {
+        assert_eq!(parse_resolution("1280x720"), Ok((1280, 720)));
+        assert!(parse_resolution("1280").is_err());
+        assert!(parse_resolution("0x720").is_err());
+        assert!(parse_resolution("wide x720").is_err());
+    }
+
+    #[test]
+    fn monitors_are_selected_by_position_or_all_together() {
+        let monitor = |x| Monitor {
+            x,
+            y: 0,
+            width: 800,
+            height: 600,
+        };
+        let monitors = [monitor(0), monitor(800)];
+        assert_eq!(parse_monitor("1"), Ok(MonitorArg::Index(1)));
+        assert_eq!(parse_monitor("all"), Ok(MonitorArg::All));
+        assert!(parse_monitor("left").is_err());
+        assert_eq!(MonitorArg::Index(1).select(&monitors), Ok(monitor(800)));
+        assert!(MonitorArg::Index(2).select(&monitors).is_err());
+        let span = MonitorArg::All.select(&monitors).unwrap();
+        assert_eq!((span.x, span.width, span.height), (0, 1600, 600));
+    }
+
+    #[test]
+    fn xscreensaver_options_are_understood() {
+        let parse = |options: &[&str]| {
+            let options = options.iter().map(OsString::from);
+            PlasmaArgs::try_parse_from(xscreensaver_options(options)).unwrap()
+        };
+        let args = parse(&["plasma", "-window-id", "0x1a00003", "-e", "fire"]);
+        assert_eq!(args.window_id, Some(0x1a0_0003));
+        assert_eq!(args.effect, "fire");
+        assert!(parse(&["plasma", "--screensaver", "-root"]).wallpaper);
+    }
+
+    #[test]
+    fn command_line_overrides_the_config_file() {
+        let source = "effect = \"fire\"\nwidth = 640\n\n[plasma]\npalette = \"hot\"\n";
+        let options = config_arguments(source).unwrap();
+        let args = ["plasma"]
+            .into_iter()
+            .map(String::from)
+            .chain(options)
+            .chain(["--width", "800"].map(String::from));
+        let args = PlasmaArgs::try_parse_from(args).unwrap();
+        assert_eq!(args.effect, "fire");
+        assert_eq!(args.width, 800);
+        assert_eq!(args.settings.palette, Palette::Hot);
+    }
+
+    #[test]
+    fn config_arrays_repeat_the_option() {
+        let source = "layer = [\"plasma:alpha\", \"starfield:screen\"]\nhypno-speed = -0.5\n";
+        let options = config_arguments(source).unwrap();
+        let args = std::iter::once("plasma".to_string()).chain(options);
+        let args = PlasmaArgs::try_parse_from(args).unwrap();
+        assert_eq!(args.layers.len(), 2);
+        assert_eq!(args.settings.hypno.speed, -0.5);
+    }
+
+    #[test]
+    fn config_files_set_the_superformula_parameters() {
+        let source = "shape = \"superformula\"\n\n[superformula]\nsuperformula-m = 6\nsuperformula-n1 = 0.5\n";
+        let options = config_arguments(source).unwrap();
+        let args = std::iter::once("plasma".to_string()).chain(options);
+        let args = PlasmaArgs::try_parse_from(args).unwrap();
+        let superformula = &args.settings.superformula;
+        assert_eq!(
+            (superformula.m, superformula.n1, superformula.n2),
+            (6.0, 0.5, 7.0)
+        );
+    }
+
+    #[test]
+    fn palette_files_replace_the_palette() {
+        let path = std::env::temp_dir().join(format!("plasma-args-{}", std::process::id()));
+        fs::write(&path, "#000000 #ff0000@0.5 #ffffff").unwrap();
+        let file = path.to_str().unwrap();
+        let args = PlasmaArgs::try_parse_from(["plasma", "--palette-file", file]);
+        let conflict = PlasmaArgs::try_parse_from(["plasma", "--palette-file", file, "-p", "hot"]);
+        let nearest = ["--palette-file", file, "--palette-lookup", "nearest"];
+        let nearest = PlasmaArgs::try_parse_from(["plasma"].into_iter().chain(nearest));
+        fs::remove_file(&path).unwrap();
+        let args = with_palette_file(args.unwrap());
+        assert_eq!(args.settings.palette.rgb(0.5), (255, 0, 0));
+        assert!(conflict.is_err());
+        let nearest = with_palette_file(nearest.unwrap());
+        assert_eq!(nearest.settings.palette.rgb(0.3), (255, 0, 0));
+        assert!(PlasmaArgs::try_parse_from(["plasma", "--palette-lookup", "nearest"]).is_err());
+        assert!(PlasmaArgs::try_parse_from(["plasma", "--palette-file", "/nonexistent"]).is_err());
+    }
+
+    #[test]
+    fn palette_images_replace_the_palette() {
+        let path = std::env::temp_dir().join(format!("plasma-args-{}.png", std::process::id()));
+        let pixels = [0xff00_0000, 0xffff_0000, 0xffff_ffff];
+        fs::write(&path, export::encode_png(&pixels, 3, 1)).unwrap();
+        let image = path.to_str().unwrap();
+        let nearest = ["--palette-image", image, "--palette-lookup", "nearest"];
+        let nearest = PlasmaArgs::try_parse_from(["plasma"].into_iter().chain(nearest));
+        let both = ["--palette-image", image, "--palette-file", image];
+        let both = PlasmaArgs::try_parse_from(["plasma"].into_iter().chain(both));
+        fs::remove_file(&path).unwrap();
+        let nearest = with_palette_file(nearest.unwrap());
+        assert_eq!(nearest.settings.palette.rgb(0.4), (255, 0, 0));
+        assert!(both.is_err());
+        assert!(PlasmaArgs::try_parse_from(["plasma", "--palette-image", image]).is_err());
+    }
+
+    #[test]
+    fn watch_sends_the_file_when_it_changes() {
+        let path = std::env::temp_dir().join(format!("plasma-watch-{}.toml", std::process::id()));
+        fs::write(&path, "width = 1").unwrap();
+        let changes = watch(path.clone()).unwrap();
+        fs::write(&path, "width = 2").unwrap();
+        let source = changes.recv_timeout(std::time::Duration::from_secs(5));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(source.unwrap(), "width = 2");
+    }
+
+    #[test]
+    fn function_keys_select_preset_slots() {
+        assert_eq!(preset_slot(Key::F1), Some(1));
+        assert_eq!(preset_slot(Key::F8), Some(preset::SLOTS));
+        assert_eq!(preset_slot(Key::F9), None);
+    }
+
+    #[test]
+    fn tab_skips_effects_failing_to_build() {
+        let args = PlasmaArgs::try_parse_from(["plasma"]).unwrap();
+        let mut setup = Setup::new(args).unwrap();
+        let fire = |w, h| effects::create("fire", w, h, &Settings::default());
+        let mut registry = EffectRegistry::new();
+        registry.register("a", "", fire);
+        registry.register("broken", "", |_, _| Err("broken".into()));
+        registry.register("c", "", fire);
+        setup.names = registry.names().map(String::from).collect();
+        setup.registry = registry;
+        let next = |current, backward| {
+            switch_effect(&setup, current, backward, 8, 8).map(|(index, _)| index)
+        };
+        assert_eq!(next(0, false), Some(2));
+        assert_eq!(next(0, true), Some(2));
+        assert_eq!(next(2, false), Some(0));
+        setup.names.truncate(2);
+        assert!(switch_effect(&setup, 0, false, 8, 8).is_none());
+    }
+
+    #[test]
+    fn tab_cycles_through_effects_in_both_directions() {
+        assert_eq!(cycle(0, 3, false), 1);
+        assert_eq!(cycle(2, 3, false), 0);
+        assert_eq!(cycle(0, 3, true), 2);
+        assert_eq!(cycle(1, 3, true), 0);
+    }
+}
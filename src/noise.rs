@@ -0,0 +1,194 @@
+//! Gradient noise shared by effects that need smooth organic randomness.
+//!
+//! This is Ken Perlin's improved noise in three dimensions. Two of the dimensions usually
+//! map to the screen while the third is driven by time, so the pattern evolves smoothly
+//! instead of scrolling. Several octaves can be layered with [`Noise::fbm`] to add finer
+//! detail on top of the broad shapes.
+
+/// Number of entries in the permutation table, must be a power of two
+const TABLE_SIZE: usize = 256;
+
+/// A seeded three-dimensional gradient noise generator
+#[derive(Debug, Clone)]
+pub struct Noise {
+    /// Shuffled lattice hashes, duplicated to avoid wrapping indices
+    perm: [u8; TABLE_SIZE * 2],
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut table: Vec<u8> = (0..TABLE_SIZE).map(|i| i as u8).collect();
+        rng.shuffle(&mut table);
+
+        let mut perm = [0; TABLE_SIZE * 2];
+        for (i, entry) in perm.iter_mut().enumerate() {
+            *entry = table[i % TABLE_SIZE];
+        }
+        Self { perm }
+    }
+
+    /// Samples the noise at a point, returning a value roughly in [-1,1].
+    ///
+    /// The noise is zero at every integer lattice point and varies smoothly in between.
+    pub fn sample(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, yi, zi) = (lattice(x), lattice(y), lattice(z));
+        let (x, y, z) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.perm;
+        let hash = |i: usize, j: usize, k: usize| p[p[p[i] as usize + j] as usize + k];
+        let corner = |i: usize, j: usize, k: usize, dx: f32, dy: f32, dz: f32| {
+            gradient(hash(xi + i, yi + j, zi + k), x - dx, y - dy, z - dz)
+        };
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(
+                    u,
+                    corner(0, 0, 0, 0.0, 0.0, 0.0),
+                    corner(1, 0, 0, 1.0, 0.0, 0.0),
+                ),
+                lerp(
+                    u,
+                    corner(0, 1, 0, 0.0, 1.0, 0.0),
+                    corner(1, 1, 0, 1.0, 1.0, 0.0),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    corner(0, 0, 1, 0.0, 0.0, 1.0),
+                    corner(1, 0, 1, 1.0, 0.0, 1.0),
+                ),
+                lerp(
+                    u,
+                    corner(0, 1, 1, 0.0, 1.0, 1.0),
+                    corner(1, 1, 1, 1.0, 1.0, 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Sums several octaves of noise, each twice the frequency and half the amplitude of
+    /// the previous one (fractional Brownian motion).
+    ///
+    /// The result is normalized back to roughly [-1,1] regardless of the octave count.
+    pub fn fbm(&self, x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+        for _ in 0..octaves.max(1) {
+            total += self.sample(x * frequency, y * frequency, z * frequency) * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+
+    /// Sums the absolute value of several octaves of noise, like [`Noise::fbm`].
+    ///
+    /// Folding every octave at zero creates sharp creases, which is the classic turbulence
+    /// used for marble veins and flames. The result is normalized to roughly [0,1].
+    pub fn turbulence(&self, x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+        for _ in 0..octaves.max(1) {
+            total += self
+                .sample(x * frequency, y * frequency, z * frequency)
+                .abs()
+                * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+}
+
+/// Returns the permutation table index of the lattice cell containing `v`.
+fn lattice(v: f32) -> usize {
+    (v.floor() as i64).rem_euclid(TABLE_SIZE as i64) as usize
+}
+
+/// Smoothly eases a value in [0,1] so the noise has continuous second derivatives.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Returns the dot product of the offset with one of 12 gradient directions picked by
+/// the hash.
+fn gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_zero_on_lattice_points() {
+        let noise = Noise::new(1);
+        assert_eq!(noise.sample(3.0, -2.0, 7.0), 0.0);
+    }
+
+    #[test]
+    fn noise_stays_within_range() {
+        let noise = Noise::new(2);
+        for i in 0..1000 {
+            let t = i as f32 * 0.173;
+            let v = noise.sample(t, t * 0.7 - 3.0, t * 1.3);
+            assert!((-1.1..=1.1).contains(&v), "{v} out of range");
+        }
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        let noise = Noise::new(3);
+        let a = noise.sample(1.5, 2.25, 0.75);
+        let b = noise.sample(1.501, 2.25, 0.75);
+        assert!((a - b).abs() < 0.01);
+    }
+
+    #[test]
+    fn same_seed_produces_same_noise() {
+        let (a, b) = (Noise::new(4), Noise::new(4));
+        assert_eq!(a.sample(0.3, 0.6, 0.9), b.sample(0.3, 0.6, 0.9));
+    }
+
+    #[test]
+    fn fbm_is_normalized() {
+        let noise = Noise::new(5);
+        for i in 0..200 {
+            let t = i as f32 * 0.31;
+            assert!((-1.1..=1.1).contains(&noise.fbm(t, -t, 0.5, 6)));
+        }
+    }
+
+    #[test]
+    fn turbulence_is_never_negative() {
+        let noise = Noise::new(6);
+        for i in 0..200 {
+            let t = i as f32 * 0.29;
+            assert!((0.0..=1.1).contains(&noise.turbulence(t, t * 0.5, -t, 4)));
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Command line options written in TOML files.
+//!
+//! Configuration files and the scenes of sequence files set options of the program with
+//! `key = value` pairs, read with the `toml` crate. [`arguments`] turns them back into
+//! the long options parsed by clap, so every option can be given in a file without
+//! being declared twice.
+use toml::{Table, Value};
+
+/// Converts the pairs of `table`, except those listed in `skip`, into long command line
+/// options.
+///
+/// `key = value` becomes `--key=value`, which keeps values starting with a dash from
+/// being taken for options, and arrays repeat the option for every item, so
+/// `layer = ["plasma:alpha", "fire:add"]` becomes `--layer=plasma:alpha --layer=fire:add`.
+/// `key = true` becomes a bare `--key`, and `key = false` is left out.
+///
+/// # Errors
+/// Fails if a value is a table, or an array holding arrays or tables, which no option
+/// takes.
+pub fn arguments(table: &Table, skip: &[&str]) -> Result<Vec<String>, String> {
+    let mut arguments = Vec::new();
+    for (key, value) in table
+        .iter()
+        .filter(|(key, _)| !skip.contains(&key.as_str()))
+    {
+        match value {
+            Value::Boolean(false) => {}
+            Value::Boolean(true) => arguments.push(format!("--{key}")),
+            Value::Array(items) => {
+                for item in items {
+                    arguments.push(format!("--{key}={}", to_argument(key, item)?));
+                }
+            }
+            value => arguments.push(format!("--{key}={}", to_argument(key, value)?)),
+        }
+    }
+    Ok(arguments)
+}
+
+/// Formats the value of `key` the way it is written on the command line, with strings
+/// unquoted.
+fn to_argument(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(n) => Ok(n.to_string()),
+        Value::Float(n) => Ok(n.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        Value::Datetime(d) => Ok(d.to_string()),
+        Value::Array(_) | Value::Table(_) => Err(format!("'{key}' cannot hold nested values")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(source: &str) -> Table {
+        source.parse().unwrap()
+    }
+
+    #[test]
+    fn pairs_become_long_options() {
+        let table = table(
+            "palette = \"hot\"\nscale = 2.5\nwidth = 640\nreset-time = true\nquiet = false\neffect = \"fire\"",
+        );
+        assert_eq!(
+            arguments(&table, &["effect"]).unwrap(),
+            [
+                "--palette=hot",
+                "--scale=2.5",
+                "--width=640",
+                "--reset-time"
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_repeat_the_option() {
+        let table =
+            table("layer = [\"plasma:alpha\", \"fire:add:0.5\"]\npost = [\"bloom\", \"crt\"]");
+        assert_eq!(
+            arguments(&table, &[]).unwrap(),
+            [
+                "--layer=plasma:alpha",
+                "--layer=fire:add:0.5",
+                "--post=bloom",
+                "--post=crt"
+            ]
+        );
+    }
+
+    #[test]
+    fn values_starting_with_a_dash_stay_values() {
+        let table = table("hypno-speed = -0.75\npalette = \"-inverted\"");
+        assert_eq!(
+            arguments(&table, &[]).unwrap(),
+            ["--hypno-speed=-0.75", "--palette=-inverted"]
+        );
+    }
+
+    #[test]
+    fn nested_values_are_rejected() {
+        assert!(arguments(&table("a = { b = 1 }"), &[]).is_err());
+        assert!(arguments(&table("a = [[1, 2]]"), &[]).is_err());
+    }
+}
@@ -0,0 +1,1154 @@
+//! Color palettes shared by all demo effects.
+//!
+//! A palette maps a normalized value in the [0,1] range onto a color. Effects compute a
+//! scalar field (plasma height, heat, density, ...) and let the active palette decide how
+//! it looks, so every palette works with every effect.
+//!
+//! Besides the built-in palettes, a [`Gradient`] blends between colors given as hex
+//! codes, optionally followed by their position along the palette, such as
+//! `#000000, #ff8000@0.7, #ffffff`. Colors without a position are spread evenly between
+//! their neighbors, the first and last ones defaulting to the ends of the palette. The
+//! colors may be separated by commas, spaces, or new lines, and may also be written as a
+//! JSON array of strings, so palettes can be kept in files of either form. Gradients blend
+//! their colors in the OKLab color space, so the ramps between them look even instead of
+//! dipping into dark or grayish tones half way.
+//!
+//! Palette files may also come from other pixel-art tools: JASC-PAL files of Paint Shop
+//! Pro and Aseprite and GIMP palettes list colors that are spread evenly along the
+//! gradient, and the segments of GIMP gradients become stops at their ends and middle.
+//! With [`Lookup::Nearest`] the colors of a file form flat bands instead of blending.
+//! [`Palette::from_image`] takes the colors from the top row of a PNG image instead, so a
+//! gradient painted in any image editor maps the values from its left edge to its right.
+//!
+//! The built-in gradient palettes are given by their colors like custom ones, among them
+//! samples of the perceptually uniform viridis, magma, inferno, and cividis colormaps of
+//! matplotlib, whose lightness rises steadily from one end to the other.
+//!
+//! Cosine palettes, popularized by Inigo Quilez, compute every channel as
+//! `a + b * cos(2 * pi * (c * v + d))`, where the offset `a`, amplitude `b`, frequency `c`,
+//! and phase `d` hold one value per channel. A handful of such palettes are built in, and
+//! others are written `cosine:A:B:C:D` with every vector given as three numbers separated
+//! by commas, such as `cosine:0.5,0.5,0.5:0.5,0.5,0.5:1,1,1:0,0.33,0.67`.
+//!
+//! Effects switching palettes while running hold them in a [`Crossfade`], which blends
+//! the colors of the old and new palettes for half a second instead of switching at once.
+//! It may also run the palettes backward or turn their hues, tuning the look of any
+//! palette without defining a new one.
+use crate::common::color::{HueRotation, Oklab};
+use crate::common::rgb;
+use crate::image::Image;
+use clap::ValueEnum;
+use std::error::Error;
+use std::f32::consts::TAU;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock};
+
+/// Time in seconds a palette takes to fade into the next one
+const CROSSFADE_TIME: f32 = 0.5;
+
+/// Number of colors sampled along a gradient
+const TABLE_SIZE: usize = 4096;
+
+/// Built-in cosine palettes by name, with their offset, amplitude, frequency, and phase
+const COSINE_PRESETS: [(&str, [[f32; 3]; 4]); 6] = [
+    (
+        "spectrum",
+        [
+            [0.5, 0.5, 0.5],
+            [0.5, 0.5, 0.5],
+            [1.0, 1.0, 1.0],
+            [0.0, 0.33, 0.67],
+        ],
+    ),
+    (
+        "sunset",
+        [
+            [0.5, 0.5, 0.5],
+            [0.5, 0.5, 0.5],
+            [1.0, 1.0, 1.0],
+            [0.0, 0.1, 0.2],
+        ],
+    ),
+    (
+        "ocean",
+        [
+            [0.5, 0.5, 0.5],
+            [0.5, 0.5, 0.5],
+            [1.0, 1.0, 1.0],
+            [0.3, 0.2, 0.2],
+        ],
+    ),
+    (
+        "lime",
+        [
+            [0.5, 0.5, 0.5],
+            [0.5, 0.5, 0.5],
+            [1.0, 1.0, 0.5],
+            [0.8, 0.9, 0.3],
+        ],
+    ),
+    (
+        "desert",
+        [
+            [0.5, 0.5, 0.5],
+            [0.5, 0.5, 0.5],
+            [1.0, 0.7, 0.4],
+            [0.0, 0.15, 0.2],
+        ],
+    ),
+    (
+        "neon",
+        [
+            [0.5, 0.5, 0.5],
+            [0.5, 0.5, 0.5],
+            [2.0, 1.0, 0.0],
+            [0.5, 0.2, 0.25],
+        ],
+    ),
+];
+
+/// Colors of the `fire` palette, spread evenly from one end to the other
+const FIRE: [u32; 6] = [0x000000, 0x800000, 0xe03000, 0xff9000, 0xffe040, 0xffffff];
+
+/// Colors of the `pastel` palette, ending where it starts so cycled colors wrap smoothly
+const PASTEL: [u32; 7] = [
+    0xffb3ba, 0xffdfba, 0xffffba, 0xbaffc9, 0xbae1ff, 0xd7baff, 0xffb3ba,
+];
+
+/// The viridis colormap of matplotlib, sampled at nine points
+const VIRIDIS: [u32; 9] = [
+    0x440154, 0x472d7b, 0x3b528b, 0x2c728e, 0x21918c, 0x28ae80, 0x5ec962, 0xaddc30, 0xfde725,
+];
+
+/// The magma colormap of matplotlib, sampled at nine points
+const MAGMA: [u32; 9] = [
+    0x000004, 0x1c1044, 0x4f127b, 0x812581, 0xb5367a, 0xe55064, 0xfb8761, 0xfec287, 0xfcfdbf,
+];
+
+/// The inferno colormap of matplotlib, sampled at nine points
+const INFERNO: [u32; 9] = [
+    0x000004, 0x1f0c48, 0x550f6d, 0x88226a, 0xba3655, 0xe35933, 0xf98e09, 0xf9cb35, 0xfcffa4,
+];
+
+/// The cividis colormap of matplotlib, sampled at nine points
+const CIVIDIS: [u32; 9] = [
+    0x00224e, 0x123570, 0x3b496c, 0x575d6d, 0x707173, 0x8a8779, 0xa69d75, 0xc4b56c, 0xfee838,
+];
+
+/// Gradients of the built-in palettes given by their colors, in the order of their
+/// variants, sampled into tables the first time one is used
+static GRADIENTS: LazyLock<[Gradient; 6]> = LazyLock::new(|| {
+    let colors: [&[u32]; 6] = [&FIRE, &PASTEL, &VIRIDIS, &MAGMA, &INFERNO, &CIVIDIS];
+    colors.map(|colors| {
+        let colors = colors
+            .iter()
+            .map(|color| {
+                let [_, r, g, b] = color.to_be_bytes();
+                (r, g, b)
+            })
+            .collect();
+        Gradient::evenly(colors).expect("built-in gradients have colors")
+    })
+});
+
+/// Available color palettes for rendering the effects
+#[derive(Debug, PartialEq, Clone)]
+pub enum Palette {
+    Rainbow,
+    BlueCyan,
+    Hot,
+    PurplePink,
+    BlackWhite,
+    /// Black through red and orange to white, like glowing embers
+    Fire,
+    /// Soft pink, peach, yellow, mint, blue, and lilac, back to pink
+    Pastel,
+    /// Perceptually uniform dark blue through green to yellow, also readable in grayscale
+    Viridis,
+    /// Perceptually uniform black through purple and pink to pale yellow
+    Magma,
+    /// Perceptually uniform black through purple and orange to bright yellow
+    Inferno,
+    /// Perceptually uniform blue to yellow, readable with color vision deficiencies
+    Cividis,
+    /// Colors blended along a gradient, read from the command line or a file
+    Custom(Arc<Gradient>),
+    /// Channels following the cosine `a + b * cos(2 * pi * (c * v + d))`
+    Cosine {
+        /// Offset of every channel
+        a: [f32; 3],
+        /// Amplitude of every channel
+        b: [f32; 3],
+        /// Frequency of every channel
+        c: [f32; 3],
+        /// Phase of every channel, in turns
+        d: [f32; 3],
+    },
+}
+
+impl Palette {
+    /// Built-in palettes in cycling order
+    pub const BUILTINS: [Palette; 17] = [
+        Palette::Rainbow,
+        Palette::BlueCyan,
+        Palette::Hot,
+        Palette::PurplePink,
+        Palette::BlackWhite,
+        Palette::cosine(COSINE_PRESETS[0].1),
+        Palette::cosine(COSINE_PRESETS[1].1),
+        Palette::cosine(COSINE_PRESETS[2].1),
+        Palette::cosine(COSINE_PRESETS[3].1),
+        Palette::cosine(COSINE_PRESETS[4].1),
+        Palette::cosine(COSINE_PRESETS[5].1),
+        Palette::Fire,
+        Palette::Pastel,
+        Palette::Viridis,
+        Palette::Magma,
+        Palette::Inferno,
+        Palette::Cividis,
+    ];
+
+    /// Returns the cosine palette of the offset, amplitude, frequency, and phase vectors.
+    const fn cosine([a, b, c, d]: [[f32; 3]; 4]) -> Self {
+        Palette::Cosine { a, b, c, d }
+    }
+
+    /// Reads a custom palette from a file of gradient colors, a JASC-PAL or GIMP palette,
+    /// or a GIMP gradient, told apart by their first line.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or does not hold a palette.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let source = std::fs::read_to_string(path)?;
+        let gradient = match source.lines().next().map(str::trim) {
+            Some("JASC-PAL") => parse_jasc(&source),
+            Some("GIMP Palette") => parse_gpl(&source),
+            Some("GIMP Gradient") => parse_ggr(&source),
+            _ => source.parse(),
+        };
+        let gradient = gradient.map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Palette::Custom(Arc::new(gradient)))
+    }
+
+    /// Reads a custom palette from the top row of a PNG image, spreading its pixels evenly
+    /// from one end of the palette to the other.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or is not a PNG image.
+    pub fn from_image(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let image = Image::load(path)?;
+        let colors = image
+            .top_row()
+            .iter()
+            .map(|&pixel| {
+                let [_, r, g, b] = pixel.to_be_bytes();
+                (r, g, b)
+            })
+            .collect();
+        let gradient = Gradient::evenly(colors).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Palette::Custom(Arc::new(gradient)))
+    }
+
+    /// Returns the palette looking its colors up the way `lookup` tells, which only
+    /// changes custom palettes.
+    pub fn with_lookup(self, lookup: Lookup) -> Self {
+        match (self, lookup) {
+            (Palette::Custom(gradient), Lookup::Nearest) => {
+                Palette::Custom(Arc::new(gradient.nearest()))
+            }
+            (palette, _) => palette,
+        }
+    }
+
+    /// Returns the command line name of the palette, or `custom` for gradients and
+    /// `cosine` for cosine palettes that are not built in.
+    pub fn name(&self) -> &'static str {
+        match self {
+            &Palette::Cosine { a, b, c, d } => COSINE_PRESETS
+                .iter()
+                .find(|(_, vectors)| *vectors == [a, b, c, d])
+                .map_or("cosine", |(name, _)| name),
+            Palette::Rainbow => "rainbow",
+            Palette::BlueCyan => "blue-cyan",
+            Palette::Hot => "hot",
+            Palette::PurplePink => "purple-pink",
+            Palette::BlackWhite => "black-white",
+            Palette::Fire => "fire",
+            Palette::Pastel => "pastel",
+            Palette::Viridis => "viridis",
+            Palette::Magma => "magma",
+            Palette::Inferno => "inferno",
+            Palette::Cividis => "cividis",
+            Palette::Custom(_) => "custom",
+        }
+    }
+
+    /// Returns the palette following this one in the cycling sequence, which goes through
+    /// the built-in palettes and continues with the first one after a custom palette.
+    pub fn next(&self) -> Self {
+        let index = Self::BUILTINS.iter().position(|palette| palette == self);
+        let next = index.map_or(0, |index| (index + 1) % Self::BUILTINS.len());
+        Self::BUILTINS[next].clone()
+    }
+
+    /// Maps a normalized value in the [0,1] range onto red, green, and blue components.
+    pub fn rgb(&self, v: f32) -> (u8, u8, u8) {
+        match self {
+            Palette::Rainbow => hsv_to_rgb(v * 360.0, 1.0, 1.0),
+            Palette::BlueCyan => hsv_to_rgb(v * 120.0 + 180.0, 0.8, 1.0),
+            Palette::Hot => hsv_to_rgb(v * 60.0, 1.0, 1.0),
+            Palette::PurplePink => hsv_to_rgb(v * 60.0 + 270.0, 0.7, 1.0),
+            Palette::BlackWhite => {
+                let gray = (v * 255.0) as u8;
+                (gray, gray, gray)
+            }
+            Palette::Fire => GRADIENTS[0].rgb(v),
+            Palette::Pastel => GRADIENTS[1].rgb(v),
+            Palette::Viridis => GRADIENTS[2].rgb(v),
+            Palette::Magma => GRADIENTS[3].rgb(v),
+            Palette::Inferno => GRADIENTS[4].rgb(v),
+            Palette::Cividis => GRADIENTS[5].rgb(v),
+            Palette::Custom(gradient) => gradient.rgb(v),
+            Palette::Cosine { a, b, c, d } => {
+                let channel =
+                    |i: usize| (a[i] + b[i] * (TAU * (c[i] * v + d[i])).cos()).clamp(0.0, 1.0);
+                let byte = |i: usize| channel(i).mul_add(255.0, 0.5) as u8;
+                (byte(0), byte(1), byte(2))
+            }
+        }
+    }
+
+    /// Maps a normalized value in the [0,1] range onto a packed ARGB pixel.
+    pub fn color(&self, v: f32) -> u32 {
+        let (r, g, b) = self.rgb(v);
+        rgb(r, g, b)
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Parses the name of a built-in palette, ignoring case, the vectors of a cosine
+    /// palette, or the colors of a gradient.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(palette) = Self::BUILTINS
+            .iter()
+            .find(|palette| palette.name().eq_ignore_ascii_case(s.trim()))
+        {
+            return Ok(palette.clone());
+        }
+        if let Some(vectors) = s.trim().strip_prefix("cosine:") {
+            return parse_cosine(vectors);
+        }
+        if s.trim_start().starts_with(['#', '[']) {
+            return Ok(Palette::Custom(Arc::new(s.parse()?)));
+        }
+        let names: Vec<&str> = Self::BUILTINS.iter().map(Palette::name).collect();
+        Err(format!(
+            "unknown palette '{s}', expected one of {}, cosine:A:B:C:D, or gradient colors such as '#000000,#ffffff'",
+            names.join(", ")
+        ))
+    }
+}
+
+impl fmt::Display for Palette {
+    /// Writes the name of a built-in palette, or the colors of a gradient, in a form
+    /// parsed back into the same palette.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Palette::Custom(gradient) => gradient.fmt(f),
+            Palette::Cosine { a, b, c, d } if self.name() == "cosine" => {
+                let [a, b, c, d] = [a, b, c, d].map(|[x, y, z]| format!("{x},{y},{z}"));
+                write!(f, "cosine:{a}:{b}:{c}:{d}")
+            }
+            palette => f.write_str(palette.name()),
+        }
+    }
+}
+
+/// How the colors of a custom palette map onto the values of the effects
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Lookup {
+    /// Blend between the neighboring colors
+    Interpolate,
+    /// Take the color of the nearest stop, forming flat bands
+    Nearest,
+}
+
+/// A color at a position along a gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    /// Position along the gradient in the [0,1] range
+    pub position: f32,
+    /// Red, green, and blue components
+    pub color: (u8, u8, u8),
+}
+
+/// Colors blended in OKLab between stops sorted by position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    /// Stops in increasing position, at least one
+    stops: Vec<Stop>,
+    /// Colors sampled at evenly spread positions, looked up rather than blending the
+    /// stops in OKLab for every pixel
+    table: Vec<(u8, u8, u8)>,
+}
+
+impl Gradient {
+    /// Returns the stops of the gradient in increasing position.
+    pub fn stops(&self) -> &[Stop] {
+        &self.stops
+    }
+
+    /// Maps a normalized value in the [0,1] range onto red, green, and blue components,
+    /// taking the color of the nearest stop beyond the first and last ones.
+    pub fn rgb(&self, v: f32) -> (u8, u8, u8) {
+        let last = self.table.len() - 1;
+        self.table[(v.clamp(0.0, 1.0) * last as f32).round() as usize]
+    }
+
+    /// Returns a gradient of flat bands, every point taking the color of the nearest stop
+    /// of this gradient.
+    pub fn nearest(&self) -> Self {
+        let mut stops = Vec::with_capacity(self.stops.len() * 2);
+        for (i, stop) in self.stops.iter().enumerate() {
+            let middle = |other: &Stop| (stop.position + other.position) * 0.5;
+            let start = i
+                .checked_sub(1)
+                .map_or(stop.position, |i| middle(&self.stops[i]));
+            let end = self.stops.get(i + 1).map_or(stop.position, middle);
+            stops.push(Stop {
+                position: start,
+                color: stop.color,
+            });
+            stops.push(Stop {
+                position: end,
+                color: stop.color,
+            });
+        }
+        Self::new(stops)
+    }
+
+    /// Returns a gradient through `colors` spread evenly from one end to the other.
+    ///
+    /// # Errors
+    /// Fails without colors.
+    fn evenly(colors: Vec<(u8, u8, u8)>) -> Result<Self, String> {
+        if colors.is_empty() {
+            return Err("a palette needs at least one color".into());
+        }
+        let positions = spread(vec![None; colors.len()]);
+        let stops = colors
+            .into_iter()
+            .zip(positions)
+            .map(|(color, position)| Stop { position, color })
+            .collect();
+        Ok(Self::new(stops))
+    }
+
+    /// Returns a gradient through `stops`, which must be sorted by position.
+    fn new(stops: Vec<Stop>) -> Self {
+        let colors: Vec<Oklab> = stops
+            .iter()
+            .map(|stop| Oklab::from_rgb(stop.color))
+            .collect();
+        let blend = |v: f32| {
+            let next = stops.partition_point(|stop| stop.position <= v);
+            match (stops.get(next.wrapping_sub(1)), stops.get(next)) {
+                (Some(before), Some(after)) => {
+                    let t = (v - before.position) / (after.position - before.position);
+                    colors[next - 1].lerp(colors[next], t).to_rgb()
+                }
+                (None, _) => stops[0].color,
+                (_, None) => stops[stops.len() - 1].color,
+            }
+        };
+        let last = (TABLE_SIZE - 1) as f32;
+        let table = (0..TABLE_SIZE).map(|i| blend(i as f32 / last)).collect();
+        Self { stops, table }
+    }
+}
+
+impl FromStr for Gradient {
+    type Err = String;
+
+    /// Parses hex colors, each optionally followed by `@` and its position, separated by
+    /// commas or white space, or written as a JSON array of strings.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let separator = |c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '"');
+        let mut colors = Vec::new();
+        for token in s.split(separator).filter(|token| !token.is_empty()) {
+            let (color, position) = match token.split_once('@') {
+                Some((color, position)) => {
+                    let position = position
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|p| (0.0..=1.0).contains(p))
+                        .ok_or_else(|| format!("invalid position in '{token}', expected 0 to 1"))?;
+                    (color, Some(position))
+                }
+                None => (token, None),
+            };
+            colors.push((parse_hex(color)?, position));
+        }
+        if colors.is_empty() {
+            return Err("a gradient needs at least one color".into());
+        }
+        let positions = spread(colors.iter().map(|&(_, position)| position).collect());
+        if positions.windows(2).any(|pair| pair[1] < pair[0]) {
+            return Err("gradient positions must not decrease".into());
+        }
+        let stops = colors
+            .iter()
+            .zip(positions)
+            .map(|(&(color, _), position)| Stop { position, color })
+            .collect();
+        Ok(Self::new(stops))
+    }
+}
+
+impl fmt::Display for Gradient {
+    /// Writes every stop as `#rrggbb@position`, separated by commas.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, stop) in self.stops.iter().enumerate() {
+            let (r, g, b) = stop.color;
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{separator}#{r:02x}{g:02x}{b:02x}@{}", stop.position)?;
+        }
+        Ok(())
+    }
+}
+
+/// A palette fading in over the one it replaced, possibly reversed or with its hues
+/// turned, which carries over to the following palettes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crossfade {
+    /// Palette shown once the fade is over
+    palette: Palette,
+    /// Palette fading out, if a fade is under way
+    previous: Option<Palette>,
+    /// Time elapsed since the fade started in seconds
+    elapsed: f32,
+    /// Whether the palettes run from their end to their start
+    reversed: bool,
+    /// Angle the hues of the palettes are turned by in degrees, from 0 to 360
+    hue: f32,
+    /// Rotation of the hues, if they are turned at all
+    rotation: Option<HueRotation>,
+}
+
+impl Crossfade {
+    /// Returns a crossfade showing `palette` right away.
+    pub fn new(palette: Palette) -> Self {
+        Self {
+            palette,
+            previous: None,
+            elapsed: 0.0,
+            reversed: false,
+            hue: 0.0,
+            rotation: None,
+        }
+    }
+
+    /// Returns the palette shown once the fade is over.
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Starts fading into `palette`. A fade still under way jumps to its end first.
+    pub fn set(&mut self, palette: Palette) {
+        self.previous = Some(std::mem::replace(&mut self.palette, palette));
+        self.elapsed = 0.0;
+    }
+
+    /// Starts fading into the palette following the current one in the cycling sequence.
+    pub fn next(&mut self) {
+        self.set(self.palette.next());
+    }
+
+    /// Returns whether the palettes run from their end to their start.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Swaps the ends of the palettes.
+    pub fn reverse(&mut self) {
+        self.reversed = !self.reversed;
+    }
+
+    /// Returns the angle the hues of the palettes are turned by in degrees.
+    pub fn hue(&self) -> f32 {
+        self.hue
+    }
+
+    /// Turns the hues of the palettes by `degrees` more, negative to turn them back.
+    pub fn rotate_hue(&mut self, degrees: f32) {
+        self.hue = (self.hue + degrees).rem_euclid(360.0);
+        // Angles close to a full turn are snapped back to no rotation at all
+        if self.hue.min(360.0 - self.hue) < 1e-3 {
+            self.hue = 0.0;
+        }
+        self.rotation = (self.hue != 0.0).then(|| HueRotation::new(self.hue));
+    }
+
+    /// Reverses the palettes or not, and turns their hues by `hue` degrees.
+    pub fn set_look(&mut self, reversed: bool, hue: f32) {
+        self.reversed = reversed;
+        self.hue = 0.0;
+        self.rotate_hue(hue);
+    }
+
+    /// Advances the fade by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        if self.elapsed >= CROSSFADE_TIME {
+            self.previous = None;
+        }
+    }
+
+    /// Maps a normalized value in the [0,1] range onto red, green, and blue components,
+    /// blending the colors of both palettes while fading.
+    pub fn rgb(&self, v: f32) -> (u8, u8, u8) {
+        let v = if self.reversed { 1.0 - v } else { v };
+        let color = match &self.previous {
+            Some(previous) => {
+                let t = self.elapsed / CROSSFADE_TIME;
+                let ((r0, g0, b0), (r1, g1, b1)) = (previous.rgb(v), self.palette.rgb(v));
+                let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                (mix(r0, r1), mix(g0, g1), mix(b0, b1))
+            }
+            None => self.palette.rgb(v),
+        };
+        match &self.rotation {
+            Some(rotation) => rotation.apply(color),
+            None => color,
+        }
+    }
+
+    /// Maps a normalized value in the [0,1] range onto a packed ARGB pixel.
+    pub fn color(&self, v: f32) -> u32 {
+        let (r, g, b) = self.rgb(v);
+        rgb(r, g, b)
+    }
+}
+
+/// Parses the offset, amplitude, frequency, and phase vectors of a cosine palette,
+/// written `A:B:C:D` with three numbers separated by commas in every vector.
+fn parse_cosine(vectors: &str) -> Result<Palette, String> {
+    let invalid = || {
+        format!(
+            "invalid cosine palette '{vectors}', expected four vectors A:B:C:D of three numbers"
+        )
+    };
+    let vectors: Vec<[f32; 3]> = vectors
+        .split(':')
+        .map(|vector| {
+            let numbers: Vec<f32> = vector
+                .split(',')
+                .map(|number| number.trim().parse::<f32>().map_err(|_| invalid()))
+                .collect::<Result<_, _>>()?;
+            numbers.try_into().map_err(|_| invalid())
+        })
+        .collect::<Result<_, _>>()?;
+    let vectors: [[f32; 3]; 4] = vectors.try_into().map_err(|_| invalid())?;
+    Ok(Palette::cosine(vectors))
+}
+
+/// Parses a JASC-PAL palette: a header, a version, the number of colors, and a line of
+/// decimal red, green, and blue components per color.
+fn parse_jasc(source: &str) -> Result<Gradient, String> {
+    let mut lines = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let count = lines
+        .nth(2)
+        .and_then(|line| line.parse::<usize>().ok())
+        .ok_or("missing number of colors in JASC palette")?;
+    let colors = lines.map(parse_rgb).collect::<Result<Vec<_>, _>>()?;
+    if colors.len() != count {
+        return Err(format!(
+            "JASC palette announces {count} colors but holds {}",
+            colors.len()
+        ));
+    }
+    Gradient::evenly(colors)
+}
+
+/// Parses a GIMP palette, whose colors are lines of decimal red, green, and blue
+/// components optionally followed by a name, among comments and `Name:` or `Columns:`
+/// lines.
+fn parse_gpl(source: &str) -> Result<Gradient, String> {
+    let colors = source
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_digit()))
+        .map(parse_rgb)
+        .collect::<Result<Vec<_>, _>>()?;
+    Gradient::evenly(colors)
+}
+
+/// Parses a GIMP gradient: a header, an optional `Name:` line, the number of segments,
+/// and a line per segment starting with its left, middle, and right positions and the
+/// red, green, blue, and alpha components of its left and right colors from 0 to 1.
+///
+/// Every segment is blended linearly through the average of its colors at its middle,
+/// whatever its blending function and coloring, and the alpha is ignored.
+fn parse_ggr(source: &str) -> Result<Gradient, String> {
+    let mut lines = source
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("Name:"));
+    let count = lines
+        .next()
+        .and_then(|line| line.parse::<usize>().ok())
+        .ok_or("missing number of segments in GIMP gradient")?;
+    let mut stops = Vec::with_capacity(count * 3);
+    for line in lines.by_ref().take(count) {
+        let invalid = || format!("invalid gradient segment '{line}'");
+        let values = line
+            .split_whitespace()
+            .take(11)
+            .map(|value| value.parse::<f32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [left, middle, right, r0, g0, b0, _, r1, g1, b1, _] =
+            values.try_into().map_err(|_| invalid())?;
+        let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let (from, to) = ([r0, g0, b0].map(byte), [r1, g1, b1].map(byte));
+        let average = |i: usize| (from[i] as u16 + to[i] as u16).div_ceil(2) as u8;
+        for (position, color) in [
+            (left, (from[0], from[1], from[2])),
+            (middle, (average(0), average(1), average(2))),
+            (right, (to[0], to[1], to[2])),
+        ] {
+            stops.push(Stop { position, color });
+        }
+    }
+    if stops.len() != count * 3 || count == 0 {
+        return Err(format!(
+            "GIMP gradient announces {count} segments but holds {}",
+            stops.len() / 3
+        ));
+    }
+    let positions: Vec<f32> = stops.iter().map(|stop| stop.position).collect();
+    if positions.windows(2).any(|pair| pair[1] < pair[0])
+        || positions.iter().any(|p| !(0.0..=1.0).contains(p))
+    {
+        return Err("GIMP gradient segments must follow each other from 0 to 1".into());
+    }
+    Ok(Gradient::new(stops))
+}
+
+/// Parses a line starting with decimal red, green, and blue components.
+fn parse_rgb(line: &str) -> Result<(u8, u8, u8), String> {
+    let mut components = line.split_whitespace().map(str::parse::<u8>);
+    match (components.next(), components.next(), components.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Ok((r, g, b)),
+        _ => Err(format!(
+            "invalid color '{line}', expected red, green, and blue from 0 to 255"
+        )),
+    }
+}
+
+/// Parses a color written `#rrggbb`.
+fn parse_hex(color: &str) -> Result<(u8, u8, u8), String> {
+    let invalid = || format!("invalid color '{color}', expected #rrggbb");
+    let digits = color.strip_prefix('#').ok_or_else(invalid)?;
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let component = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid());
+    Ok((component(0)?, component(2)?, component(4)?))
+}
+
+/// Fills in the missing positions of gradient stops, spreading them evenly between the
+/// given ones, with the first and last stops at the ends when missing.
+fn spread(mut positions: Vec<Option<f32>>) -> Vec<f32> {
+    let last = positions.len() - 1;
+    positions[0] = positions[0].or(Some(0.0));
+    positions[last] = positions[last].or(Some(if last == 0 { 0.0 } else { 1.0 }));
+    let mut start = 0;
+    for end in 1..=last {
+        let Some(to) = positions[end] else { continue };
+        let from = positions[start].unwrap_or_default();
+        for (i, position) in positions[start + 1..end].iter_mut().enumerate() {
+            let t = (i + 1) as f32 / (end - start) as f32;
+            *position = Some(from + (to - from) * t);
+        }
+        start = end;
+    }
+    positions
+        .into_iter()
+        .map(Option::unwrap_or_default)
+        .collect()
+}
+
+/// Converts HSV (Hue, Saturation, Value) color values to RGB (Red, Green, Blue)
+///
+/// # Arguments
+///
+/// * `h` - Hue angle in degrees [0, 360)
+/// * `s` - Saturation value [0, 1]
+/// * `v` - Value/brightness [0, 1]
+///
+/// # Returns
+///
+/// A tuple of (red, green, blue) values as 8-bit unsigned integers [0, 255]
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
+    // Normalize hue to [0,360) degree range
+    let h = ((h % 360.0) + 360.0) % 360.0;
+    // Calculate chroma (color intensity) from value and saturation
+    let c = v * s;
+    // Convert hue to sector position (60° per sector)
+    let h_prime = h / 60.0;
+    // Calculate intermediate value for RGB conversion based on hue position
+    let x = c * (1.0 - ((h_prime % 2.0) - 1.0).abs());
+    // Calculate value adjustment to maintain brightness level
+    let m = v - c;
+
+    let (r, g, b) = match h_prime as u8 {
+        0 => (c, x, 0.0), // Red to Yellow: R constant, G increasing
+        1 => (x, c, 0.0), // Yellow to Green: R decreasing, G constant
+        2 => (0.0, c, x), // Green to Cyan: G constant, B increasing
+        3 => (0.0, x, c), // Cyan to Blue: G decreasing, B constant
+        4 => (x, 0.0, c), // Blue to Magenta: B constant, R increasing
+        5 => (c, 0.0, x), // Magenta to Red: R constant, B decreasing
+        _ => (c, 0.0, x), // Fallback case (should not occur with normalized input)
+    };
+
+    (
+        (r + m).mul_add(255.0, 0.5) as u8,
+        (g + m).mul_add(255.0, 0.5) as u8,
+        (b + m).mul_add(255.0, 0.5) as u8,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_visits_every_palette_before_repeating() {
+        let mut palette = Palette::Rainbow;
+        for _ in 0..Palette::BUILTINS.len() {
+            palette = palette.next();
+        }
+        assert_eq!(palette, Palette::Rainbow);
+    }
+
+    #[test]
+    fn names_parse_back_into_palettes() {
+        for palette in Palette::BUILTINS {
+            assert_eq!(palette.to_string().parse(), Ok(palette.clone()));
+        }
+        assert_eq!("Hot".parse(), Ok(Palette::Hot));
+        assert!("lava".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn cosine_palettes_follow_their_formula() {
+        let spectrum: Palette = "spectrum".parse().unwrap();
+        // 0.5 + 0.5 * cos(2 * pi * (v + d)) for d = 0, 0.33, 0.67
+        assert_eq!(spectrum.rgb(0.0), (255, 66, 66));
+        assert_eq!(spectrum.rgb(0.5), (0, 189, 189));
+        assert_eq!(spectrum.name(), "spectrum");
+        assert_eq!(spectrum.to_string(), "spectrum");
+    }
+
+    #[test]
+    fn gradient_palettes_run_through_their_colors() {
+        assert_eq!(Palette::Viridis.rgb(0.0), (0x44, 0x01, 0x54));
+        assert_eq!(Palette::Viridis.rgb(0.5), (0x21, 0x91, 0x8c));
+        assert_eq!(Palette::Magma.rgb(1.0), (0xfc, 0xfd, 0xbf));
+        assert_eq!(Palette::Fire.rgb(0.0), (0, 0, 0));
+        assert_eq!(Palette::Pastel.rgb(0.0), Palette::Pastel.rgb(1.0));
+        // The colormaps get lighter all the way along
+        for palette in [
+            Palette::Viridis,
+            Palette::Magma,
+            Palette::Inferno,
+            Palette::Cividis,
+        ] {
+            let lightness = |v: f32| Oklab::from_rgb(palette.rgb(v)).l;
+            for i in 0..20 {
+                let v = i as f32 / 20.0;
+                assert!(lightness(v + 0.05) > lightness(v), "{palette} at {v}");
+            }
+        }
+    }
+
+    #[test]
+    fn cosine_vectors_parse_back_into_palettes() {
+        let spec = "cosine:0.5,0.5,0.5:0.5,0.5,0.5:1,1,1:0,0.33,0.67";
+        assert_eq!(spec.parse::<Palette>().unwrap().name(), "spectrum");
+        let custom: Palette = "cosine:0.8,0.5,0.4:0.2,0.4,0.2:2,1,1:0,0.25,0.25"
+            .parse()
+            .unwrap();
+        assert_eq!(custom.name(), "cosine");
+        assert_eq!(custom.to_string().parse(), Ok(custom.clone()));
+        assert_eq!(
+            custom.to_string(),
+            "cosine:0.8,0.5,0.4:0.2,0.4,0.2:2,1,1:0,0.25,0.25"
+        );
+        for spec in [
+            "cosine:1,1,1:1,1,1:1,1,1",
+            "cosine:1,1:1,1,1:1,1,1:1,1,1",
+            "cosine:a",
+        ] {
+            assert!(spec.parse::<Palette>().is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn crossfades_blend_the_old_and_new_palettes() {
+        let mut fade = Crossfade::new(Palette::BlackWhite);
+        assert_eq!(fade.rgb(1.0), (255, 255, 255));
+        fade.set("#ff0000".parse().unwrap());
+        assert_eq!(fade.palette().name(), "custom");
+        assert_eq!(fade.rgb(1.0), (255, 255, 255));
+        fade.update(CROSSFADE_TIME / 2.0);
+        assert_eq!(fade.rgb(1.0), (255, 128, 128));
+        fade.update(CROSSFADE_TIME / 2.0);
+        assert_eq!(fade.color(1.0), rgb(255, 0, 0));
+        fade.next();
+        assert_eq!(fade.palette(), &Palette::Rainbow);
+    }
+
+    #[test]
+    fn crossfades_reverse_and_turn_every_palette() {
+        let mut fade = Crossfade::new(Palette::BlackWhite);
+        fade.reverse();
+        assert!(fade.is_reversed());
+        assert_eq!(fade.rgb(0.0), (255, 255, 255));
+        fade.set("#000000,#ff0000".parse().unwrap());
+        fade.update(CROSSFADE_TIME);
+        assert_eq!(fade.rgb(0.0), (255, 0, 0));
+        fade.rotate_hue(120.0);
+        assert_eq!(fade.rgb(0.0), (0, 113, 0));
+        // Turning back by a whole turn and more wraps around to no rotation
+        fade.rotate_hue(-480.0);
+        assert_eq!(fade.hue(), 0.0);
+        assert_eq!(fade.rgb(0.0), (255, 0, 0));
+        fade.rotate_hue(-30.0);
+        assert_eq!(fade.hue(), 330.0);
+        fade.set_look(false, 390.0);
+        assert!(!fade.is_reversed());
+        assert_eq!(fade.hue(), 30.0);
+    }
+
+    #[test]
+    fn gradients_blend_between_their_stops() {
+        let palette: Palette = "#000000, #ff8000@0.5, #ffffff".parse().unwrap();
+        assert_eq!(palette.rgb(0.0), (0, 0, 0));
+        // Half way in OKLab from black to orange
+        assert_eq!(palette.rgb(0.25), (99, 46, 0));
+        assert_eq!(palette.rgb(0.5), (255, 128, 0));
+        assert_eq!(palette.rgb(1.0), (255, 255, 255));
+        assert_eq!(palette.rgb(2.0), (255, 255, 255));
+        assert_eq!(palette.name(), "custom");
+        assert_eq!(palette.next(), Palette::Rainbow);
+    }
+
+    #[test]
+    fn gradients_spread_colors_without_positions() {
+        let gradient: Gradient = "#000000 #111111 #222222@0.5 #333333 #444444"
+            .parse()
+            .unwrap();
+        let positions: Vec<f32> = gradient.stops().iter().map(|stop| stop.position).collect();
+        assert_eq!(positions, [0.0, 0.25, 0.5, 0.75, 1.0]);
+        let single: Gradient = "#102030".parse().unwrap();
+        assert_eq!(single.rgb(0.7), (0x10, 0x20, 0x30));
+    }
+
+    #[test]
+    fn gradients_read_json_arrays_and_print_back() {
+        let json = r##"["#ff0000@0", "#0000ff@1"]"##;
+        let palette: Palette = json.parse().unwrap();
+        assert_eq!(palette.to_string(), "#ff0000@0,#0000ff@1");
+        assert_eq!(palette.to_string().parse(), Ok(palette));
+    }
+
+    #[test]
+    fn invalid_gradients_are_rejected() {
+        for spec in [
+            "#12345",
+            "#gg0000",
+            "#000000@2",
+            "#000000@0.6,#ffffff@0.4",
+            "[]",
+        ] {
+            assert!(spec.parse::<Gradient>().is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn palettes_load_from_files() {
+        let path = std::env::temp_dir().join(format!("plasma-palette-{}", std::process::id()));
+        std::fs::write(&path, "#000000\n#ffffff\n").unwrap();
+        let palette = Palette::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // Gray of half the lightness of white
+        assert_eq!(palette.rgb(0.5), (99, 99, 99));
+    }
+
+    #[test]
+    fn palettes_load_from_the_top_row_of_images() {
+        let path = std::env::temp_dir().join(format!("plasma-palette-{}.png", std::process::id()));
+        let pixels = [rgb(255, 0, 0), rgb(0, 0, 255), rgb(0, 0, 0), rgb(0, 0, 0)];
+        std::fs::write(&path, crate::export::encode_png(&pixels, 2, 2)).unwrap();
+        let palette = Palette::from_image(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(palette.rgb(0.0), (255, 0, 0));
+        assert_eq!(palette.rgb(1.0), (0, 0, 255));
+        assert!(Palette::from_image(&path).is_err());
+    }
+
+    #[test]
+    fn jasc_palettes_spread_their_colors() {
+        let gradient =
+            parse_jasc("JASC-PAL\r\n0100\r\n3\r\n0 0 0\r\n255 0 0\r\n255 255 255\r\n").unwrap();
+        assert_eq!(gradient.rgb(0.0), (0, 0, 0));
+        assert_eq!(gradient.rgb(0.5), (255, 0, 0));
+        assert_eq!(gradient.rgb(1.0), (255, 255, 255));
+        assert!(parse_jasc("JASC-PAL\n0100\n2\n0 0 0\n").is_err());
+        assert!(parse_jasc("JASC-PAL\n0100\n1\n0 0 300\n").is_err());
+    }
+
+    #[test]
+    fn gimp_palettes_skip_names_and_comments() {
+        let source =
+            "GIMP Palette\nName: Duo\nColumns: 2\n#\n  0  16  32\tNavy\n255 255 255 White\n";
+        let gradient = parse_gpl(source).unwrap();
+        assert_eq!(gradient.stops().len(), 2);
+        assert_eq!(gradient.rgb(0.0), (0, 16, 32));
+        assert_eq!(gradient.rgb(1.0), (255, 255, 255));
+        assert!(parse_gpl("GIMP Palette\nName: Empty\n").is_err());
+    }
+
+    #[test]
+    fn gimp_gradients_become_stops() {
+        let source = "GIMP Gradient\nName: Fire\n2\n\
+            0 0.25 0.5 0 0 0 1 1 0 0 1 0 0\n\
+            0.5 0.75 1 1 0 0 1 1 1 0 1 0 0\n";
+        let gradient = parse_ggr(source).unwrap();
+        assert_eq!(gradient.stops().len(), 6);
+        assert_eq!(gradient.rgb(0.25), (128, 0, 0));
+        assert_eq!(gradient.rgb(0.5), (255, 0, 0));
+        assert_eq!(gradient.rgb(0.75), (255, 128, 0));
+        assert!(parse_ggr("GIMP Gradient\n2\n0 0.5 1 0 0 0 1 1 1 1 1 0 0\n").is_err());
+        assert!(parse_ggr("GIMP Gradient\n1\n0 0.5 1 0 0\n").is_err());
+    }
+
+    #[test]
+    fn nearest_lookups_form_flat_bands() {
+        let palette: Palette = "#000000, #ff0000, #ffffff".parse().unwrap();
+        let palette = palette.with_lookup(Lookup::Nearest);
+        assert_eq!(palette.rgb(0.2), (0, 0, 0));
+        assert_eq!(palette.rgb(0.3), (255, 0, 0));
+        assert_eq!(palette.rgb(0.7), (255, 0, 0));
+        assert_eq!(palette.rgb(0.8), (255, 255, 255));
+        assert_eq!(palette.to_string().parse(), Ok(palette));
+        assert_eq!(Palette::Hot.with_lookup(Lookup::Nearest), Palette::Hot);
+    }
+
+    #[test]
+    fn color_packs_rgb_components() {
+        let (r, g, b) = Palette::Hot.rgb(0.5);
+        assert_eq!(Palette::Hot.color(0.5), rgb(r, g, b));
+    }
+
+    #[test]
+    fn hsv_to_rgb_converts_primary_colors_correctly() {
+        // Red (0° hue)
+        let (r, g, b) = hsv_to_rgb(0.0, 1.0, 1.0);
+        assert_eq!((r, g, b), (255, 0, 0), "Pure red should be (255, 0, 0)");
+
+        // Green (120° hue)
+        let (r, g, b) = hsv_to_rgb(120.0, 1.0, 1.0);
+        assert_eq!((r, g, b), (0, 255, 0), "Pure green should be (0, 255, 0)");
+
+        // Blue (240° hue)
+        let (r, g, b) = hsv_to_rgb(240.0, 1.0, 1.0);
+        assert_eq!((r, g, b), (0, 0, 255), "Pure blue should be (0, 0, 0)");
+    }
+
+    #[test]
+    fn hsv_to_rgb_converts_secondary_colors_correctly() {
+        // Yellow (60° hue)
+        let (r, g, b) = hsv_to_rgb(60.0, 1.0, 1.0);
+        assert_eq!((r, g, b), (255, 255, 0), "Yellow should be (255, 255, 0)");
+
+        // Cyan (180° hue)
+        let (r, g, b) = hsv_to_rgb(180.0, 1.0, 1.0);
+        assert_eq!((r, g, b), (0, 255, 255), "Cyan should be (0, 255, 255)");
+
+        // Magenta (300° hue)
+        let (r, g, b) = hsv_to_rgb(300.0, 1.0, 1.0);
+        assert_eq!((r, g, b), (255, 0, 255), "Magenta should be (255, 0, 255)");
+    }
+
+    #[test]
+    fn hsv_to_rgb_handles_grayscale_correctly() {
+        // Black (V = 0)
+        let (r, g, b) = hsv_to_rgb(0.0, 0.0, 0.0);
+        assert_eq!((r, g, b), (0, 0, 0), "Black should be (0, 0, 0)");
+
+        // White (V = 1, S = 0)
+        let (r, g, b) = hsv_to_rgb(0.0, 0.0, 1.0);
+        assert_eq!(
+            (r, g, b),
+            (255, 255, 255),
+            "White should be (255, 255, 255)"
+        );
+
+        // 50% Gray (V = 0.5, S = 0)
+        let (r, g, b) = hsv_to_rgb(0.0, 0.0, 0.5);
+        assert_eq!(
+            (r, g, b),
+            (128, 128, 128),
+            "50% gray should be (128, 128, 128)"
+        );
+    }
+
+    #[test]
+    fn hsv_to_rgb_handles_hue_wrapping() {
+        // Test that 360° wraps to 0°
+        let color1 = hsv_to_rgb(0.0, 1.0, 1.0);
+        let color2 = hsv_to_rgb(360.0, 1.0, 1.0);
+        assert_eq!(color1, color2, "0° and 360° hue should produce same color");
+
+        // Test that negative hues work correctly
+        let color3 = hsv_to_rgb(-120.0, 1.0, 1.0);
+        let color4 = hsv_to_rgb(240.0, 1.0, 1.0);
+        assert_eq!(
+            color3, color4,
+            "-120° and 240° hue should produce same color"
+        );
+    }
+
+    #[test]
+    fn hsv_to_rgb_handles_saturation_correctly() {
+        let hue = 0.0; // Red
+        let value = 1.0;
+
+        // Full saturation
+        let (r1, g1, b1) = hsv_to_rgb(hue, 1.0, value);
+        assert_eq!((r1, g1, b1), (255, 0, 0), "Full saturation red");
+
+        // Half saturation
+        let (r2, g2, b2) = hsv_to_rgb(hue, 0.5, value);
+        assert_eq!((r2, g2, b2), (255, 128, 128), "Half saturation red");
+
+        // Zero saturation (should be white at full value)
+        let (r3, g3, b3) = hsv_to_rgb(hue, 0.0, value);
+        assert_eq!(
+            (r3, g3, b3),
+            (255, 255, 255),
+            "Zero saturation at full value"
+        );
+    }
+}
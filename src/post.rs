@@ -0,0 +1,756 @@
+//! Post-processing passes applied to the finished frame.
+//!
+//! A pass runs after the effect has drawn a frame and rewrites the pixel buffer in place,
+//! so every pass works with every effect. Passes are chained by applying them one after
+//! the other, each one reading the output of the previous one. Colors are processed as
+//! floating point channels in the [0,1] range and packed back into opaque pixels.
+//!
+//! A [`ColorGrade`] runs after all the passes and adjusts the gamma, brightness,
+//! contrast, and saturation of the frame. It works on the 8-bit channels through tables
+//! of 256 entries computed once per frame, so grading costs a few lookups per pixel.
+//!
+//! A [`ColorDepth`] comes last and imitates the few colors of retro displays, from 16-bit
+//! high color down to the four greens of the Game Boy. The colors lost are made up for by
+//! ordered dithering: a 4x4 Bayer matrix nudges neighboring pixels toward different
+//! neighboring colors, so flat areas average out to the original shade.
+use crate::common::{rgb, DemoBase};
+use clap::{Args, ValueEnum};
+
+/// Radius of the gaussian blur pass in pixels
+const BLUR_RADIUS: usize = 3;
+
+/// Brightness above which pixels start to glow
+const BLOOM_THRESHOLD: f32 = 0.6;
+
+/// Radius of the glow around bright pixels in pixels
+const BLOOM_RADIUS: usize = 6;
+
+/// Intensity of the glow added back onto the frame
+const BLOOM_STRENGTH: f32 = 1.2;
+
+/// Fraction of the brightness removed from every other row
+const SCANLINE_DARKNESS: f32 = 0.35;
+
+/// Fraction of the brightness removed in the corners
+const VIGNETTE_STRENGTH: f32 = 0.7;
+
+/// Distance in pixels between the red and blue channels at the left and right edges
+const ABERRATION_SHIFT: f32 = 4.0;
+
+/// Amount of barrel distortion bending the picture at the corners
+const CRT_CURVATURE: f32 = 0.15;
+
+/// Weights of the red, green, and blue channels in the luma of a pixel, out of 256
+const LUMA_WEIGHTS: [i32; 3] = [54, 183, 19];
+
+/// Thresholds of the 4x4 Bayer matrix, in sixteenths
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Colors of the high intensity CGA palette 1
+const CGA_COLORS: [(u8, u8, u8); 4] = [
+    (0x00, 0x00, 0x00),
+    (0x55, 0xff, 0xff),
+    (0xff, 0x55, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+/// Colors of the default EGA palette
+const EGA_COLORS: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0x00, 0x00, 0xaa),
+    (0x00, 0xaa, 0x00),
+    (0x00, 0xaa, 0xaa),
+    (0xaa, 0x00, 0x00),
+    (0xaa, 0x00, 0xaa),
+    (0xaa, 0x55, 0x00),
+    (0xaa, 0xaa, 0xaa),
+    (0x55, 0x55, 0x55),
+    (0x55, 0x55, 0xff),
+    (0x55, 0xff, 0x55),
+    (0x55, 0xff, 0xff),
+    (0xff, 0x55, 0x55),
+    (0xff, 0x55, 0xff),
+    (0xff, 0xff, 0x55),
+    (0xff, 0xff, 0xff),
+];
+
+/// Shades of the original Game Boy screen from darkest to lightest
+const GAMEBOY_COLORS: [(u8, u8, u8); 4] = [
+    (0x0f, 0x38, 0x0f),
+    (0x30, 0x62, 0x30),
+    (0x8b, 0xac, 0x0f),
+    (0x9b, 0xbc, 0x0f),
+];
+
+/// A pass rewriting the finished frame
+pub trait PostEffect {
+    /// Processes the pixel buffer in place.
+    ///
+    /// # Arguments
+    /// * `buffer` - Finished frame of `base.width * base.height` pixels
+    /// * `base` - Dimensions of the frame
+    fn apply(&self, buffer: &mut [u32], base: DemoBase);
+}
+
+/// Post-processing passes selectable on the command line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Pass {
+    /// Gaussian blur
+    Blur,
+    /// Glow around the bright parts of the picture
+    Bloom,
+    /// Darkened rows of a CRT screen
+    Scanlines,
+    /// Darkened corners
+    Vignette,
+    /// Color fringes toward the edges
+    Chromatic,
+    /// Barrel distortion of a curved CRT screen
+    Crt,
+}
+
+impl Pass {
+    /// Builds the pass with its default parameters.
+    pub fn create(self) -> Box<dyn PostEffect> {
+        match self {
+            Pass::Blur => Box::new(GaussianBlur {
+                radius: BLUR_RADIUS,
+            }),
+            Pass::Bloom => Box::new(Bloom {
+                threshold: BLOOM_THRESHOLD,
+                radius: BLOOM_RADIUS,
+                strength: BLOOM_STRENGTH,
+            }),
+            Pass::Scanlines => Box::new(Scanlines {
+                darkness: SCANLINE_DARKNESS,
+            }),
+            Pass::Vignette => Box::new(Vignette {
+                strength: VIGNETTE_STRENGTH,
+            }),
+            Pass::Chromatic => Box::new(ChromaticAberration {
+                shift: ABERRATION_SHIFT,
+            }),
+            Pass::Crt => Box::new(Crt {
+                curvature: CRT_CURVATURE,
+            }),
+        }
+    }
+}
+
+/// Splits a pixel into red, green, and blue channels in the [0,1] range.
+fn unpack(pixel: u32) -> [f32; 3] {
+    [16, 8, 0].map(|shift| ((pixel >> shift) & 0xff) as f32 / 255.0)
+}
+
+/// Packs channels in the [0,1] range into an opaque pixel, clamping out of range values.
+fn pack([r, g, b]: [f32; 3]) -> u32 {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    rgb(channel(r), channel(g), channel(b))
+}
+
+/// Blurs an image of unpacked pixels with a separable gaussian kernel.
+///
+/// Pixels beyond the borders repeat the nearest edge pixel, so borders do not darken.
+fn blur(pixels: &mut [[f32; 3]], base: DemoBase, radius: usize) {
+    if radius == 0 || base.pixel_count() == 0 {
+        return;
+    }
+    // Most of the gaussian lies within two standard deviations of its center
+    let sigma = radius as f32 * 0.5;
+    let radius = radius as isize;
+    let mut kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|weight| *weight /= total);
+
+    // Blur the rows into a scratch buffer, then the columns back into the image
+    let mut scratch = pixels.to_vec();
+    blur_axis(pixels, &mut scratch, base, &kernel, (1, 0));
+    blur_axis(&scratch, pixels, base, &kernel, (0, 1));
+}
+
+/// Convolves an image with a one dimensional kernel laid along `step`.
+fn blur_axis(
+    source: &[[f32; 3]],
+    target: &mut [[f32; 3]],
+    base: DemoBase,
+    kernel: &[f32],
+    step: (isize, isize),
+) {
+    let (width, height) = (base.width as isize, base.height as isize);
+    let radius = kernel.len() as isize / 2;
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0; 3];
+            for (offset, &weight) in (-radius..=radius).zip(kernel) {
+                let sx = (x + offset * step.0).clamp(0, width - 1);
+                let sy = (y + offset * step.1).clamp(0, height - 1);
+                let sample = source[(sy * width + sx) as usize];
+                for c in 0..3 {
+                    sum[c] += sample[c] * weight;
+                }
+            }
+            target[(y * width + x) as usize] = sum;
+        }
+    }
+}
+
+/// Softens the picture with a gaussian blur
+pub struct GaussianBlur {
+    /// Radius of the blur kernel in pixels
+    pub radius: usize,
+}
+
+impl PostEffect for GaussianBlur {
+    fn apply(&self, buffer: &mut [u32], base: DemoBase) {
+        let mut pixels: Vec<[f32; 3]> = buffer.iter().map(|&p| unpack(p)).collect();
+        blur(&mut pixels, base, self.radius);
+        for (pixel, &color) in buffer.iter_mut().zip(&pixels) {
+            *pixel = pack(color);
+        }
+    }
+}
+
+/// Makes the bright parts of the picture glow.
+///
+/// The brightness above the threshold is blurred and added back onto the frame.
+pub struct Bloom {
+    /// Brightness above which pixels start to glow, in the [0,1] range
+    pub threshold: f32,
+    /// Radius of the glow in pixels
+    pub radius: usize,
+    /// Intensity of the glow
+    pub strength: f32,
+}
+
+impl PostEffect for Bloom {
+    fn apply(&self, buffer: &mut [u32], base: DemoBase) {
+        let mut glow: Vec<[f32; 3]> = buffer
+            .iter()
+            .map(|&pixel| {
+                let color = unpack(pixel);
+                let brightness = color.iter().copied().fold(0.0, f32::max);
+                // Keep the hue of the pixel while passing only the excess brightness
+                let excess = (brightness - self.threshold).max(0.0) / (1.0 - self.threshold);
+                color.map(|c| c * excess)
+            })
+            .collect();
+        blur(&mut glow, base, self.radius);
+        for (pixel, glow) in buffer.iter_mut().zip(&glow) {
+            let color = unpack(*pixel);
+            *pixel = pack([0, 1, 2].map(|c| color[c] + glow[c] * self.strength));
+        }
+    }
+}
+
+/// Darkens every other row like the gaps between the lines of a CRT screen
+pub struct Scanlines {
+    /// Fraction of the brightness removed from the dark rows
+    pub darkness: f32,
+}
+
+impl PostEffect for Scanlines {
+    fn apply(&self, buffer: &mut [u32], base: DemoBase) {
+        for row in buffer
+            .chunks_exact_mut(base.width.max(1))
+            .skip(1)
+            .step_by(2)
+        {
+            for pixel in row {
+                *pixel = pack(unpack(*pixel).map(|c| c * (1.0 - self.darkness)));
+            }
+        }
+    }
+}
+
+/// Darkens the picture toward the corners
+pub struct Vignette {
+    /// Fraction of the brightness removed in the corners
+    pub strength: f32,
+}
+
+impl PostEffect for Vignette {
+    fn apply(&self, buffer: &mut [u32], base: DemoBase) {
+        let (center_x, center_y) = base.center();
+        for (i, pixel) in buffer.iter_mut().enumerate() {
+            // Distance from the center with the corners at 1
+            let dx = ((i % base.width) as f32 + 0.5 - center_x) / center_x;
+            let dy = ((i / base.width) as f32 + 0.5 - center_y) / center_y;
+            let falloff = 1.0 - self.strength * (dx * dx + dy * dy) * 0.5;
+            *pixel = pack(unpack(*pixel).map(|c| c * falloff));
+        }
+    }
+}
+
+/// Shifts the red and blue channels apart toward the left and right edges, like a cheap
+/// lens failing to focus all colors at the same point
+pub struct ChromaticAberration {
+    /// Distance in pixels between the red and blue channels at the edges
+    pub shift: f32,
+}
+
+impl PostEffect for ChromaticAberration {
+    fn apply(&self, buffer: &mut [u32], base: DemoBase) {
+        let source = buffer.to_vec();
+        let (center_x, _) = base.center();
+        let last = base.width as f32 - 1.0;
+        for (y, row) in buffer.chunks_exact_mut(base.width.max(1)).enumerate() {
+            let line = &source[y * base.width..(y + 1) * base.width];
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let offset = (x as f32 - center_x) / center_x * self.shift * 0.5;
+                let sample =
+                    |dx: f32| unpack(line[(x as f32 + dx).round().clamp(0.0, last) as usize]);
+                let [_, g, _] = unpack(*pixel);
+                *pixel = pack([sample(-offset)[0], g, sample(offset)[2]]);
+            }
+        }
+    }
+}
+
+/// Bends the picture as if shown on a curved CRT screen.
+///
+/// Every pixel samples the frame further from the center the closer it is to the corners,
+/// pulling the picture inward. The uncovered corners turn black.
+pub struct Crt {
+    /// Amount of distortion, 0 leaves the picture untouched
+    pub curvature: f32,
+}
+
+impl PostEffect for Crt {
+    fn apply(&self, buffer: &mut [u32], base: DemoBase) {
+        let source = buffer.to_vec();
+        let (center_x, center_y) = base.center();
+        for (i, pixel) in buffer.iter_mut().enumerate() {
+            // Position relative to the center with the edges at -1 and 1
+            let u = ((i % base.width) as f32 + 0.5 - center_x) / center_x;
+            let v = ((i / base.width) as f32 + 0.5 - center_y) / center_y;
+            let bend = 1.0 + self.curvature * (u * u + v * v);
+            let (su, sv) = (u * bend, v * bend);
+            *pixel = if su.abs() > 1.0 || sv.abs() > 1.0 {
+                rgb(0, 0, 0)
+            } else {
+                let sx = ((su * center_x + center_x) as usize).min(base.width - 1);
+                let sy = ((sv * center_y + center_y) as usize).min(base.height - 1);
+                source[sy * base.width + sx]
+            };
+        }
+    }
+}
+
+/// Parameter of a [`ColorGrade`] changed with the hotkeys
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grading {
+    Gamma,
+    Brightness,
+    Contrast,
+    Saturation,
+}
+
+/// Final adjustment of the colors of every frame
+#[derive(Debug, Clone, Copy, PartialEq, Args)]
+#[command(next_help_heading = "Color grading options")]
+#[group(id = "grading-options")]
+pub struct ColorGrade {
+    #[arg(
+        long,
+        value_name = "GAMMA",
+        default_value_t = 1.0,
+        value_parser = parse_gamma,
+        help = "Gamma of the frames, above 1 to brighten the midtones"
+    )]
+    pub gamma: f32,
+
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        default_value_t = 0.0,
+        allow_negative_numbers = true,
+        value_parser = parse_brightness,
+        help = "Brightness added to the frames, from -1 to 1"
+    )]
+    pub brightness: f32,
+
+    #[arg(
+        long,
+        value_name = "FACTOR",
+        default_value_t = 1.0,
+        value_parser = parse_factor,
+        help = "Contrast of the frames, 0 for flat gray"
+    )]
+    pub contrast: f32,
+
+    #[arg(
+        long,
+        value_name = "FACTOR",
+        default_value_t = 1.0,
+        value_parser = parse_factor,
+        help = "Saturation of the frames, 0 for black and white"
+    )]
+    pub saturation: f32,
+}
+
+impl Default for ColorGrade {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl ColorGrade {
+    /// Returns whether grading leaves the frames unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Raises a parameter by `steps` hotkey steps, or lowers it for negative steps,
+    /// keeping it within its range.
+    pub fn step(&mut self, grading: Grading, steps: f32) {
+        match grading {
+            Grading::Gamma => self.gamma = (self.gamma + steps * 0.1).max(0.1),
+            Grading::Brightness => {
+                self.brightness = (self.brightness + steps * 0.05).clamp(-1.0, 1.0);
+            }
+            Grading::Contrast => self.contrast = (self.contrast + steps * 0.1).max(0.0),
+            Grading::Saturation => self.saturation = (self.saturation + steps * 0.1).max(0.0),
+        }
+    }
+
+    /// Returns the graded value of every 8-bit channel value, before saturation.
+    ///
+    /// The contrast stretches the values around the middle gray, the brightness is added
+    /// to the result, and the gamma curve comes last.
+    fn table(&self) -> [u8; 256] {
+        std::array::from_fn(|i| {
+            let v = (i as f32 / 255.0 - 0.5) * self.contrast + 0.5 + self.brightness;
+            let v = v.clamp(0.0, 1.0).powf(1.0 / self.gamma);
+            (v * 255.0).round() as u8
+        })
+    }
+}
+
+impl PostEffect for ColorGrade {
+    /// Grades every pixel through the channel table, then moves its channels toward or
+    /// away from its luma by the saturation, itself split into two tables of the terms
+    /// `saturation * channel` and `(1 - saturation) * luma`.
+    fn apply(&self, buffer: &mut [u32], _base: DemoBase) {
+        if self.is_identity() {
+            return;
+        }
+        let table = self.table();
+        let scaled: [i32; 256] =
+            std::array::from_fn(|i| (i as f32 * self.saturation).round() as i32);
+        let gray: [i32; 256] =
+            std::array::from_fn(|i| (i as f32 * (1.0 - self.saturation)).round() as i32);
+        for pixel in buffer.iter_mut() {
+            let channels = [16, 8, 0].map(|shift| table[((*pixel >> shift) & 0xff) as usize]);
+            let luma = channels
+                .iter()
+                .zip(LUMA_WEIGHTS)
+                .map(|(&c, weight)| c as i32 * weight)
+                .sum::<i32>()
+                >> 8;
+            let [r, g, b] =
+                channels.map(|c| (scaled[c as usize] + gray[luma as usize]).clamp(0, 255) as u8);
+            *pixel = rgb(r, g, b);
+        }
+    }
+}
+
+/// Color depths of retro displays, imitated with ordered dithering
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorDepth {
+    /// 16-bit high color with 5 bits of red, 6 of green, and 5 of blue
+    Rgb565,
+    /// 8-bit color with 3 bits of red, 3 of green, and 2 of blue
+    Rgb332,
+    /// Black, cyan, magenta, and white of the CGA
+    Cga,
+    /// The 16 colors of the EGA
+    Ega,
+    /// The four shades of green of the Game Boy
+    Gameboy,
+}
+
+impl ColorDepth {
+    /// Returns the color the pixel `(r, g, b)` takes at the dithering threshold `t`, from
+    /// -0.5 to 0.5.
+    fn quantize(self, (r, g, b): (u8, u8, u8), t: f32) -> (u8, u8, u8) {
+        // Rounds a channel to one of `levels` evenly spread values
+        let level = |c: u8, levels: u8| {
+            let steps = (levels - 1) as f32;
+            let step = (c as f32 / 255.0 * steps + t).round().clamp(0.0, steps);
+            (step / steps * 255.0).round() as u8
+        };
+        match self {
+            ColorDepth::Rgb565 => (level(r, 32), level(g, 64), level(b, 32)),
+            ColorDepth::Rgb332 => (level(r, 8), level(g, 8), level(b, 4)),
+            ColorDepth::Cga => nearest(&CGA_COLORS, (r, g, b), t * 0.5),
+            ColorDepth::Ega => nearest(&EGA_COLORS, (r, g, b), t / 3.0),
+            ColorDepth::Gameboy => {
+                let luma = [r, g, b]
+                    .into_iter()
+                    .zip(LUMA_WEIGHTS)
+                    .map(|(c, weight)| c as i32 * weight)
+                    .sum::<i32>()
+                    >> 8;
+                let shade = (luma as f32 / 255.0 * 3.0 + t).round().clamp(0.0, 3.0);
+                GAMEBOY_COLORS[shade as usize]
+            }
+        }
+    }
+}
+
+impl PostEffect for ColorDepth {
+    fn apply(&self, buffer: &mut [u32], base: DemoBase) {
+        for (y, row) in buffer.chunks_exact_mut(base.width.max(1)).enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let t = (BAYER[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5;
+                let [r, g, b] = [16, 8, 0].map(|shift| (*pixel >> shift) as u8);
+                let (r, g, b) = self.quantize((r, g, b), t);
+                *pixel = rgb(r, g, b);
+            }
+        }
+    }
+}
+
+/// Returns the color of `colors` closest to `color` once every channel is shifted by the
+/// fraction `offset` of the full range.
+fn nearest(colors: &[(u8, u8, u8)], (r, g, b): (u8, u8, u8), offset: f32) -> (u8, u8, u8) {
+    let shift = |c: u8| c as f32 + offset * 255.0;
+    let (r, g, b) = (shift(r), shift(g), shift(b));
+    let distance = |&&(cr, cg, cb): &&(u8, u8, u8)| {
+        let square = |a: f32, b: u8| (a - b as f32) * (a - b as f32);
+        square(r, cr) + square(g, cg) + square(b, cb)
+    };
+    *colors
+        .iter()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .unwrap_or(&(0, 0, 0))
+}
+
+/// Parses a gamma, which must be positive.
+fn parse_gamma(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(gamma) if gamma > 0.0 && gamma.is_finite() => Ok(gamma),
+        _ => Err(format!(
+            "invalid gamma '{spec}', expected a positive number"
+        )),
+    }
+}
+
+/// Parses a brightness from -1 to 1.
+fn parse_brightness(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(brightness) if (-1.0..=1.0).contains(&brightness) => Ok(brightness),
+        _ => Err(format!("invalid brightness '{spec}', expected -1 to 1")),
+    }
+}
+
+/// Parses a contrast or saturation factor, which must not be negative.
+fn parse_factor(spec: &str) -> Result<f32, String> {
+    match spec.parse::<f32>() {
+        Ok(factor) if factor >= 0.0 && factor.is_finite() => Ok(factor),
+        _ => Err(format!("invalid factor '{spec}', expected 0 or more")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: DemoBase = DemoBase {
+        width: 16,
+        height: 12,
+    };
+
+    fn dot(color: u32) -> Vec<u32> {
+        let mut buffer = vec![rgb(0, 0, 0); BASE.pixel_count()];
+        buffer[6 * 16 + 8] = color;
+        buffer
+    }
+
+    #[test]
+    fn every_pass_keeps_the_center_of_a_flat_picture() {
+        for pass in Pass::value_variants() {
+            let mut buffer = vec![rgb(80, 80, 80); BASE.pixel_count()];
+            pass.create().apply(&mut buffer, BASE);
+            let center = unpack(buffer[6 * 16 + 8]);
+            assert!(
+                center[1] >= 80.0 / 255.0 - 0.05,
+                "{pass:?} darkened the center"
+            );
+        }
+    }
+
+    #[test]
+    fn blur_spreads_a_dot() {
+        let mut buffer = dot(rgb(255, 255, 255));
+        GaussianBlur { radius: 2 }.apply(&mut buffer, BASE);
+        assert!(buffer[6 * 16 + 9] != rgb(0, 0, 0));
+        assert!(buffer[6 * 16 + 8] != rgb(255, 255, 255));
+        assert_eq!(buffer[0], rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn bloom_only_spreads_bright_pixels() {
+        let bloom = Bloom {
+            threshold: 0.6,
+            radius: 2,
+            strength: 1.0,
+        };
+        let mut dim = dot(rgb(100, 100, 100));
+        bloom.apply(&mut dim, BASE);
+        assert_eq!(dim, dot(rgb(100, 100, 100)));
+
+        let mut bright = dot(rgb(255, 255, 255));
+        bloom.apply(&mut bright, BASE);
+        assert!(bright[6 * 16 + 9] != rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn scanlines_darken_every_other_row() {
+        let mut buffer = vec![rgb(200, 200, 200); BASE.pixel_count()];
+        Scanlines { darkness: 0.5 }.apply(&mut buffer, BASE);
+        assert_eq!(buffer[0], rgb(200, 200, 200));
+        assert_eq!(buffer[16], rgb(100, 100, 100));
+        assert_eq!(buffer[32], rgb(200, 200, 200));
+    }
+
+    #[test]
+    fn vignette_darkens_the_corners() {
+        let mut buffer = vec![rgb(200, 200, 200); BASE.pixel_count()];
+        Vignette { strength: 0.7 }.apply(&mut buffer, BASE);
+        assert!(unpack(buffer[0])[0] < unpack(buffer[6 * 16 + 8])[0]);
+    }
+
+    #[test]
+    fn chromatic_aberration_separates_red_and_blue() {
+        let mut buffer = vec![rgb(0, 0, 0); BASE.pixel_count()];
+        buffer[16 * 3 + 12] = rgb(255, 255, 255);
+        ChromaticAberration { shift: 4.0 }.apply(&mut buffer, BASE);
+        let row = &buffer[16 * 3..16 * 4];
+        let red = row.iter().position(|&p| unpack(p)[0] > 0.5).unwrap();
+        let blue = row.iter().position(|&p| unpack(p)[2] > 0.5).unwrap();
+        assert!(red > blue);
+    }
+
+    #[test]
+    fn crt_blacks_out_the_corners() {
+        let mut buffer = vec![rgb(200, 200, 200); BASE.pixel_count()];
+        Crt { curvature: 0.3 }.apply(&mut buffer, BASE);
+        assert_eq!(buffer[0], rgb(0, 0, 0));
+        assert_eq!(buffer[6 * 16 + 8], rgb(200, 200, 200));
+    }
+
+    fn graded(grade: ColorGrade, color: u32) -> u32 {
+        let mut buffer = vec![color; 4];
+        grade.apply(&mut buffer, DemoBase::new(2, 2));
+        buffer[0]
+    }
+
+    #[test]
+    fn default_grading_keeps_the_frame() {
+        assert!(ColorGrade::default().is_identity());
+        let color = rgb(12, 150, 255);
+        assert_eq!(graded(ColorGrade::default(), color), color);
+    }
+
+    #[test]
+    fn grading_adjusts_every_channel() {
+        let grade = |gamma, brightness, contrast| ColorGrade {
+            gamma,
+            brightness,
+            contrast,
+            saturation: 1.0,
+        };
+        // sqrt(64 / 255) is about half way
+        assert_eq!(
+            graded(grade(2.0, 0.0, 1.0), rgb(64, 0, 255)),
+            rgb(128, 0, 255)
+        );
+        assert_eq!(
+            graded(grade(1.0, 0.2, 1.0), rgb(100, 0, 255)),
+            rgb(151, 51, 255)
+        );
+        // Half the contrast moves every channel half way to the middle gray
+        assert_eq!(
+            graded(grade(1.0, 0.0, 0.5), rgb(200, 0, 255)),
+            rgb(164, 64, 191)
+        );
+    }
+
+    #[test]
+    fn saturation_moves_channels_around_the_luma() {
+        let grade = |saturation| ColorGrade {
+            saturation,
+            ..ColorGrade::default()
+        };
+        assert_eq!(graded(grade(0.0), rgb(255, 0, 0)), rgb(53, 53, 53));
+        assert_eq!(graded(grade(2.0), rgb(100, 150, 200)), rgb(57, 157, 255));
+    }
+
+    #[test]
+    fn hotkey_steps_stay_within_range() {
+        let mut grade = ColorGrade::default();
+        grade.step(Grading::Gamma, 2.0);
+        assert!((grade.gamma - 1.2).abs() < 1e-6);
+        for _ in 0..30 {
+            grade.step(Grading::Gamma, -1.0);
+            grade.step(Grading::Brightness, 1.0);
+            grade.step(Grading::Contrast, -1.0);
+            grade.step(Grading::Saturation, -1.0);
+        }
+        assert_eq!((grade.gamma, grade.brightness), (0.1, 1.0));
+        assert_eq!((grade.contrast, grade.saturation), (0.0, 0.0));
+    }
+
+    #[test]
+    fn color_depths_round_to_their_colors() {
+        let base = DemoBase::new(4, 4);
+        let mut buffer = vec![rgb(255, 0, 255); 16];
+        ColorDepth::Rgb565.apply(&mut buffer, base);
+        assert!(buffer.iter().all(|&pixel| pixel == rgb(255, 0, 255)));
+        for (depth, colors) in [
+            (ColorDepth::Cga, &CGA_COLORS[..]),
+            (ColorDepth::Ega, &EGA_COLORS[..]),
+            (ColorDepth::Gameboy, &GAMEBOY_COLORS[..]),
+        ] {
+            let mut buffer: Vec<u32> = (0..16).map(|i| rgb(i * 16, 200, 255 - i * 16)).collect();
+            depth.apply(&mut buffer, base);
+            for pixel in buffer {
+                let color = ((pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8);
+                assert!(colors.contains(&color), "{depth:?} gave {color:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn dithering_keeps_the_average_shade() {
+        let base = DemoBase::new(4, 4);
+        // Blue has 4 levels in RGB332, 85 apart, and 128 falls between 85 and 170
+        let mut buffer = vec![rgb(0, 0, 128); 16];
+        ColorDepth::Rgb332.apply(&mut buffer, base);
+        let total: u32 = buffer.iter().map(|&pixel| pixel & 0xff).sum();
+        assert!((total as f32 / 16.0 - 128.0).abs() < 6.0, "{}", total / 16);
+        assert!(buffer.contains(&rgb(0, 0, 85)) && buffer.contains(&rgb(0, 0, 170)));
+        // Black and white are shown as the darkest and lightest shades
+        let mut buffer = vec![rgb(0, 0, 0), rgb(255, 255, 255)];
+        ColorDepth::Gameboy.apply(&mut buffer, DemoBase::new(2, 1));
+        assert_eq!(buffer, [rgb(0x0f, 0x38, 0x0f), rgb(0x9b, 0xbc, 0x0f)]);
+    }
+
+    #[test]
+    fn grading_options_are_validated() {
+        assert_eq!(parse_gamma("2.2"), Ok(2.2));
+        assert!(parse_gamma("0").is_err());
+        assert_eq!(parse_brightness("-0.5"), Ok(-0.5));
+        assert!(parse_brightness("1.5").is_err());
+        assert_eq!(parse_factor("0"), Ok(0.0));
+        assert!(parse_factor("-1").is_err());
+    }
+}
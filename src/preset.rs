@@ -0,0 +1,374 @@
+//! Effects saved along with their parameters and recalled later.
+//!
+//! Presets are kept in numbered slots of a presets file in TOML, one `[[preset]]` table
+//! per slot:
+//!
+//! ```toml
+//! [[preset]]
+//! slot = 1
+//! effect = "plasma"
+//! shape = "spiral"
+//! palette = "hot"
+//! scale = 20
+//! palette-reversed = false
+//! palette-hue = 0
+//! plasma-emitters = 3
+//! superformula-m = 5
+//! superformula-n1 = 2
+//! superformula-n2 = 7
+//! superformula-n3 = 7
+//! ```
+//!
+//! Every key besides `slot` and `effect` is a long option, read with
+//! [`crate::options::arguments`] and parsed by clap like the scenes of a sequence, so
+//! parameters are checked against the same ranges as on the command line. A preset
+//! holds the parameters shared by the effects along with the ones individual effects
+//! change with their controls, such as `hypno-arms`, while the options it leaves out
+//! keep the values given on the command line.
+use crate::effects::Settings;
+use crate::options;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Number of preset slots
+pub const SLOTS: usize = 8;
+
+/// An effect and the parameters it was running with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    /// Name of the effect
+    pub effect: String,
+    /// Parameters of the effect, keyed by the long option they stand for
+    pub options: Table,
+}
+
+impl Preset {
+    /// Captures the named effect running with `settings`.
+    pub fn new(effect: &str, settings: &Settings) -> Self {
+        let options = [
+            ("shape", value_name(&settings.shape).into()),
+            ("palette", settings.palette.to_string().into()),
+            ("scale", float(settings.scale)),
+            ("palette-reversed", settings.palette_reversed.into()),
+            ("palette-hue", float(settings.palette_hue)),
+            ("plasma-emitters", settings.plasma_emitters.into()),
+            ("superformula-m", float(settings.superformula.m)),
+            ("superformula-n1", float(settings.superformula.n1)),
+            ("superformula-n2", float(settings.superformula.n2)),
+            ("superformula-n3", float(settings.superformula.n3)),
+            ("radius-ratio", float(settings.spirograph.ratio)),
+            ("pen-offset", float(settings.spirograph.pen_offset)),
+            ("emitters", (settings.wavepool.emitters as i64).into()),
+            ("rain-intensity", float(settings.rain.intensity)),
+            ("munch-mask", settings.munching.mask.into()),
+            ("hypno-arms", settings.hypno.arms.into()),
+            ("hypno-speed", float(settings.hypno.speed)),
+            ("caustics-brightness", float(settings.caustics.brightness)),
+            ("sand-brush", value_name(&settings.sand.brush).into()),
+        ];
+        Self {
+            effect: effect.to_string(),
+            options: options
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        }
+    }
+
+    /// Overrides the parameters held by the preset in `settings`.
+    ///
+    /// # Errors
+    /// Fails if a key is not a long option, or if its value is invalid for the option.
+    pub fn apply(&self, settings: &mut Settings) -> Result<(), String> {
+        let arguments = options::arguments(&self.options, &[])?;
+        let command = PresetArgs::command();
+        let mut matches = command
+            .clone()
+            .try_get_matches_from(arguments)
+            .map_err(clap_error)?;
+        // Forget the defaults clap filled in for the options the preset leaves out, so
+        // they keep their current value. Flags set to `false` are left out of the
+        // arguments too, but still reset the current value.
+        for arg in command.get_arguments() {
+            let held = arg
+                .get_long()
+                .is_some_and(|long| self.options.contains_key(long));
+            if !held {
+                let _ = matches.try_clear_id(arg.get_id().as_str());
+            }
+        }
+        settings
+            .update_from_arg_matches(&matches)
+            .map_err(clap_error)
+    }
+
+    /// Reads the `[[preset]]` table of a presets file, checking its parameters.
+    fn from_table(mut table: Table) -> Result<Self, String> {
+        let effect = match table.remove("effect") {
+            Some(Value::String(effect)) => effect,
+            Some(_) => return Err("'effect' must be the name of an effect".to_string()),
+            None => return Err("missing 'effect'".to_string()),
+        };
+        table.remove("slot");
+        let preset = Self {
+            effect,
+            options: table,
+        };
+        preset.apply(&mut Settings::default())?;
+        Ok(preset)
+    }
+
+    /// Writes out the `[[preset]]` table of a preset saved in a slot numbered from 1.
+    fn to_table(&self, slot: usize) -> Table {
+        let mut table = Table::new();
+        table.insert("slot".to_string(), (slot as i64).into());
+        table.insert("effect".to_string(), self.effect.clone().into());
+        table.extend(self.options.clone());
+        table
+    }
+}
+
+/// Command line parser reading the parameters of a preset
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct PresetArgs {
+    #[command(flatten)]
+    settings: Settings,
+}
+
+/// The preset slots, numbered from 1 to [`SLOTS`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Presets {
+    /// Preset saved in every slot, if any
+    slots: [Option<Preset>; SLOTS],
+}
+
+impl Presets {
+    /// Reads the presets file, returning empty slots if it does not exist yet.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match std::fs::read_to_string(path) {
+            Ok(source) => Ok(Self::parse(&source)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the presets file, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(path, self.to_toml())?;
+        Ok(())
+    }
+
+    /// Parses the contents of a presets file.
+    ///
+    /// # Errors
+    /// Fails if the file is not valid TOML, if a preset has no slot between 1 and
+    /// [`SLOTS`], or if a parameter is invalid, naming its slot and option.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file: PresetsFile =
+            toml::from_str(source).map_err(|e| e.to_string().trim_end().to_string())?;
+        let mut presets = Self::default();
+        for table in file.preset {
+            let slot = table
+                .get("slot")
+                .and_then(Value::as_integer)
+                .and_then(|slot| usize::try_from(slot).ok())
+                .filter(|slot| (1..=SLOTS).contains(slot))
+                .ok_or(format!("preset slot must be between 1 and {SLOTS}"))?;
+            let preset =
+                Preset::from_table(table).map_err(|e| format!("preset slot {slot}: {e}"))?;
+            presets.slots[slot - 1] = Some(preset);
+        }
+        Ok(presets)
+    }
+
+    /// Formats the presets as the contents of a presets file.
+    pub fn to_toml(&self) -> String {
+        let preset = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, preset)| Some(preset.as_ref()?.to_table(i + 1)))
+            .collect();
+        toml::to_string(&PresetsFile { preset }).unwrap_or_default()
+    }
+
+    /// Returns the preset saved in a slot numbered from 1.
+    pub fn get(&self, slot: usize) -> Option<&Preset> {
+        self.slots.get(slot.checked_sub(1)?)?.as_ref()
+    }
+
+    /// Saves a preset into a slot numbered from 1, replacing the preset saved there.
+    ///
+    /// # Panics
+    /// Panics if the slot is not between 1 and [`SLOTS`].
+    pub fn set(&mut self, slot: usize, preset: Preset) {
+        self.slots[slot - 1] = Some(preset);
+    }
+}
+
+/// Tables of a presets file
+#[derive(Serialize, Deserialize)]
+struct PresetsFile {
+    /// Saved slots in ascending order
+    #[serde(default)]
+    preset: Vec<Table>,
+}
+
+/// Returns the first line of a clap error, which names the option at fault, without the
+/// usage and tips meant for the command line.
+fn clap_error(error: clap::Error) -> String {
+    let message = error.to_string();
+    let line = message.lines().next().unwrap_or_default();
+    line.trim_start_matches("error: ").to_string()
+}
+
+/// Returns a float value written with the shortest digits reading back as `value`, rather
+/// than those of its widening to `f64`.
+fn float(value: f32) -> Value {
+    Value::Float(value.to_string().parse().unwrap_or_default())
+}
+
+/// Returns the command line name of an enum value.
+pub(crate) fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Returns the location of the presets file in the XDG config directory, or `None` when
+/// neither `XDG_CONFIG_HOME` nor `HOME` is set.
+pub fn default_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("plasma").join("presets.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::{plasma::Shape, sand::Element};
+    use crate::palette::Palette;
+
+    fn fire() -> Preset {
+        let mut settings = Settings {
+            shape: Shape::Circle,
+            palette: Palette::BlueCyan,
+            scale: 2.5,
+            palette_reversed: true,
+            palette_hue: 105.0,
+            plasma_emitters: 5,
+            ..Settings::default()
+        };
+        settings.superformula.m = 3.5;
+        settings.superformula.n1 = 0.25;
+        settings.spirograph.ratio = 0.45;
+        settings.spirograph.pen_offset = 0.9;
+        settings.wavepool.emitters = 6;
+        settings.rain.intensity = 120.0;
+        settings.munching.mask = 0b1010_0101;
+        settings.hypno.arms = 7;
+        settings.hypno.speed = -0.75;
+        settings.caustics.brightness = 1.5;
+        settings.sand.brush = Element::Empty;
+        Preset::new("fire", &settings)
+    }
+
+    #[test]
+    fn presets_survive_a_round_trip() {
+        let mut presets = Presets::default();
+        presets.set(3, fire());
+        presets.set(8, Preset::new("plasma", &Settings::default()));
+        let toml = presets.to_toml();
+        assert!(toml.contains("radius-ratio = 0.45\n"));
+        let parsed = Presets::parse(&toml).unwrap();
+        assert_eq!(parsed, presets);
+        assert_eq!(parsed.get(3), Some(&fire()));
+        assert_eq!(parsed.get(1), None);
+    }
+
+    #[test]
+    fn applying_a_preset_overrides_the_parameters_it_holds() {
+        let mut settings = Settings::default();
+        settings.rain.lightning = true;
+        fire().apply(&mut settings).unwrap();
+        assert_eq!(Preset::new("fire", &settings), fire());
+        assert_eq!(settings.hypno.speed, -0.75);
+        // Options the preset does not hold keep their value
+        assert!(settings.rain.lightning);
+        // Flags the preset holds are cleared even though false flags are left out
+        Preset::new("plasma", &Settings::default())
+            .apply(&mut settings)
+            .unwrap();
+        assert!(!settings.palette_reversed);
+    }
+
+    #[test]
+    fn slots_outside_the_range_are_rejected() {
+        let preset = "effect = \"fire\"\nshape = \"ripple\"\npalette = \"hot\"\nscale = 1";
+        assert!(Presets::parse(&format!("[[preset]]\nslot = 9\n{preset}")).is_err());
+        assert!(Presets::parse(&format!("[[preset]]\nslot = 0\n{preset}")).is_err());
+        assert!(Presets::parse(&format!("[[preset]]\nslot = 1\n{preset}")).is_ok());
+    }
+
+    #[test]
+    fn missing_parameters_keep_their_value() {
+        let source = "[[preset]]\nslot = 1\neffect = \"fire\"\nshape = \"ripple\"\npalette = \"hot\"\nscale = 1";
+        let preset = Presets::parse(source).unwrap().get(1).cloned().unwrap();
+        let mut settings = Settings {
+            plasma_emitters: 4,
+            ..Settings::default()
+        };
+        preset.apply(&mut settings).unwrap();
+        assert_eq!(settings.palette, Palette::Hot);
+        assert_eq!(settings.plasma_emitters, 4);
+        let source = format!("{source}\nplasma-emitters = \"many\"");
+        assert!(Presets::parse(&source).is_err());
+    }
+
+    #[test]
+    fn parameters_outside_their_range_are_rejected() {
+        let source = "[[preset]]\nslot = 4\neffect = \"hypno\"\nshape = \"ripple\"\npalette = \"hot\"\nscale = 1";
+        let error =
+            |parameter: &str| Presets::parse(&format!("{source}\n{parameter}")).unwrap_err();
+        for parameter in [
+            "hypno-arms = 300",
+            "pen-offset = -1",
+            "munch-mask = 256",
+            "plasma-emitters = 6",
+            "emitters = 13",
+            "rain-intensity = 5000",
+            "caustics-brightness = 4",
+            "radius-ratio = 1.5",
+        ] {
+            let (key, _) = parameter.split_once(' ').unwrap();
+            let error = error(parameter);
+            assert!(error.starts_with("preset slot 4: "), "{error}");
+            assert!(error.contains(&format!("--{key}")), "{error}");
+        }
+        assert_eq!(
+            error("hypno-arms = 300"),
+            "preset slot 4: invalid value '300' for '--hypno-arms <COUNT>': 300 is not in 1..=32"
+        );
+        assert_eq!(
+            error("unknown = 1"),
+            "preset slot 4: unexpected argument '--unknown' found"
+        );
+        assert!(Presets::parse(&format!("{source}\nhypno-arms = 32")).is_ok());
+    }
+
+    #[test]
+    fn missing_presets_file_has_empty_slots() {
+        let presets = Presets::load(Path::new("/nonexistent/presets.toml")).unwrap();
+        assert_eq!(presets, Presets::default());
+    }
+}
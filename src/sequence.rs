@@ -0,0 +1,426 @@
+//! Scripted demos playing several effects one after the other.
+//!
+//! A sequence file lists the scenes of the demo in TOML, one `[[scene]]` table per
+//! scene:
+//!
+//! ```toml
+//! [[scene]]
+//! effect = "plasma"
+//! duration = 10
+//! palette = "hot"
+//!
+//! [[scene]]
+//! effect = "starfield"
+//! duration = 8
+//! transition = "crossfade"
+//! transition-time = 2
+//! ```
+//!
+//! Every scene names its `effect` and its `duration` in seconds. A scene starts when the
+//! previous one ends unless it gives its own `start` time, and it enters with a
+//! `transition`: `cut` (the default), `fade` through black, or `crossfade`. Transitions
+//! last `transition-time` seconds, and scenes entering with a transition start that much
+//! earlier so they overlap the end of the previous scene. Any other key is a parameter
+//! of the effect named like its command line option, so `palette = "hot"` stands for
+//! `--palette=hot`, read with [`crate::options::arguments`]. The [`Sequencer`] plays the
+//! scenes as a single effect, following the time it is drawn at so rewinding the demo
+//! rewinds the sequence, and starts over after the last one.
+use crate::common::{rgb, BlendMode, DemoBase, DemoEffect, InputEvent, Layer};
+use crate::effects::{self, Settings};
+use crate::options;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::error::Error;
+use std::path::Path;
+
+/// Length of fades and crossfades in seconds when the scene does not give one
+const DEFAULT_TRANSITION_TIME: f32 = 1.0;
+
+/// Keys describing the scene itself rather than the parameters of its effect
+const SCENE_KEYS: &[&str] = &[
+    "effect",
+    "start",
+    "duration",
+    "transition",
+    "transition-time",
+];
+
+/// How a scene replaces the picture of the scenes before it
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transition {
+    /// Switches to the scene at once
+    Cut,
+    /// Dims the picture to black, then brightens the scene
+    Fade,
+    /// Blends the scene over the picture with a growing opacity
+    Crossfade,
+}
+
+/// An effect shown for some time within the sequence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    /// Name of the effect
+    pub effect: String,
+    /// Time at which the scene starts in seconds
+    pub start: f32,
+    /// Time the scene lasts in seconds
+    pub duration: f32,
+    /// How the scene enters
+    pub transition: Transition,
+    /// Length of the transition in seconds
+    pub transition_time: f32,
+    /// Parameters of the effect
+    pub settings: Settings,
+}
+
+impl Scene {
+    /// Returns whether the scene is showing at `time` seconds into the sequence.
+    fn is_active(&self, time: f32) -> bool {
+        (self.start..self.start + self.duration).contains(&time)
+    }
+
+    /// Returns how far the transition has progressed, from 0 to 1, at `time` seconds into
+    /// the scene.
+    fn progress(&self, time: f32) -> f32 {
+        if self.transition_time > 0.0 {
+            (time / self.transition_time).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Tables of a sequence file
+#[derive(Deserialize)]
+struct SequenceFile {
+    /// Scenes in file order, read one by one to tell which one is invalid
+    #[serde(default)]
+    scene: Vec<toml::Table>,
+}
+
+/// A `[[scene]]` table as written in a sequence file
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct SceneTable {
+    effect: String,
+    start: Option<f32>,
+    duration: f32,
+    #[serde(default = "cut")]
+    transition: Transition,
+    transition_time: Option<f32>,
+}
+
+/// Returns the transition of scenes giving none.
+fn cut() -> Transition {
+    Transition::Cut
+}
+
+/// Command line parser reading the effect parameters of a scene
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct SceneArgs {
+    #[command(flatten)]
+    settings: Settings,
+}
+
+/// The scenes of a scripted demo
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence {
+    /// Scenes in file order
+    pub scenes: Vec<Scene>,
+}
+
+impl Sequence {
+    /// Reads a sequence file.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&source)?)
+    }
+
+    /// Parses the contents of a sequence file.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file: SequenceFile =
+            toml::from_str(source).map_err(|e| e.to_string().trim_end().to_string())?;
+        let mut scenes: Vec<Scene> = Vec::new();
+        for (i, table) in file.scene.into_iter().enumerate() {
+            let end = scenes
+                .last()
+                .map_or(0.0, |scene| scene.start + scene.duration);
+            let scene = parse_scene(table, end).map_err(|e| format!("scene {}: {}", i + 1, e))?;
+            scenes.push(scene);
+        }
+        if scenes.is_empty() {
+            return Err("the sequence has no [[scene]] tables".to_string());
+        }
+        Ok(Self { scenes })
+    }
+
+    /// Returns the time at which the last scene ends in seconds.
+    pub fn duration(&self) -> f32 {
+        self.scenes
+            .iter()
+            .map(|scene| scene.start + scene.duration)
+            .fold(0.0, f32::max)
+    }
+}
+
+/// Reads a scene following a scene ending at `end` seconds.
+fn parse_scene(table: toml::Table, end: f32) -> Result<Scene, String> {
+    let arguments = options::arguments(&table, SCENE_KEYS)?;
+    let scene = SceneTable::deserialize(table).map_err(|e| e.message().to_string())?;
+    let seconds = |key: &str, value: Option<f32>| match value {
+        Some(n) if n < 0.0 => Err(format!("'{key}' must be a positive number of seconds")),
+        value => Ok(value),
+    };
+
+    if !effects::names().any(|name| name == scene.effect) {
+        return Err(format!("unknown effect '{}'", scene.effect));
+    }
+    let duration = Some(scene.duration)
+        .filter(|&duration| duration > 0.0)
+        .ok_or("'duration' must be a positive number of seconds")?;
+    let transition_time = match scene.transition {
+        Transition::Cut => 0.0,
+        _ => seconds("transition-time", scene.transition_time)?
+            .unwrap_or(DEFAULT_TRANSITION_TIME)
+            .min(duration),
+    };
+    let start = match seconds("start", scene.start)? {
+        Some(start) => start,
+        None => (end - transition_time).max(0.0),
+    };
+    let settings = SceneArgs::try_parse_from(arguments)
+        .map_err(|e| e.to_string().trim_end().to_string())?
+        .settings;
+
+    Ok(Scene {
+        effect: scene.effect,
+        start,
+        duration,
+        transition: scene.transition,
+        transition_time,
+        settings,
+    })
+}
+
+/// Plays a sequence as a single effect, looping after the last scene
+pub struct Sequencer {
+    /// Scenes of the sequence
+    scenes: Vec<Scene>,
+    /// Effect of every scene, in the order of `scenes`
+    layers: Vec<Layer>,
+    /// Length of one pass through the sequence in seconds
+    duration: f32,
+    /// Time into the current pass through the sequence of the last frame drawn
+    time: Cell<f32>,
+}
+
+impl Sequencer {
+    /// Builds the effects of every scene the way [`effects::create`] builds any effect,
+    /// so scenes may cycle their palette.
+    ///
+    /// # Errors
+    /// Fails if the effect of a scene cannot be built.
+    pub fn new(width: usize, height: usize, sequence: &Sequence) -> Result<Self, Box<dyn Error>> {
+        let base = DemoBase::new(width, height);
+        let mut layers = Vec::new();
+        for scene in &sequence.scenes {
+            let effect = effects::create(&scene.effect, width, height, &scene.settings)?;
+            layers.push(Layer::new(base, effect));
+        }
+        Ok(Self {
+            scenes: sequence.scenes.clone(),
+            layers,
+            duration: sequence.duration(),
+            time: Cell::new(0.0),
+        })
+    }
+
+    /// Returns the time into the current pass through the sequence of the last frame
+    /// drawn, in seconds.
+    fn clock(&self) -> f32 {
+        self.time.get()
+    }
+
+    /// Returns the layers of the scenes showing in the last frame drawn, from the bottom
+    /// up.
+    fn active_layers(&mut self) -> impl Iterator<Item = &mut Layer> {
+        let time = self.clock();
+        self.scenes
+            .iter()
+            .zip(&mut self.layers)
+            .filter(move |(scene, _)| scene.is_active(time))
+            .map(|(_, layer)| layer)
+    }
+}
+
+impl DemoEffect for Sequencer {
+    /// Advances the scenes showing in the last frame drawn, leaving the others paused.
+    fn update(&mut self, dt: f32) {
+        for layer in self.active_layers() {
+            layer.update(dt);
+        }
+    }
+
+    fn interpolate(&mut self, alpha: f32) {
+        for layer in self.active_layers() {
+            layer.interpolate(alpha);
+        }
+    }
+
+    /// Draws the scenes showing at `time`, which picks the point of the sequence so
+    /// rewinding the time rewinds the sequence.
+    fn draw(&self, buffer: &mut [u32], time: f32) {
+        let time = time.rem_euclid(self.duration);
+        self.time.set(time);
+        let black = rgb(0, 0, 0);
+        buffer.fill(black);
+        for (scene, layer) in self.scenes.iter().zip(&self.layers) {
+            if !scene.is_active(time) {
+                continue;
+            }
+            let local = time - scene.start;
+            let progress = scene.progress(local);
+            match scene.transition {
+                Transition::Fade if progress < 0.5 => {
+                    for pixel in buffer.iter_mut() {
+                        *pixel = BlendMode::Alpha.blend(*pixel, black, progress * 2.0);
+                    }
+                }
+                Transition::Fade => {
+                    let pixels = layer.render(local);
+                    for (pixel, &above) in buffer.iter_mut().zip(pixels.iter()) {
+                        *pixel = BlendMode::Alpha.blend(black, above, progress * 2.0 - 1.0);
+                    }
+                }
+                Transition::Cut | Transition::Crossfade => {
+                    let pixels = layer.render(local);
+                    for (pixel, &above) in buffer.iter_mut().zip(pixels.iter()) {
+                        *pixel = BlendMode::Alpha.blend(*pixel, above, progress);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        for layer in self.active_layers() {
+            layer.handle_input(event);
+        }
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        for layer in &mut self.layers {
+            layer.resize(DemoBase::new(width, height));
+        }
+    }
+
+    /// Saves the parameters of the scenes showing in the last frame drawn, so the scene
+    /// entering last wins while two of them overlap.
+    fn save_settings(&self, settings: &mut Settings) {
+        let time = self.clock();
+        for (scene, layer) in self.scenes.iter().zip(&self.layers) {
+            if scene.is_active(time) {
+                layer.save_settings(settings);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::Palette;
+
+    const DEMO: &str = r#"
+[[scene]]
+effect = "plasma"
+duration = 4
+palette = "hot"
+
+[[scene]]
+effect = "fire"
+duration = 3
+transition = "crossfade"
+transition-time = 1
+
+[[scene]]
+effect = "starfield"
+start = 10
+duration = 2
+"#;
+
+    #[test]
+    fn scenes_follow_each_other() {
+        let sequence = Sequence::parse(DEMO).unwrap();
+        let starts: Vec<f32> = sequence.scenes.iter().map(|s| s.start).collect();
+        // The crossfade overlaps the end of the plasma
+        assert_eq!(starts, [0.0, 3.0, 10.0]);
+        assert_eq!(sequence.duration(), 12.0);
+    }
+
+    #[test]
+    fn other_keys_configure_the_effect() {
+        let sequence = Sequence::parse(DEMO).unwrap();
+        assert_eq!(sequence.scenes[0].settings.palette, Palette::Hot);
+        assert_eq!(sequence.scenes[1].settings, Settings::default());
+    }
+
+    #[test]
+    fn invalid_scenes_are_rejected() {
+        assert!(Sequence::parse("").is_err());
+        assert!(Sequence::parse("[[scene]]\neffect = \"nothing\"\nduration = 1").is_err());
+        assert!(Sequence::parse("[[scene]]\neffect = \"fire\"").is_err());
+        assert!(Sequence::parse("[[scene]]\neffect = \"fire\"\nduration = 1\nwarp = 9").is_err());
+        let error =
+            Sequence::parse("[[scene]]\neffect = \"fire\"\nduration = 1\ntransition = \"wipe\"");
+        assert!(error.unwrap_err().starts_with("scene 1:"));
+    }
+
+    #[test]
+    fn sequencer_loops_through_the_scenes() {
+        let sequence = Sequence::parse(DEMO).unwrap();
+        let mut sequencer = Sequencer::new(32, 24, &sequence).unwrap();
+        let mut buffer = vec![0; 32 * 24];
+        for frame in 1..=30 {
+            sequencer.update(0.5);
+            sequencer.draw(&mut buffer, frame as f32 * 0.5);
+        }
+        assert_eq!(sequencer.clock(), 3.0);
+    }
+
+    #[test]
+    fn sequencer_follows_the_time_it_is_drawn_at() {
+        let sequence = Sequence::parse(DEMO).unwrap();
+        let sequencer = Sequencer::new(16, 16, &sequence).unwrap();
+        let (mut first, mut again) = (vec![0; 256], vec![0; 256]);
+        sequencer.draw(&mut first, 1.0);
+        sequencer.draw(&mut again, 11.0);
+        assert_eq!(sequencer.clock(), 11.0);
+        // Going back to the start shows the first scene again
+        sequencer.draw(&mut again, 1.0);
+        assert_eq!(sequencer.clock(), 1.0);
+        assert_eq!(first, again);
+    }
+
+    #[test]
+    fn sequencer_saves_the_settings_of_the_scene_showing() {
+        let sequence = Sequence::parse(DEMO).unwrap();
+        let sequencer = Sequencer::new(8, 8, &sequence).unwrap();
+        sequencer.draw(&mut vec![0; 64], 1.0);
+        let mut settings = Settings::default();
+        sequencer.save_settings(&mut settings);
+        assert_eq!(settings.palette, Palette::Hot);
+    }
+
+    #[test]
+    fn gaps_between_scenes_are_black() {
+        let sequence = Sequence::parse(DEMO).unwrap();
+        let sequencer = Sequencer::new(8, 8, &sequence).unwrap();
+        let mut buffer = vec![1; 64];
+        sequencer.draw(&mut buffer, 8.0);
+        assert!(buffer.iter().all(|&p| p == rgb(0, 0, 0)));
+    }
+}
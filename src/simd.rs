@@ -0,0 +1,65 @@
+//! Eight lanes of `f32` computed at once, for the hot loops of per-pixel effects.
+//!
+//! Vectors are [`f32x8`] from the `wide` crate, which uses the vector instructions of
+//! every target with them, and plain scalar code elsewhere. Its sines are a polynomial
+//! approximation free of branches, accurate to about 1e-6 over the ranges effects use,
+//! since the functions of the standard library cannot be vectorized. The helpers below
+//! fill vectors the way effects walk their rows.
+pub use wide::f32x8;
+
+/// Number of lanes of [`f32x8`]
+pub const LANES: usize = 8;
+
+/// Returns the first eight values of `values`.
+///
+/// # Panics
+/// Panics if `values` holds fewer than eight values.
+pub fn load(values: &[f32]) -> f32x8 {
+    let mut lanes = [0.0; LANES];
+    lanes.copy_from_slice(&values[..LANES]);
+    f32x8::new(lanes)
+}
+
+/// Returns the lanes `start`, `start + 1`, and so on.
+pub fn ramp(start: f32) -> f32x8 {
+    f32x8::new(std::array::from_fn(|lane| start + lane as f32))
+}
+
+/// Applies `f` to every lane of `x`.
+pub fn map(x: f32x8, f: impl Fn(f32) -> f32) -> f32x8 {
+    f32x8::new(x.to_array().map(f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sines_match_the_standard_library() {
+        for step in -2_500..=2_500 {
+            let x = ramp(step as f32 * 8.0) * 0.005;
+            for (a, e) in x.sin().to_array().iter().zip(x.to_array()) {
+                assert!((a - e.sin()).abs() < 5e-7, "sin({e})");
+            }
+        }
+    }
+
+    #[test]
+    fn lanes_are_computed_independently() {
+        let x = ramp(-3.0) * 0.5;
+        let expected = map(x, |v| (v * 2.0 + 1.0).sin());
+        let actual = (x * f32x8::splat(2.0) + 1.0).sin();
+        for (a, e) in actual.to_array().iter().zip(expected.to_array()) {
+            assert!((a - e).abs() < 5e-7);
+        }
+        assert_eq!(x.to_array()[1], -1.0);
+        assert_eq!(load(&[2.0; 9]), f32x8::splat(2.0));
+        let ramp = ramp(-4.0);
+        assert_eq!(
+            ramp.abs().to_array(),
+            [4.0, 3.0, 2.0, 1.0, 0.0, 1.0, 2.0, 3.0]
+        );
+        assert_eq!(ramp.min(f32x8::splat(0.0)).to_array()[6], 0.0);
+        assert_eq!(ramp.max(f32x8::splat(0.0)).to_array()[0], 0.0);
+    }
+}
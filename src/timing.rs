@@ -0,0 +1,280 @@
+//! Pacing of the frames shown while running, and of the simulations behind them.
+//!
+//! [`FramePacer`] holds the main loop back to a steady frame rate. Sleeping alone wakes
+//! up late by as much as a scheduler tick, so it sleeps until shortly before a frame is
+//! due and spins for the rest, which keeps the CPU mostly idle at low frame rates while
+//! frames still come out on time.
+//!
+//! [`FixedTimestep`] splits the time between frames into updates of a fixed length, so
+//! simulations behave the same whatever the frame rate. The time left over is reported
+//! as a fraction of an update, for effects to draw between their last two states.
+//!
+//! [`Clock`] keeps the animation time apart from the real time, so the animation can be
+//! paused, stepped, rewound, or run slower or faster than real time.
+use std::time::{Duration, Instant};
+
+/// Time before a frame is due that is spent spinning rather than sleeping
+const SPIN_TIME: Duration = Duration::from_millis(2);
+
+/// Longest real time in seconds the animation advances by between two frames
+///
+/// Longer stalls, such as dragging the window, are cut short rather than making up for
+/// them with a burst of updates that would slow the next frames down in turn. The cut
+/// applies before the time scale, so a faster animation still runs faster.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+/// Factor the time scale changes by with every press of `[` or `]`
+const TIME_SCALE_FACTOR: f32 = 2.0;
+
+/// Slowest and fastest time scales
+const TIME_SCALES: (f32, f32) = (1.0 / 16.0, 16.0);
+
+/// Waits between frames to show them at a fixed rate
+#[derive(Debug, Clone)]
+pub struct FramePacer {
+    /// Time between two frames
+    frame_time: Duration,
+    /// Time the next frame is due
+    next_frame: Instant,
+}
+
+impl FramePacer {
+    /// Returns a pacer showing `frame_rate` frames per second, starting now.
+    ///
+    /// # Panics
+    /// Panics if the frame rate is not positive and finite.
+    pub fn new(frame_rate: f32) -> Self {
+        Self {
+            frame_time: Duration::from_secs_f32(1.0 / frame_rate),
+            next_frame: Instant::now(),
+        }
+    }
+
+    /// Returns the number of frames shown per second.
+    pub fn frame_rate(&self) -> f32 {
+        1.0 / self.frame_time.as_secs_f32()
+    }
+
+    /// Waits until the next frame is due.
+    ///
+    /// Frames running late are shown right away, and a frame later than a whole frame
+    /// time starts the schedule over instead of rushing the following frames to catch up.
+    pub fn wait(&mut self) {
+        if let Some(sleep) = self
+            .next_frame
+            .checked_duration_since(Instant::now())
+            .and_then(|remaining| remaining.checked_sub(SPIN_TIME))
+        {
+            std::thread::sleep(sleep);
+        }
+        while Instant::now() < self.next_frame {
+            std::hint::spin_loop();
+        }
+        let now = Instant::now();
+        self.next_frame += self.frame_time;
+        if self.next_frame < now {
+            self.next_frame = now + self.frame_time;
+        }
+    }
+}
+
+/// Splits elapsed time into updates of a fixed length
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTimestep {
+    /// Length of an update in seconds
+    step: f32,
+    /// Time elapsed but not simulated yet in seconds, less than a step
+    remainder: f32,
+}
+
+impl FixedTimestep {
+    /// Returns a timestep running `rate` updates per second.
+    pub fn new(rate: f32) -> Self {
+        Self {
+            step: 1.0 / rate,
+            remainder: 0.0,
+        }
+    }
+
+    /// Returns the length of an update in seconds.
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Adds `dt` elapsed seconds and returns the number of updates now due.
+    pub fn advance(&mut self, dt: f32) -> usize {
+        self.remainder += dt.max(0.0);
+        let steps = (self.remainder / self.step) as usize;
+        self.remainder -= steps as f32 * self.step;
+        steps
+    }
+
+    /// Returns the fraction of an update elapsed since the last one, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        (self.remainder / self.step).clamp(0.0, 1.0)
+    }
+}
+
+/// Time of the animation, which runs along with the real time unless paused, scaled by a
+/// multiplier
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clock {
+    /// Animation time in seconds
+    time: f32,
+    /// Animation seconds per real second
+    scale: f32,
+    /// Whether the animation time stands still
+    paused: bool,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl Clock {
+    /// Returns a running clock at time 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the animation time in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Returns the animation seconds that pass per real second.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Returns whether the animation time stands still.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Moves the animation time forward by `elapsed` real seconds, of at most a quarter
+    /// second, times the time scale, unless paused, and returns the animation seconds
+    /// that passed.
+    pub fn advance(&mut self, elapsed: f32) -> f32 {
+        let dt = if self.paused {
+            0.0
+        } else {
+            elapsed.min(MAX_FRAME_TIME) * self.scale
+        };
+        self.time += dt;
+        dt
+    }
+
+    /// Moves the animation time by `seconds`, forward or backward, even while paused. The
+    /// time never goes back past 0.
+    pub fn step(&mut self, seconds: f32) {
+        self.time = (self.time + seconds).max(0.0);
+    }
+
+    /// Sets the animation time back to 0.
+    pub fn reset(&mut self) {
+        self.time = 0.0;
+    }
+
+    /// Pauses a running clock, or resumes a paused one.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Doubles the speed of the animation, up to 16 times the real time.
+    pub fn speed_up(&mut self) {
+        self.scale = (self.scale * TIME_SCALE_FACTOR).min(TIME_SCALES.1);
+    }
+
+    /// Halves the speed of the animation, down to a sixteenth of the real time.
+    pub fn slow_down(&mut self) {
+        self.scale = (self.scale / TIME_SCALE_FACTOR).max(TIME_SCALES.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_spaced_by_the_frame_time() {
+        let mut pacer = FramePacer::new(100.0);
+        let start = Instant::now();
+        for _ in 0..6 {
+            pacer.wait();
+        }
+        // The first frame is due right away and the five others 10 ms apart
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(50), "{elapsed:?}");
+        assert!((pacer.frame_rate() - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn late_frames_do_not_rush_the_next_ones() {
+        let mut pacer = FramePacer::new(100.0);
+        pacer.wait();
+        std::thread::sleep(Duration::from_millis(35));
+        pacer.wait();
+        let start = Instant::now();
+        pacer.wait();
+        assert!(start.elapsed() >= Duration::from_millis(9));
+    }
+
+    #[test]
+    fn elapsed_time_is_split_into_fixed_steps() {
+        let mut timestep = FixedTimestep::new(10.0);
+        assert_eq!(timestep.advance(0.05), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-5);
+        assert_eq!(timestep.advance(0.21), 2);
+        assert!((timestep.alpha() - 0.6).abs() < 1e-4);
+        assert_eq!(timestep.step(), 0.1);
+    }
+
+    #[test]
+    fn long_stalls_are_cut_short() {
+        let mut clock = Clock::new();
+        assert_eq!(clock.advance(10.0), 0.25);
+        // The stall is cut before the time scale applies
+        clock.speed_up();
+        clock.speed_up();
+        assert_eq!(clock.advance(10.0), 1.0);
+        assert_eq!(clock.advance(0.2), 0.8);
+        let mut timestep = FixedTimestep::new(100.0);
+        assert_eq!(timestep.advance(0.5), 50);
+    }
+
+    #[test]
+    fn clocks_follow_the_scaled_real_time() {
+        let mut clock = Clock::new();
+        assert_eq!(clock.advance(0.125), 0.125);
+        clock.speed_up();
+        assert_eq!(clock.advance(0.125), 0.25);
+        assert_eq!(clock.time(), 0.375);
+        for _ in 0..10 {
+            clock.slow_down();
+        }
+        assert_eq!(clock.scale(), 1.0 / 16.0);
+        clock.reset();
+        assert_eq!(clock.time(), 0.0);
+    }
+
+    #[test]
+    fn paused_clocks_only_move_by_steps() {
+        let mut clock = Clock::new();
+        clock.toggle_pause();
+        assert!(clock.is_paused());
+        assert_eq!(clock.advance(1.0), 0.0);
+        clock.step(0.25);
+        assert_eq!(clock.time(), 0.25);
+        clock.step(-1.0);
+        assert_eq!(clock.time(), 0.0);
+        clock.toggle_pause();
+        assert_eq!(clock.advance(0.25), 0.25);
+    }
+}